@@ -0,0 +1,1270 @@
+//! Local activity feed for Ralph-Beads
+//!
+//! An append-only JSONL event log that agents, hooks, and the CLI itself
+//! publish progress events into. Every reader (`activity list` and friends)
+//! works from the same file, making it the single progress bus for a
+//! workflow.
+//!
+//! Writers take an exclusive lock on a sidecar `.lock` file, and the last
+//! id handed out is kept in a sidecar `.seq` file, so concurrent emits get
+//! distinct ids and ids are never reused, even after the feed is pruned.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::state::{current_run, sidecar};
+
+/// Default location of the activity feed, relative to the project root
+/// (`--data-dir` moves it; see [`crate::data_dirs`])
+pub const DEFAULT_FEED_FILE: &str = ".beads/activity.jsonl";
+
+/// Kinds of events accepted on the feed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    /// A worker claimed the issue
+    Claimed,
+    /// Free-form progress update
+    Progress,
+    /// Preflight checks ran
+    Preflight,
+    /// The issue was completed
+    Completed,
+    /// An attempt failed
+    Failed,
+    /// Work is blocked on something external
+    Blocked,
+    /// A gate was created or resolved
+    Gate,
+    /// CI status changed
+    Ci,
+    /// Older events rolled up by compaction (written by `activity prune`)
+    Summary,
+}
+
+impl EventKind {
+    /// All kinds, in lifecycle order
+    pub const ALL: [EventKind; 9] = [
+        EventKind::Claimed,
+        EventKind::Progress,
+        EventKind::Preflight,
+        EventKind::Completed,
+        EventKind::Failed,
+        EventKind::Blocked,
+        EventKind::Gate,
+        EventKind::Ci,
+        EventKind::Summary,
+    ];
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventKind::Claimed => write!(f, "claimed"),
+            EventKind::Progress => write!(f, "progress"),
+            EventKind::Preflight => write!(f, "preflight"),
+            EventKind::Completed => write!(f, "completed"),
+            EventKind::Failed => write!(f, "failed"),
+            EventKind::Blocked => write!(f, "blocked"),
+            EventKind::Gate => write!(f, "gate"),
+            EventKind::Ci => write!(f, "ci"),
+            EventKind::Summary => write!(f, "summary"),
+        }
+    }
+}
+
+impl FromStr for EventKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "claimed" | "claim" => Ok(EventKind::Claimed),
+            "progress" => Ok(EventKind::Progress),
+            "preflight" => Ok(EventKind::Preflight),
+            "completed" | "complete" | "done" => Ok(EventKind::Completed),
+            "failed" | "failure" => Ok(EventKind::Failed),
+            "blocked" => Ok(EventKind::Blocked),
+            "gate" => Ok(EventKind::Gate),
+            "ci" => Ok(EventKind::Ci),
+            "summary" => Ok(EventKind::Summary),
+            _ => Err(format!(
+                "Unknown event kind: {} (expected one of: {})",
+                s,
+                EventKind::ALL
+                    .iter()
+                    .map(|k| k.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+}
+
+/// A single event on the activity feed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActivityEvent {
+    /// Monotonic sequence number within the feed
+    pub id: u64,
+    /// Unix timestamp (seconds)
+    pub timestamp: u64,
+    pub issue: String,
+    pub kind: EventKind,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worker: Option<String>,
+    /// Run ID of the session that emitted the event
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+/// Current Unix time in seconds
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Format a Unix timestamp as an RFC 3339 UTC string
+pub fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+
+    // Civil-from-days (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3_600,
+        (rem % 3_600) / 60,
+        rem % 60
+    )
+}
+
+/// Parse an RFC 3339 timestamp (as written by bd) into Unix seconds
+///
+/// Accepts fractional seconds and `Z` or `±HH:MM` offsets.
+pub fn parse_timestamp(s: &str) -> Option<u64> {
+    let s = s.trim();
+    if s.len() < 19 || !s.is_char_boundary(19) {
+        return None;
+    }
+    let (datetime, zone) = s.split_at(19);
+    let num = |range: std::ops::Range<usize>| datetime.get(range)?.parse::<i64>().ok();
+    let (year, month, day) = (num(0..4)?, num(5..7)?, num(8..10)?);
+    let (hour, minute, second) = (num(11..13)?, num(14..16)?, num(17..19)?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // Skip fractional seconds, then read the offset
+    let zone = zone.trim_start_matches(|c: char| c == '.' || c.is_ascii_digit());
+    let offset = match zone {
+        "" | "Z" | "z" => 0,
+        _ => {
+            let sign = match zone.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            let hh: i64 = zone.get(1..3)?.parse().ok()?;
+            let mm: i64 = zone.get(4..6)?.parse().ok()?;
+            sign * (hh * 3_600 + mm * 60)
+        }
+    };
+
+    // Days-from-civil (Howard Hinnant's algorithm)
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    let secs = days * 86_400 + hour * 3_600 + minute * 60 + second - offset;
+    u64::try_from(secs).ok()
+}
+
+/// Parse a human duration such as `90s`, `30m`, `24h`, or `7d` into seconds
+///
+/// A bare number is interpreted as seconds.
+pub fn parse_duration(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let value: u64 = number
+        .parse()
+        .map_err(|_| format!("Invalid duration: {}", s))?;
+    let multiplier = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 3_600,
+        "d" => 86_400,
+        "w" => 604_800,
+        _ => {
+            return Err(format!(
+                "Invalid duration unit in {} (use s, m, h, d, w)",
+                s
+            ))
+        }
+    };
+    value
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("Duration too large: {}", s))
+}
+
+/// Format a number of seconds as a compact duration (e.g. `2h05m`)
+pub fn format_duration(secs: u64) -> String {
+    let (h, m, s) = (secs / 3_600, (secs % 3_600) / 60, secs % 60);
+    if h > 0 {
+        format!("{}h{:02}m", h, m)
+    } else if m > 0 {
+        format!("{}m{:02}s", m, s)
+    } else {
+        format!("{}s", s)
+    }
+}
+
+/// Read all events from a feed file
+///
+/// A missing file is an empty feed. Malformed lines are skipped so a
+/// partially written line from a crashed writer doesn't poison readers.
+pub fn read_events(feed_file: &Path) -> Result<Vec<ActivityEvent>, String> {
+    if !feed_file.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(feed_file)
+        .map_err(|e| format!("Failed to read {}: {}", feed_file.display(), e))?;
+
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Append a new event to the feed, assigning its sequence id and timestamp
+///
+/// The event is tagged with the current run ID (`RALPH_BEADS_RUN`), if any.
+pub fn emit_event(
+    feed_file: &Path,
+    issue: &str,
+    kind: EventKind,
+    message: &str,
+    worker: Option<String>,
+    data: Option<Value>,
+) -> Result<ActivityEvent, String> {
+    if issue.trim().is_empty() {
+        return Err("Event issue must not be empty".to_string());
+    }
+    if kind == EventKind::Summary {
+        return Err("Summary events are written by compaction, not emitted".to_string());
+    }
+    if let Some(ref d) = data {
+        if !d.is_object() {
+            return Err("Event data must be a JSON object".to_string());
+        }
+    }
+
+    let _lock = lock_feed(feed_file)?;
+    let next_id = last_id(feed_file)? + 1;
+
+    let event = ActivityEvent {
+        id: next_id,
+        timestamp: now_secs(),
+        issue: issue.to_string(),
+        kind,
+        message: message.to_string(),
+        worker,
+        run: current_run(),
+        data,
+    };
+
+    append_json_line(feed_file, &event)?;
+    record_last_id(feed_file, next_id)?;
+    Ok(event)
+}
+
+/// Take the exclusive lock writers of `feed_file` hold; released when the
+/// returned file is dropped
+pub(crate) fn lock_feed(feed_file: &Path) -> Result<File, String> {
+    let lock_path = sidecar(feed_file, "lock");
+    if let Some(parent) = lock_path.parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+    }
+    let lock = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .map_err(|e| format!("Failed to open {}: {}", lock_path.display(), e))?;
+    lock.lock()
+        .map_err(|e| format!("Failed to lock {}: {}", lock_path.display(), e))?;
+    Ok(lock)
+}
+
+/// Id of the last event in the feed, read from the end of the file
+fn last_feed_id(feed_file: &Path) -> Result<u64, String> {
+    let read_err = |e: std::io::Error| format!("Failed to read {}: {}", feed_file.display(), e);
+    let mut file = match File::open(feed_file) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(read_err(e)),
+    };
+    let len = file.metadata().map_err(read_err)?.len();
+    // Widen the window until it holds a complete event
+    let mut window: u64 = 64 * 1024;
+    loop {
+        let start = len.saturating_sub(window);
+        file.seek(SeekFrom::Start(start)).map_err(read_err)?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes).map_err(read_err)?;
+        let text = String::from_utf8_lossy(&bytes);
+        let mut lines = text.lines().rev();
+        // The window's first line may be cut off
+        if start > 0 {
+            lines.next_back();
+        }
+        let found = lines.find_map(|line| serde_json::from_str::<ActivityEvent>(line).ok());
+        match found {
+            Some(event) => return Ok(event.id),
+            None if start == 0 => return Ok(0),
+            None => window *= 4,
+        }
+    }
+}
+
+/// The highest id handed out on `feed_file` so far; 0 for a new feed
+pub(crate) fn last_id(feed_file: &Path) -> Result<u64, String> {
+    let recorded = fs::read_to_string(sidecar(feed_file, "seq"))
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+        .unwrap_or(0);
+    Ok(recorded.max(last_feed_id(feed_file)?))
+}
+
+/// Remember `id` as the highest handed out on `feed_file`; call with the
+/// feed locked
+pub(crate) fn record_last_id(feed_file: &Path, id: u64) -> Result<(), String> {
+    let path = sidecar(feed_file, "seq");
+    fs::write(&path, format!("{}\n", id))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Events per page when `--cursor` is given without `--page-size`
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// One page of `activity list --page-size`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EventPage {
+    pub events: Vec<ActivityEvent>,
+    /// Pass as `--cursor` for the next page; `null` on the last one
+    pub next_cursor: Option<String>,
+}
+
+/// Parse a cursor handed out in `next_cursor`
+pub fn parse_cursor(cursor: &str) -> Result<u64, String> {
+    cursor
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid cursor: {} (use a next_cursor value)", cursor))
+}
+
+/// The first `page_size` of `events` after `cursor`, in id order
+///
+/// The cursor is the id of the last event already returned. Ids are never
+/// reused and compaction keeps the ids of what it retains, so walking the
+/// pages of a growing feed neither skips nor repeats an event. A summary
+/// takes the highest id of the events it replaces and can show up after
+/// some of them were already returned.
+pub fn paginate(
+    mut events: Vec<ActivityEvent>,
+    cursor: Option<u64>,
+    page_size: usize,
+) -> EventPage {
+    events.sort_by_key(|e| e.id);
+    let after = cursor.unwrap_or(0);
+    let mut rest = events.into_iter().filter(|e| e.id > after);
+    let page: Vec<ActivityEvent> = rest.by_ref().take(page_size.max(1)).collect();
+    let next_cursor = match (rest.next(), page.last()) {
+        (Some(_), Some(last)) => Some(last.id.to_string()),
+        _ => None,
+    };
+    EventPage {
+        events: page,
+        next_cursor,
+    }
+}
+
+/// Append `record` as one JSON line, creating the file and its parent
+/// directories as needed
+pub(crate) fn append_json_line<T: Serialize>(path: &Path, record: &T) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+    }
+
+    let line = serde_json::to_string(record).map_err(|e| e.to_string())?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Filter criteria shared by feed readers
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    pub issue: Option<String>,
+    pub kind: Option<EventKind>,
+    pub worker: Option<String>,
+    pub run: Option<String>,
+    /// Regex matched against the issue, message, and serialized data
+    pub pattern: Option<Regex>,
+}
+
+impl EventFilter {
+    /// Whether an event passes this filter
+    pub fn matches(&self, event: &ActivityEvent) -> bool {
+        if let Some(ref issue) = self.issue {
+            if &event.issue != issue {
+                return false;
+            }
+        }
+        if let Some(kind) = self.kind {
+            if event.kind != kind {
+                return false;
+            }
+        }
+        if let Some(ref worker) = self.worker {
+            if event.worker.as_ref() != Some(worker) {
+                return false;
+            }
+        }
+        if let Some(ref run) = self.run {
+            if event.run.as_ref() != Some(run) {
+                return false;
+            }
+        }
+        if let Some(ref pattern) = self.pattern {
+            let data = event
+                .data
+                .as_ref()
+                .map(|d| d.to_string())
+                .unwrap_or_default();
+            if !pattern.is_match(&event.issue)
+                && !pattern.is_match(&event.message)
+                && !pattern.is_match(&data)
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Incremental reader over a feed file that is being appended to
+///
+/// Tracks the byte offset already consumed and buffers a trailing partial
+/// line until its writer finishes it. If the file shrinks (rotated or
+/// pruned), reading restarts from the beginning.
+#[derive(Debug, Default)]
+pub struct FeedTail {
+    offset: u64,
+    partial: Vec<u8>,
+    /// Drop the first line read: `skip_to` landed inside it
+    skip_partial: bool,
+}
+
+impl FeedTail {
+    /// Start tailing at the current end of `feed_file`
+    pub fn at_end(feed_file: &Path) -> Self {
+        let offset = fs::metadata(feed_file).map(|m| m.len()).unwrap_or(0);
+        FeedTail {
+            offset,
+            ..Default::default()
+        }
+    }
+
+    /// Read events appended since the last call
+    pub fn poll(&mut self, feed_file: &Path) -> Result<Vec<ActivityEvent>, String> {
+        self.poll_limited(feed_file, u64::MAX)
+    }
+
+    /// Read events appended since the last call, reading at most
+    /// `max_bytes`; the rest is left for the next call
+    pub fn poll_limited(
+        &mut self,
+        feed_file: &Path,
+        max_bytes: u64,
+    ) -> Result<Vec<ActivityEvent>, String> {
+        let file = match File::open(feed_file) {
+            Ok(f) => f,
+            Err(_) => return Ok(Vec::new()),
+        };
+
+        let len = file
+            .metadata()
+            .map_err(|e| format!("Failed to stat {}: {}", feed_file.display(), e))?
+            .len();
+        if len < self.offset {
+            self.offset = 0;
+            self.partial.clear();
+            self.skip_partial = false;
+        }
+        if len == self.offset {
+            return Ok(Vec::new());
+        }
+
+        let mut file = file;
+        file.seek(SeekFrom::Start(self.offset))
+            .map_err(|e| format!("Failed to seek {}: {}", feed_file.display(), e))?;
+        let read = file
+            .take(max_bytes)
+            .read_to_end(&mut self.partial)
+            .map_err(|e| format!("Failed to read {}: {}", feed_file.display(), e))?;
+        self.offset += read as u64;
+
+        let mut events = Vec::new();
+        while let Some(newline) = self.partial.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.partial.drain(..=newline).collect();
+            if std::mem::take(&mut self.skip_partial) {
+                continue;
+            }
+            if let Ok(event) = serde_json::from_slice(&line) {
+                events.push(event);
+            }
+        }
+        Ok(events)
+    }
+
+    /// Bytes of `feed_file` not read yet
+    pub fn unread(&self, feed_file: &Path) -> u64 {
+        let len = fs::metadata(feed_file).map(|m| m.len()).unwrap_or(0);
+        len.saturating_sub(self.offset)
+    }
+
+    /// Continue reading at `offset`, dropping what was buffered
+    pub fn skip_to(&mut self, offset: u64) {
+        self.offset = offset;
+        self.partial.clear();
+        self.skip_partial = offset > 0;
+    }
+
+    fn rotated(&self, feed_file: &Path) -> bool {
+        fs::metadata(feed_file).is_ok_and(|m| m.len() < self.offset)
+    }
+}
+
+/// Matching events `follow` keeps per poll by default before skipping the
+/// oldest
+pub const DEFAULT_MAX_BACKLOG: usize = 1000;
+
+/// Bytes of feed `follow` reads per poll for each event of backlog
+pub const BACKLOG_BYTES_PER_EVENT: u64 = 4096;
+
+/// Drop all but the newest `max` of `events`, returning how many were dropped
+pub fn trim_backlog(events: &mut Vec<ActivityEvent>, max: usize) -> usize {
+    let skipped = events.len().saturating_sub(max);
+    events.drain(..skipped);
+    skipped
+}
+
+/// What `follow_events` passes on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Followed<'a> {
+    Event(&'a ActivityEvent),
+    /// Events were skipped to catch up with the feed
+    Skipped(Skipped),
+}
+
+/// Events a follower skipped to catch up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Skipped {
+    /// How many events were skipped, matching the filter or not
+    pub skipped: u64,
+    /// Id of the first event handled after the skip
+    pub resume_at: Option<u64>,
+}
+
+/// How a follow ended
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FollowSummary {
+    /// Events passed on
+    pub delivered: u64,
+    /// Events skipped to catch up
+    pub skipped: u64,
+    /// Id of the newest event seen
+    pub last_id: Option<u64>,
+    /// Whether `stop` ended the follow
+    pub interrupted: bool,
+}
+
+/// Follow a feed, invoking `on_event` for each new event matching `filter`
+///
+/// Polls every `interval` until `on_event` returns `false` or `stop` is
+/// set. Only events newer than the last one seen are passed on, so
+/// following a feed that is pruned or rotated underneath resumes where it
+/// left off instead of replaying it; a rotated feed whose ids start over is
+/// followed from its start. A failed read is retried on the next poll.
+///
+/// Each poll reads at most `max_backlog` events' worth of the feed
+/// ([`BACKLOG_BYTES_PER_EVENT`] each) and hands on at most `max_backlog`
+/// matching events. A follower that falls further behind (a slow
+/// `on_event` on a busy feed) jumps ahead and is told with
+/// [`Followed::Skipped`]; the replay of `from_start` is read in full.
+pub fn follow_events<F>(
+    feed_file: &Path,
+    filter: &EventFilter,
+    from_start: bool,
+    interval: Duration,
+    max_backlog: usize,
+    stop: &AtomicBool,
+    mut on_event: F,
+) -> Result<FollowSummary, String>
+where
+    F: FnMut(Followed) -> bool,
+{
+    let max_bytes = (max_backlog.max(1) as u64).saturating_mul(BACKLOG_BYTES_PER_EVENT);
+    let mut summary = FollowSummary::default();
+    let mut tail = if from_start {
+        FeedTail::default()
+    } else {
+        summary.last_id = last_feed_id(feed_file).ok().filter(|&id| id > 0);
+        FeedTail::at_end(feed_file)
+    };
+    let mut replay = from_start;
+    let mut failing = false;
+
+    loop {
+        if stop.load(Ordering::Relaxed) {
+            summary.interrupted = true;
+            return Ok(summary);
+        }
+        if tail.rotated(feed_file) {
+            let newest = last_feed_id(feed_file).unwrap_or(0);
+            if summary.last_id.is_some_and(|last| newest < last) {
+                tracing::warn!("{} starts over at a lower id", feed_file.display());
+                summary.last_id = None;
+            }
+        }
+        let jumped = !replay && tail.unread(feed_file) > max_bytes;
+        if jumped {
+            let len = fs::metadata(feed_file).map(|m| m.len()).unwrap_or(0);
+            tail.skip_to(len.saturating_sub(max_bytes));
+        }
+
+        let polled = match tail.poll_limited(feed_file, max_bytes) {
+            Ok(events) => {
+                if failing {
+                    tracing::info!("reading {} again", feed_file.display());
+                    failing = false;
+                }
+                events
+            }
+            Err(e) => {
+                if !failing {
+                    tracing::warn!("{}; retrying", e);
+                    failing = true;
+                }
+                Vec::new()
+            }
+        };
+        let last_id = summary.last_id;
+        let mut events: Vec<ActivityEvent> = polled
+            .into_iter()
+            .filter(|e| last_id.is_none_or(|last| e.id > last))
+            .collect();
+        // Ids are handed out in order, so a jump skipped those in between
+        let mut skipped = match (jumped, last_id, events.first()) {
+            (true, Some(last), Some(first)) => first.id.saturating_sub(last + 1),
+            _ => 0,
+        };
+        let resume_at = events.first().map(|e| e.id);
+        if let Some(newest) = events.last() {
+            summary.last_id = Some(newest.id);
+        }
+        events.retain(|e| filter.matches(e));
+        if !replay {
+            skipped += trim_backlog(&mut events, max_backlog) as u64;
+        }
+        replay = replay && tail.unread(feed_file) > 0;
+
+        if skipped > 0 {
+            summary.skipped += skipped;
+            let notice = Skipped {
+                skipped,
+                resume_at: events.first().map(|e| e.id).or(resume_at),
+            };
+            if !on_event(Followed::Skipped(notice)) {
+                return Ok(summary);
+            }
+        }
+        for event in &events {
+            summary.delivered += 1;
+            if !on_event(Followed::Event(event)) {
+                return Ok(summary);
+            }
+        }
+        if !replay {
+            thread::sleep(interval);
+        }
+    }
+}
+
+/// Set when the process receives SIGINT, once [`stop_on_interrupt`] ran
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+extern "C" fn on_interrupt(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::Relaxed);
+}
+
+/// Turn SIGINT into a flag instead of exiting, so followers can stop
+/// cleanly; elsewhere Ctrl-C still ends the process
+pub fn stop_on_interrupt() -> &'static AtomicBool {
+    #[cfg(unix)]
+    // SAFETY: the handler only stores to an atomic, which is signal safe
+    unsafe {
+        libc::signal(
+            libc::SIGINT,
+            on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+    &INTERRUPTED
+}
+
+/// Run a shell hook for an event, passing the event JSON on stdin
+///
+/// The event kind, issue, and id are also exported as `RALPH_EVENT_KIND`,
+/// `RALPH_EVENT_ISSUE`, and `RALPH_EVENT_ID`. Returns whether the hook
+/// exited successfully.
+pub fn run_event_hook(command: &str, event: &ActivityEvent) -> Result<bool, String> {
+    let payload = serde_json::to_string(event).map_err(|e| e.to_string())?;
+
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("RALPH_EVENT_KIND", event.kind.to_string())
+        .env("RALPH_EVENT_ISSUE", &event.issue)
+        .env("RALPH_EVENT_ID", event.id.to_string())
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run hook: {}", e))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook that ignores stdin may close it early; that's not an error
+        let _ = stdin.write_all(payload.as_bytes());
+    }
+
+    child
+        .wait()
+        .map(|status| status.success())
+        .map_err(|e| format!("Failed to wait for hook: {}", e))
+}
+
+/// One-line text rendering of an event
+pub fn format_event(event: &ActivityEvent) -> String {
+    let worker = match (&event.worker, &event.run) {
+        (Some(w), Some(r)) => format!(" ({}, {})", w, r),
+        (Some(w), None) => format!(" ({})", w),
+        (None, Some(r)) => format!(" ({})", r),
+        (None, None) => String::new(),
+    };
+    format!(
+        "{} #{} [{}] {}{}: {}",
+        format_timestamp(event.timestamp),
+        event.id,
+        event.kind,
+        event.issue,
+        worker,
+        event.message
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_event_kind_from_str() {
+        assert_eq!(
+            "progress".parse::<EventKind>().unwrap(),
+            EventKind::Progress
+        );
+        assert_eq!("CLAIM".parse::<EventKind>().unwrap(), EventKind::Claimed);
+        assert_eq!("done".parse::<EventKind>().unwrap(), EventKind::Completed);
+        let err = "bogus".parse::<EventKind>().unwrap_err();
+        assert!(err.contains("expected one of"));
+    }
+
+    #[test]
+    fn test_event_kind_roundtrip() {
+        for kind in EventKind::ALL {
+            assert_eq!(kind.to_string().parse::<EventKind>().unwrap(), kind);
+        }
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(format_timestamp(1_768_237_529), "2026-01-12T17:05:29Z");
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse_timestamp("2000-02-29T00:00:00Z"), Some(951_782_400));
+        assert_eq!(
+            parse_timestamp("2026-01-12T11:05:29.503273-06:00"),
+            Some(1_768_237_529)
+        );
+        assert_eq!(
+            parse_timestamp(&format_timestamp(1_768_237_529)),
+            Some(1_768_237_529)
+        );
+        assert_eq!(parse_timestamp("yesterday"), None);
+        assert_eq!(parse_timestamp("2026-13-01T00:00:00Z"), None);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90"), Ok(90));
+        assert_eq!(parse_duration("90s"), Ok(90));
+        assert_eq!(parse_duration("30m"), Ok(1_800));
+        assert_eq!(parse_duration("24h"), Ok(86_400));
+        assert_eq!(parse_duration("7d"), Ok(604_800));
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("5y").is_err());
+        assert!(parse_duration("99999999999999999w")
+            .unwrap_err()
+            .contains("too large"));
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(42), "42s");
+        assert_eq!(format_duration(125), "2m05s");
+        assert_eq!(format_duration(7_500), "2h05m");
+    }
+
+    #[test]
+    fn test_emit_assigns_sequential_ids() {
+        let dir = TempDir::new().unwrap();
+        let feed = dir.path().join("nested/activity.jsonl");
+
+        let first = emit_event(&feed, "bd-1", EventKind::Claimed, "start", None, None).unwrap();
+        let second = emit_event(
+            &feed,
+            "bd-1",
+            EventKind::Progress,
+            "halfway",
+            Some("worker-a".to_string()),
+            Some(json!({"percent": 50})),
+        )
+        .unwrap();
+
+        assert_eq!(first.id, 1);
+        assert_eq!(second.id, 2);
+
+        let events = read_events(&feed).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].worker.as_deref(), Some("worker-a"));
+        assert_eq!(events[1].data, Some(json!({"percent": 50})));
+    }
+
+    #[test]
+    fn test_concurrent_emits_get_distinct_ids() {
+        let dir = TempDir::new().unwrap();
+        let feed = dir.path().join("activity.jsonl");
+        emit_event(&feed, "bd-1", EventKind::Claimed, "start", None, None).unwrap();
+
+        let handles: Vec<_> = (0..16)
+            .map(|i| {
+                let feed = feed.clone();
+                thread::spawn(move || {
+                    let message = format!("step {}", i);
+                    emit_event(&feed, "bd-1", EventKind::Progress, &message, None, None)
+                        .unwrap()
+                        .id
+                })
+            })
+            .collect();
+        let mut ids: Vec<u64> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        ids.sort_unstable();
+
+        assert_eq!(ids, (2..=17).collect::<Vec<_>>());
+        assert_eq!(last_id(&feed).unwrap(), 17);
+    }
+
+    #[test]
+    fn test_emit_rejects_non_object_data() {
+        let dir = TempDir::new().unwrap();
+        let feed = dir.path().join("activity.jsonl");
+
+        let result = emit_event(
+            &feed,
+            "bd-1",
+            EventKind::Progress,
+            "x",
+            None,
+            Some(json!([1])),
+        );
+        assert!(result.is_err());
+        assert!(!feed.exists());
+    }
+
+    #[test]
+    fn test_read_skips_malformed_lines() {
+        let dir = TempDir::new().unwrap();
+        let feed = dir.path().join("activity.jsonl");
+        emit_event(&feed, "bd-1", EventKind::Progress, "ok", None, None).unwrap();
+        fs::write(
+            &feed,
+            format!("{}{{\"truncated\n", fs::read_to_string(&feed).unwrap()),
+        )
+        .unwrap();
+
+        assert_eq!(read_events(&feed).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let event = ActivityEvent {
+            id: 1,
+            timestamp: 0,
+            issue: "bd-1".to_string(),
+            kind: EventKind::Failed,
+            message: "boom".to_string(),
+            worker: None,
+            run: None,
+            data: None,
+        };
+
+        assert!(EventFilter::default().matches(&event));
+        assert!(EventFilter {
+            issue: Some("bd-1".to_string()),
+            kind: Some(EventKind::Failed),
+            ..Default::default()
+        }
+        .matches(&event));
+        assert!(!EventFilter {
+            issue: Some("bd-2".to_string()),
+            ..Default::default()
+        }
+        .matches(&event));
+    }
+
+    #[test]
+    fn test_filter_worker_run_and_pattern() {
+        let event = ActivityEvent {
+            id: 1,
+            timestamp: 0,
+            issue: "bd-1".to_string(),
+            kind: EventKind::Ci,
+            message: "pipeline finished".to_string(),
+            worker: Some("worker-a".to_string()),
+            run: Some("run-1".to_string()),
+            data: Some(json!({"conclusion": "failure"})),
+        };
+
+        let by_worker = |w: &str| EventFilter {
+            worker: Some(w.to_string()),
+            ..Default::default()
+        };
+        assert!(by_worker("worker-a").matches(&event));
+        assert!(!by_worker("worker-b").matches(&event));
+
+        let by_run = |r: &str| EventFilter {
+            run: Some(r.to_string()),
+            ..Default::default()
+        };
+        assert!(by_run("run-1").matches(&event));
+        assert!(!by_run("run-2").matches(&event));
+
+        let by_pattern = |p: &str| EventFilter {
+            pattern: Some(Regex::new(p).unwrap()),
+            ..Default::default()
+        };
+        assert!(by_pattern("pipeline").matches(&event));
+        assert!(by_pattern("conclusion.*failure").matches(&event));
+        assert!(!by_pattern("^success$").matches(&event));
+    }
+
+    #[test]
+    fn test_feed_tail_reads_only_new_complete_lines() {
+        let dir = TempDir::new().unwrap();
+        let feed = dir.path().join("activity.jsonl");
+        emit_event(&feed, "bd-1", EventKind::Claimed, "old", None, None).unwrap();
+
+        let mut tail = FeedTail::at_end(&feed);
+        assert!(tail.poll(&feed).unwrap().is_empty());
+
+        emit_event(&feed, "bd-1", EventKind::Progress, "new", None, None).unwrap();
+        let line = serde_json::to_string(&ActivityEvent {
+            id: 3,
+            timestamp: 0,
+            issue: "bd-1".to_string(),
+            kind: EventKind::Completed,
+            message: "done".to_string(),
+            worker: None,
+            run: None,
+            data: None,
+        })
+        .unwrap();
+        let (head, rest) = line.split_at(10);
+        let mut file = OpenOptions::new().append(true).open(&feed).unwrap();
+        write!(file, "{}", head).unwrap();
+
+        let events = tail.poll(&feed).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].message, "new");
+
+        writeln!(file, "{}", rest).unwrap();
+        let events = tail.poll(&feed).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].message, "done");
+    }
+
+    #[test]
+    fn test_feed_tail_restarts_after_truncation() {
+        let dir = TempDir::new().unwrap();
+        let feed = dir.path().join("activity.jsonl");
+        emit_event(&feed, "bd-1", EventKind::Claimed, "a", None, None).unwrap();
+        emit_event(&feed, "bd-1", EventKind::Progress, "b", None, None).unwrap();
+
+        let mut tail = FeedTail::at_end(&feed);
+        fs::write(&feed, "").unwrap();
+        emit_event(&feed, "bd-2", EventKind::Claimed, "c", None, None).unwrap();
+
+        let events = tail.poll(&feed).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].issue, "bd-2");
+    }
+
+    #[test]
+    fn test_follow_resumes_after_rotation_without_replay() {
+        let dir = TempDir::new().unwrap();
+        let feed = dir.path().join("activity.jsonl");
+        for message in ["a", "b", "c"] {
+            emit_event(&feed, "bd-1", EventKind::Progress, message, None, None).unwrap();
+        }
+
+        let mut seen = Vec::new();
+        let summary = follow_events(
+            &feed,
+            &EventFilter::default(),
+            true,
+            Duration::from_millis(1),
+            2,
+            &AtomicBool::new(false),
+            |followed| {
+                let Followed::Event(event) = followed else {
+                    panic!("nothing should be skipped");
+                };
+                seen.push(event.id);
+                if event.id == 3 {
+                    // Prune to the newest event, then publish one more
+                    let kept = serde_json::to_string(event).unwrap();
+                    fs::write(&feed, kept + "\n").unwrap();
+                    emit_event(&feed, "bd-1", EventKind::Progress, "d", None, None).unwrap();
+                }
+                event.id < 4
+            },
+        )
+        .unwrap();
+
+        assert_eq!(seen, vec![1, 2, 3, 4]);
+        assert_eq!(summary.delivered, 4);
+        assert!(!summary.interrupted);
+    }
+
+    #[test]
+    fn test_follow_skips_with_a_notice_when_far_behind() {
+        let dir = TempDir::new().unwrap();
+        let feed = dir.path().join("activity.jsonl");
+        emit_event(&feed, "bd-1", EventKind::Claimed, "start", None, None).unwrap();
+        let long = "x".repeat(1000);
+
+        let mut seen = Vec::new();
+        let summary = follow_events(
+            &feed,
+            &EventFilter::default(),
+            true,
+            Duration::from_millis(1),
+            2,
+            &AtomicBool::new(false),
+            |followed| match followed {
+                Followed::Event(event) => {
+                    seen.push(event.id.to_string());
+                    if event.id == 1 {
+                        // Far more than two events' worth of bytes at once
+                        for _ in 0..20 {
+                            emit_event(&feed, "bd-1", EventKind::Progress, &long, None, None)
+                                .unwrap();
+                        }
+                    }
+                    event.id < 21
+                }
+                Followed::Skipped(notice) => {
+                    seen.push(format!("skipped {}", notice.skipped));
+                    assert_eq!(notice.resume_at, Some(20));
+                    true
+                }
+            },
+        )
+        .unwrap();
+
+        assert_eq!(seen, vec!["1", "skipped 18", "20", "21"]);
+        assert_eq!((summary.delivered, summary.skipped), (3, 18));
+    }
+
+    #[test]
+    fn test_follow_restarts_with_ids_and_stops_on_interrupt() {
+        let dir = TempDir::new().unwrap();
+        let feed = dir.path().join("activity.jsonl");
+        for message in ["a", "b", "c"] {
+            emit_event(&feed, "bd-1", EventKind::Progress, message, None, None).unwrap();
+        }
+        let stop = AtomicBool::new(false);
+
+        let mut seen = Vec::new();
+        let summary = follow_events(
+            &feed,
+            &EventFilter::default(),
+            true,
+            Duration::from_millis(1),
+            10,
+            &stop,
+            |followed| {
+                let Followed::Event(event) = followed else {
+                    return true;
+                };
+                seen.push(event.message.clone());
+                if event.id == 3 {
+                    // A fresh feed whose ids start over
+                    fs::remove_file(&feed).unwrap();
+                    fs::remove_file(sidecar(&feed, "seq")).unwrap();
+                    emit_event(&feed, "bd-2", EventKind::Claimed, "fresh", None, None).unwrap();
+                }
+                if event.message == "fresh" {
+                    stop.store(true, Ordering::Relaxed);
+                }
+                true
+            },
+        )
+        .unwrap();
+
+        assert_eq!(seen, vec!["a", "b", "c", "fresh"]);
+        assert!(summary.interrupted);
+        assert_eq!(summary.last_id, Some(1));
+    }
+
+    #[test]
+    fn test_trim_backlog_keeps_newest() {
+        let mut events: Vec<ActivityEvent> = (1..=5)
+            .map(|id| ActivityEvent {
+                id,
+                timestamp: id,
+                issue: "bd-1".to_string(),
+                kind: EventKind::Progress,
+                message: String::new(),
+                worker: None,
+                run: None,
+                data: None,
+            })
+            .collect();
+
+        assert_eq!(trim_backlog(&mut events, 2), 3);
+        assert_eq!(events.iter().map(|e| e.id).collect::<Vec<_>>(), vec![4, 5]);
+        assert_eq!(trim_backlog(&mut events, 10), 0);
+    }
+
+    #[test]
+    fn test_run_event_hook_receives_payload() {
+        let dir = TempDir::new().unwrap();
+        let out = dir.path().join("hook.out");
+        let event = ActivityEvent {
+            id: 7,
+            timestamp: 0,
+            issue: "bd-9".to_string(),
+            kind: EventKind::Failed,
+            message: "boom".to_string(),
+            worker: None,
+            run: None,
+            data: None,
+        };
+
+        let command = format!("cat > {} && [ \"$RALPH_EVENT_ID\" = 7 ]", out.display());
+        assert!(run_event_hook(&command, &event).unwrap());
+
+        let written: ActivityEvent =
+            serde_json::from_str(&fs::read_to_string(&out).unwrap()).unwrap();
+        assert_eq!(written, event);
+        assert!(!run_event_hook("exit 3", &event).unwrap());
+    }
+
+    #[test]
+    fn test_paginate_walks_the_feed_once() {
+        let events: Vec<ActivityEvent> = [5, 1, 3, 4, 2]
+            .iter()
+            .map(|&id| ActivityEvent {
+                id,
+                timestamp: id,
+                issue: "bd-1".to_string(),
+                kind: EventKind::Progress,
+                message: String::new(),
+                worker: None,
+                run: None,
+                data: None,
+            })
+            .collect();
+
+        let first = paginate(events.clone(), None, 2);
+        let cursor = parse_cursor(first.next_cursor.as_deref().unwrap()).unwrap();
+        let second = paginate(events.clone(), Some(cursor), 2);
+        let last = paginate(events, Some(4), 2);
+
+        let ids = |page: &EventPage| page.events.iter().map(|e| e.id).collect::<Vec<_>>();
+        assert_eq!(ids(&first), vec![1, 2]);
+        assert_eq!(ids(&second), vec![3, 4]);
+        assert_eq!(second.next_cursor.as_deref(), Some("4"));
+        assert_eq!(ids(&last), vec![5]);
+        assert_eq!(last.next_cursor, None);
+        assert!(parse_cursor("abc").is_err());
+    }
+}
@@ -0,0 +1,187 @@
+//! bd version detection and feature compatibility
+//!
+//! bd's flags and subcommands have changed across releases. Rather than
+//! failing with a parse error on an older bd, callers ask whether a
+//! [`BdFeature`] is available and pick the invocation that version
+//! understands. The installed version is detected once per process from
+//! `bd --version`; an unrecognized version string is treated as current.
+
+use once_cell::sync::{Lazy, OnceCell};
+use regex::Regex;
+use serde::Serialize;
+use std::fmt;
+use std::path::Path;
+
+use crate::beads::{run_bd, BdError};
+
+/// A bd release number
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub struct BdVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl BdVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        BdVersion {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Whether this release supports `feature`
+    pub fn supports(&self, feature: BdFeature) -> bool {
+        *self >= feature.since()
+    }
+}
+
+impl fmt::Display for BdVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+static VERSION_NUMBER: Lazy<Regex> = Lazy::new(|| Regex::new(r"(\d+)\.(\d+)(?:\.(\d+))?").unwrap());
+
+/// Extract the version from `bd --version` output (e.g. "bd version 0.30.2 (dev)")
+pub fn parse_version(output: &str) -> Option<BdVersion> {
+    let caps = VERSION_NUMBER.captures(output)?;
+    let part = |i: usize| caps.get(i).map_or(Some(0), |m| m.as_str().parse().ok());
+    Some(BdVersion::new(part(1)?, part(2)?, part(3)?))
+}
+
+/// bd capabilities that differ between releases
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BdFeature {
+    /// `bd dep add --type=<type>`
+    DepType,
+    /// `bd update --body-file -` reading the description from stdin
+    BodyFile,
+    /// Molecules (`bd mol ...`), required by the plugin
+    Molecules,
+    /// `bd state` / `bd set-state` dimensions
+    StateDimensions,
+}
+
+impl BdFeature {
+    pub const ALL: [BdFeature; 4] = [
+        BdFeature::DepType,
+        BdFeature::BodyFile,
+        BdFeature::Molecules,
+        BdFeature::StateDimensions,
+    ];
+
+    /// First bd release with this feature
+    ///
+    /// No bd changelog entry backs these thresholds yet; they are
+    /// unverified estimates. An unrecognized version is treated as current,
+    /// and `check_bd` still probes `bd mol` when the version is unknown.
+    /// Replace each with the release that introduced the feature once it is
+    /// confirmed against the beads release notes.
+    pub fn since(&self) -> BdVersion {
+        match self {
+            // Unverified: `dep add --type`
+            BdFeature::DepType => BdVersion::new(0, 9, 0),
+            // Unverified: `update --body-file -`
+            BdFeature::BodyFile => BdVersion::new(0, 21, 0),
+            // Unverified: `bd mol`
+            BdFeature::Molecules => BdVersion::new(0, 25, 0),
+            // Unverified: `bd state` / `bd set-state`
+            BdFeature::StateDimensions => BdVersion::new(0, 28, 0),
+        }
+    }
+}
+
+impl fmt::Display for BdFeature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            BdFeature::DepType => "dep-type",
+            BdFeature::BodyFile => "body-file",
+            BdFeature::Molecules => "molecules",
+            BdFeature::StateDimensions => "state-dimensions",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+static DETECTED: OnceCell<Option<BdVersion>> = OnceCell::new();
+
+/// The installed bd version, detected on first use and cached for the process
+///
+/// `None` when bd is missing or reports a version we can't parse.
+pub fn detected_version(dir: &Path) -> Option<BdVersion> {
+    *DETECTED.get_or_init(|| {
+        let version = run_bd(dir, &["--version"])
+            .ok()
+            .and_then(|out| parse_version(&out));
+        tracing::debug!(version = ?version.map(|v| v.to_string()), "detected bd version");
+        version
+    })
+}
+
+/// Whether the installed bd supports `feature` (assumed when the version is unknown)
+pub fn supports(dir: &Path, feature: BdFeature) -> bool {
+    detected_version(dir).is_none_or(|v| v.supports(feature))
+}
+
+/// Error unless the installed bd supports `feature`
+pub fn require(dir: &Path, feature: BdFeature) -> Result<(), BdError> {
+    match detected_version(dir) {
+        Some(version) => require_version(version, feature),
+        None => Ok(()),
+    }
+}
+
+/// Error unless bd `version` supports `feature`
+pub fn require_version(version: BdVersion, feature: BdFeature) -> Result<(), BdError> {
+    if version.supports(feature) {
+        return Ok(());
+    }
+    Err(BdError::BdUnavailable {
+        reason: format!(
+            "bd {} does not support {} (needs bd >= {})",
+            version,
+            feature,
+            feature.since()
+        ),
+        hint: "Upgrade the beads CLI".to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(
+            parse_version("bd version 0.30.2 (dev)"),
+            Some(BdVersion::new(0, 30, 2))
+        );
+        assert_eq!(parse_version("v1.2"), Some(BdVersion::new(1, 2, 0)));
+        assert_eq!(parse_version("bd (unknown)"), None);
+    }
+
+    #[test]
+    fn test_feature_matrix() {
+        let old = BdVersion::new(0, 20, 5);
+        assert!(old.supports(BdFeature::DepType));
+        assert!(!old.supports(BdFeature::BodyFile));
+        assert!(!old.supports(BdFeature::StateDimensions));
+        assert!(require_version(old, BdFeature::Molecules)
+            .unwrap_err()
+            .is_unavailable());
+
+        let current = BdVersion::new(1, 0, 0);
+        assert!(BdFeature::ALL.iter().all(|f| current.supports(*f)));
+    }
+
+    #[test]
+    fn test_version_ordering() {
+        assert!(BdVersion::new(0, 10, 0) > BdVersion::new(0, 9, 12));
+        assert_eq!(BdVersion::new(0, 28, 1).to_string(), "0.28.1");
+    }
+}
@@ -0,0 +1,770 @@
+//! Beads (`bd`) client for Ralph-Beads
+//!
+//! Thin wrapper over the `bd` CLI mirroring the plugin's TypeScript
+//! `BeadsClient`: every call shells out with `--json` and parses the
+//! result into typed structs.
+
+use once_cell::sync::{Lazy, OnceCell};
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+use std::io::{ErrorKind, Read, Write};
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::bd_version::{self, BdFeature};
+use crate::fixtures::{self, Fixture};
+use crate::limits::{self, LimitError};
+
+/// A dependency edge as reported by `bd ... --json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dependency {
+    #[serde(default)]
+    pub issue_id: String,
+    pub depends_on_id: String,
+    #[serde(rename = "type", default = "default_dep_type")]
+    pub dep_type: String,
+}
+
+fn default_dep_type() -> String {
+    "blocks".to_string()
+}
+
+/// Dependencies appear either as full edge objects or as bare IDs
+/// depending on the bd command and version
+fn deserialize_dependencies<'de, D>(deserializer: D) -> Result<Vec<Dependency>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum DepRepr {
+        Edge(Dependency),
+        Id(String),
+    }
+
+    let raw: Option<Vec<DepRepr>> = Option::deserialize(deserializer)?;
+    Ok(raw
+        .unwrap_or_default()
+        .into_iter()
+        .map(|d| match d {
+            DepRepr::Edge(edge) => edge,
+            DepRepr::Id(id) => Dependency {
+                issue_id: String::new(),
+                depends_on_id: id,
+                dep_type: default_dep_type(),
+            },
+        })
+        .collect())
+}
+
+/// An issue (bead) as reported by `bd show/list --json`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Issue {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub acceptance_criteria: String,
+    pub design: String,
+    pub notes: String,
+    pub status: String,
+    pub priority: Option<u8>,
+    pub issue_type: String,
+    pub assignee: Option<String>,
+    pub labels: Vec<String>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+    pub closed_at: Option<String>,
+    #[serde(deserialize_with = "deserialize_dependencies")]
+    pub dependencies: Vec<Dependency>,
+}
+
+impl Issue {
+    /// Whether the issue is closed
+    pub fn is_closed(&self) -> bool {
+        self.status == "closed"
+    }
+
+    /// IDs of issues this one is blocked by
+    pub fn blockers(&self) -> impl Iterator<Item = &str> {
+        self.dependencies
+            .iter()
+            .filter(|d| d.dep_type == "blocks")
+            .map(|d| d.depends_on_id.as_str())
+    }
+
+    /// ID of the parent issue, if any
+    pub fn parent(&self) -> Option<&str> {
+        self.dependencies
+            .iter()
+            .find(|d| d.dep_type == "parent-child")
+            .map(|d| d.depends_on_id.as_str())
+    }
+}
+
+/// Task fixture for tests: `id` with the given status, blocked by `blockers`
+#[cfg(test)]
+pub(crate) fn test_issue(id: &str, status: &str, blockers: &[&str]) -> Issue {
+    Issue {
+        id: id.to_string(),
+        title: format!("Title of {}", id),
+        status: status.to_string(),
+        issue_type: "task".to_string(),
+        dependencies: blockers
+            .iter()
+            .map(|b| Dependency {
+                issue_id: id.to_string(),
+                depends_on_id: b.to_string(),
+                dep_type: "blocks".to_string(),
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// A comment on an issue as reported by `bd comments --json`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Comment {
+    pub author: String,
+    pub text: String,
+    pub created_at: Option<String>,
+}
+
+/// Filters accepted by `bd list`
+#[derive(Debug, Clone, Default)]
+pub struct ListFilter {
+    pub parent: Option<String>,
+    pub issue_type: Option<String>,
+    pub status: Option<String>,
+    pub label: Option<String>,
+}
+
+/// The `[bd]` configuration section: limits for every `bd` call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BdConfig {
+    /// Seconds before a `bd` call is killed
+    pub timeout_secs: u64,
+    /// Extra attempts after a transient failure (lock contention, daemon restart)
+    pub retries: u32,
+    /// Delay before the first retry, doubled for each further retry
+    pub backoff_ms: u64,
+}
+
+impl Default for BdConfig {
+    fn default() -> Self {
+        BdConfig {
+            timeout_secs: 30,
+            retries: 2,
+            backoff_ms: 200,
+        }
+    }
+}
+
+impl BdConfig {
+    /// Reject a zero timeout
+    pub fn validate(&self) -> Result<(), String> {
+        if self.timeout_secs == 0 {
+            return Err("bd.timeout_secs must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    /// Delay before retry number `attempt` (1-based)
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        Duration::from_millis(
+            self.backoff_ms
+                .saturating_mul(1u64 << (attempt.saturating_sub(1)).min(16)),
+        )
+    }
+}
+
+static BD_CONFIG: OnceCell<BdConfig> = OnceCell::new();
+
+/// Set the limits used by every `bd` call in this process (first call wins)
+pub fn configure(config: BdConfig) {
+    let _ = BD_CONFIG.set(config);
+}
+
+fn bd_config() -> BdConfig {
+    BD_CONFIG.get().copied().unwrap_or_default()
+}
+
+/// bd stderr messages that indicate a failure worth retrying
+static TRANSIENT_ERROR: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)database is locked|\block(ed)?\b|resource temporarily unavailable|daemon (is )?(not running|restarting|unavailable)|connection (refused|reset)",
+    )
+    .unwrap()
+});
+
+/// Whether a bd error message describes a transient condition
+pub fn is_transient_error(stderr: &str) -> bool {
+    TRANSIENT_ERROR.is_match(stderr)
+}
+
+/// Run `command` to completion, killing it after `timeout`
+///
+/// Returns `Ok(None)` when the timeout expired.
+pub fn run_with_timeout(
+    command: &mut Command,
+    input: Option<&str>,
+    timeout: Duration,
+) -> std::io::Result<Option<Output>> {
+    let mut child = command
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Feed and drain the pipes on threads so a chatty child can't block
+    let writer = child.stdin.take().map(|mut stdin| {
+        let input = input.unwrap_or_default().to_string();
+        thread::spawn(move || {
+            let _ = stdin.write_all(input.as_bytes());
+        })
+    });
+    let drain = |pipe: Option<Box<dyn Read + Send>>| {
+        thread::spawn(move || {
+            let mut buf = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut buf);
+            }
+            buf
+        })
+    };
+    let stdout = drain(child.stdout.take().map(|p| Box::new(p) as _));
+    let stderr = drain(child.stderr.take().map(|p| Box::new(p) as _));
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            break None;
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    // After a timeout, grandchildren may still hold the pipes open; leave
+    // the reader threads behind rather than block on them
+    let Some(status) = status else {
+        return Ok(None);
+    };
+    if let Some(writer) = writer {
+        let _ = writer.join();
+    }
+    Ok(Some(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    }))
+}
+
+/// bd stderr messages that mean the daemon can't be reached at all
+static DAEMON_UNREACHABLE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)daemon (is )?(not running|unavailable)|connection refused|\.sock: no such file",
+    )
+    .unwrap()
+});
+
+const INSTALL_HINT: &str =
+    "Install the beads CLI and make sure `bd` is on PATH; local commands (detect-complexity, detect-framework, calc-iterations, activity, metrics, cost) work without it";
+const DAEMON_HINT: &str =
+    "Check that the beads daemon is running, or retry with a longer --bd-timeout once it has restarted";
+
+/// Why a `bd` call failed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "error", rename_all = "snake_case")]
+pub enum BdError {
+    /// bd isn't installed or its daemon can't be reached
+    BdUnavailable { reason: String, hint: String },
+    /// The call exceeded the configured timeout
+    TimedOut { command: String, timeout_secs: u64 },
+    /// bd ran and reported an error
+    Failed { command: String, stderr: String },
+    /// bd succeeded but its output couldn't be parsed
+    InvalidOutput { command: String, reason: String },
+}
+
+impl BdError {
+    /// Whether bd itself is missing or unreachable (rather than the call failing)
+    pub fn is_unavailable(&self) -> bool {
+        matches!(self, BdError::BdUnavailable { .. })
+    }
+
+    fn invalid_output(args: &[&str], reason: String) -> Self {
+        BdError::InvalidOutput {
+            command: format!("bd {}", args.join(" ")),
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for BdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BdError::BdUnavailable { reason, hint } => {
+                write!(f, "bd is unavailable: {}\nHint: {}", reason, hint)
+            }
+            BdError::TimedOut {
+                command,
+                timeout_secs,
+            } => write!(f, "{} timed out after {}s", command, timeout_secs),
+            BdError::Failed { command, stderr } => write!(f, "{} failed: {}", command, stderr),
+            BdError::InvalidOutput { command, reason } => write!(f, "{}: {}", command, reason),
+        }
+    }
+}
+
+impl From<BdError> for String {
+    fn from(e: BdError) -> Self {
+        e.to_string()
+    }
+}
+
+/// Run `bd` with the configured timeout, retrying transient failures
+fn run_bd_attempts(dir: &Path, args: &[&str], input: Option<&str>) -> Result<String, BdError> {
+    let _span = tracing::debug_span!("bd", args = %args.join(" ")).entered();
+    let config = bd_config();
+    let timeout = Duration::from_secs(config.timeout_secs);
+    let command = format!("bd {}", args.join(" "));
+    let mut attempt = 0;
+
+    loop {
+        let output = match fixtures::replayed(args, input) {
+            Some(fixture) => fixture,
+            None => spawn_bd(dir, args, input, &command, timeout)?,
+        };
+
+        let stderr = output.stderr.as_str();
+        if output.success() {
+            tracing::debug!(bytes = output.stdout.len(), attempt, "bd succeeded");
+            return Ok(output.stdout);
+        }
+        if attempt < config.retries && is_transient_error(stderr) {
+            attempt += 1;
+            let delay = config.backoff(attempt);
+            tracing::info!(stderr = %stderr.trim(), attempt, ?delay, "retrying bd");
+            thread::sleep(delay);
+            continue;
+        }
+
+        tracing::debug!(status = ?output.status, stderr = %stderr.trim(), "bd failed");
+        if DAEMON_UNREACHABLE.is_match(stderr) {
+            return Err(BdError::BdUnavailable {
+                reason: stderr.trim().to_string(),
+                hint: DAEMON_HINT.to_string(),
+            });
+        }
+        return Err(BdError::Failed {
+            command,
+            stderr: stderr.trim().to_string(),
+        });
+    }
+}
+
+/// Spawn `bd` once under the subprocess limits, recording the call when
+/// fixtures are being recorded
+fn spawn_bd(
+    dir: &Path,
+    args: &[&str],
+    input: Option<&str>,
+    command: &str,
+    timeout: Duration,
+) -> Result<Fixture, BdError> {
+    let _permit = limits::acquire(command).map_err(|e| match e {
+        LimitError::Queued(message) => {
+            tracing::warn!("{}", message);
+            BdError::TimedOut {
+                command: format!("{} (queued under [limits])", command),
+                timeout_secs: limits::limiter().queue_timeout_secs(),
+            }
+        }
+        LimitError::Io(message) => BdError::Failed {
+            command: command.to_string(),
+            stderr: message,
+        },
+    })?;
+    let output = run_with_timeout(
+        Command::new("bd").args(args).current_dir(dir),
+        input,
+        timeout,
+    )
+    .map_err(|e| BdError::BdUnavailable {
+        reason: if e.kind() == ErrorKind::NotFound {
+            "bd not found on PATH".to_string()
+        } else {
+            format!("failed to run bd: {}", e)
+        },
+        hint: INSTALL_HINT.to_string(),
+    })?;
+    let Some(output) = output else {
+        tracing::debug!(timeout_secs = timeout.as_secs(), "bd timed out");
+        return Err(BdError::TimedOut {
+            command: command.to_string(),
+            timeout_secs: timeout.as_secs(),
+        });
+    };
+
+    let fixture = Fixture {
+        args: args.iter().map(|a| a.to_string()).collect(),
+        stdin: input.map(String::from),
+        status: output.status.code(),
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+    };
+    fixtures::record(&fixture);
+    Ok(fixture)
+}
+
+/// Run `bd` with `args` in `dir`, returning stdout on success
+pub fn run_bd(dir: &Path, args: &[&str]) -> Result<String, BdError> {
+    run_bd_attempts(dir, args, None)
+}
+
+/// Run `bd` with `args` in `dir`, feeding `input` on stdin
+pub fn run_bd_with_input(dir: &Path, args: &[&str], input: &str) -> Result<String, BdError> {
+    run_bd_attempts(dir, args, Some(input))
+}
+
+/// Availability of the installed bd, as reported by `info --check-bd`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BdStatus {
+    pub available: bool,
+    /// Output of `bd --version`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Whether bd supports molecules (`bd mol`), which the plugin requires
+    pub compatible: bool,
+    /// Features of the detected version (empty when the version is unknown)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub features: Vec<BdFeature>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<BdError>,
+}
+
+/// Probe the installed bd: version, features, and molecule support
+pub fn check_bd(dir: &Path) -> BdStatus {
+    let version = match run_bd(dir, &["--version"]) {
+        Ok(out) => out.trim().to_string(),
+        Err(e) => {
+            return BdStatus {
+                available: !e.is_unavailable(),
+                version: None,
+                compatible: false,
+                features: Vec::new(),
+                error: Some(e),
+            }
+        }
+    };
+    let parsed = bd_version::parse_version(&version);
+    let features = parsed
+        .map(|v| {
+            BdFeature::ALL
+                .into_iter()
+                .filter(|f| v.supports(*f))
+                .collect()
+        })
+        .unwrap_or_default();
+    // A recognized version answers from the feature matrix; otherwise ask bd
+    let molecules = match parsed {
+        Some(v) => bd_version::require_version(v, BdFeature::Molecules),
+        None => run_bd(dir, &["--no-daemon", "mol", "--help"]).map(|_| ()),
+    };
+    BdStatus {
+        available: true,
+        version: Some(version).filter(|v| !v.is_empty()),
+        compatible: molecules.is_ok(),
+        features,
+        error: molecules.err(),
+    }
+}
+
+/// Parse `bd list --json` output (a JSON array, or empty output)
+pub fn parse_issue_list(json: &str) -> Result<Vec<Issue>, String> {
+    if json.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_str(json).map_err(|e| format!("Failed to parse bd output: {}", e))
+}
+
+/// Parse `bd show --json` output, which is an object or a one-element array
+pub fn parse_issue(json: &str) -> Result<Issue, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(json).map_err(|e| format!("Failed to parse bd output: {}", e))?;
+    let value = match value {
+        serde_json::Value::Array(mut items) if !items.is_empty() => items.remove(0),
+        other => other,
+    };
+    serde_json::from_value(value).map_err(|e| format!("Failed to parse bd output: {}", e))
+}
+
+/// List issues matching `filter`
+pub fn list_issues(dir: &Path, filter: &ListFilter) -> Result<Vec<Issue>, BdError> {
+    let mut args = vec!["list".to_string()];
+    if let Some(ref parent) = filter.parent {
+        args.push(format!("--parent={}", parent));
+    }
+    if let Some(ref issue_type) = filter.issue_type {
+        args.push(format!("--type={}", issue_type));
+    }
+    if let Some(ref status) = filter.status {
+        args.push(format!("--status={}", status));
+    }
+    if let Some(ref label) = filter.label {
+        args.push(format!("--label={}", label));
+    }
+    args.push("--json".to_string());
+
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    parse_issue_list(&run_bd(dir, &args)?).map_err(|e| BdError::invalid_output(&args, e))
+}
+
+/// Fetch a single issue
+pub fn show_issue(dir: &Path, id: &str) -> Result<Issue, BdError> {
+    let args = ["show", id, "--json"];
+    parse_issue(&run_bd(dir, &args)?).map_err(|e| BdError::invalid_output(&args, e))
+}
+
+/// Fields for `bd create`
+#[derive(Debug, Clone, Default)]
+pub struct NewIssue {
+    pub title: String,
+    pub issue_type: String,
+    pub priority: Option<u8>,
+    pub parent: Option<String>,
+    pub description: Option<String>,
+    pub acceptance_criteria: Option<String>,
+}
+
+/// Create an issue, returning it as reported by bd
+///
+/// The description is written with a follow-up `bd update --body-file -`
+/// so multi-line text survives intact; acceptance criteria follow with
+/// `bd update --acceptance`.
+pub fn create_issue(dir: &Path, new: &NewIssue) -> Result<Issue, BdError> {
+    let mut args = vec!["create".to_string(), format!("--type={}", new.issue_type)];
+    if let Some(priority) = new.priority {
+        args.push(format!("--priority={}", priority));
+    }
+    if let Some(ref parent) = new.parent {
+        args.push(format!("--parent={}", parent));
+    }
+    args.push(format!("--title={}", new.title));
+    args.push("--json".to_string());
+
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let mut issue =
+        parse_issue(&run_bd(dir, &args)?).map_err(|e| BdError::invalid_output(&args, e))?;
+
+    if let Some(ref description) = new.description {
+        if bd_version::supports(dir, BdFeature::BodyFile) {
+            run_bd_with_input(dir, &["update", &issue.id, "--body-file", "-"], description)?;
+        } else {
+            let description_arg = format!("--description={}", description);
+            run_bd(dir, &["update", &issue.id, &description_arg])?;
+        }
+        issue.description = description.clone();
+    }
+    if let Some(ref criteria) = new.acceptance_criteria {
+        update_acceptance(dir, &issue.id, criteria)?;
+        issue.acceptance_criteria = criteria.clone();
+    }
+    Ok(issue)
+}
+
+/// Replace the acceptance criteria of `id`
+pub fn update_acceptance(dir: &Path, id: &str, criteria: &str) -> Result<(), BdError> {
+    let criteria_arg = format!("--acceptance={}", criteria);
+    run_bd(dir, &["update", id, &criteria_arg])?;
+    Ok(())
+}
+
+/// Delete an issue outright
+pub fn delete_issue(dir: &Path, id: &str) -> Result<(), BdError> {
+    run_bd(dir, &["delete", id, "--force"])?;
+    Ok(())
+}
+
+/// Record that `from` depends on `to`
+pub fn add_dependency(dir: &Path, from: &str, to: &str, dep_type: &str) -> Result<(), BdError> {
+    // Releases before typed dependencies only create blocking edges
+    if dep_type == "blocks" && !bd_version::supports(dir, BdFeature::DepType) {
+        run_bd(dir, &["dep", "add", from, to])?;
+        return Ok(());
+    }
+    bd_version::require(dir, BdFeature::DepType)?;
+    let dep_type = format!("--type={}", dep_type);
+    run_bd(dir, &["dep", "add", from, to, &dep_type])?;
+    Ok(())
+}
+
+/// Set the status of `id` (e.g. `in_progress`)
+pub fn update_status(dir: &Path, id: &str, status: &str) -> Result<(), BdError> {
+    let status = format!("--status={}", status);
+    run_bd(dir, &["update", id, &status])?;
+    Ok(())
+}
+
+/// Add a comment to `id`
+pub fn add_comment(dir: &Path, id: &str, text: &str) -> Result<(), BdError> {
+    run_bd(dir, &["comments", "add", id, text])?;
+    Ok(())
+}
+
+/// Comments on `id`, oldest first
+pub fn list_comments(dir: &Path, id: &str) -> Result<Vec<Comment>, BdError> {
+    let args = ["comments", id, "--json"];
+    parse_comments(&run_bd(dir, &args)?).map_err(|e| BdError::invalid_output(&args, e))
+}
+
+/// Parse `bd comments --json` output (`null` or empty when there are none)
+pub fn parse_comments(json: &str) -> Result<Vec<Comment>, String> {
+    if json.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let comments: Option<Vec<Comment>> =
+        serde_json::from_str(json).map_err(|e| format!("Failed to parse bd output: {}", e))?;
+    Ok(comments.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_issue_with_edge_dependencies() {
+        let json = r#"{"id":"rb-0p0","title":"Write tests","status":"in_progress",
+            "priority":2,"issue_type":"task","labels":["testing"],
+            "dependencies":[
+                {"issue_id":"rb-0p0","depends_on_id":"rb-zqa","type":"blocks"},
+                {"issue_id":"rb-0p0","depends_on_id":"rb-epic","type":"parent-child"}
+            ]}"#;
+
+        let issue = parse_issue(json).unwrap();
+
+        assert_eq!(issue.id, "rb-0p0");
+        assert_eq!(issue.priority, Some(2));
+        assert_eq!(issue.blockers().collect::<Vec<_>>(), vec!["rb-zqa"]);
+        assert_eq!(issue.parent(), Some("rb-epic"));
+        assert!(!issue.is_closed());
+    }
+
+    #[test]
+    fn test_parse_issue_with_id_dependencies() {
+        let json = r#"[{"id":"rb-1","title":"t","status":"open","dependencies":["rb-2"]}]"#;
+
+        let issue = parse_issue(json).unwrap();
+
+        assert_eq!(issue.blockers().collect::<Vec<_>>(), vec!["rb-2"]);
+        assert_eq!(issue.description, "");
+    }
+
+    #[test]
+    fn test_parse_issue_list() {
+        let json = r#"[{"id":"a","title":"A"},{"id":"b","title":"B","dependencies":null}]"#;
+
+        let issues = parse_issue_list(json).unwrap();
+
+        assert_eq!(issues.len(), 2);
+        assert!(issues[1].dependencies.is_empty());
+        assert!(parse_issue_list("  \n").unwrap().is_empty());
+        assert!(parse_issue_list("not json").is_err());
+    }
+
+    #[test]
+    fn test_parse_comments() {
+        let json = r#"[{"id":1,"issue_id":"a","author":"worker-1",
+            "text":"Exposed parse_plan()","created_at":"2026-01-12T10:00:00Z"}]"#;
+
+        let comments = parse_comments(json).unwrap();
+
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].author, "worker-1");
+        assert_eq!(comments[0].text, "Exposed parse_plan()");
+        assert!(parse_comments("null").unwrap().is_empty());
+        assert!(parse_comments("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_is_transient_error() {
+        assert!(is_transient_error("Error: database is locked"));
+        assert!(is_transient_error("daemon not running, restarting"));
+        assert!(is_transient_error("dial unix: connection refused"));
+        assert!(!is_transient_error("Error: issue bd-9 not found"));
+        assert!(!is_transient_error("unknown flag: --blocked-by"));
+    }
+
+    #[test]
+    fn test_backoff_doubles() {
+        let config = BdConfig {
+            backoff_ms: 100,
+            ..Default::default()
+        };
+        assert_eq!(config.backoff(1), Duration::from_millis(100));
+        assert_eq!(config.backoff(3), Duration::from_millis(400));
+        assert!(BdConfig {
+            timeout_secs: 0,
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn test_run_with_timeout() {
+        let output = run_with_timeout(
+            Command::new("sh").args(["-c", "cat; echo err >&2"]),
+            Some("hello"),
+            Duration::from_secs(5),
+        )
+        .unwrap()
+        .unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"hello");
+        assert_eq!(output.stderr, b"err\n");
+
+        let started = Instant::now();
+        let timed_out = run_with_timeout(
+            Command::new("sh").args(["-c", "sleep 5"]),
+            None,
+            Duration::from_millis(100),
+        )
+        .unwrap();
+        assert!(timed_out.is_none());
+        assert!(started.elapsed() < Duration::from_secs(4));
+    }
+
+    #[test]
+    fn test_bd_error_display_and_json() {
+        let failed = BdError::Failed {
+            command: "bd show bd-9".to_string(),
+            stderr: "not found".to_string(),
+        };
+        assert_eq!(failed.to_string(), "bd show bd-9 failed: not found");
+        assert!(!failed.is_unavailable());
+
+        let unavailable = BdError::BdUnavailable {
+            reason: "bd not found on PATH".to_string(),
+            hint: INSTALL_HINT.to_string(),
+        };
+        assert!(unavailable.is_unavailable());
+        assert!(unavailable.to_string().contains("\nHint: Install"));
+        let json = serde_json::to_value(&unavailable).unwrap();
+        assert_eq!(json["error"], "bd_unavailable");
+        assert_eq!(json["reason"], "bd not found on PATH");
+    }
+}
@@ -0,0 +1,604 @@
+//! Typed state dimensions for beads issues
+//!
+//! bd stores free-form `dimension=value` pairs via `bd set-state`. This
+//! module layers a schema on top: each dimension has a fixed set of values
+//! and, optionally, a table of legal transitions between them. The built-in
+//! `mode` and `health` dimensions can be extended with user-defined ones
+//! from the `[state]` section of `.ralph-beads.toml`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::bd_version::{self, BdFeature};
+use crate::beads::run_bd;
+use crate::exit_code::ExitCode;
+use crate::state::WorkflowMode;
+
+/// A state dimension and its state machine
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Dimension {
+    /// Allowed values
+    pub values: Vec<String>,
+    /// Legal next values keyed by current value; when empty, any value may
+    /// follow any other
+    pub transitions: BTreeMap<String, Vec<String>>,
+    /// Values the dimension may start in; when empty, any value
+    pub initial: Vec<String>,
+}
+
+impl Dimension {
+    fn builtin(values: &[&str], transitions: &[(&str, &[&str])], initial: &[&str]) -> Self {
+        let owned = |items: &[&str]| items.iter().map(|s| s.to_string()).collect();
+        Dimension {
+            values: owned(values),
+            transitions: transitions
+                .iter()
+                .map(|(from, to)| (from.to_string(), owned(to)))
+                .collect(),
+            initial: owned(initial),
+        }
+    }
+
+    /// Whether `value` is one of the dimension's values
+    pub fn has_value(&self, value: &str) -> bool {
+        self.values.iter().any(|v| v == value)
+    }
+
+    /// Values reachable from `from` (or the initial values when unset)
+    pub fn next_values(&self, from: Option<&str>) -> Vec<String> {
+        match from {
+            None if !self.initial.is_empty() => self.initial.clone(),
+            Some(current) if !self.transitions.is_empty() => {
+                self.transitions.get(current).cloned().unwrap_or_default()
+            }
+            _ => self.values.clone(),
+        }
+    }
+
+    /// Check that the dimension's tables only mention known values
+    fn validate(&self, name: &str) -> Result<(), String> {
+        if self.values.is_empty() {
+            return Err(format!("State dimension '{}' has no values", name));
+        }
+        let referenced = self
+            .transitions
+            .iter()
+            .flat_map(|(from, to)| std::iter::once(from).chain(to))
+            .chain(&self.initial);
+        for value in referenced {
+            if !self.has_value(value) {
+                return Err(format!(
+                    "State dimension '{}' references unknown value '{}'",
+                    name, value
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The `[state]` configuration section
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StateConfig {
+    /// User-defined dimensions; a built-in name replaces the built-in
+    pub dimensions: BTreeMap<String, Dimension>,
+}
+
+impl StateConfig {
+    /// Reject dimensions whose transition tables are inconsistent
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, dimension) in &self.dimensions {
+            dimension.validate(name)?;
+        }
+        Ok(())
+    }
+}
+
+/// All known dimensions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StateSchema {
+    pub dimensions: BTreeMap<String, Dimension>,
+}
+
+/// The `mode` dimension, following the workflow mode transition table
+fn mode_dimension() -> Dimension {
+    let names = |modes: &[WorkflowMode]| modes.iter().map(|m| m.to_string()).collect();
+    Dimension {
+        values: names(&WorkflowMode::ALL),
+        transitions: WorkflowMode::ALL
+            .iter()
+            .map(|m| (m.to_string(), names(m.next_modes())))
+            .collect(),
+        initial: names(&WorkflowMode::INITIAL),
+    }
+}
+
+impl StateSchema {
+    /// Built-in `mode` and `health` dimensions
+    pub fn builtin() -> Self {
+        let mut dimensions = BTreeMap::new();
+        dimensions.insert("mode".to_string(), mode_dimension());
+        dimensions.insert(
+            "health".to_string(),
+            Dimension::builtin(&["healthy", "warning", "degraded", "critical"], &[], &[]),
+        );
+        StateSchema { dimensions }
+    }
+
+    /// Built-in dimensions extended (or overridden) by configuration
+    pub fn with_config(config: &StateConfig) -> Self {
+        let mut schema = Self::builtin();
+        schema.dimensions.extend(config.dimensions.clone());
+        schema
+    }
+
+    /// Look up a dimension by name
+    pub fn dimension(&self, name: &str) -> Result<&Dimension, String> {
+        self.dimensions.get(name).ok_or_else(|| {
+            let known: Vec<&str> = self.dimensions.keys().map(String::as_str).collect();
+            format!(
+                "Unknown state dimension '{}' (known dimensions: {})",
+                name,
+                known.join(", ")
+            )
+        })
+    }
+
+    /// Check that moving `name` from `from` to `to` is legal
+    pub fn validate_transition(
+        &self,
+        name: &str,
+        from: Option<&str>,
+        to: &str,
+    ) -> Result<(), String> {
+        let dimension = self.dimension(name)?;
+        if !dimension.has_value(to) {
+            return Err(format!(
+                "Invalid value '{}' for {} (valid values: {})",
+                to,
+                name,
+                dimension.values.join(", ")
+            ));
+        }
+        if from == Some(to) {
+            return Ok(());
+        }
+
+        let allowed = dimension.next_values(from);
+        if allowed.iter().any(|v| v == to) {
+            return Ok(());
+        }
+        let from = from.unwrap_or("(unset)");
+        if allowed.is_empty() {
+            Err(format!("{} cannot leave '{}'", name, from))
+        } else {
+            Err(format!(
+                "Illegal {} transition {} -> {} (allowed: {})",
+                name,
+                from,
+                to,
+                allowed.join(", ")
+            ))
+        }
+    }
+}
+
+/// Parse `bd state <id> <dimension>` output into the current value
+///
+/// bd prints the bare value, a `dimension: value` line, or a parenthesized
+/// note when the dimension was never set.
+pub fn parse_state_output(dimension: &str, output: &str) -> Option<String> {
+    let line = output.lines().map(str::trim).find(|l| !l.is_empty())?;
+    if line.starts_with('(') {
+        return None;
+    }
+    let value = [':', '=']
+        .iter()
+        .find_map(|sep| {
+            line.split_once(*sep)
+                .filter(|(key, _)| key.trim() == dimension)
+                .map(|(_, value)| value.trim())
+        })
+        .unwrap_or(line);
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// Current value of `dimension` on issue `id`
+pub fn get_state(dir: &Path, id: &str, dimension: &str) -> Result<Option<String>, String> {
+    bd_version::require(dir, BdFeature::StateDimensions)?;
+    let output = run_bd(dir, &["state", id, dimension])?;
+    Ok(parse_state_output(dimension, &output))
+}
+
+/// Validate and apply a transition, returning the previous value
+pub fn set_state(
+    dir: &Path,
+    schema: &StateSchema,
+    id: &str,
+    dimension: &str,
+    value: &str,
+    reason: Option<&str>,
+    force: bool,
+) -> Result<Option<String>, String> {
+    // Reject unknown dimensions and values before touching bd; `force`
+    // only skips the transition check
+    schema.validate_transition(dimension, Some(value), value)?;
+    let current = get_state(dir, id, dimension)?;
+    if !force {
+        schema.validate_transition(dimension, current.as_deref(), value)?;
+    }
+
+    let assignment = format!("{}={}", dimension, value);
+    let mut args = vec!["set-state".to_string(), id.to_string(), assignment];
+    if let Some(reason) = reason {
+        args.push(format!("--reason={}", reason));
+    }
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    run_bd(dir, &args)?;
+    Ok(current)
+}
+
+/// Outcome of setting a dimension on one issue in a bulk operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BulkResult {
+    pub id: String,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Set a dimension on each of `ids`, continuing past failures
+///
+/// Duplicate IDs are applied once.
+pub fn set_state_bulk(
+    dir: &Path,
+    schema: &StateSchema,
+    ids: &[String],
+    dimension: &str,
+    value: &str,
+    reason: Option<&str>,
+    force: bool,
+) -> Vec<BulkResult> {
+    let mut seen = std::collections::BTreeSet::new();
+    ids.iter()
+        .filter(|id| seen.insert(id.as_str()))
+        .map(
+            |id| match set_state(dir, schema, id, dimension, value, reason, force) {
+                Ok(from) => BulkResult {
+                    id: id.clone(),
+                    ok: true,
+                    from,
+                    error: None,
+                },
+                Err(e) => BulkResult {
+                    id: id.clone(),
+                    ok: false,
+                    from: None,
+                    error: Some(e),
+                },
+            },
+        )
+        .collect()
+}
+
+/// Exit status for a bulk operation: `Ok` when every issue succeeded,
+/// `Error` when all failed (or there was nothing to do), `Partial` otherwise
+pub fn bulk_exit_code(results: &[BulkResult]) -> ExitCode {
+    let failed = results.iter().filter(|r| !r.ok).count();
+    if results.is_empty() || failed == results.len() {
+        ExitCode::Error
+    } else if failed > 0 {
+        ExitCode::Partial
+    } else {
+        ExitCode::Ok
+    }
+}
+
+/// A change observed while watching a dimension
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StateChange {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    /// Whether this is the value seen on the first poll
+    pub initial: bool,
+    /// Unix time the change was observed
+    pub observed_at: u64,
+}
+
+/// How a watch ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchOutcome {
+    /// The target value was reached
+    Reached(String),
+    /// The timeout expired; holds the last observed value
+    TimedOut(Option<String>),
+}
+
+/// Poll a dimension until it equals `until` or `timeout` expires
+///
+/// `read` returns the current value; `on_change` sees the initial value
+/// (as a change from `None`) and every transition after it. Without
+/// `until` the watch runs until the timeout, or forever without one.
+pub fn watch_state<R, C>(
+    mut read: R,
+    until: Option<&str>,
+    timeout: Option<Duration>,
+    interval: Duration,
+    mut on_change: C,
+) -> Result<WatchOutcome, String>
+where
+    R: FnMut() -> Result<Option<String>, String>,
+    C: FnMut(&StateChange),
+{
+    let started = Instant::now();
+    let mut last: Option<Option<String>> = None;
+
+    loop {
+        let current = read()?;
+        if last.as_ref() != Some(&current) {
+            on_change(&StateChange {
+                from: last.clone().flatten(),
+                to: current.clone(),
+                initial: last.is_none(),
+                observed_at: crate::activity::now_secs(),
+            });
+            last = Some(current.clone());
+        }
+
+        if let (Some(target), Some(value)) = (until, current.as_deref()) {
+            if target == value {
+                return Ok(WatchOutcome::Reached(value.to_string()));
+            }
+        }
+        if timeout.is_some_and(|t| started.elapsed() >= t) {
+            return Ok(WatchOutcome::TimedOut(current));
+        }
+        thread::sleep(interval);
+    }
+}
+
+/// Render a dimension's state machine as text
+pub fn render_transitions(name: &str, dimension: &Dimension) -> String {
+    let mut out = format!("{}: {}\n", name, dimension.values.join(" | "));
+    let initial = dimension.next_values(None);
+    out.push_str(&format!("  (unset) -> {}\n", initial.join(", ")));
+    for value in &dimension.values {
+        let next: Vec<String> = dimension
+            .next_values(Some(value))
+            .into_iter()
+            .filter(|v| v != value)
+            .collect();
+        if next.is_empty() {
+            out.push_str(&format!("  {} -> (final)\n", value));
+        } else {
+            out.push_str(&format!("  {} -> {}\n", value, next.join(", ")));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_mode_transitions() {
+        let schema = StateSchema::builtin();
+
+        assert!(schema.validate_transition("mode", None, "planning").is_ok());
+        assert!(schema
+            .validate_transition("mode", Some("planning"), "building")
+            .is_ok());
+        assert!(schema
+            .validate_transition("mode", Some("building"), "building")
+            .is_ok());
+
+        let err = schema
+            .validate_transition("mode", Some("complete"), "building")
+            .unwrap_err();
+        assert!(err.contains("complete -> building"));
+        assert!(err.contains("allowed: planning"));
+
+        let err = schema
+            .validate_transition("mode", None, "paused")
+            .unwrap_err();
+        assert!(err.contains("(unset) -> paused"));
+    }
+
+    #[test]
+    fn test_health_allows_any_transition() {
+        let schema = StateSchema::builtin();
+
+        assert!(schema
+            .validate_transition("health", Some("healthy"), "critical")
+            .is_ok());
+        assert!(schema
+            .validate_transition("health", Some("critical"), "healthy")
+            .is_ok());
+        let err = schema
+            .validate_transition("health", None, "fine")
+            .unwrap_err();
+        assert!(err.contains("valid values: healthy, warning, degraded, critical"));
+    }
+
+    #[test]
+    fn test_unknown_dimension() {
+        let err = StateSchema::builtin()
+            .validate_transition("color", None, "red")
+            .unwrap_err();
+        assert!(err.contains("known dimensions: health, mode"));
+    }
+
+    #[test]
+    fn test_config_dimensions() {
+        let mut config = StateConfig::default();
+        config.dimensions.insert(
+            "review".to_string(),
+            Dimension::builtin(
+                &["pending", "approved", "rejected"],
+                &[
+                    ("pending", &["approved", "rejected"]),
+                    ("rejected", &["pending"]),
+                ],
+                &["pending"],
+            ),
+        );
+        assert!(config.validate().is_ok());
+
+        let schema = StateSchema::with_config(&config);
+
+        assert!(schema.dimension("mode").is_ok());
+        assert!(schema
+            .validate_transition("review", Some("pending"), "approved")
+            .is_ok());
+        let err = schema
+            .validate_transition("review", Some("approved"), "pending")
+            .unwrap_err();
+        assert!(err.contains("cannot leave 'approved'"));
+    }
+
+    #[test]
+    fn test_config_rejects_unknown_values() {
+        let mut config = StateConfig::default();
+        config.dimensions.insert(
+            "review".to_string(),
+            Dimension::builtin(&["pending"], &[("pending", &["merged"])], &[]),
+        );
+
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("unknown value 'merged'"));
+    }
+
+    #[test]
+    fn test_parse_state_output() {
+        assert_eq!(
+            parse_state_output("mode", "building\n"),
+            Some("building".to_string())
+        );
+        assert_eq!(
+            parse_state_output("mode", "mode: paused\n"),
+            Some("paused".to_string())
+        );
+        assert_eq!(
+            parse_state_output("mode", "mode=planning"),
+            Some("planning".to_string())
+        );
+        assert_eq!(parse_state_output("mode", "(no mode state set)\n"), None);
+        assert_eq!(parse_state_output("mode", "  \n"), None);
+    }
+
+    fn bulk(id: &str, ok: bool) -> BulkResult {
+        BulkResult {
+            id: id.to_string(),
+            ok,
+            from: None,
+            error: (!ok).then(|| "failed".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_bulk_exit_code() {
+        assert_eq!(
+            bulk_exit_code(&[bulk("a", true), bulk("b", true)]),
+            ExitCode::Ok
+        );
+        assert_eq!(
+            bulk_exit_code(&[bulk("a", true), bulk("b", false)]),
+            ExitCode::Partial
+        );
+        assert_eq!(bulk_exit_code(&[bulk("a", false)]), ExitCode::Error);
+        assert_eq!(bulk_exit_code(&[]), ExitCode::Error);
+    }
+
+    #[test]
+    fn test_set_state_bulk_reports_invalid_values_per_issue() {
+        let dir = std::env::temp_dir();
+        let ids = vec!["a".to_string(), "b".to_string(), "a".to_string()];
+
+        // An invalid value fails validation before bd is invoked
+        let results = set_state_bulk(
+            &dir,
+            &StateSchema::builtin(),
+            &ids,
+            "health",
+            "fine",
+            None,
+            false,
+        );
+
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| !r.ok));
+        assert!(results[0].error.as_ref().unwrap().contains("Invalid value"));
+    }
+
+    #[test]
+    fn test_watch_state_reaches_target() {
+        let mut values = vec![None, Some("planning"), Some("planning"), Some("building")]
+            .into_iter()
+            .map(|v| v.map(String::from));
+        let mut changes = Vec::new();
+
+        let outcome = watch_state(
+            || Ok(values.next().unwrap()),
+            Some("building"),
+            None,
+            Duration::ZERO,
+            |c| changes.push((c.from.clone(), c.to.clone())),
+        )
+        .unwrap();
+
+        assert_eq!(outcome, WatchOutcome::Reached("building".to_string()));
+        assert_eq!(
+            changes,
+            vec![
+                (None, None),
+                (None, Some("planning".to_string())),
+                (Some("planning".to_string()), Some("building".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_watch_state_times_out() {
+        let outcome = watch_state(
+            || Ok(Some("paused".to_string())),
+            Some("complete"),
+            Some(Duration::ZERO),
+            Duration::ZERO,
+            |_| {},
+        )
+        .unwrap();
+
+        assert_eq!(outcome, WatchOutcome::TimedOut(Some("paused".to_string())));
+    }
+
+    #[test]
+    fn test_watch_state_propagates_errors() {
+        let result = watch_state(
+            || Err("bd missing".to_string()),
+            None,
+            None,
+            Duration::ZERO,
+            |_| {},
+        );
+
+        assert_eq!(result, Err("bd missing".to_string()));
+    }
+
+    #[test]
+    fn test_render_transitions() {
+        let schema = StateSchema::builtin();
+        let text = render_transitions("mode", schema.dimension("mode").unwrap());
+
+        assert!(text.starts_with("mode: planning | building | paused | complete\n"));
+        assert!(text.contains("  (unset) -> planning, building\n"));
+        assert!(text.contains("  complete -> planning\n"));
+    }
+}
@@ -0,0 +1,411 @@
+//! Worktree bootstrap recipes
+//!
+//! A fresh worktree has the tracked files and nothing else: no `.env`, no
+//! installed dependencies. The `[worktree.bootstrap]` section describes how
+//! to make one usable, and `worktree create-for-issue` runs it:
+//!
+//! - `copy`: files or directories copied from the main worktree
+//! - `symlink`: paths linked back to the main worktree's copy
+//! - `env`: variables templated with the worktree's issue, branch, and
+//!   name, set for every command
+//! - `run`: shell commands run in the new worktree, in order
+//!
+//! Steps stop at the first failure; the caller then removes the worktree.
+
+use minijinja::value::Serde;
+use minijinja::{Environment, UndefinedBehavior};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path};
+use std::process::Command;
+
+use crate::worktree::WorktreeContext;
+
+/// The `[worktree.bootstrap]` configuration section
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BootstrapConfig {
+    /// Paths (relative to the repository root) copied into the worktree
+    pub copy: Vec<String>,
+    /// Paths linked from the worktree to the main worktree's copy
+    pub symlink: Vec<String>,
+    /// Environment for `run` commands; values are templates
+    pub env: BTreeMap<String, String>,
+    /// Shell commands run in the worktree; templates too
+    pub run: Vec<String>,
+}
+
+impl BootstrapConfig {
+    pub fn is_empty(&self) -> bool {
+        self.copy.is_empty()
+            && self.symlink.is_empty()
+            && self.env.is_empty()
+            && self.run.is_empty()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for (key, paths) in [("copy", &self.copy), ("symlink", &self.symlink)] {
+            for path in paths {
+                let relative = Path::new(path);
+                if path.trim().is_empty()
+                    || relative.is_absolute()
+                    || relative.components().any(|c| c == Component::ParentDir)
+                {
+                    return Err(format!(
+                        "worktree.bootstrap.{} entry {:?} must be a path inside the repository",
+                        key, path
+                    ));
+                }
+            }
+        }
+        if self.run.iter().any(|c| c.trim().is_empty()) {
+            return Err("worktree.bootstrap.run contains an empty command".to_string());
+        }
+        let vars = TemplateVars::default();
+        for template in self.env.values().chain(&self.run) {
+            vars.render(template)
+                .map_err(|e| format!("worktree.bootstrap: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+/// What templates can reference
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TemplateVars {
+    pub issue: String,
+    pub title: String,
+    pub branch: String,
+    /// Directory name of the worktree
+    pub worktree: String,
+    /// Absolute path of the worktree
+    pub path: String,
+    /// Root of the main worktree
+    pub repo: String,
+}
+
+impl TemplateVars {
+    pub fn new(context: &WorktreeContext, worktree: &Path, repo_root: &Path) -> Self {
+        TemplateVars {
+            issue: context.issue.clone(),
+            title: context.title.clone(),
+            branch: context.branch.clone(),
+            worktree: worktree
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            path: worktree.display().to_string(),
+            repo: repo_root.display().to_string(),
+        }
+    }
+
+    /// Render `template`; unknown variables are an error
+    pub fn render(&self, template: &str) -> Result<String, String> {
+        let mut env = Environment::new();
+        env.set_undefined_behavior(UndefinedBehavior::Strict);
+        env.render_str(template, Serde(self))
+            .map_err(|e| format!("template {:?}: {}", template, e))
+    }
+}
+
+/// One bootstrap step and how it went
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BootstrapStep {
+    /// `copy`, `symlink`, or `run`
+    pub kind: &'static str,
+    /// Path or command
+    pub target: String,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl BootstrapStep {
+    fn from_result(kind: &'static str, target: &str, result: Result<(), String>) -> Self {
+        BootstrapStep {
+            kind,
+            target: target.to_string(),
+            success: result.is_ok(),
+            exit_code: None,
+            error: result.err(),
+        }
+    }
+}
+
+/// Everything a bootstrap did
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BootstrapReport {
+    /// Rendered environment
+    pub env: BTreeMap<String, String>,
+    pub steps: Vec<BootstrapStep>,
+}
+
+impl BootstrapReport {
+    pub fn success(&self) -> bool {
+        self.steps.iter().all(|s| s.success)
+    }
+
+    /// The step that failed, if any
+    pub fn failure(&self) -> Option<&BootstrapStep> {
+        self.steps.iter().find(|s| !s.success)
+    }
+}
+
+fn copy_path(from: &Path, to: &Path) -> Result<(), String> {
+    let meta =
+        fs::metadata(from).map_err(|e| format!("Failed to read {}: {}", from.display(), e))?;
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    if !meta.is_dir() {
+        return fs::copy(from, to)
+            .map(|_| ())
+            .map_err(|e| format!("Failed to copy {}: {}", from.display(), e));
+    }
+    fs::create_dir_all(to).map_err(|e| format!("Failed to create {}: {}", to.display(), e))?;
+    let entries =
+        fs::read_dir(from).map_err(|e| format!("Failed to read {}: {}", from.display(), e))?;
+    for entry in entries.flatten() {
+        copy_path(&entry.path(), &to.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(not(unix))]
+fn symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    if target.is_dir() {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    }
+}
+
+fn link_path(target: &Path, link: &Path) -> Result<(), String> {
+    if !target.exists() {
+        return Err(format!("{} does not exist", target.display()));
+    }
+    if link.symlink_metadata().is_ok() {
+        return Err(format!("{} already exists in the worktree", link.display()));
+    }
+    if let Some(parent) = link.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    symlink(target, link).map_err(|e| format!("Failed to link {}: {}", link.display(), e))
+}
+
+fn run_command(worktree: &Path, command: &str, env: &BTreeMap<String, String>) -> BootstrapStep {
+    let _span = tracing::debug_span!("bootstrap", command).entered();
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .current_dir(worktree)
+        .envs(env)
+        .output();
+    let mut step = BootstrapStep::from_result("run", command, Ok(()));
+    match output {
+        Ok(output) => {
+            step.success = output.status.success();
+            step.exit_code = output.status.code();
+            if !step.success {
+                // The end of stderr is where build tools say what went wrong
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                let lines: Vec<&str> = stderr.trim_end().lines().collect();
+                step.error = Some(lines[lines.len().saturating_sub(5)..].join("\n"));
+            }
+        }
+        Err(e) => {
+            step.success = false;
+            step.error = Some(format!("Failed to run command: {}", e));
+        }
+    }
+    tracing::debug!(success = step.success, exit_code = ?step.exit_code, "bootstrap step finished");
+    step
+}
+
+/// Run `config` for the worktree at `worktree`, stopping at the first failure
+///
+/// `base_env` is set for `run` commands under the recipe's own variables.
+pub fn bootstrap(
+    config: &BootstrapConfig,
+    vars: &TemplateVars,
+    base_env: &BTreeMap<String, String>,
+    repo_root: &Path,
+    worktree: &Path,
+) -> BootstrapReport {
+    let mut report = BootstrapReport::default();
+    for (key, template) in &config.env {
+        match vars.render(template) {
+            Ok(value) => {
+                report.env.insert(key.clone(), value);
+            }
+            Err(e) => {
+                report
+                    .steps
+                    .push(BootstrapStep::from_result("env", key, Err(e)));
+                return report;
+            }
+        }
+    }
+    let mut env = base_env.clone();
+    env.extend(report.env.clone());
+    env.insert("RALPH_ISSUE".to_string(), vars.issue.clone());
+    env.insert("RALPH_WORKTREE".to_string(), vars.path.clone());
+
+    for path in &config.copy {
+        let result = copy_path(&repo_root.join(path), &worktree.join(path));
+        report
+            .steps
+            .push(BootstrapStep::from_result("copy", path, result));
+        if !report.success() {
+            return report;
+        }
+    }
+    for path in &config.symlink {
+        let result = link_path(&repo_root.join(path), &worktree.join(path));
+        report
+            .steps
+            .push(BootstrapStep::from_result("symlink", path, result));
+        if !report.success() {
+            return report;
+        }
+    }
+    for template in &config.run {
+        let step = match vars.render(template) {
+            Ok(command) => run_command(worktree, &command, &env),
+            Err(e) => BootstrapStep::from_result("run", template, Err(e)),
+        };
+        report.steps.push(step);
+        if !report.success() {
+            return report;
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn vars(worktree: &Path, repo: &Path) -> TemplateVars {
+        let context = WorktreeContext {
+            issue: "bd-42".to_string(),
+            title: "Add login".to_string(),
+            branch: "task/bd-42-add-login".to_string(),
+            base: "main".to_string(),
+            created_at: 0,
+        };
+        TemplateVars::new(&context, worktree, repo)
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut config = BootstrapConfig {
+            copy: vec![".env".to_string()],
+            env: BTreeMap::from([("DB".to_string(), "app_{{ issue }}".to_string())]),
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+
+        config.symlink = vec!["../outside".to_string()];
+        assert!(config
+            .validate()
+            .unwrap_err()
+            .contains("inside the repository"));
+        config.symlink.clear();
+        config.run = vec!["echo {{ isue }}".to_string()];
+        assert!(config.validate().unwrap_err().contains("echo {{ isue }}"));
+    }
+
+    #[test]
+    fn test_template_vars() {
+        let vars = vars(
+            Path::new("/w/repo-worktrees/bd-42-add-login"),
+            Path::new("/w/repo"),
+        );
+
+        assert_eq!(
+            vars.render("{{ worktree }}:{{ issue | upper }}").unwrap(),
+            "bd-42-add-login:BD-42"
+        );
+        assert_eq!(vars.render("{{ repo }}").unwrap(), "/w/repo");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_bootstrap_runs_steps_in_order() {
+        let repo = TempDir::new().unwrap();
+        let worktree = TempDir::new().unwrap();
+        fs::write(repo.path().join(".env"), "SECRET=1\n").unwrap();
+        fs::create_dir_all(repo.path().join("node_modules/pkg")).unwrap();
+        let config = BootstrapConfig {
+            copy: vec![".env".to_string()],
+            symlink: vec!["node_modules".to_string()],
+            env: BTreeMap::from([("PORT_NAME".to_string(), "svc-{{ issue }}".to_string())]),
+            run: vec!["echo \"$PORT_NAME $RALPH_ISSUE {{ branch }}\" > out.txt".to_string()],
+        };
+        let vars = vars(worktree.path(), repo.path());
+
+        let report = bootstrap(
+            &config,
+            &vars,
+            &BTreeMap::new(),
+            repo.path(),
+            worktree.path(),
+        );
+
+        assert!(report.success(), "{:?}", report);
+        assert_eq!(report.env["PORT_NAME"], "svc-bd-42");
+        let kinds: Vec<&str> = report.steps.iter().map(|s| s.kind).collect();
+        assert_eq!(kinds, vec!["copy", "symlink", "run"]);
+        assert_eq!(
+            fs::read_to_string(worktree.path().join(".env")).unwrap(),
+            "SECRET=1\n"
+        );
+        assert!(worktree.path().join("node_modules/pkg").is_dir());
+        assert_eq!(
+            fs::read_to_string(worktree.path().join("out.txt")).unwrap(),
+            "svc-bd-42 bd-42 task/bd-42-add-login\n"
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_bootstrap_stops_at_first_failure() {
+        let repo = TempDir::new().unwrap();
+        let worktree = TempDir::new().unwrap();
+        let config = BootstrapConfig {
+            run: vec![
+                "echo broken >&2; exit 3".to_string(),
+                "touch never".to_string(),
+            ],
+            ..Default::default()
+        };
+        let vars = vars(worktree.path(), repo.path());
+
+        let report = bootstrap(
+            &config,
+            &vars,
+            &BTreeMap::new(),
+            repo.path(),
+            worktree.path(),
+        );
+
+        assert!(!report.success());
+        let failure = report.failure().unwrap();
+        assert_eq!(failure.exit_code, Some(3));
+        assert_eq!(failure.error.as_deref(), Some("broken"));
+        assert_eq!(report.steps.len(), 1);
+        assert!(!worktree.path().join("never").exists());
+    }
+}
@@ -0,0 +1,369 @@
+//! Build caches shared between worktrees
+//!
+//! Every worktree `create-for-issue` makes starts with an empty `target/`
+//! and no installed packages, so N parallel workers pay for N cold builds.
+//! The `[worktree.cache]` section points the build tools of every worktree
+//! at one set of caches instead:
+//!
+//! - `cargo = "target"`: one `CARGO_TARGET_DIR` for all worktrees (builds
+//!   in different worktrees then wait for each other's lock)
+//! - `cargo = "sccache"`: `RUSTC_WRAPPER=sccache` with a shared
+//!   `SCCACHE_DIR`, keeping per-worktree target directories
+//! - `pnpm = true`: one pnpm store (`npm_config_store_dir`)
+//! - `pip = true`: one pip cache (`PIP_CACHE_DIR`)
+//!
+//! The caches live under `dir`, by default `ralph-beads/cache` in the
+//! repository's common git directory. Bootstrap commands run with the
+//! variables set; `worktree info --format env` prints them for a shell.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::health::{CheckStatus, HealthCheck};
+use crate::worktree::common_dir;
+
+/// Cache root relative to the common git directory
+pub const DEFAULT_CACHE_DIR: &str = "ralph-beads/cache";
+
+/// How cargo builds share work
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CargoCache {
+    /// Each worktree builds from scratch
+    #[default]
+    Off,
+    /// One target directory for all worktrees
+    Target,
+    /// Compilation results shared through sccache
+    Sccache,
+}
+
+impl fmt::Display for CargoCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CargoCache::Off => "off",
+            CargoCache::Target => "target",
+            CargoCache::Sccache => "sccache",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The `[worktree.cache]` configuration section
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    /// Root of the shared caches; relative paths are against the main
+    /// worktree
+    pub dir: Option<String>,
+    pub cargo: CargoCache,
+    pub pnpm: bool,
+    pub pip: bool,
+}
+
+impl CacheConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.cargo != CargoCache::Off || self.pnpm || self.pip
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self.dir {
+            Some(ref dir) if dir.trim().is_empty() => {
+                Err("worktree.cache.dir must not be empty".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Where the caches live, given the repository's common git directory
+    pub fn root(&self, common_dir: &Path) -> PathBuf {
+        match self.dir {
+            Some(ref dir) if Path::new(dir).is_absolute() => PathBuf::from(dir),
+            // The common directory of a non-bare repository is the main
+            // worktree's .git
+            Some(ref dir) => common_dir.parent().unwrap_or(common_dir).join(dir),
+            None => common_dir.join(DEFAULT_CACHE_DIR),
+        }
+    }
+
+    /// Short description of what is shared, e.g. `cargo=sccache pnpm`
+    pub fn mode(&self) -> String {
+        let mut parts = Vec::new();
+        if self.cargo != CargoCache::Off {
+            parts.push(format!("cargo={}", self.cargo));
+        }
+        if self.pnpm {
+            parts.push("pnpm".to_string());
+        }
+        if self.pip {
+            parts.push("pip".to_string());
+        }
+        if parts.is_empty() {
+            "off".to_string()
+        } else {
+            parts.join(" ")
+        }
+    }
+
+    /// Environment that points build tools at the caches under `root`
+    pub fn env(&self, root: &Path) -> BTreeMap<String, String> {
+        let path = |name: &str| root.join(name).display().to_string();
+        let mut env = BTreeMap::new();
+        match self.cargo {
+            CargoCache::Off => {}
+            CargoCache::Target => {
+                env.insert("CARGO_TARGET_DIR".to_string(), path("cargo-target"));
+            }
+            CargoCache::Sccache => {
+                env.insert("RUSTC_WRAPPER".to_string(), "sccache".to_string());
+                env.insert("SCCACHE_DIR".to_string(), path("sccache"));
+            }
+        }
+        if self.pnpm {
+            env.insert("npm_config_store_dir".to_string(), path("pnpm-store"));
+        }
+        if self.pip {
+            env.insert("PIP_CACHE_DIR".to_string(), path("pip"));
+        }
+        env
+    }
+}
+
+/// Cache settings as applied to a worktree
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CacheInfo {
+    pub mode: String,
+    pub root: PathBuf,
+    pub env: BTreeMap<String, String>,
+}
+
+impl CacheInfo {
+    pub fn new(config: &CacheConfig, common_dir: &Path) -> Self {
+        let root = config.root(common_dir);
+        CacheInfo {
+            mode: config.mode(),
+            env: config.env(&root),
+            root,
+        }
+    }
+
+    /// The cache directories the environment names
+    fn dirs(&self) -> impl Iterator<Item = &Path> {
+        self.env
+            .values()
+            .map(Path::new)
+            .filter(|p| p.starts_with(&self.root))
+    }
+
+    /// Create the directories the environment names
+    pub fn prepare(&self) -> Result<(), String> {
+        self.dirs().try_for_each(|dir| {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))
+        })
+    }
+
+    /// Whether `prepare` could succeed, without creating anything: each
+    /// directory, or its nearest existing ancestor, is a writable directory.
+    /// Returns the directories not created yet.
+    pub fn check_dirs(&self) -> Result<Vec<&Path>, String> {
+        let mut missing = Vec::new();
+        for dir in self.dirs() {
+            let existing = dir
+                .ancestors()
+                .find(|p| p.exists())
+                .ok_or_else(|| format!("{} has no existing parent", dir.display()))?;
+            let metadata = fs::metadata(existing)
+                .map_err(|e| format!("Failed to stat {}: {}", existing.display(), e))?;
+            if !metadata.is_dir() {
+                return Err(format!("{} is not a directory", existing.display()));
+            }
+            if metadata.permissions().readonly() {
+                return Err(format!("{} is not writable", existing.display()));
+            }
+            if existing != dir {
+                missing.push(dir);
+            }
+        }
+        Ok(missing)
+    }
+
+    /// `export` lines for a POSIX shell
+    pub fn exports(&self) -> Vec<String> {
+        self.env
+            .iter()
+            .map(|(key, value)| format!("export {}='{}'", key, value.replace('\'', "'\\''")))
+            .collect()
+    }
+}
+
+/// The cache settings of the repository containing `dir`, `None` when
+/// caching is off
+pub fn cache_info(dir: &Path, config: &CacheConfig) -> Result<Option<CacheInfo>, String> {
+    if !config.is_enabled() {
+        return Ok(None);
+    }
+    Ok(Some(CacheInfo::new(config, &common_dir(dir)?)))
+}
+
+fn on_path(program: &str) -> bool {
+    Command::new(program)
+        .arg("--version")
+        .output()
+        .is_ok_and(|o| o.status.success())
+}
+
+/// The `build-cache` health check: the cache root is usable, the tools it
+/// relies on are installed, and the current environment doesn't point
+/// elsewhere. `current` looks up a variable of the current environment.
+pub fn check_cache(
+    config: &CacheConfig,
+    info: &CacheInfo,
+    current: impl Fn(&str) -> Option<String>,
+) -> HealthCheck {
+    const NAME: &str = "build-cache";
+    let missing = match info.check_dirs() {
+        Ok(missing) => missing,
+        Err(e) => {
+            return HealthCheck::new(NAME, CheckStatus::Fail, e)
+                .with_hint("Set worktree.cache.dir to a writable directory")
+        }
+    };
+    if config.cargo == CargoCache::Sccache && !on_path("sccache") {
+        return HealthCheck::new(
+            NAME,
+            CheckStatus::Warn,
+            "cargo=sccache but sccache is missing",
+        )
+        .with_hint("cargo install sccache");
+    }
+    if config.pnpm && !on_path("pnpm") {
+        return HealthCheck::new(
+            NAME,
+            CheckStatus::Warn,
+            "pnpm store shared but pnpm is missing",
+        )
+        .with_hint("npm install -g pnpm");
+    }
+    let conflicts: Vec<String> = info
+        .env
+        .iter()
+        .filter_map(|(key, value)| {
+            let set = current(key)?;
+            (set != *value).then(|| format!("{}={} (shared: {})", key, set, value))
+        })
+        .collect();
+    if !conflicts.is_empty() {
+        return HealthCheck::new(
+            NAME,
+            CheckStatus::Warn,
+            format!("environment overrides the cache: {}", conflicts.join(", ")),
+        )
+        .with_hint("eval \"$(ralph-beads-cli worktree info --format env)\"");
+    }
+    let pending = if missing.is_empty() {
+        ""
+    } else {
+        " (created by the next create-for-issue)"
+    };
+    HealthCheck::new(
+        NAME,
+        CheckStatus::Pass,
+        format!("{} in {}{}", info.mode, info.root.display(), pending),
+    )
+}
+
+/// The `build-cache` check for the repository containing `dir`, against the
+/// process environment; nothing when caching is off
+pub fn check_repo_cache(dir: &Path, config: &CacheConfig) -> Option<HealthCheck> {
+    match cache_info(dir, config) {
+        Ok(info) => info.map(|info| check_cache(config, &info, |key| std::env::var(key).ok())),
+        Err(e) => Some(HealthCheck::new("build-cache", CheckStatus::Warn, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_env_per_mode() {
+        let config = CacheConfig {
+            cargo: CargoCache::Sccache,
+            pip: true,
+            ..Default::default()
+        };
+
+        let env = config.env(Path::new("/cache"));
+
+        assert_eq!(config.mode(), "cargo=sccache pip");
+        assert_eq!(env["RUSTC_WRAPPER"], "sccache");
+        assert_eq!(env["SCCACHE_DIR"], "/cache/sccache");
+        assert_eq!(env["PIP_CACHE_DIR"], "/cache/pip");
+        assert!(!env.contains_key("CARGO_TARGET_DIR"));
+        assert!(CacheConfig::default().env(Path::new("/cache")).is_empty());
+        assert_eq!(CacheConfig::default().mode(), "off");
+    }
+
+    #[test]
+    fn test_root_resolution() {
+        let common = Path::new("/src/app/.git");
+        let mut config = CacheConfig::default();
+        assert_eq!(
+            config.root(common),
+            PathBuf::from("/src/app/.git/ralph-beads/cache")
+        );
+
+        config.dir = Some(".cache/worktrees".to_string());
+        assert_eq!(
+            config.root(common),
+            PathBuf::from("/src/app/.cache/worktrees")
+        );
+
+        config.dir = Some("/var/cache/app".to_string());
+        assert_eq!(config.root(common), PathBuf::from("/var/cache/app"));
+
+        config.dir = Some(" ".to_string());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_check_flags_environment_overrides() {
+        let tmp = TempDir::new().unwrap();
+        let config = CacheConfig {
+            cargo: CargoCache::Target,
+            ..Default::default()
+        };
+        let info = CacheInfo::new(&config, tmp.path());
+
+        let before = check_cache(&config, &info, |_| None);
+        // Checking creates nothing
+        assert!(!tmp.path().join(DEFAULT_CACHE_DIR).exists());
+        info.prepare().unwrap();
+        let clean = check_cache(&config, &info, |_| None);
+        let overridden = check_cache(&config, &info, |key| {
+            (key == "CARGO_TARGET_DIR").then(|| "/elsewhere".to_string())
+        });
+
+        assert_eq!(before.status, CheckStatus::Pass);
+        assert!(before
+            .detail
+            .ends_with("(created by the next create-for-issue)"));
+        assert_eq!(clean.status, CheckStatus::Pass);
+        assert!(!clean.detail.contains("created by"));
+        assert_eq!(overridden.status, CheckStatus::Warn);
+        assert!(overridden.detail.contains("CARGO_TARGET_DIR=/elsewhere"));
+        assert_eq!(
+            info.exports(),
+            vec![format!(
+                "export CARGO_TARGET_DIR='{}'",
+                info.root.join("cargo-target").display()
+            )]
+        );
+    }
+}
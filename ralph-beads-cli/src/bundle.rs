@@ -0,0 +1,291 @@
+//! Per-task execution context bundles
+//!
+//! `task context <id>` gathers what a worker needs before starting a task
+//! into one document: the issue with its acceptance criteria, a summary of
+//! the parent epic, what each blocker left behind (the comments on closed
+//! blockers), and the project's test command. Workers read one bundle
+//! instead of calling `bd show`, `bd comments`, and friends themselves.
+
+use serde::Serialize;
+
+use crate::activity::format_timestamp;
+use crate::beads::{Comment, Issue};
+
+/// The parent epic, cut down to what orients a worker
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EpicSummary {
+    pub id: String,
+    pub title: String,
+    pub status: String,
+    /// First paragraph of the epic's description
+    pub summary: String,
+    /// Closed children of the epic
+    pub done: usize,
+    /// All children of the epic
+    pub total: usize,
+}
+
+impl EpicSummary {
+    /// Summarize `epic` given its children
+    pub fn new(epic: &Issue, children: &[Issue]) -> Self {
+        EpicSummary {
+            id: epic.id.clone(),
+            title: epic.title.clone(),
+            status: epic.status.clone(),
+            summary: first_paragraph(&epic.description),
+            done: children.iter().filter(|c| c.is_closed()).count(),
+            total: children.len(),
+        }
+    }
+}
+
+/// A blocker of the task and, once closed, what was said on it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DependencyOutput {
+    pub id: String,
+    pub title: String,
+    pub status: String,
+    /// Comments left on the blocker; empty while it is still open
+    pub comments: Vec<Comment>,
+}
+
+/// The detected test framework and how to run its tests
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FrameworkCommands {
+    pub framework: String,
+    pub test_command: String,
+}
+
+/// Everything a worker needs to start on a task
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TaskBundle {
+    pub task: Issue,
+    pub epic: Option<EpicSummary>,
+    pub dependencies: Vec<DependencyOutput>,
+    pub framework: FrameworkCommands,
+    pub generated_at: String,
+}
+
+impl TaskBundle {
+    /// Assemble the bundle; `blockers` pairs each blocker with its comments
+    pub fn new(
+        task: Issue,
+        epic: Option<EpicSummary>,
+        blockers: Vec<(Issue, Vec<Comment>)>,
+        framework: FrameworkCommands,
+        now: u64,
+    ) -> Self {
+        let dependencies = blockers
+            .into_iter()
+            .map(|(issue, comments)| DependencyOutput {
+                comments: if issue.is_closed() {
+                    comments
+                } else {
+                    Vec::new()
+                },
+                id: issue.id,
+                title: issue.title,
+                status: issue.status,
+            })
+            .collect();
+        TaskBundle {
+            task,
+            epic,
+            dependencies,
+            framework,
+            generated_at: format_timestamp(now),
+        }
+    }
+
+    /// Blockers that are not closed yet
+    pub fn open_blockers(&self) -> Vec<&str> {
+        self.dependencies
+            .iter()
+            .filter(|d| d.status != "closed")
+            .map(|d| d.id.as_str())
+            .collect()
+    }
+}
+
+fn first_paragraph(text: &str) -> String {
+    text.trim()
+        .split("\n\n")
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+}
+
+/// Render the bundle as a markdown brief
+pub fn render_markdown(bundle: &TaskBundle) -> String {
+    let task = &bundle.task;
+    let mut out = format!("# {}: {}\n\n", task.id, task.title);
+    out.push_str(&format!(
+        "Type: {} | Status: {}",
+        task.issue_type, task.status
+    ));
+    if let Some(priority) = task.priority {
+        out.push_str(&format!(" | Priority: P{}", priority));
+    }
+    out.push('\n');
+    let open = bundle.open_blockers();
+    if !open.is_empty() {
+        out.push_str(&format!("\n**Still blocked by:** {}\n", open.join(", ")));
+    }
+
+    let description = task.description.trim();
+    out.push_str(&format!(
+        "\n## Task\n\n{}\n",
+        if description.is_empty() {
+            "(no description)"
+        } else {
+            description
+        }
+    ));
+    if !task.acceptance_criteria.trim().is_empty() {
+        out.push_str(&format!(
+            "\n## Acceptance Criteria\n\n{}\n",
+            task.acceptance_criteria.trim()
+        ));
+    }
+    if !task.design.trim().is_empty() {
+        out.push_str(&format!("\n## Design\n\n{}\n", task.design.trim()));
+    }
+
+    if let Some(ref epic) = bundle.epic {
+        out.push_str(&format!(
+            "\n## Epic {}: {}\n\n{}/{} tasks done\n",
+            epic.id, epic.title, epic.done, epic.total
+        ));
+        if !epic.summary.is_empty() {
+            out.push_str(&format!("\n{}\n", epic.summary));
+        }
+    }
+
+    if !bundle.dependencies.is_empty() {
+        out.push_str("\n## Dependencies\n");
+        for dep in &bundle.dependencies {
+            out.push_str(&format!(
+                "\n### {}: {} ({})\n",
+                dep.id, dep.title, dep.status
+            ));
+            if !dep.comments.is_empty() {
+                out.push('\n');
+            }
+            for comment in &dep.comments {
+                let author = if comment.author.is_empty() {
+                    String::new()
+                } else {
+                    format!("**{}:** ", comment.author)
+                };
+                out.push_str(&format!("- {}{}\n", author, comment.text.trim()));
+            }
+        }
+    }
+
+    out.push_str(&format!(
+        "\n## Commands\n\nFramework: {}\nTest: `{}`\n",
+        bundle.framework.framework, bundle.framework.test_command
+    ));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beads::test_issue;
+
+    fn comment(author: &str, text: &str) -> Comment {
+        Comment {
+            author: author.to_string(),
+            text: text.to_string(),
+            created_at: None,
+        }
+    }
+
+    fn framework() -> FrameworkCommands {
+        FrameworkCommands {
+            framework: "rust".to_string(),
+            test_command: "cargo test".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_epic_summary_counts_children() {
+        let epic = Issue {
+            description: "Parse plans into epics.\n\nDetails follow.".to_string(),
+            ..test_issue("bd-1", "open", &[])
+        };
+        let children = [
+            test_issue("bd-1.1", "closed", &[]),
+            test_issue("bd-1.2", "in_progress", &[]),
+            test_issue("bd-1.3", "open", &[]),
+        ];
+
+        let summary = EpicSummary::new(&epic, &children);
+
+        assert_eq!(summary.summary, "Parse plans into epics.");
+        assert_eq!((summary.done, summary.total), (1, 3));
+    }
+
+    #[test]
+    fn test_bundle_keeps_comments_of_closed_blockers_only() {
+        let bundle = TaskBundle::new(
+            test_issue("bd-1.3", "open", &[]),
+            None,
+            vec![
+                (
+                    test_issue("bd-1.1", "closed", &[]),
+                    vec![comment("w1", "Added Plan::parse")],
+                ),
+                (
+                    test_issue("bd-1.2", "open", &[]),
+                    vec![comment("w2", "half way")],
+                ),
+            ],
+            framework(),
+            0,
+        );
+
+        assert_eq!(bundle.dependencies[0].comments.len(), 1);
+        assert!(bundle.dependencies[1].comments.is_empty());
+        assert_eq!(bundle.open_blockers(), vec!["bd-1.2"]);
+        assert_eq!(bundle.generated_at, "1970-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let task = Issue {
+            description: "Wire the parser into scaffold".to_string(),
+            acceptance_criteria: "- scaffold reads plans".to_string(),
+            priority: Some(1),
+            ..test_issue("bd-1.3", "open", &[])
+        };
+        let epic = EpicSummary::new(
+            &test_issue("bd-1", "open", &[]),
+            &[test_issue("bd-1.1", "closed", &[])],
+        );
+        let bundle = TaskBundle::new(
+            task,
+            Some(epic),
+            vec![(
+                test_issue("bd-1.1", "closed", &[]),
+                vec![comment("w1", "Added Plan::parse")],
+            )],
+            framework(),
+            0,
+        );
+
+        let md = render_markdown(&bundle);
+
+        assert!(md.starts_with("# bd-1.3: Title of bd-1.3\n"));
+        assert!(md.contains("Type: task | Status: open | Priority: P1\n"));
+        assert!(!md.contains("Still blocked by"));
+        assert!(md.contains("## Acceptance Criteria\n\n- scaffold reads plans\n"));
+        assert!(md.contains("## Epic bd-1: Title of bd-1\n\n1/1 tasks done\n"));
+        assert!(
+            md.contains("### bd-1.1: Title of bd-1.1 (closed)\n\n- **w1:** Added Plan::parse\n")
+        );
+        assert!(md.contains("Test: `cargo test`"));
+    }
+}
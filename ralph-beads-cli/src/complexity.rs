@@ -1,13 +1,14 @@
 use once_cell::sync::Lazy;
 use regex::Regex;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
 use std::fmt;
 use std::str::FromStr;
 
 use crate::state::WorkflowMode;
 
 /// Task complexity levels that determine iteration counts and validation requirements
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Complexity {
     /// Trivial: typos, comments, whitespace (2-5 iterations, skip validation)
@@ -20,6 +21,7 @@ pub enum Complexity {
     Critical,
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for Complexity {
     fn default() -> Self {
         Complexity::Standard
@@ -52,6 +54,13 @@ impl FromStr for Complexity {
 }
 
 impl Complexity {
+    pub const ALL: [Complexity; 4] = [
+        Complexity::Trivial,
+        Complexity::Simple,
+        Complexity::Standard,
+        Complexity::Critical,
+    ];
+
     /// Whether validation should be enabled by default for this complexity
     pub fn default_validation(&self) -> bool {
         match self {
@@ -75,6 +84,7 @@ struct ComplexityPatterns {
 
 /// Lazily compiled regex patterns for complexity detection
 /// Compiled once on first use, then reused for all subsequent calls
+#[rustfmt::skip]
 static PATTERNS: Lazy<ComplexityPatterns> = Lazy::new(|| ComplexityPatterns {
     // TRIVIAL patterns: typo fixes, comments, whitespace, spelling, renaming
     trivial: Regex::new(
@@ -153,6 +163,9 @@ const BUILDING_ITERATIONS: [(Complexity, u32); 4] = [
 
 /// Calculate the maximum number of iterations based on workflow mode and complexity
 ///
+/// Uses the built-in table; `IterationsConfig::max_iterations` applies a
+/// project's `[iterations]` overrides.
+///
 /// # Arguments
 /// * `mode` - The current workflow mode (planning or building)
 /// * `complexity` - The detected or specified complexity level
@@ -191,6 +204,312 @@ pub fn calculate_max_iterations(mode: &WorkflowMode, complexity: &Complexity) ->
     }
 }
 
+/// Built-in iteration table for one mode
+fn default_row(table: &[(Complexity, u32); 4]) -> BTreeMap<Complexity, u32> {
+    table.iter().copied().collect()
+}
+
+fn planning_with_defaults<'de, D>(deserializer: D) -> Result<BTreeMap<Complexity, u32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let mut table = default_row(&PLANNING_ITERATIONS);
+    table.extend(BTreeMap::<Complexity, u32>::deserialize(deserializer)?);
+    Ok(table)
+}
+
+fn building_with_defaults<'de, D>(deserializer: D) -> Result<BTreeMap<Complexity, u32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let mut table = default_row(&BUILDING_ITERATIONS);
+    table.extend(BTreeMap::<Complexity, u32>::deserialize(deserializer)?);
+    Ok(table)
+}
+
+/// The `[iterations]` configuration section: the mode x complexity table
+///
+/// Cells left out of the file keep their built-in values, so a project can
+/// raise just `building.critical` without restating the rest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IterationsConfig {
+    #[serde(deserialize_with = "planning_with_defaults")]
+    pub planning: BTreeMap<Complexity, u32>,
+    #[serde(deserialize_with = "building_with_defaults")]
+    pub building: BTreeMap<Complexity, u32>,
+}
+
+impl Default for IterationsConfig {
+    fn default() -> Self {
+        IterationsConfig {
+            planning: default_row(&PLANNING_ITERATIONS),
+            building: default_row(&BUILDING_ITERATIONS),
+        }
+    }
+}
+
+impl IterationsConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        for (mode, table) in [("planning", &self.planning), ("building", &self.building)] {
+            if let Some((complexity, _)) = table.iter().find(|(_, n)| **n == 0) {
+                return Err(format!(
+                    "iterations.{}.{} must be at least 1",
+                    mode, complexity
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Maximum iterations for `mode` and `complexity` under this table
+    pub fn max_iterations(&self, mode: &WorkflowMode, complexity: &Complexity) -> u32 {
+        let table = match mode {
+            WorkflowMode::Planning => &self.planning,
+            WorkflowMode::Building => &self.building,
+            // Paused and Complete don't need iteration calculations
+            WorkflowMode::Paused | WorkflowMode::Complete => return 0,
+        };
+        table
+            .get(complexity)
+            .copied()
+            .unwrap_or_else(|| calculate_max_iterations(mode, complexity))
+    }
+
+    /// How `max_iterations` arrives at its answer, for `calc-iterations --explain`
+    pub fn explain(&self, mode: &WorkflowMode, complexity: &Complexity) -> IterationExplanation {
+        let overrides = [
+            (WorkflowMode::Planning, &self.planning),
+            (WorkflowMode::Building, &self.building),
+        ]
+        .into_iter()
+        .flat_map(|(m, table)| {
+            table.iter().filter_map(move |(cx, n)| {
+                let default = calculate_max_iterations(&m, cx);
+                (*n != default).then(|| IterationOverride {
+                    cell: format!("{}.{}", m, cx),
+                    value: *n,
+                    default,
+                })
+            })
+        })
+        .collect();
+        IterationExplanation {
+            mode: *mode,
+            complexity: *complexity,
+            max_iterations: self.max_iterations(mode, complexity),
+            default: calculate_max_iterations(mode, complexity),
+            row: (
+                self.max_iterations(&WorkflowMode::Planning, complexity),
+                self.max_iterations(&WorkflowMode::Building, complexity),
+            ),
+            overrides,
+        }
+    }
+}
+
+/// A table cell changed from its built-in value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IterationOverride {
+    /// `mode.complexity`, e.g. `building.critical`
+    pub cell: String,
+    pub value: u32,
+    pub default: u32,
+}
+
+/// The table lookup behind one `calc-iterations` answer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IterationExplanation {
+    pub mode: WorkflowMode,
+    pub complexity: Complexity,
+    pub max_iterations: u32,
+    /// Built-in value for the same cell
+    pub default: u32,
+    /// The complexity's row: (planning, building)
+    pub row: (u32, u32),
+    /// Every cell the configuration changes
+    pub overrides: Vec<IterationOverride>,
+}
+
+/// One level of a custom complexity taxonomy
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ComplexityLevel {
+    pub name: String,
+    /// Words or phrases that put a task at this level (case-insensitive,
+    /// whole words)
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// Scales the `standard` cell of the iteration table for each mode
+    pub multiplier: f64,
+}
+
+/// The `[complexity]` configuration section: a team's own taxonomy
+///
+/// With no `levels`, the built-in trivial/simple/standard/critical set is
+/// used. Otherwise levels are tried in order and the first whose keywords
+/// match the task wins; tasks matching none get `default`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ComplexityConfig {
+    pub default: Option<String>,
+    pub levels: Vec<ComplexityLevel>,
+}
+
+/// How a custom level's iteration limit is derived, for `calc-iterations --explain`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LevelExplanation {
+    pub mode: WorkflowMode,
+    pub level: String,
+    pub multiplier: f64,
+    /// The `standard` cell of the iteration table for the mode
+    pub base: u32,
+    pub max_iterations: u32,
+}
+
+impl ComplexityConfig {
+    /// Whether the configuration replaces the built-in taxonomy
+    pub fn is_custom(&self) -> bool {
+        !self.levels.is_empty()
+    }
+
+    /// Level names in precedence order
+    pub fn names(&self) -> Vec<String> {
+        if self.is_custom() {
+            self.levels.iter().map(|l| l.name.clone()).collect()
+        } else {
+            Complexity::ALL.iter().map(Complexity::to_string).collect()
+        }
+    }
+
+    /// Check the taxonomy is one `detect-complexity` and `calc-iterations`
+    /// agree on: every level detection can return has an iteration limit
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.is_custom() {
+            return match self.default {
+                Some(ref name) => Err(format!(
+                    "complexity.default = \"{}\" needs complexity.levels",
+                    name
+                )),
+                None => Ok(()),
+            };
+        }
+        let mut seen = Vec::new();
+        for level in &self.levels {
+            let name = level.name.as_str();
+            if name.is_empty() || name != name.trim() || name.to_lowercase() != name {
+                return Err(format!(
+                    "level name \"{}\" must be non-empty lowercase without surrounding spaces",
+                    name
+                ));
+            }
+            if seen.contains(&name) {
+                return Err(format!("level \"{}\" is defined twice", name));
+            }
+            seen.push(name);
+            if !(level.multiplier.is_finite() && level.multiplier > 0.0) {
+                return Err(format!("level \"{}\" needs a positive multiplier", name));
+            }
+            if level.keywords.iter().any(|k| k.trim().is_empty()) {
+                return Err(format!("level \"{}\" has an empty keyword", name));
+            }
+        }
+        let default = self
+            .default
+            .as_deref()
+            .ok_or("complexity.default is required with complexity.levels")?;
+        if !seen.contains(&default) {
+            return Err(format!(
+                "complexity.default \"{}\" is not one of the levels ({})",
+                default,
+                seen.join(", ")
+            ));
+        }
+        if let Some(level) = self
+            .levels
+            .iter()
+            .find(|l| l.keywords.is_empty() && l.name != default)
+        {
+            return Err(format!(
+                "level \"{}\" has no keywords, so it can never be detected",
+                level.name
+            ));
+        }
+        Ok(())
+    }
+
+    /// Level of `task` under this taxonomy
+    pub fn detect(&self, task: &str) -> String {
+        if !self.is_custom() {
+            return detect_complexity(task).to_string();
+        }
+        self.levels
+            .iter()
+            .find(|level| level.keywords.iter().any(|k| keyword_matches(k, task)))
+            .map(|level| level.name.clone())
+            .or_else(|| self.default.clone())
+            .unwrap_or_default()
+    }
+
+    fn level(&self, name: &str) -> Result<&ComplexityLevel, String> {
+        let name = name.to_lowercase();
+        self.levels.iter().find(|l| l.name == name).ok_or_else(|| {
+            format!(
+                "Unknown complexity level: {} (configured: {})",
+                name,
+                self.names().join(", ")
+            )
+        })
+    }
+
+    /// Iteration limit for the level `name` in `mode`
+    ///
+    /// Built-in levels read `table` directly; custom levels scale its
+    /// `standard` cell by their multiplier (at least 1 iteration).
+    pub fn max_iterations(
+        &self,
+        table: &IterationsConfig,
+        mode: &WorkflowMode,
+        name: &str,
+    ) -> Result<u32, String> {
+        if !self.is_custom() {
+            let complexity = name.parse::<Complexity>()?;
+            return Ok(table.max_iterations(mode, &complexity));
+        }
+        self.explain(table, mode, name).map(|e| e.max_iterations)
+    }
+
+    /// How a custom level's limit is derived from `table`
+    pub fn explain(
+        &self,
+        table: &IterationsConfig,
+        mode: &WorkflowMode,
+        name: &str,
+    ) -> Result<LevelExplanation, String> {
+        let level = self.level(name)?;
+        let base = table.max_iterations(mode, &Complexity::Standard);
+        let max_iterations = match base {
+            0 => 0,
+            base => ((f64::from(base) * level.multiplier).round() as u32).max(1),
+        };
+        Ok(LevelExplanation {
+            mode: *mode,
+            level: level.name.clone(),
+            multiplier: level.multiplier,
+            base,
+            max_iterations,
+        })
+    }
+}
+
+/// Whether `keyword` occurs in `task` as whole words, ignoring case
+fn keyword_matches(keyword: &str, task: &str) -> bool {
+    let words: Vec<String> = keyword.split_whitespace().map(regex::escape).collect();
+    Regex::new(&format!(r"(?i)\b{}\b", words.join(r"\s+")))
+        .map(|re| re.is_match(task))
+        .unwrap_or(false)
+}
+
 /// Get iteration limits for a complexity level
 ///
 /// Returns (planning_iterations, building_iterations)
@@ -413,4 +732,152 @@ mod tests {
         assert_eq!(get_iteration_limits(&Complexity::Standard), (5, 20));
         assert_eq!(get_iteration_limits(&Complexity::Critical), (8, 40));
     }
+
+    #[test]
+    fn test_iterations_config_keeps_unset_cells() {
+        let config: IterationsConfig = toml::from_str("[building]\ncritical = 60\n").unwrap();
+        assert_eq!(
+            config.max_iterations(&WorkflowMode::Building, &Complexity::Critical),
+            60
+        );
+        assert_eq!(
+            config.max_iterations(&WorkflowMode::Building, &Complexity::Standard),
+            20
+        );
+        assert_eq!(
+            config.max_iterations(&WorkflowMode::Planning, &Complexity::Critical),
+            8
+        );
+        assert!(toml::from_str::<IterationsConfig>("[building]\nhuge = 3\n").is_err());
+
+        let zero: IterationsConfig = toml::from_str("[planning]\ntrivial = 0\n").unwrap();
+        assert_eq!(
+            zero.validate().unwrap_err(),
+            "iterations.planning.trivial must be at least 1"
+        );
+    }
+
+    fn taxonomy() -> ComplexityConfig {
+        toml::from_str(
+            r#"
+default = "normal"
+
+[[levels]]
+name = "risky"
+keywords = ["payments", "data loss"]
+multiplier = 2.5
+
+[[levels]]
+name = "chore"
+keywords = ["bump", "lint"]
+multiplier = 0.25
+
+[[levels]]
+name = "normal"
+multiplier = 1.0
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_custom_taxonomy_detection() {
+        let config = taxonomy();
+        config.validate().unwrap();
+        assert_eq!(config.detect("Bump serde"), "chore");
+        assert_eq!(config.detect("lint payments module"), "risky");
+        assert_eq!(config.detect("prevent DATA   loss on retry"), "risky");
+        assert_eq!(config.detect("linter setup"), "normal");
+        assert_eq!(config.names(), vec!["risky", "chore", "normal"]);
+
+        let builtin = ComplexityConfig::default();
+        assert_eq!(builtin.detect("fix typo"), "trivial");
+        assert_eq!(builtin.names().len(), 4);
+    }
+
+    #[test]
+    fn test_custom_taxonomy_iterations() {
+        let config = taxonomy();
+        let table = IterationsConfig::default();
+        let building = |name: &str| config.max_iterations(&table, &WorkflowMode::Building, name);
+        assert_eq!(building("risky"), Ok(50));
+        assert_eq!(building("CHORE"), Ok(5));
+        assert_eq!(building("normal"), Ok(20));
+        assert!(building("critical")
+            .unwrap_err()
+            .contains("configured: risky"));
+        assert_eq!(
+            config.max_iterations(&table, &WorkflowMode::Paused, "risky"),
+            Ok(0)
+        );
+
+        // Every level detection can return has a limit
+        for task in ["bump", "payments", "anything else"] {
+            assert!(building(&config.detect(task)).is_ok());
+        }
+
+        let builtin = ComplexityConfig::default();
+        assert_eq!(
+            builtin.max_iterations(&table, &WorkflowMode::Planning, "critical"),
+            Ok(8)
+        );
+    }
+
+    #[test]
+    fn test_custom_taxonomy_validation() {
+        let mut config = taxonomy();
+        config.default = Some("huge".to_string());
+        assert!(config
+            .validate()
+            .unwrap_err()
+            .contains("not one of the levels"));
+
+        let mut config = taxonomy();
+        config.default = None;
+        assert!(config
+            .validate()
+            .unwrap_err()
+            .contains("default is required"));
+
+        let mut config = taxonomy();
+        config.levels[1].keywords.clear();
+        assert!(config.validate().unwrap_err().contains("never be detected"));
+
+        let mut config = taxonomy();
+        config.levels[1].name = "risky".to_string();
+        assert!(config.validate().unwrap_err().contains("defined twice"));
+
+        let mut config = taxonomy();
+        config.levels[0].multiplier = 0.0;
+        assert!(config
+            .validate()
+            .unwrap_err()
+            .contains("positive multiplier"));
+
+        let orphan_default = ComplexityConfig {
+            default: Some("normal".to_string()),
+            levels: Vec::new(),
+        };
+        assert!(orphan_default.validate().is_err());
+    }
+
+    #[test]
+    fn test_explain() {
+        let config: IterationsConfig = toml::from_str("[building]\ncritical = 60\n").unwrap();
+        let explanation = config.explain(&WorkflowMode::Planning, &Complexity::Critical);
+        assert_eq!(explanation.max_iterations, 8);
+        assert_eq!(explanation.row, (8, 60));
+        assert_eq!(
+            explanation.overrides,
+            vec![IterationOverride {
+                cell: "building.critical".to_string(),
+                value: 60,
+                default: 40,
+            }]
+        );
+        assert!(IterationsConfig::default()
+            .explain(&WorkflowMode::Building, &Complexity::Simple)
+            .overrides
+            .is_empty());
+    }
 }
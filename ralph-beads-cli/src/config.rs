@@ -0,0 +1,451 @@
+//! Project configuration for Ralph-Beads
+//!
+//! Settings live in `.ralph-beads.toml` at the project root (or any parent
+//! directory). Every section is optional; missing keys fall back to the
+//! built-in defaults.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::beads::BdConfig;
+use crate::beads_state::StateConfig;
+use crate::complexity::{ComplexityConfig, IterationsConfig};
+use crate::cost::CostConfig;
+use crate::hooks::HooksConfig;
+use crate::limits::LimitsConfig;
+use crate::lint::LintConfig;
+use crate::retention::ActivityConfig;
+use crate::stop::StopConfig;
+use crate::toolchain::ToolchainConfig;
+use crate::workspace::WorkspaceConfig;
+use crate::worktree::WorktreeConfig;
+
+/// Name of the per-project configuration file
+pub const CONFIG_FILE: &str = ".ralph-beads.toml";
+
+/// Top-level configuration
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub activity: ActivityConfig,
+    pub lint: LintConfig,
+    pub state: StateConfig,
+    pub hooks: HooksConfig,
+    pub cost: CostConfig,
+    pub bd: BdConfig,
+    pub limits: LimitsConfig,
+    pub iterations: IterationsConfig,
+    pub complexity: ComplexityConfig,
+    pub workspace: WorkspaceConfig,
+    pub worktree: WorktreeConfig,
+    pub stop: StopConfig,
+    pub toolchain: ToolchainConfig,
+}
+
+/// Configuration together with where it was loaded from
+#[derive(Debug, Clone, Default)]
+pub struct LoadedConfig {
+    pub config: Config,
+    /// File the configuration was read from, `None` when using defaults
+    pub source: Option<PathBuf>,
+}
+
+impl LoadedConfig {
+    /// Human-readable description of the applied configuration
+    pub fn source_description(&self) -> String {
+        match self.source {
+            Some(ref path) => path.display().to_string(),
+            None => "built-in defaults".to_string(),
+        }
+    }
+}
+
+impl Config {
+    /// Validation errors across all sections (empty when valid)
+    pub fn validation_errors(&self) -> Vec<String> {
+        [
+            ("activity", self.activity.validate()),
+            ("lint", self.lint.validate()),
+            ("state", self.state.validate()),
+            ("hooks", self.hooks.validate()),
+            ("cost", self.cost.validate()),
+            ("bd", self.bd.validate()),
+            ("limits", self.limits.validate()),
+            ("iterations", self.iterations.validate()),
+            ("complexity", self.complexity.validate()),
+            ("workspace", self.workspace.validate()),
+            ("worktree", self.worktree.validate()),
+        ]
+        .into_iter()
+        .filter_map(|(section, result)| result.err().map(|e| format!("[{}] {}", section, e)))
+        .collect()
+    }
+}
+
+/// Parse configuration from TOML text
+pub fn parse_config(content: &str) -> Result<Config, String> {
+    let config: Config =
+        toml::from_str(content).map_err(|e| format!("Invalid configuration: {}", e))?;
+    match config.validation_errors().into_iter().next() {
+        Some(e) => Err(e),
+        None => Ok(config),
+    }
+}
+
+/// Find the nearest `.ralph-beads.toml` in `dir` or its ancestors
+pub fn find_config_file(dir: &Path) -> Option<PathBuf> {
+    let start = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    start
+        .ancestors()
+        .map(|d| d.join(CONFIG_FILE))
+        .find(|p| p.is_file())
+}
+
+/// Load the configuration that applies to `dir`
+pub fn load_config(dir: &Path) -> Result<LoadedConfig, String> {
+    let Some(path) = find_config_file(dir) else {
+        return Ok(LoadedConfig::default());
+    };
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let config = parse_config(&content).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    Ok(LoadedConfig {
+        config,
+        source: Some(path),
+    })
+}
+
+/// Where an effective setting came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Provenance {
+    /// Built-in default
+    Default,
+    /// `.ralph-beads.toml`
+    File,
+    /// Command-line override (e.g. `--bd-timeout`)
+    Flag,
+}
+
+impl std::fmt::Display for Provenance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Provenance::Default => "default",
+            Provenance::File => "file",
+            Provenance::Flag => "flag",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// One effective setting, keyed by its dotted path (e.g. `bd.timeout_secs`)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigEntry {
+    pub key: String,
+    pub value: Value,
+    pub source: Provenance,
+}
+
+/// Result of validating the configuration that applies to a directory
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigCheck {
+    /// File the configuration was read from, `None` when using defaults
+    pub file: Option<PathBuf>,
+    pub valid: bool,
+    pub errors: Vec<String>,
+    /// Effective settings; defaults stand in for sections that failed to parse
+    pub entries: Vec<ConfigEntry>,
+}
+
+impl ConfigCheck {
+    /// Record a command-line override of `key`
+    pub fn apply_override(&mut self, key: &str, value: Value) {
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.key == key) {
+            entry.value = value;
+            entry.source = Provenance::Flag;
+        }
+    }
+}
+
+/// Dotted key for a path, quoting segments that contain dots
+fn dotted_key(path: &[String]) -> String {
+    path.iter()
+        .map(|p| {
+            if p.contains('.') {
+                format!("\"{}\"", p)
+            } else {
+                p.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Flatten `value` into leaf entries; `raw` is the file's table at the same path
+fn flatten(
+    path: &mut Vec<String>,
+    value: &Value,
+    raw: Option<&toml::Value>,
+    out: &mut Vec<ConfigEntry>,
+) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                path.push(key.clone());
+                let raw_child = raw.and_then(|r| r.get(key));
+                flatten(path, child, raw_child, out);
+                path.pop();
+            }
+        }
+        _ => out.push(ConfigEntry {
+            key: dotted_key(path),
+            value: value.clone(),
+            source: if raw.is_some() {
+                Provenance::File
+            } else {
+                Provenance::Default
+            },
+        }),
+    }
+}
+
+/// Effective settings of `config`, marking keys present in `raw` as from the file
+pub fn effective_entries(config: &Config, raw: Option<&toml::Value>) -> Vec<ConfigEntry> {
+    let value = serde_json::to_value(config).unwrap_or(Value::Null);
+    let mut entries = Vec::new();
+    flatten(&mut Vec::new(), &value, raw, &mut entries);
+    entries
+}
+
+/// Validate the configuration that applies to `dir`, collecting every error
+pub fn check_config(dir: &Path) -> ConfigCheck {
+    let file = find_config_file(dir);
+    let mut errors = Vec::new();
+    let mut config = Config::default();
+    let mut raw = None;
+
+    if let Some(ref path) = file {
+        match fs::read_to_string(path) {
+            Err(e) => errors.push(format!("Failed to read {}: {}", path.display(), e)),
+            Ok(content) => match toml::from_str::<toml::Value>(&content) {
+                Err(e) => errors.push(format!("Invalid TOML: {}", e)),
+                Ok(value) => {
+                    match Config::deserialize(value.clone()) {
+                        Ok(parsed) => {
+                            errors.extend(parsed.validation_errors());
+                            config = parsed;
+                        }
+                        Err(e) => errors.push(format!("Invalid configuration: {}", e)),
+                    }
+                    raw = Some(value);
+                }
+            },
+        }
+    }
+
+    ConfigCheck {
+        file,
+        valid: errors.is_empty(),
+        errors,
+        entries: effective_entries(&config, raw.as_ref()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lint::Severity;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_empty_config_uses_defaults() {
+        let config = parse_config("").unwrap();
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn test_parse_lint_section() {
+        let config = parse_config(
+            r#"
+            [lint]
+            stale_days = 30
+
+            [lint.rules.stale]
+            severity = "error"
+
+            [lint.rules.required-sections]
+            enabled = false
+
+            [lint.required_sections]
+            bug = ["description", "Steps to Reproduce"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.lint.stale_days, 30);
+        assert_eq!(config.lint.rules["stale"].severity, Some(Severity::Error));
+        assert_eq!(config.lint.rules["required-sections"].enabled, Some(false));
+        assert_eq!(
+            config.lint.required_sections["bug"],
+            vec!["description".to_string(), "Steps to Reproduce".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_parse_state_section() {
+        let config = parse_config(
+            r#"
+            [state.dimensions.review]
+            values = ["pending", "approved", "rejected"]
+            initial = ["pending"]
+            transitions = { pending = ["approved", "rejected"], rejected = ["pending"] }
+            "#,
+        )
+        .unwrap();
+
+        let review = &config.state.dimensions["review"];
+        assert_eq!(review.values.len(), 3);
+        assert_eq!(review.transitions["pending"].len(), 2);
+
+        let err = parse_config(
+            "[state.dimensions.review]
+values = [\"a\"]
+initial = [\"b\"]
+",
+        )
+        .unwrap_err();
+        assert!(err.contains("unknown value 'b'"));
+    }
+
+    #[test]
+    fn test_parse_hooks_section() {
+        let config = parse_config(
+            r#"
+            [hooks]
+            on-failure = ["notify-send 'ralph failed'"]
+            post-iteration = ["./scripts/record.sh", "git status --short"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.hooks.on_failure.len(), 1);
+        assert_eq!(config.hooks.post_iteration.len(), 2);
+        assert!(parse_config(
+            "[hooks]
+on-success = [\"x\"]\n"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_cost_section() {
+        let config = parse_config(
+            r#"
+            [cost]
+            max_cost_usd = 10.0
+
+            [cost.models.sonnet]
+            input = 3.0
+            output = 15.0
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.cost.max_cost_usd, Some(10.0));
+        assert_eq!(config.cost.price("sonnet", 1_000_000, 0), Some(3.0));
+        assert!(parse_config("[cost]\nmax_cost_usd = -1.0\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_bd_section() {
+        let config = parse_config("[bd]\ntimeout_secs = 5\nretries = 0\n").unwrap();
+        assert_eq!(config.bd.timeout_secs, 5);
+        assert_eq!(config.bd.retries, 0);
+        assert_eq!(config.bd.backoff_ms, BdConfig::default().backoff_ms);
+        assert!(parse_config("[bd]\ntimeout_secs = 0\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_limits_section() {
+        let config =
+            parse_config("[limits]\nmax_concurrent = 4\nspawns_per_minute = 120\n").unwrap();
+        assert_eq!(config.limits.max_concurrent, 4);
+        assert_eq!(config.limits.spawns_per_minute, Some(120));
+        assert!(parse_config("[limits]\nspawns_per_minute = 0\n").is_err());
+    }
+
+    #[test]
+    fn test_unknown_rule_rejected() {
+        let err = parse_config("[lint.rules.no-such-rule]\nenabled = false\n").unwrap_err();
+        assert!(err.contains("no-such-rule"));
+    }
+
+    #[test]
+    fn test_unknown_section_rejected() {
+        assert!(parse_config("[bogus]\nkey = 1\n").is_err());
+    }
+
+    #[test]
+    fn test_load_config_searches_ancestors() {
+        let dir = TempDir::new().unwrap();
+        let nested = dir.path().join("a/b");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(dir.path().join(CONFIG_FILE), "[lint]\nstale_days = 7\n").unwrap();
+
+        let loaded = load_config(&nested).unwrap();
+
+        assert_eq!(loaded.config.lint.stale_days, 7);
+        assert!(loaded.source.unwrap().ends_with(CONFIG_FILE));
+    }
+
+    #[test]
+    fn test_load_config_without_file() {
+        let dir = TempDir::new().unwrap();
+
+        let loaded = load_config(dir.path()).unwrap();
+
+        assert!(loaded.source.is_none());
+        assert_eq!(loaded.source_description(), "built-in defaults");
+    }
+
+    #[test]
+    fn test_check_config_reports_provenance() {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join(CONFIG_FILE),
+            "[lint]\nstale_days = 7\n\n[cost.models.\"gpt-4.1\"]\ninput = 2.0\noutput = 8.0\n",
+        )
+        .unwrap();
+
+        let mut check = check_config(dir.path());
+        check.apply_override("bd.timeout_secs", serde_json::json!(5));
+
+        assert!(check.valid);
+        let source = |key: &str| check.entries.iter().find(|e| e.key == key).unwrap().source;
+        assert_eq!(source("lint.stale_days"), Provenance::File);
+        assert_eq!(source("cost.models.\"gpt-4.1\".input"), Provenance::File);
+        assert_eq!(source("bd.retries"), Provenance::Default);
+        assert_eq!(source("bd.timeout_secs"), Provenance::Flag);
+    }
+
+    #[test]
+    fn test_check_config_collects_all_errors() {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join(CONFIG_FILE),
+            "[cost]\nmax_cost_usd = -1.0\n\n[bd]\ntimeout_secs = 0\n",
+        )
+        .unwrap();
+
+        let check = check_config(dir.path());
+
+        assert!(!check.valid);
+        assert_eq!(check.errors.len(), 2);
+        assert!(check.errors[0].starts_with("[cost]"));
+        assert!(check.errors[1].starts_with("[bd]"));
+    }
+}
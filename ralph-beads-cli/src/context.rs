@@ -0,0 +1,391 @@
+//! Agent context compilation
+//!
+//! Renders a prompt-ready markdown blob for one issue from a minijinja
+//! template. Templates see:
+//!
+//! - `issue`: the issue as reported by `bd show`
+//! - `state`: each state dimension's current value (missing when unset)
+//! - `gates`: the latest gate event per gate (e.g. lint) for the issue
+//! - `activity`: the issue's most recent activity events, oldest first
+//! - `generated_at`: RFC 3339 time of compilation
+//!
+//! `--template NAME` loads `.ralph-beads/templates/NAME.md` if present, then
+//! falls back to the built-in `builder` and `planner` templates; a path to a
+//! file is used as-is.
+//!
+//! With a token budget, the oldest activity events and then the oldest gate
+//! results are dropped until the rendered context fits; the issue itself is
+//! always kept.
+
+use minijinja::syntax::SyntaxConfig;
+use minijinja::value::Serde;
+use minijinja::Environment;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::activity::{format_timestamp, now_secs, ActivityEvent, EventKind};
+use crate::beads::Issue;
+
+/// Project directory holding user templates
+pub const TEMPLATE_DIR: &str = ".ralph-beads/templates";
+
+const BUILDER_TEMPLATE: &str = r#"# {{ issue.id }}: {{ issue.title }}
+
+Type: {{ issue.issue_type }} | Status: {{ issue.status }}{% if issue.priority is not none %} | Priority: P{{ issue.priority }}{% endif +%}
+{% if state %}
+State: {% for name, value in state|items %}{{ name }}={{ value }}{% if not loop.last %}, {% endif %}{% endfor +%}
+{% endif %}
+
+## Task
+
+{{ issue.description or "(no description)" }}
+{% if issue.acceptance_criteria %}
+
+## Acceptance Criteria
+
+{{ issue.acceptance_criteria }}
+{% endif %}
+{% if issue.design %}
+
+## Design
+
+{{ issue.design }}
+{% endif %}
+{% if gates %}
+
+## Gates
+
+{% for gate in gates %}
+- {{ gate.data.gate }}: {{ gate.message }}
+{% endfor %}
+{% endif %}
+{% if activity %}
+
+## Recent Activity
+
+{% for event in activity %}
+- [{{ event.kind }}] {{ event.message }}
+{% endfor %}
+{% endif %}
+"#;
+
+const PLANNER_TEMPLATE: &str = r#"# Plan {{ issue.id }}: {{ issue.title }}
+
+{{ issue.description or "(no description)" }}
+{% if issue.acceptance_criteria %}
+
+## Acceptance Criteria
+
+{{ issue.acceptance_criteria }}
+{% endif %}
+{% if issue.dependencies %}
+
+## Dependencies
+
+{% for dep in issue.dependencies %}
+- {{ dep.type }}: {{ dep.depends_on_id }}
+{% endfor %}
+{% endif %}
+{% if issue.notes %}
+
+## Notes
+
+{{ issue.notes }}
+{% endif %}
+
+Break this into phases of small, independently testable tasks.
+"#;
+
+/// Built-in template names
+pub const BUILTIN_TEMPLATES: [&str; 2] = ["builder", "planner"];
+
+fn builtin_template(name: &str) -> Option<&'static str> {
+    match name {
+        "builder" => Some(BUILDER_TEMPLATE),
+        "planner" => Some(PLANNER_TEMPLATE),
+        _ => None,
+    }
+}
+
+/// Everything a template can reference
+#[derive(Debug, Clone, Serialize)]
+pub struct ContextData {
+    pub issue: Issue,
+    pub state: BTreeMap<String, String>,
+    pub gates: Vec<ActivityEvent>,
+    pub activity: Vec<ActivityEvent>,
+    pub generated_at: String,
+}
+
+impl ContextData {
+    /// Assemble the context for `issue` from its state and the activity feed
+    pub fn new(
+        issue: Issue,
+        state: BTreeMap<String, String>,
+        events: &[ActivityEvent],
+        activity_limit: usize,
+    ) -> Self {
+        let events: Vec<&ActivityEvent> = events.iter().filter(|e| e.issue == issue.id).collect();
+
+        // Latest event per gate, in the order the gates last reported
+        let mut latest: BTreeMap<String, &ActivityEvent> = BTreeMap::new();
+        for event in events.iter().filter(|e| e.kind == EventKind::Gate) {
+            let gate = event
+                .data
+                .as_ref()
+                .and_then(|d| d["gate"].as_str())
+                .unwrap_or("gate");
+            latest.insert(gate.to_string(), event);
+        }
+        let mut gates: Vec<ActivityEvent> = latest.into_values().cloned().collect();
+        gates.sort_by_key(|e| e.id);
+
+        let skip = events.len().saturating_sub(activity_limit);
+        ContextData {
+            issue,
+            state,
+            gates,
+            activity: events.into_iter().skip(skip).cloned().collect(),
+            generated_at: format_timestamp(now_secs()),
+        }
+    }
+}
+
+/// Resolve `name` to template source: a file path, a project template, or a built-in
+pub fn load_template(dir: &Path, name: &str) -> Result<String, String> {
+    let as_path = Path::new(name);
+    let candidates: Vec<PathBuf> = if name.contains('/') || as_path.extension().is_some() {
+        vec![as_path.to_path_buf(), dir.join(as_path)]
+    } else {
+        vec![dir.join(TEMPLATE_DIR).join(format!("{}.md", name))]
+    };
+    if let Some(path) = candidates.iter().find(|p| p.is_file()) {
+        return fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e));
+    }
+    builtin_template(name).map(str::to_string).ok_or_else(|| {
+        format!(
+            "Unknown template: {} (built-in: {}; or add {}/{}.md)",
+            name,
+            BUILTIN_TEMPLATES.join(", "),
+            TEMPLATE_DIR,
+            name
+        )
+    })
+}
+
+/// Template names usable with `--template`: built-ins plus project templates
+pub fn available_templates(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = BUILTIN_TEMPLATES.iter().map(|n| n.to_string()).collect();
+    if let Ok(entries) = fs::read_dir(dir.join(TEMPLATE_DIR)) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|e| e == "md") {
+                if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                    names.push(stem.to_string());
+                }
+            }
+        }
+    }
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Render `template` with `data`
+pub fn render(template: &str, data: &ContextData) -> Result<String, String> {
+    let mut env = Environment::new();
+    env.set_syntax(
+        SyntaxConfig::builder()
+            .trim_blocks(true)
+            .lstrip_blocks(true)
+            .build()
+            .map_err(|e| e.to_string())?,
+    );
+    env.render_str(template, Serde(data))
+        .map_err(|e| format!("Template error: {}", e))
+}
+
+/// Rough token count for budgeting: about four characters per token
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Outcome of rendering within a token budget
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BudgetedRender {
+    pub output: String,
+    pub tokens: usize,
+    /// Whether the output fits the budget (the issue alone may not)
+    pub fits: bool,
+    /// Activity event IDs left out, oldest first
+    pub excluded_activity: Vec<u64>,
+    /// Gate event IDs left out, oldest first
+    pub excluded_gates: Vec<u64>,
+}
+
+/// Render `template`, dropping the least recent activity and gates until
+/// the result fits `max_tokens`
+pub fn render_within(
+    template: &str,
+    data: &ContextData,
+    max_tokens: usize,
+) -> Result<BudgetedRender, String> {
+    let mut data = data.clone();
+    let mut excluded_activity = Vec::new();
+    let mut excluded_gates = Vec::new();
+    loop {
+        let output = render(template, &data)?;
+        let tokens = estimate_tokens(&output);
+        let fits = tokens <= max_tokens;
+        if fits || (data.activity.is_empty() && data.gates.is_empty()) {
+            return Ok(BudgetedRender {
+                output,
+                tokens,
+                fits,
+                excluded_activity,
+                excluded_gates,
+            });
+        }
+        if !data.activity.is_empty() {
+            excluded_activity.push(data.activity.remove(0).id);
+        } else {
+            excluded_gates.push(data.gates.remove(0).id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn event(id: u64, issue: &str, kind: EventKind, message: &str) -> ActivityEvent {
+        ActivityEvent {
+            id,
+            timestamp: 1_768_176_000 + id,
+            issue: issue.to_string(),
+            kind,
+            message: message.to_string(),
+            worker: None,
+            run: None,
+            data: None,
+        }
+    }
+
+    fn gate(id: u64, issue: &str, name: &str, message: &str) -> ActivityEvent {
+        ActivityEvent {
+            data: Some(json!({ "gate": name })),
+            ..event(id, issue, EventKind::Gate, message)
+        }
+    }
+
+    fn issue() -> Issue {
+        Issue {
+            id: "bd-1".to_string(),
+            title: "Add login".to_string(),
+            description: "Build the login form".to_string(),
+            acceptance_criteria: "- [ ] Form validates".to_string(),
+            status: "in_progress".to_string(),
+            issue_type: "task".to_string(),
+            priority: Some(1),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_context_data_selects_issue_events() {
+        let events = vec![
+            gate(1, "bd-1", "lint", "lint failed (1 errors, 0 warnings)"),
+            event(2, "bd-2", EventKind::Progress, "other issue"),
+            event(3, "bd-1", EventKind::Progress, "half done"),
+            gate(4, "bd-1", "lint", "lint passed (0 warnings)"),
+            event(5, "bd-1", EventKind::Progress, "tests pass"),
+        ];
+
+        let data = ContextData::new(issue(), BTreeMap::new(), &events, 2);
+
+        assert_eq!(data.gates.len(), 1);
+        assert_eq!(data.gates[0].message, "lint passed (0 warnings)");
+        let ids: Vec<u64> = data.activity.iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![4, 5]);
+    }
+
+    #[test]
+    fn test_render_builder() {
+        let mut state = BTreeMap::new();
+        state.insert("mode".to_string(), "building".to_string());
+        let events = vec![gate(1, "bd-1", "lint", "lint passed (0 warnings)")];
+        let data = ContextData::new(issue(), state, &events, 10);
+
+        let out = render(&load_template(Path::new("."), "builder").unwrap(), &data).unwrap();
+
+        assert!(out.starts_with("# bd-1: Add login\n"));
+        assert!(out.contains("Priority: P1"));
+        assert!(out.contains("State: mode=building"));
+        assert!(out.contains("## Acceptance Criteria\n\n- [ ] Form validates"));
+        assert!(out.contains("- lint: lint passed (0 warnings)"));
+        assert!(!out.contains("## Design"));
+    }
+
+    #[test]
+    fn test_load_template_prefers_project_file() {
+        let dir = TempDir::new().unwrap();
+        let templates = dir.path().join(TEMPLATE_DIR);
+        fs::create_dir_all(&templates).unwrap();
+        fs::write(templates.join("builder.md"), "custom {{ issue.id }}").unwrap();
+        fs::write(dir.path().join("one-off.j2"), "file {{ issue.title }}").unwrap();
+
+        let data = ContextData::new(issue(), BTreeMap::new(), &[], 10);
+        let custom = load_template(dir.path(), "builder").unwrap();
+        assert_eq!(render(&custom, &data).unwrap(), "custom bd-1");
+        let file = load_template(dir.path(), "one-off.j2").unwrap();
+        assert_eq!(render(&file, &data).unwrap(), "file Add login");
+
+        let err = load_template(dir.path(), "reviewer").unwrap_err();
+        assert!(err.contains("builder, planner"));
+        assert_eq!(available_templates(dir.path()), vec!["builder", "planner"]);
+    }
+
+    #[test]
+    fn test_render_reports_template_errors() {
+        let data = ContextData::new(issue(), BTreeMap::new(), &[], 10);
+        assert!(render("{% if %}", &data)
+            .unwrap_err()
+            .starts_with("Template error"));
+    }
+
+    #[test]
+    fn test_render_within_drops_oldest_activity_first() {
+        let events = vec![
+            gate(1, "bd-1", "lint", "lint passed (0 warnings)"),
+            event(
+                2,
+                "bd-1",
+                EventKind::Progress,
+                &"long progress note ".repeat(20),
+            ),
+            event(3, "bd-1", EventKind::Progress, "tests pass"),
+        ];
+        let data = ContextData::new(issue(), BTreeMap::new(), &events, 10);
+        let template = load_template(Path::new("."), "builder").unwrap();
+        let full = estimate_tokens(&render(&template, &data).unwrap());
+
+        let roomy = render_within(&template, &data, full).unwrap();
+        assert!(roomy.fits && roomy.excluded_activity.is_empty());
+
+        let tight = render_within(&template, &data, full - 50).unwrap();
+        assert!(tight.fits);
+        assert_eq!(tight.excluded_activity, vec![1, 2]);
+        assert!(tight.output.contains("tests pass"));
+        assert!(tight.excluded_gates.is_empty());
+
+        let tiny = render_within(&template, &data, 1).unwrap();
+        assert!(!tiny.fits);
+        assert_eq!(tiny.excluded_gates, vec![1]);
+        assert!(tiny.output.contains("# bd-1: Add login"));
+    }
+}
@@ -0,0 +1,428 @@
+//! Cost tracking for agent runs
+//!
+//! Each model call (or batch of calls) is appended to a JSONL ledger
+//! (`.beads/costs.jsonl` by default) with its token counts. Prices per
+//! million tokens come from the `[cost.models]` section of
+//! `.ralph-beads.toml`; entries for unpriced models keep their token counts
+//! and are flagged in reports rather than silently counted as free.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::activity::{append_json_line, now_secs};
+use crate::timeline::in_epic;
+
+/// Default location of the cost ledger, relative to the project root
+/// (`--data-dir` moves it; see [`crate::data_dirs`])
+pub const DEFAULT_COST_FILE: &str = ".beads/costs.jsonl";
+
+/// Price of a model in USD per million tokens
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+}
+
+/// Whether `amount` is a usable price or cost (finite and non-negative)
+fn valid_amount(amount: f64) -> bool {
+    amount.is_finite() && amount >= 0.0
+}
+
+/// The `[cost]` configuration section
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CostConfig {
+    /// Budget in USD above which `cost check` fails
+    pub max_cost_usd: Option<f64>,
+    /// Prices keyed by model name
+    pub models: BTreeMap<String, ModelPrice>,
+}
+
+impl CostConfig {
+    /// Reject negative prices and budgets
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_cost_usd.is_some_and(|m| !valid_amount(m)) {
+            return Err("cost.max_cost_usd must be a non-negative number".to_string());
+        }
+        for (model, price) in &self.models {
+            if !valid_amount(price.input) || !valid_amount(price.output) {
+                return Err(format!("cost.models.{} has a negative price", model));
+            }
+        }
+        Ok(())
+    }
+
+    /// Cost of a call, if the model is priced
+    pub fn price(&self, model: &str, tokens_in: u64, tokens_out: u64) -> Option<f64> {
+        self.models
+            .get(model)
+            .map(|p| (tokens_in as f64 * p.input + tokens_out as f64 * p.output) / 1_000_000.0)
+    }
+}
+
+/// One ledger entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostEntry {
+    /// Unix timestamp (seconds)
+    pub timestamp: u64,
+    pub session: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issue: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worker: Option<String>,
+    pub model: String,
+    pub tokens_in: u64,
+    pub tokens_out: u64,
+    /// Cost in USD; `None` when the model has no configured price
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+}
+
+/// Fields for a new ledger entry
+#[derive(Debug, Clone, Default)]
+pub struct NewCost {
+    pub session: String,
+    pub issue: Option<String>,
+    pub worker: Option<String>,
+    pub model: String,
+    pub tokens_in: u64,
+    pub tokens_out: u64,
+    /// Explicit cost, overriding the configured price
+    pub cost_usd: Option<f64>,
+}
+
+/// Price and append an entry to the ledger
+pub fn record_cost(
+    cost_file: &Path,
+    config: &CostConfig,
+    new: NewCost,
+) -> Result<CostEntry, String> {
+    if new.session.trim().is_empty() {
+        return Err("Cost session must not be empty".to_string());
+    }
+    if new.model.trim().is_empty() {
+        return Err("Cost model must not be empty".to_string());
+    }
+    if new.cost_usd.is_some_and(|c| !valid_amount(c)) {
+        return Err("Cost must be a non-negative number".to_string());
+    }
+
+    let entry = CostEntry {
+        timestamp: now_secs(),
+        cost_usd: new
+            .cost_usd
+            .or_else(|| config.price(&new.model, new.tokens_in, new.tokens_out)),
+        session: new.session,
+        issue: new.issue,
+        worker: new.worker,
+        model: new.model,
+        tokens_in: new.tokens_in,
+        tokens_out: new.tokens_out,
+    };
+    append_json_line(cost_file, &entry)?;
+    Ok(entry)
+}
+
+/// Read the ledger (missing file = empty, malformed lines skipped)
+pub fn read_costs(cost_file: &Path) -> Result<Vec<CostEntry>, String> {
+    if !cost_file.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(cost_file)
+        .map_err(|e| format!("Failed to read {}: {}", cost_file.display(), e))?;
+
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Which entries a report or budget check covers
+#[derive(Debug, Clone, Default)]
+pub struct CostFilter {
+    pub session: Option<String>,
+    /// Epic ID; matches the epic and its hierarchical children
+    pub epic: Option<String>,
+    /// Only entries at or after this Unix time
+    pub since: Option<u64>,
+}
+
+impl CostFilter {
+    pub fn matches(&self, entry: &CostEntry) -> bool {
+        self.session.as_ref().is_none_or(|s| &entry.session == s)
+            && self.epic.as_ref().is_none_or(|epic| {
+                entry
+                    .issue
+                    .as_ref()
+                    .is_some_and(|issue| in_epic(issue, epic))
+            })
+            && self.since.is_none_or(|t| entry.timestamp >= t)
+    }
+}
+
+/// Token and cost totals for a group of entries
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CostTotals {
+    pub entries: usize,
+    pub tokens_in: u64,
+    pub tokens_out: u64,
+    pub cost_usd: f64,
+    /// Entries without a price, excluded from `cost_usd`
+    pub unpriced: usize,
+}
+
+impl CostTotals {
+    fn add(&mut self, entry: &CostEntry) {
+        self.entries += 1;
+        self.tokens_in += entry.tokens_in;
+        self.tokens_out += entry.tokens_out;
+        match entry.cost_usd {
+            Some(cost) => self.cost_usd += cost,
+            None => self.unpriced += 1,
+        }
+    }
+}
+
+/// Cost aggregated overall and per task, worker, and model
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CostReport {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub epic: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+    pub total: CostTotals,
+    pub by_task: BTreeMap<String, CostTotals>,
+    pub by_worker: BTreeMap<String, CostTotals>,
+    pub by_model: BTreeMap<String, CostTotals>,
+}
+
+/// Label for entries without an issue or worker
+const UNATTRIBUTED: &str = "(none)";
+
+/// Aggregate the entries matching `filter`
+pub fn build_report(entries: &[CostEntry], filter: &CostFilter) -> CostReport {
+    let mut report = CostReport {
+        epic: filter.epic.clone(),
+        session: filter.session.clone(),
+        ..Default::default()
+    };
+
+    for entry in entries.iter().filter(|e| filter.matches(e)) {
+        report.total.add(entry);
+        let task = entry.issue.as_deref().unwrap_or(UNATTRIBUTED);
+        report
+            .by_task
+            .entry(task.to_string())
+            .or_default()
+            .add(entry);
+        let worker = entry.worker.as_deref().unwrap_or(UNATTRIBUTED);
+        report
+            .by_worker
+            .entry(worker.to_string())
+            .or_default()
+            .add(entry);
+        report
+            .by_model
+            .entry(entry.model.clone())
+            .or_default()
+            .add(entry);
+    }
+    report
+}
+
+fn render_group(out: &mut String, heading: &str, groups: &BTreeMap<String, CostTotals>) {
+    if groups.is_empty() {
+        return;
+    }
+    out.push_str(&format!("\n{}:\n", heading));
+    let mut rows: Vec<(&String, &CostTotals)> = groups.iter().collect();
+    rows.sort_by(|a, b| b.1.cost_usd.total_cmp(&a.1.cost_usd).then(a.0.cmp(b.0)));
+    for (name, totals) in rows {
+        out.push_str(&format!(
+            "  {:<24} ${:>9.4}  {:>10} in  {:>10} out{}\n",
+            name,
+            totals.cost_usd,
+            totals.tokens_in,
+            totals.tokens_out,
+            if totals.unpriced > 0 {
+                format!("  ({} unpriced)", totals.unpriced)
+            } else {
+                String::new()
+            }
+        ));
+    }
+}
+
+/// Render a report as text
+pub fn render_text(report: &CostReport) -> String {
+    let scope = match (&report.epic, &report.session) {
+        (Some(epic), _) => format!("epic {}", epic),
+        (None, Some(session)) => format!("session {}", session),
+        (None, None) => "all runs".to_string(),
+    };
+    let mut out = format!(
+        "Cost for {}: ${:.4} ({} entries, {} in / {} out tokens)\n",
+        scope,
+        report.total.cost_usd,
+        report.total.entries,
+        report.total.tokens_in,
+        report.total.tokens_out
+    );
+    if report.total.unpriced > 0 {
+        out.push_str(&format!(
+            "Warning: {} entries use models without a price in [cost.models]\n",
+            report.total.unpriced
+        ));
+    }
+    render_group(&mut out, "By task", &report.by_task);
+    render_group(&mut out, "By worker", &report.by_worker);
+    render_group(&mut out, "By model", &report.by_model);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn config() -> CostConfig {
+        let mut models = BTreeMap::new();
+        models.insert(
+            "big".to_string(),
+            ModelPrice {
+                input: 3.0,
+                output: 15.0,
+            },
+        );
+        CostConfig {
+            max_cost_usd: Some(1.0),
+            models,
+        }
+    }
+
+    fn entry(issue: Option<&str>, worker: &str, model: &str, cost: Option<f64>) -> CostEntry {
+        CostEntry {
+            timestamp: 100,
+            session: "s1".to_string(),
+            issue: issue.map(String::from),
+            worker: Some(worker.to_string()),
+            model: model.to_string(),
+            tokens_in: 1000,
+            tokens_out: 100,
+            cost_usd: cost,
+        }
+    }
+
+    #[test]
+    fn test_price() {
+        let config = config();
+        assert_eq!(config.price("big", 1_000_000, 0), Some(3.0));
+        assert_eq!(config.price("big", 0, 2_000_000), Some(30.0));
+        assert_eq!(config.price("unknown", 1, 1), None);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(config().validate().is_ok());
+        let mut bad = config();
+        bad.models.get_mut("big").unwrap().input = -1.0;
+        assert!(bad.validate().unwrap_err().contains("cost.models.big"));
+        let bad = CostConfig {
+            max_cost_usd: Some(-5.0),
+            ..Default::default()
+        };
+        assert!(bad.validate().is_err());
+    }
+
+    #[test]
+    fn test_record_cost() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("costs.jsonl");
+
+        let priced = record_cost(
+            &file,
+            &config(),
+            NewCost {
+                session: "s1".to_string(),
+                model: "big".to_string(),
+                tokens_in: 1_000_000,
+                tokens_out: 100_000,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let unpriced = record_cost(
+            &file,
+            &config(),
+            NewCost {
+                session: "s1".to_string(),
+                model: "other".to_string(),
+                tokens_in: 10,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let explicit = record_cost(
+            &file,
+            &config(),
+            NewCost {
+                session: "s1".to_string(),
+                model: "other".to_string(),
+                cost_usd: Some(0.25),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(priced.cost_usd, Some(4.5));
+        assert_eq!(unpriced.cost_usd, None);
+        assert_eq!(explicit.cost_usd, Some(0.25));
+        assert_eq!(read_costs(&file).unwrap().len(), 3);
+
+        let err = record_cost(&file, &config(), NewCost::default()).unwrap_err();
+        assert!(err.contains("session"));
+    }
+
+    #[test]
+    fn test_build_report() {
+        let entries = vec![
+            entry(Some("bd-1.1"), "w1", "big", Some(0.5)),
+            entry(Some("bd-1.2"), "w2", "big", Some(0.25)),
+            entry(Some("bd-1.2"), "w2", "other", None),
+            entry(Some("bd-2.1"), "w1", "big", Some(9.0)),
+            entry(None, "w1", "big", Some(1.0)),
+        ];
+        let filter = CostFilter {
+            epic: Some("bd-1".to_string()),
+            ..Default::default()
+        };
+
+        let report = build_report(&entries, &filter);
+
+        assert_eq!(report.total.entries, 3);
+        assert_eq!(report.total.cost_usd, 0.75);
+        assert_eq!(report.total.unpriced, 1);
+        assert_eq!(report.by_task["bd-1.2"].entries, 2);
+        assert_eq!(report.by_worker["w1"].cost_usd, 0.5);
+        assert_eq!(report.by_model["other"].unpriced, 1);
+
+        let all = build_report(&entries, &CostFilter::default());
+        assert_eq!(all.total.cost_usd, 10.75);
+        assert_eq!(all.by_task[UNATTRIBUTED].entries, 1);
+    }
+
+    #[test]
+    fn test_render_text() {
+        let entries = vec![entry(Some("bd-1.1"), "w1", "other", None)];
+        let text = render_text(&build_report(&entries, &CostFilter::default()));
+
+        assert!(text.starts_with("Cost for all runs: $0.0000 (1 entries"));
+        assert!(text.contains("Warning: 1 entries use models without a price"));
+        assert!(text.contains("By worker:\n  w1"));
+    }
+}
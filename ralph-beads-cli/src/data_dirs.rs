@@ -0,0 +1,274 @@
+//! Where the CLI keeps its files
+//!
+//! Per-project state (session, activity feed, cost ledger, metrics) lives
+//! in the project's `.beads/` directory next to the beads database. Per-user
+//! data, state, and caches follow the XDG base directory spec under a
+//! `ralph-beads` subdirectory; lock files coordinating processes live in the
+//! user's runtime directory (`$XDG_RUNTIME_DIR`, else under the state
+//! directory), so other users can neither block nor hold them.
+//!
+//! `--data-dir` (or `RALPH_BEADS_DATA_DIR`) moves all of it under one
+//! directory, which keeps test runs and sandboxes from touching real state.
+
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Environment variable naming the data directory, like `--data-dir`
+pub const DATA_DIR_ENV: &str = "RALPH_BEADS_DATA_DIR";
+
+/// Subdirectory of each XDG base directory (and of the temp directory)
+pub const APP_DIR: &str = "ralph-beads";
+
+/// Per-project state directory, relative to the project
+pub const PROJECT_DIR: &str = ".beads";
+
+pub const SESSION_FILE: &str = "session.json";
+pub const FEED_FILE: &str = "activity.jsonl";
+pub const COST_FILE: &str = "costs.jsonl";
+pub const METRICS_FILE: &str = "metrics.jsonl";
+
+/// Effective locations for everything the CLI writes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DataDirs {
+    /// Per-project state: session, activity feed, costs, metrics
+    pub project: PathBuf,
+    /// Per-user data (`$XDG_DATA_HOME/ralph-beads`)
+    pub data: PathBuf,
+    /// Per-user state such as logs (`$XDG_STATE_HOME/ralph-beads`)
+    pub state: PathBuf,
+    /// Per-user caches (`$XDG_CACHE_HOME/ralph-beads`)
+    pub cache: PathBuf,
+    /// Per-user lock files shared between processes
+    /// (`$XDG_RUNTIME_DIR/ralph-beads`)
+    pub runtime: PathBuf,
+    /// Whether `--data-dir` or `RALPH_BEADS_DATA_DIR` chose the locations
+    pub overridden: bool,
+}
+
+/// One location as `info --paths` lists it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Location {
+    pub name: &'static str,
+    pub path: PathBuf,
+    pub purpose: &'static str,
+}
+
+impl DataDirs {
+    /// Locations for this process: `data_dir`, else `RALPH_BEADS_DATA_DIR`,
+    /// else the defaults
+    pub fn resolve(data_dir: Option<&Path>) -> Self {
+        Self::from_env(data_dir, |name| env::var_os(name))
+    }
+
+    /// [`DataDirs::resolve`] reading variables through `var`
+    pub fn from_env(data_dir: Option<&Path>, var: impl Fn(&str) -> Option<OsString>) -> Self {
+        let root = data_dir.map(Path::to_path_buf).or_else(|| {
+            var(DATA_DIR_ENV)
+                .filter(|v| !v.is_empty())
+                .map(PathBuf::from)
+        });
+        if let Some(root) = root {
+            return DataDirs {
+                project: root.clone(),
+                data: root.join("data"),
+                state: root.join("state"),
+                cache: root.join("cache"),
+                runtime: root.join("run"),
+                overridden: true,
+            };
+        }
+
+        let home = var("HOME").filter(|v| !v.is_empty()).map(PathBuf::from);
+        // The spec says relative values are invalid and must be ignored
+        let base = |name: &str, fallback: &str, kind: &str| {
+            var(name)
+                .map(PathBuf::from)
+                .filter(|p| p.is_absolute())
+                .or_else(|| home.as_ref().map(|h| h.join(fallback)))
+                .map(|base| base.join(APP_DIR))
+                .unwrap_or_else(|| env::temp_dir().join(APP_DIR).join(kind))
+        };
+        let state = base("XDG_STATE_HOME", ".local/state", "state");
+        let runtime = var("XDG_RUNTIME_DIR")
+            .map(PathBuf::from)
+            .filter(|p| p.is_absolute())
+            .map(|base| base.join(APP_DIR))
+            .unwrap_or_else(|| state.join("run"));
+        DataDirs {
+            project: PathBuf::from(PROJECT_DIR),
+            data: base("XDG_DATA_HOME", ".local/share", "data"),
+            state,
+            cache: base("XDG_CACHE_HOME", ".cache", "cache"),
+            runtime,
+            overridden: false,
+        }
+    }
+
+    pub fn session_file(&self) -> PathBuf {
+        self.project.join(SESSION_FILE)
+    }
+
+    pub fn feed_file(&self) -> PathBuf {
+        self.project.join(FEED_FILE)
+    }
+
+    pub fn cost_file(&self) -> PathBuf {
+        self.project.join(COST_FILE)
+    }
+
+    pub fn metrics_file(&self) -> PathBuf {
+        self.project.join(METRICS_FILE)
+    }
+
+    /// Lock files for subprocess limits
+    pub fn lock_dir(&self) -> PathBuf {
+        self.runtime.join("limits")
+    }
+
+    /// Every location, in the order `info --paths` shows them
+    pub fn locations(&self) -> Vec<Location> {
+        let location = |name, path, purpose| Location {
+            name,
+            path,
+            purpose,
+        };
+        vec![
+            location(
+                "project",
+                self.project.clone(),
+                "per-project state directory",
+            ),
+            location("session", self.session_file(), "workflow session state"),
+            location("activity", self.feed_file(), "activity feed"),
+            location("costs", self.cost_file(), "token cost ledger"),
+            location("metrics", self.metrics_file(), "workflow metrics"),
+            location("data", self.data.clone(), "per-user data"),
+            location("state", self.state.clone(), "per-user state"),
+            location("cache", self.cache.clone(), "per-user caches"),
+            location("runtime", self.runtime.clone(), "per-user runtime files"),
+            location("locks", self.lock_dir(), "subprocess limit lock files"),
+        ]
+    }
+}
+
+/// Create `dir` and missing parents, readable only by the current user
+pub fn create_private_dir(dir: &Path) -> io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(dir)
+}
+
+static DIRS: OnceCell<DataDirs> = OnceCell::new();
+
+/// Set the locations for this process (first call wins)
+pub fn configure(dirs: DataDirs) {
+    let _ = DIRS.set(dirs);
+}
+
+/// The process-wide locations
+pub fn dirs() -> &'static DataDirs {
+    DIRS.get_or_init(|| DataDirs::resolve(None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::activity::DEFAULT_FEED_FILE;
+    use crate::cost::DEFAULT_COST_FILE;
+    use crate::metrics::DEFAULT_METRICS_FILE;
+    use crate::state::DEFAULT_SESSION_FILE;
+    use std::collections::HashMap;
+
+    fn resolve(data_dir: Option<&str>, vars: &[(&str, &str)]) -> DataDirs {
+        let vars: HashMap<String, OsString> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), OsString::from(v)))
+            .collect();
+        DataDirs::from_env(data_dir.map(Path::new), |name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn test_defaults_follow_xdg() {
+        let dirs = resolve(
+            None,
+            &[("HOME", "/home/u"), ("XDG_CACHE_HOME", "/var/cache/u")],
+        );
+
+        assert_eq!(dirs.session_file(), PathBuf::from(DEFAULT_SESSION_FILE));
+        assert_eq!(dirs.feed_file(), PathBuf::from(DEFAULT_FEED_FILE));
+        assert_eq!(dirs.cost_file(), PathBuf::from(DEFAULT_COST_FILE));
+        assert_eq!(dirs.metrics_file(), PathBuf::from(DEFAULT_METRICS_FILE));
+        assert_eq!(dirs.data, PathBuf::from("/home/u/.local/share/ralph-beads"));
+        assert_eq!(
+            dirs.state,
+            PathBuf::from("/home/u/.local/state/ralph-beads")
+        );
+        assert_eq!(dirs.cache, PathBuf::from("/var/cache/u/ralph-beads"));
+        assert_eq!(
+            dirs.lock_dir(),
+            PathBuf::from("/home/u/.local/state/ralph-beads/run/limits")
+        );
+        assert!(!dirs.overridden);
+
+        let runtime = resolve(
+            None,
+            &[("HOME", "/home/u"), ("XDG_RUNTIME_DIR", "/run/user/1000")],
+        );
+        assert_eq!(
+            runtime.lock_dir(),
+            PathBuf::from("/run/user/1000/ralph-beads/limits")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_private_dir_mode() {
+        use std::os::unix::fs::PermissionsExt;
+        let tmp = tempfile::TempDir::new().unwrap();
+        let dir = tmp.path().join("run/limits");
+
+        create_private_dir(&dir).unwrap();
+
+        for path in [&dir, &tmp.path().join("run")] {
+            let mode = fs::metadata(path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+    }
+
+    #[test]
+    fn test_relative_xdg_values_are_ignored() {
+        let dirs = resolve(None, &[("HOME", "/home/u"), ("XDG_DATA_HOME", "data")]);
+        let homeless = resolve(None, &[]);
+
+        assert_eq!(dirs.data, PathBuf::from("/home/u/.local/share/ralph-beads"));
+        assert_eq!(homeless.data, env::temp_dir().join("ralph-beads/data"));
+    }
+
+    #[test]
+    fn test_data_dir_overrides_everything() {
+        let vars = [("HOME", "/home/u"), (DATA_DIR_ENV, "/env/dir")];
+
+        let from_env = resolve(None, &vars);
+        let from_flag = resolve(Some("/flag/dir"), &vars);
+
+        assert_eq!(
+            from_env.session_file(),
+            PathBuf::from("/env/dir/session.json")
+        );
+        assert_eq!(from_flag.project, PathBuf::from("/flag/dir"));
+        assert_eq!(from_flag.cache, PathBuf::from("/flag/dir/cache"));
+        assert_eq!(from_flag.lock_dir(), PathBuf::from("/flag/dir/run/limits"));
+        assert!(from_flag.overridden);
+        assert!(from_flag
+            .locations()
+            .iter()
+            .all(|l| l.path.starts_with("/flag/dir")));
+    }
+}
@@ -0,0 +1,458 @@
+//! Structured `git diff` summaries
+//!
+//! Parses unified diff output into per-file changes (status, hunks, lines
+//! added and removed), totals by language, renames, and paths that deserve
+//! a reviewer's attention: CI configuration, migrations, dependency
+//! manifests, infrastructure, and likely secrets. `diff summarize` prints
+//! it; `activity emit --diff` attaches it to an event so reviewers see what
+//! a task touched.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use crate::worktree::git;
+
+/// How a file changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileStatus {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+}
+
+impl fmt::Display for FileStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileStatus::Added => write!(f, "added"),
+            FileStatus::Modified => write!(f, "modified"),
+            FileStatus::Deleted => write!(f, "deleted"),
+            FileStatus::Renamed => write!(f, "renamed"),
+        }
+    }
+}
+
+/// One file in the diff
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileDiff {
+    /// Path after the change (before it, for deleted files)
+    pub path: String,
+    /// Path before a rename
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_path: Option<String>,
+    pub status: FileStatus,
+    /// Rename similarity, percent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub similarity: Option<u8>,
+    pub language: String,
+    pub hunks: u32,
+    pub added: u32,
+    pub removed: u32,
+    pub binary: bool,
+}
+
+/// Change totals for one language
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LanguageStats {
+    pub files: u32,
+    pub added: u32,
+    pub removed: u32,
+}
+
+/// A changed path matching a risk category
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RiskyPath {
+    pub path: String,
+    /// `ci`, `migration`, `dependencies`, `infrastructure`, or `secrets`
+    pub category: String,
+}
+
+/// Everything `diff summarize` reports
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffSummary {
+    pub files_changed: usize,
+    pub hunks: u32,
+    pub added: u32,
+    pub removed: u32,
+    pub languages: BTreeMap<String, LanguageStats>,
+    pub risky: Vec<RiskyPath>,
+    pub files: Vec<FileDiff>,
+}
+
+impl DiffSummary {
+    /// Renamed files as `(from, to, similarity)`
+    pub fn renames(&self) -> impl Iterator<Item = (&str, &str, Option<u8>)> {
+        self.files.iter().filter_map(|f| {
+            f.old_path
+                .as_deref()
+                .map(|old| (old, f.path.as_str(), f.similarity))
+        })
+    }
+}
+
+/// Language of `path`, by extension or well-known file name
+pub fn language_of(path: &str) -> &'static str {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    match name {
+        "Dockerfile" => return "Docker",
+        "Makefile" => return "Make",
+        "Cargo.lock" => return "TOML",
+        _ => {}
+    }
+    let Some((_, ext)) = name.rsplit_once('.') else {
+        return "Other";
+    };
+    match ext.to_ascii_lowercase().as_str() {
+        "rs" => "Rust",
+        "py" => "Python",
+        "ts" | "tsx" => "TypeScript",
+        "js" | "jsx" | "mjs" | "cjs" => "JavaScript",
+        "go" => "Go",
+        "java" => "Java",
+        "kt" | "kts" => "Kotlin",
+        "rb" => "Ruby",
+        "c" | "h" => "C",
+        "cc" | "cpp" | "cxx" | "hpp" => "C++",
+        "cs" => "C#",
+        "swift" => "Swift",
+        "sh" | "bash" | "zsh" => "Shell",
+        "sql" => "SQL",
+        "html" | "htm" => "HTML",
+        "css" | "scss" => "CSS",
+        "md" | "markdown" => "Markdown",
+        "toml" => "TOML",
+        "yaml" | "yml" => "YAML",
+        "json" | "jsonl" => "JSON",
+        "tf" => "Terraform",
+        _ => "Other",
+    }
+}
+
+/// Risk category of `path`, if it is one a reviewer should look at closely
+pub fn risk_category(path: &str) -> Option<&'static str> {
+    let lower = path.to_ascii_lowercase();
+    let name = lower.rsplit('/').next().unwrap_or(&lower);
+    let dirs: Vec<&str> = lower.split('/').collect();
+    let in_dir = |dir: &str| dirs[..dirs.len() - 1].contains(&dir);
+
+    if lower.starts_with(".github/workflows/")
+        || lower.starts_with(".circleci/")
+        || lower.starts_with(".buildkite/")
+        || matches!(
+            name,
+            ".gitlab-ci.yml" | "jenkinsfile" | "azure-pipelines.yml" | ".travis.yml"
+        )
+    {
+        return Some("ci");
+    }
+    if in_dir("migrations") || in_dir("migrate") || name.contains("migration") {
+        return Some("migration");
+    }
+    if matches!(
+        name,
+        "cargo.toml"
+            | "cargo.lock"
+            | "package.json"
+            | "package-lock.json"
+            | "yarn.lock"
+            | "pnpm-lock.yaml"
+            | "go.mod"
+            | "go.sum"
+            | "pyproject.toml"
+            | "poetry.lock"
+            | "gemfile"
+            | "gemfile.lock"
+    ) || (name.starts_with("requirements") && name.ends_with(".txt"))
+    {
+        return Some("dependencies");
+    }
+    if name == "dockerfile"
+        || name.starts_with("docker-compose")
+        || name.ends_with(".tf")
+        || in_dir("k8s")
+        || in_dir("helm")
+    {
+        return Some("infrastructure");
+    }
+    if name == ".env"
+        || name.starts_with(".env.")
+        || name.ends_with(".pem")
+        || name.ends_with(".key")
+        || name.contains("secret")
+        || name.contains("credential")
+    {
+        return Some("secrets");
+    }
+    None
+}
+
+fn new_file(path: String) -> FileDiff {
+    FileDiff {
+        language: language_of(&path).to_string(),
+        path,
+        old_path: None,
+        status: FileStatus::Modified,
+        similarity: None,
+        hunks: 0,
+        added: 0,
+        removed: 0,
+        binary: false,
+    }
+}
+
+/// Parse `git diff` output (run with `-M` to see renames)
+pub fn parse_diff(diff: &str) -> DiffSummary {
+    let mut files: Vec<FileDiff> = Vec::new();
+    // Header lines only appear between `diff --git` and the first hunk;
+    // a removed line starting with "-- " must not be mistaken for one
+    let mut in_header = false;
+
+    for line in diff.lines() {
+        if let Some(rest) = line.strip_prefix("diff --git ") {
+            in_header = true;
+            // `a/<old> b/<new>`; the `+++` or `rename to` line corrects
+            // paths containing " b/"
+            let path = rest
+                .rsplit_once(" b/")
+                .map_or(rest, |(_, new)| new)
+                .to_string();
+            files.push(new_file(path));
+            continue;
+        }
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+        if !in_header {
+            if line.starts_with("@@ ") {
+                file.hunks += 1;
+            } else if line.starts_with('+') {
+                file.added += 1;
+            } else if line.starts_with('-') {
+                file.removed += 1;
+            }
+            continue;
+        }
+        if line.starts_with("@@ ") {
+            in_header = false;
+            file.hunks += 1;
+        } else if line.starts_with("new file mode") {
+            file.status = FileStatus::Added;
+        } else if line.starts_with("deleted file mode") {
+            file.status = FileStatus::Deleted;
+        } else if let Some(from) = line.strip_prefix("rename from ") {
+            file.status = FileStatus::Renamed;
+            file.old_path = Some(from.to_string());
+        } else if let Some(to) = line.strip_prefix("rename to ") {
+            file.path = to.to_string();
+        } else if let Some(percent) = line.strip_prefix("similarity index ") {
+            file.similarity = percent.trim_end_matches('%').parse().ok();
+        } else if line.starts_with("Binary files ") {
+            file.binary = true;
+        } else if let Some(p) = line.strip_prefix("--- ") {
+            if file.status == FileStatus::Deleted {
+                if let Some(old) = p.strip_prefix("a/") {
+                    file.path = old.to_string();
+                }
+            }
+        } else if let Some(p) = line.strip_prefix("+++ ") {
+            if let Some(new) = p.strip_prefix("b/") {
+                file.path = new.to_string();
+            }
+        }
+    }
+
+    let mut summary = DiffSummary {
+        files_changed: files.len(),
+        ..Default::default()
+    };
+    for file in &mut files {
+        file.language = language_of(&file.path).to_string();
+        summary.hunks += file.hunks;
+        summary.added += file.added;
+        summary.removed += file.removed;
+        let stats = summary.languages.entry(file.language.clone()).or_default();
+        stats.files += 1;
+        stats.added += file.added;
+        stats.removed += file.removed;
+        if let Some(category) = risk_category(&file.path) {
+            summary.risky.push(RiskyPath {
+                path: file.path.clone(),
+                category: category.to_string(),
+            });
+        }
+    }
+    summary.files = files;
+    summary
+}
+
+/// Summarize uncommitted changes against `base` (default `HEAD`), or only
+/// what is staged
+///
+/// `base` is anything `git diff` accepts, such as `main...HEAD` for the
+/// changes on a branch. Untracked files are not included.
+pub fn summarize(dir: &Path, base: Option<&str>, staged: bool) -> Result<DiffSummary, String> {
+    let mut args = vec!["diff", "--no-color", "-M"];
+    if staged {
+        args.push("--cached");
+    }
+    args.push(base.unwrap_or("HEAD"));
+    git(dir, &args).map(|out| parse_diff(&out))
+}
+
+/// Human-readable summary
+pub fn render_text(summary: &DiffSummary) -> String {
+    let mut out = format!(
+        "{} files changed, {} hunks, +{} -{}\n",
+        summary.files_changed, summary.hunks, summary.added, summary.removed
+    );
+    for (language, stats) in &summary.languages {
+        out.push_str(&format!(
+            "  {:<12} {:>3} files  +{} -{}\n",
+            language, stats.files, stats.added, stats.removed
+        ));
+    }
+    for file in &summary.files {
+        let change = if file.binary {
+            "binary".to_string()
+        } else {
+            format!("+{} -{}", file.added, file.removed)
+        };
+        let path = match file.old_path {
+            Some(ref old) => format!("{} -> {}", old, file.path),
+            None => file.path.clone(),
+        };
+        out.push_str(&format!(
+            "{:<9} {} ({})\n",
+            file.status.to_string(),
+            path,
+            change
+        ));
+    }
+    if !summary.risky.is_empty() {
+        out.push_str("Risky paths:\n");
+        for risky in &summary.risky {
+            out.push_str(&format!("  [{}] {}\n", risky.category, risky.path));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,4 @@
+ pub mod a;
++pub mod b;
+-pub mod c;
++pub mod d;
+@@ -40 +41 @@ fn x()
+--- removed line that looks like a header
++++ added line that looks like a header
+diff --git a/.github/workflows/ci.yml b/.github/workflows/ci.yml
+new file mode 100644
+index 0000000..3333333
+--- /dev/null
++++ b/.github/workflows/ci.yml
+@@ -0,0 +1,2 @@
++on: push
++jobs: {}
+diff --git a/docs/old.md b/docs/new.md
+similarity index 92%
+rename from docs/old.md
+rename to docs/new.md
+index 4444444..5555555 100644
+--- a/docs/old.md
++++ b/docs/new.md
+@@ -3 +3 @@
+-Old
++New
+diff --git a/db/migrations/001.sql b/db/migrations/001.sql
+deleted file mode 100644
+index 6666666..0000000
+--- a/db/migrations/001.sql
++++ /dev/null
+@@ -1 +0,0 @@
+-CREATE TABLE t;
+diff --git a/logo.png b/logo.png
+index 7777777..8888888 100644
+Binary files a/logo.png and b/logo.png differ
+";
+
+    #[test]
+    fn test_parse_diff_files() {
+        let summary = parse_diff(DIFF);
+
+        assert_eq!(summary.files_changed, 5);
+        let lib = &summary.files[0];
+        assert_eq!((lib.hunks, lib.added, lib.removed), (2, 3, 2));
+        assert_eq!(lib.status, FileStatus::Modified);
+        assert_eq!(summary.files[1].status, FileStatus::Added);
+        assert_eq!(summary.files[1].added, 2);
+        let renamed = &summary.files[2];
+        assert_eq!(renamed.status, FileStatus::Renamed);
+        assert_eq!(renamed.path, "docs/new.md");
+        assert_eq!(renamed.similarity, Some(92));
+        let deleted = &summary.files[3];
+        assert_eq!(deleted.status, FileStatus::Deleted);
+        assert_eq!(deleted.path, "db/migrations/001.sql");
+        assert!(summary.files[4].binary);
+        assert_eq!(
+            summary.renames().collect::<Vec<_>>(),
+            vec![("docs/old.md", "docs/new.md", Some(92))]
+        );
+    }
+
+    #[test]
+    fn test_totals_by_language_and_risk() {
+        let summary = parse_diff(DIFF);
+
+        assert_eq!((summary.hunks, summary.added, summary.removed), (5, 6, 4));
+        assert_eq!(summary.languages["Rust"].added, 3);
+        assert_eq!(summary.languages["YAML"].files, 1);
+        assert_eq!(summary.languages["Other"].files, 1);
+        let risky: Vec<(&str, &str)> = summary
+            .risky
+            .iter()
+            .map(|r| (r.category.as_str(), r.path.as_str()))
+            .collect();
+        assert_eq!(
+            risky,
+            vec![
+                ("ci", ".github/workflows/ci.yml"),
+                ("migration", "db/migrations/001.sql")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_risk_categories() {
+        assert_eq!(risk_category("Cargo.lock"), Some("dependencies"));
+        assert_eq!(
+            risk_category("web/requirements-dev.txt"),
+            Some("dependencies")
+        );
+        assert_eq!(risk_category("deploy/Dockerfile"), Some("infrastructure"));
+        assert_eq!(risk_category("config/.env.production"), Some("secrets"));
+        assert_eq!(risk_category("src/migrate.rs"), None);
+        assert_eq!(risk_category("src/main.rs"), None);
+    }
+
+    #[test]
+    fn test_render_text() {
+        let text = render_text(&parse_diff(DIFF));
+
+        assert!(text.starts_with("5 files changed, 5 hunks, +6 -4\n"));
+        assert!(text.contains("renamed   docs/old.md -> docs/new.md (+1 -1)\n"));
+        assert!(text.contains("modified  logo.png (binary)\n"));
+        assert!(text.contains("  [ci] .github/workflows/ci.yml\n"));
+    }
+}
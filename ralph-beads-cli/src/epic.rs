@@ -0,0 +1,533 @@
+//! Epic scaffolding from markdown plans
+//!
+//! A plan is a markdown document with one `# Epic title`, an optional
+//! description, optional `## Phase` headings, and a bullet per task:
+//!
+//! ```text
+//! # Add user authentication
+//!
+//! Session-based login for the web app.
+//!
+//! ## Foundation
+//! - Create users table {#schema}
+//!   Columns: id, email, password_hash.
+//! - Add JWT secret to config {#config} (priority: 1)
+//!
+//! ## API
+//! - Login endpoint (after: schema, config)
+//! - Logout endpoint
+//! ```
+//!
+//! Indented lines under a bullet become the task description. `{#key}`
+//! names a task (defaulting to a slug of its title) and `(after: ...)`
+//! lists the keys or titles it depends on. A task without `(after: ...)`
+//! depends on every task of the previous phase.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::path::Path;
+
+use crate::beads::{add_dependency, create_issue, delete_issue, NewIssue};
+
+static KEY_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{#([A-Za-z0-9_.-]+)\}").unwrap());
+static ATTR_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\((after|priority):\s*([^)]*)\)").unwrap());
+static CHECKBOX_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\[[ xX]\]\s*").unwrap());
+
+/// A task parsed from a plan
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PlanTask {
+    pub key: String,
+    pub title: String,
+    pub description: String,
+    /// Phase heading the task appeared under
+    pub phase: Option<String>,
+    pub priority: Option<u8>,
+    /// Acceptance criteria (plans don't set these; `plan repl` can)
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub acceptance_criteria: String,
+    /// Indexes of the tasks this one depends on
+    pub depends_on: Vec<usize>,
+}
+
+/// A parsed and validated plan
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Plan {
+    pub title: String,
+    pub description: String,
+    pub tasks: Vec<PlanTask>,
+}
+
+/// Issue IDs created for a plan
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ScaffoldResult {
+    pub epic_id: String,
+    /// Issue ID per task, in plan order
+    pub task_ids: Vec<String>,
+}
+
+/// Lowercase, dash-separated form of a title, at most `max_len` bytes when
+/// given
+///
+/// Cuts happen at word boundaries where possible.
+pub fn slugify(title: &str, max_len: Option<usize>) -> String {
+    let max_len = max_len.unwrap_or(usize::MAX);
+    let mut slug = String::new();
+    for word in title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+    {
+        let extra = if slug.is_empty() { 0 } else { 1 } + word.len();
+        if slug.len().saturating_add(extra) > max_len {
+            if slug.is_empty() {
+                let cut = word
+                    .char_indices()
+                    .map(|(i, c)| i + c.len_utf8())
+                    .take_while(|&end| end <= max_len)
+                    .last()
+                    .unwrap_or(0);
+                slug.push_str(&word[..cut]);
+            }
+            break;
+        }
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.push_str(word);
+    }
+    slug
+}
+
+/// Task under construction, before dependency references are resolved
+pub(crate) struct RawTask {
+    pub(crate) key: String,
+    pub(crate) title: String,
+    description: Vec<String>,
+    phase: Option<(usize, String)>,
+    pub(crate) priority: Option<u8>,
+    /// Keys or titles from `(after: ...)`
+    pub(crate) after: Option<Vec<String>>,
+}
+
+/// Parse the text of a task bullet: title, `{#key}`, and attributes
+pub(crate) fn parse_task_line(
+    text: &str,
+    phase: Option<(usize, String)>,
+    line_no: usize,
+) -> Result<RawTask, String> {
+    let text = CHECKBOX_RE.replace(text, "");
+    let mut after = None;
+    let mut priority = None;
+
+    for caps in ATTR_RE.captures_iter(&text) {
+        let value = caps[2].trim();
+        if caps[1].eq_ignore_ascii_case("after") {
+            after = Some(
+                value
+                    .split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect(),
+            );
+        } else {
+            priority = Some(
+                value
+                    .parse::<u8>()
+                    .map_err(|_| format!("line {}: invalid priority '{}'", line_no, value))?,
+            );
+        }
+    }
+
+    let key = KEY_RE.captures(&text).map(|c| c[1].to_string());
+    let without_key = KEY_RE.replace_all(&text, "");
+    let title = ATTR_RE.replace_all(&without_key, "");
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    if title.is_empty() {
+        return Err(format!("line {}: task has no title", line_no));
+    }
+
+    Ok(RawTask {
+        key: key.unwrap_or_else(|| slugify(&title, None)),
+        title,
+        description: Vec::new(),
+        phase,
+        priority,
+        after,
+    })
+}
+
+/// Parse a markdown plan
+pub fn parse_plan(content: &str) -> Result<Plan, String> {
+    let mut title: Option<String> = None;
+    let mut description: Vec<String> = Vec::new();
+    let mut phase: Option<(usize, String)> = None;
+    let mut phase_count = 0;
+    let mut tasks: Vec<RawTask> = Vec::new();
+
+    for (i, line) in content.lines().enumerate() {
+        let line_no = i + 1;
+        let trimmed = line.trim();
+
+        if let Some(heading) = trimmed.strip_prefix("# ") {
+            if title.is_some() {
+                return Err(format!(
+                    "line {}: plan has more than one epic title",
+                    line_no
+                ));
+            }
+            title = Some(heading.trim().to_string());
+        } else if let Some(heading) = trimmed.strip_prefix("## ") {
+            phase = Some((phase_count, heading.trim().to_string()));
+            phase_count += 1;
+        } else if let Some(item) = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")) {
+            if title.is_none() {
+                return Err(format!("line {}: task before the '# Epic title'", line_no));
+            }
+            tasks.push(parse_task_line(item, phase.clone(), line_no)?);
+        } else if line.starts_with(char::is_whitespace) && !trimmed.is_empty() {
+            match tasks.last_mut() {
+                Some(task) => task.description.push(trimmed.to_string()),
+                None => description.push(trimmed.to_string()),
+            }
+        } else if title.is_some() && tasks.is_empty() && phase.is_none() {
+            description.push(trimmed.to_string());
+        }
+    }
+
+    let title = title.ok_or("Plan has no '# Epic title' heading")?;
+    if tasks.is_empty() {
+        return Err("Plan has no task bullets".to_string());
+    }
+
+    let mut index: BTreeMap<String, usize> = BTreeMap::new();
+    for (i, task) in tasks.iter().enumerate() {
+        if index.insert(task.key.clone(), i).is_some() {
+            return Err(format!(
+                "Duplicate task key '{}' (add {{#key}} to disambiguate)",
+                task.key
+            ));
+        }
+    }
+    let resolve = |reference: &str| -> Option<usize> {
+        index.get(reference).copied().or_else(|| {
+            tasks
+                .iter()
+                .position(|t| t.title.eq_ignore_ascii_case(reference))
+        })
+    };
+
+    let mut resolved = Vec::with_capacity(tasks.len());
+    for (i, task) in tasks.iter().enumerate() {
+        let depends_on = match task.after {
+            Some(ref refs) => refs
+                .iter()
+                .map(|r| {
+                    resolve(r).ok_or_else(|| {
+                        format!("Task '{}' depends on unknown task '{}'", task.title, r)
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            None => match task.phase {
+                Some((n, _)) if n > 0 => tasks
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, t)| t.phase.as_ref().map(|p| p.0) == Some(n - 1))
+                    .map(|(j, _)| j)
+                    .collect(),
+                _ => Vec::new(),
+            },
+        };
+        if depends_on.contains(&i) {
+            return Err(format!("Task '{}' depends on itself", task.title));
+        }
+        resolved.push(PlanTask {
+            key: task.key.clone(),
+            title: task.title.clone(),
+            description: task.description.join("\n"),
+            phase: task.phase.as_ref().map(|p| p.1.clone()),
+            priority: task.priority,
+            acceptance_criteria: String::new(),
+            depends_on,
+        });
+    }
+
+    let plan = Plan {
+        title,
+        description: description.join("\n").trim().to_string(),
+        tasks: resolved,
+    };
+    topological_order(&plan)?;
+    Ok(plan)
+}
+
+/// Task indexes in dependency order, or an error naming a cycle member
+pub fn topological_order(plan: &Plan) -> Result<Vec<usize>, String> {
+    let n = plan.tasks.len();
+    let mut remaining: Vec<usize> = plan.tasks.iter().map(|t| t.depends_on.len()).collect();
+    let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for (i, task) in plan.tasks.iter().enumerate() {
+        for &dep in &task.depends_on {
+            dependents[dep].push(i);
+        }
+    }
+
+    let mut queue: VecDeque<usize> = (0..n).filter(|&i| remaining[i] == 0).collect();
+    let mut order = Vec::with_capacity(n);
+    while let Some(i) = queue.pop_front() {
+        order.push(i);
+        for &next in &dependents[i] {
+            remaining[next] -= 1;
+            if remaining[next] == 0 {
+                queue.push_back(next);
+            }
+        }
+    }
+
+    match (0..n).find(|&i| remaining[i] > 0) {
+        Some(i) => Err(format!(
+            "Dependency cycle involving task '{}'",
+            plan.tasks[i].title
+        )),
+        None => Ok(order),
+    }
+}
+
+/// Create the epic, its tasks, and their dependencies in beads
+pub fn scaffold(dir: &Path, plan: &Plan) -> Result<ScaffoldResult, String> {
+    scaffold_into(dir, plan, None)
+}
+
+/// Create the plan's tasks under `epic`, or under a new epic when `None`
+///
+/// Nothing is left behind on failure: issues created before the failing
+/// call are deleted again. Issues that can't be deleted are named in the
+/// error.
+pub fn scaffold_into(
+    dir: &Path,
+    plan: &Plan,
+    epic: Option<&str>,
+) -> Result<ScaffoldResult, String> {
+    let mut created = Vec::new();
+    match create_plan(dir, plan, epic, &mut created) {
+        Ok(result) => Ok(result),
+        Err(e) => {
+            // Children first, so the epic goes last
+            let leftover: Vec<String> = created
+                .iter()
+                .rev()
+                .filter(|id| delete_issue(dir, id).is_err())
+                .cloned()
+                .collect();
+            if leftover.is_empty() {
+                Err(format!("{} (rolled back)", e))
+            } else {
+                Err(format!(
+                    "{} (could not roll back {})",
+                    e,
+                    leftover.join(", ")
+                ))
+            }
+        }
+    }
+}
+
+/// Create everything, recording each created issue ID in `created`
+fn create_plan(
+    dir: &Path,
+    plan: &Plan,
+    epic: Option<&str>,
+    created: &mut Vec<String>,
+) -> Result<ScaffoldResult, String> {
+    let epic_id = match epic {
+        Some(id) => id.to_string(),
+        None => {
+            let epic = create_issue(
+                dir,
+                &NewIssue {
+                    title: plan.title.clone(),
+                    issue_type: "epic".to_string(),
+                    description: (!plan.description.is_empty()).then(|| plan.description.clone()),
+                    ..Default::default()
+                },
+            )?;
+            created.push(epic.id.clone());
+            epic.id
+        }
+    };
+
+    let mut task_ids = Vec::with_capacity(plan.tasks.len());
+    for task in &plan.tasks {
+        let issue = create_issue(
+            dir,
+            &NewIssue {
+                title: task.title.clone(),
+                issue_type: "task".to_string(),
+                priority: task.priority,
+                parent: Some(epic_id.clone()),
+                description: (!task.description.is_empty()).then(|| task.description.clone()),
+                acceptance_criteria: (!task.acceptance_criteria.is_empty())
+                    .then(|| task.acceptance_criteria.clone()),
+            },
+        )?;
+        created.push(issue.id.clone());
+        task_ids.push(issue.id);
+    }
+
+    for (i, task) in plan.tasks.iter().enumerate() {
+        for &dep in &task.depends_on {
+            add_dependency(dir, &task_ids[i], &task_ids[dep], "blocks")?;
+        }
+    }
+
+    Ok(ScaffoldResult { epic_id, task_ids })
+}
+
+/// Render the task graph, labelling tasks with `labels` (IDs or keys)
+pub fn render_graph(plan: &Plan, epic_label: &str, labels: &[String]) -> String {
+    let mut out = format!("{} {}\n", epic_label, plan.title);
+    let mut phase: Option<&str> = None;
+    for (i, task) in plan.tasks.iter().enumerate() {
+        if task.phase.as_deref() != phase {
+            phase = task.phase.as_deref();
+            if let Some(name) = phase {
+                out.push_str(&format!("  [{}]\n", name));
+            }
+        }
+        out.push_str(&format!("  {} {}", labels[i], task.title));
+        if !task.depends_on.is_empty() {
+            let deps: Vec<&str> = task
+                .depends_on
+                .iter()
+                .map(|&d| labels[d].as_str())
+                .collect();
+            out.push_str(&format!("  <- {}", deps.join(", ")));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAN: &str = "\
+# Add user authentication
+
+Session-based login for the web app.
+
+## Foundation
+- Create users table {#schema}
+  Columns: id, email, password_hash.
+- [ ] Add JWT secret to config {#config} (priority: 1)
+
+## API
+- Login endpoint (after: schema, config)
+- Logout endpoint (after: login-endpoint)
+- Session middleware
+
+## Docs
+- Document auth flow
+";
+
+    #[test]
+    fn test_parse_plan() {
+        let plan = parse_plan(PLAN).unwrap();
+
+        assert_eq!(plan.title, "Add user authentication");
+        assert_eq!(plan.description, "Session-based login for the web app.");
+        assert_eq!(plan.tasks.len(), 6);
+
+        let schema = &plan.tasks[0];
+        assert_eq!(schema.key, "schema");
+        assert_eq!(schema.title, "Create users table");
+        assert_eq!(schema.description, "Columns: id, email, password_hash.");
+        assert_eq!(schema.phase.as_deref(), Some("Foundation"));
+
+        let config = &plan.tasks[1];
+        assert_eq!(config.title, "Add JWT secret to config");
+        assert_eq!(config.priority, Some(1));
+    }
+
+    #[test]
+    fn test_dependencies() {
+        let plan = parse_plan(PLAN).unwrap();
+
+        // Explicit references by key
+        assert_eq!(plan.tasks[2].depends_on, vec![0, 1]);
+        // Default slug key
+        assert_eq!(plan.tasks[3].depends_on, vec![2]);
+        // Implicit: every task of the previous phase
+        assert_eq!(plan.tasks[4].depends_on, vec![0, 1]);
+        assert_eq!(plan.tasks[5].depends_on, vec![2, 3, 4]);
+        // First phase has no implicit dependencies
+        assert!(plan.tasks[0].depends_on.is_empty());
+    }
+
+    #[test]
+    fn test_reference_by_title() {
+        let plan = parse_plan("# E\n- First\n- Second (after: first)\n").unwrap();
+        assert_eq!(plan.tasks[1].depends_on, vec![0]);
+    }
+
+    #[test]
+    fn test_plan_errors() {
+        assert!(parse_plan("- orphan task\n").is_err());
+        assert!(parse_plan("# Epic only\n").unwrap_err().contains("no task"));
+        assert!(parse_plan("# E\n- A (after: nope)\n")
+            .unwrap_err()
+            .contains("unknown task 'nope'"));
+        assert!(parse_plan("# E\n- A\n- A\n")
+            .unwrap_err()
+            .contains("Duplicate task key 'a'"));
+        assert!(parse_plan("# E\n- A (after: b)\n- B (after: a)\n")
+            .unwrap_err()
+            .contains("cycle"));
+        assert!(parse_plan("# E\n- A (priority: high)\n")
+            .unwrap_err()
+            .contains("invalid priority"));
+        assert!(parse_plan("# E\n# F\n- A\n").is_err());
+    }
+
+    #[test]
+    fn test_topological_order() {
+        let plan = parse_plan(PLAN).unwrap();
+        let order = topological_order(&plan).unwrap();
+
+        let position = |i: usize| order.iter().position(|&x| x == i).unwrap();
+        for (i, task) in plan.tasks.iter().enumerate() {
+            for &dep in &task.depends_on {
+                assert!(position(dep) < position(i));
+            }
+        }
+    }
+
+    #[test]
+    fn test_render_graph() {
+        let plan = parse_plan(PLAN).unwrap();
+        let keys: Vec<String> = plan.tasks.iter().map(|t| t.key.clone()).collect();
+
+        let text = render_graph(&plan, "(epic)", &keys);
+
+        assert!(text.starts_with("(epic) Add user authentication\n  [Foundation]\n"));
+        assert!(text.contains("  login-endpoint Login endpoint  <- schema, config\n"));
+        assert!(text.contains("  [Docs]\n"));
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(
+            slugify("Add JWT secret to config!", None),
+            "add-jwt-secret-to-config"
+        );
+        assert_eq!(
+            slugify("Refactor the payment service", Some(20)),
+            "refactor-the-payment"
+        );
+        assert_eq!(slugify("Supercalifragilistic", Some(5)), "super");
+        assert_eq!(slugify("Überprüfung", Some(4)), "übe");
+    }
+}
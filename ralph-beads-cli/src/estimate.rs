@@ -0,0 +1,333 @@
+//! Issue effort estimation
+//!
+//! Combines three signals into an iteration count and wall-clock range:
+//!
+//! - complexity detected from the issue's title and description, which
+//!   sets the baseline (half the building iteration limit, which a
+//!   project's `[iterations]` table can change)
+//! - history of similar closed issues (sharing a label, or the same type
+//!   when the issue has no labels): claim-to-completion time from the
+//!   activity feed and `iterations_total{issue=...}` from the metrics log
+//! - the issue's position in its epic, as the number of open blockers
+//!   it still waits on
+
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::activity::{ActivityEvent, EventKind};
+use crate::beads::Issue;
+use crate::complexity::{detect_complexity, Complexity, IterationsConfig};
+use crate::metrics::MetricSample;
+use crate::retention::summary_of;
+use crate::state::WorkflowMode;
+
+/// How much history backs an estimate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Confidence {
+    /// No comparable history; complexity baseline only
+    Low,
+    /// One to three similar issues
+    Medium,
+    /// Four or more similar issues
+    High,
+}
+
+impl fmt::Display for Confidence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Confidence::Low => "low",
+            Confidence::Medium => "medium",
+            Confidence::High => "high",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Estimated effort for one issue
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Estimate {
+    pub issue_id: String,
+    pub complexity: Complexity,
+    /// Expected iterations
+    pub iterations: u32,
+    /// Building iteration limit for the complexity
+    pub max_iterations: u32,
+    /// Wall-clock range in seconds, when similar issues have recorded durations
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<(u64, u64)>,
+    pub confidence: Confidence,
+    /// Similar closed issues the history came from
+    pub similar: Vec<String>,
+    /// Open issues this one still waits on
+    pub open_blockers: Vec<String>,
+}
+
+/// Claim-to-completion time per issue: first `claimed` to last `completed`
+pub fn task_durations(events: &[ActivityEvent]) -> BTreeMap<String, u64> {
+    let mut claimed: BTreeMap<&str, u64> = BTreeMap::new();
+    let mut completed: BTreeMap<&str, u64> = BTreeMap::new();
+    for event in events {
+        if let Some(summary) = summary_of(event) {
+            if let Some(at) = summary.claimed_at {
+                let first = claimed.entry(&event.issue).or_insert(at);
+                *first = (*first).min(at);
+            }
+            if let Some(at) = summary.completed_at {
+                completed.insert(&event.issue, at);
+            }
+            continue;
+        }
+        match event.kind {
+            EventKind::Claimed => {
+                claimed.entry(&event.issue).or_insert(event.timestamp);
+            }
+            EventKind::Completed => {
+                completed.insert(&event.issue, event.timestamp);
+            }
+            _ => {}
+        }
+    }
+    completed
+        .into_iter()
+        .filter_map(|(issue, end)| {
+            let start = *claimed.get(issue)?;
+            (end >= start).then(|| (issue.to_string(), end - start))
+        })
+        .collect()
+}
+
+/// Iterations per issue from `iterations_total` samples labelled `issue=<id>`
+pub fn task_iterations(samples: &[MetricSample]) -> BTreeMap<String, f64> {
+    let mut totals: BTreeMap<String, f64> = BTreeMap::new();
+    for sample in samples.iter().filter(|s| s.name == "iterations_total") {
+        if let Some(issue) = sample.labels.get("issue") {
+            *totals.entry(issue.clone()).or_default() += sample.value;
+        }
+    }
+    totals
+}
+
+/// Closed issues comparable to `issue`: a shared label, or the same type
+/// when `issue` has no labels
+pub fn similar_issues<'a>(issue: &Issue, candidates: &'a [Issue]) -> Vec<&'a Issue> {
+    candidates
+        .iter()
+        .filter(|c| c.id != issue.id && c.is_closed())
+        .filter(|c| {
+            if issue.labels.is_empty() {
+                c.issue_type == issue.issue_type
+            } else {
+                c.labels.iter().any(|l| issue.labels.contains(l))
+            }
+        })
+        .collect()
+}
+
+/// Value at fraction `q` (0.0–1.0) of sorted `values`, nearest rank
+fn quantile(sorted: &[u64], q: f64) -> u64 {
+    let rank = ((sorted.len() - 1) as f64 * q).round() as usize;
+    sorted[rank]
+}
+
+/// Estimate `issue` against the known `issues` and recorded history, with
+/// limits from the project's iteration `table`
+pub fn estimate(
+    issue: &Issue,
+    issues: &[Issue],
+    durations: &BTreeMap<String, u64>,
+    iterations: &BTreeMap<String, f64>,
+    table: &IterationsConfig,
+) -> Estimate {
+    let complexity = detect_complexity(&format!("{}\n{}", issue.title, issue.description));
+    let max_iterations = table.max_iterations(&WorkflowMode::Building, &complexity);
+
+    let similar: Vec<&Issue> = similar_issues(issue, issues)
+        .into_iter()
+        .filter(|s| durations.contains_key(&s.id) || iterations.contains_key(&s.id))
+        .collect();
+
+    let mut past_iterations: Vec<u64> = similar
+        .iter()
+        .filter_map(|s| iterations.get(&s.id))
+        .map(|n| n.round() as u64)
+        .collect();
+    past_iterations.sort_unstable();
+    let iterations = if past_iterations.is_empty() {
+        (max_iterations / 2).max(1)
+    } else {
+        (quantile(&past_iterations, 0.5) as u32).clamp(1, max_iterations)
+    };
+
+    let mut past_durations: Vec<u64> = similar
+        .iter()
+        .filter_map(|s| durations.get(&s.id).copied())
+        .collect();
+    past_durations.sort_unstable();
+    // With a handful of samples the full range is the honest answer; with
+    // more, the interquartile range resists outliers
+    let duration_secs = match past_durations.len() {
+        0 => None,
+        1..=3 => Some((past_durations[0], *past_durations.last().unwrap())),
+        _ => Some((
+            quantile(&past_durations, 0.25),
+            quantile(&past_durations, 0.75),
+        )),
+    };
+
+    let open: BTreeSet<&str> = issues
+        .iter()
+        .filter(|i| !i.is_closed())
+        .map(|i| i.id.as_str())
+        .collect();
+    let open_blockers = issue
+        .blockers()
+        .filter(|b| open.contains(b))
+        .map(String::from)
+        .collect();
+
+    Estimate {
+        issue_id: issue.id.clone(),
+        complexity,
+        iterations,
+        max_iterations,
+        duration_secs,
+        confidence: match similar.len() {
+            0 => Confidence::Low,
+            1..=3 => Confidence::Medium,
+            _ => Confidence::High,
+        },
+        similar: similar.iter().map(|s| s.id.clone()).collect(),
+        open_blockers,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beads::Dependency;
+
+    fn issue(id: &str, status: &str, labels: &[&str]) -> Issue {
+        Issue {
+            id: id.to_string(),
+            title: format!("Implement {}", id),
+            description: "Add the endpoint".to_string(),
+            status: status.to_string(),
+            issue_type: "task".to_string(),
+            labels: labels.iter().map(|l| l.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn event(issue: &str, kind: EventKind, timestamp: u64) -> ActivityEvent {
+        ActivityEvent {
+            id: timestamp,
+            timestamp,
+            issue: issue.to_string(),
+            kind,
+            message: String::new(),
+            worker: None,
+            run: None,
+            data: None,
+        }
+    }
+
+    fn iterations_sample(issue: &str, value: f64) -> MetricSample {
+        MetricSample {
+            timestamp: 0,
+            name: "iterations_total".to_string(),
+            value,
+            labels: [("issue".to_string(), issue.to_string())].into(),
+        }
+    }
+
+    #[test]
+    fn test_task_durations() {
+        let events = vec![
+            event("a", EventKind::Claimed, 100),
+            event("a", EventKind::Claimed, 150),
+            event("a", EventKind::Completed, 400),
+            event("b", EventKind::Completed, 50),
+            event("c", EventKind::Claimed, 10),
+        ];
+        let durations = task_durations(&events);
+        assert_eq!(durations.len(), 1);
+        assert_eq!(durations["a"], 300);
+    }
+
+    #[test]
+    fn test_task_iterations() {
+        let samples = vec![
+            iterations_sample("a", 1.0),
+            iterations_sample("a", 2.0),
+            MetricSample {
+                name: "gate_wait_seconds".to_string(),
+                ..iterations_sample("a", 9.0)
+            },
+        ];
+        assert_eq!(task_iterations(&samples)["a"], 3.0);
+    }
+
+    #[test]
+    fn test_estimate_without_history() {
+        let target = issue("t", "open", &["api"]);
+        let est = estimate(
+            &target,
+            &[],
+            &BTreeMap::new(),
+            &BTreeMap::new(),
+            &IterationsConfig::default(),
+        );
+
+        assert_eq!(est.confidence, Confidence::Low);
+        assert_eq!(est.iterations, est.max_iterations / 2);
+        assert_eq!(est.duration_secs, None);
+    }
+
+    #[test]
+    fn test_estimate_from_similar_issues() {
+        let mut target = issue("t", "open", &["api"]);
+        target.dependencies = vec![
+            Dependency {
+                issue_id: "t".to_string(),
+                depends_on_id: "blocker".to_string(),
+                dep_type: "blocks".to_string(),
+            },
+            Dependency {
+                issue_id: "t".to_string(),
+                depends_on_id: "done-1".to_string(),
+                dep_type: "blocks".to_string(),
+            },
+        ];
+        let issues = vec![
+            target.clone(),
+            issue("blocker", "open", &[]),
+            issue("done-1", "closed", &["api"]),
+            issue("done-2", "closed", &["api", "db"]),
+            issue("other", "closed", &["ui"]),
+        ];
+        let durations: BTreeMap<String, u64> = [
+            ("done-1".to_string(), 600),
+            ("done-2".to_string(), 1800),
+            ("other".to_string(), 99_999),
+        ]
+        .into();
+        let iterations: BTreeMap<String, f64> =
+            [("done-1".to_string(), 3.0), ("done-2".to_string(), 5.0)].into();
+
+        let est = estimate(
+            &target,
+            &issues,
+            &durations,
+            &iterations,
+            &IterationsConfig::default(),
+        );
+
+        assert_eq!(est.similar, vec!["done-1", "done-2"]);
+        assert_eq!(est.confidence, Confidence::Medium);
+        assert_eq!(est.iterations, 5);
+        assert_eq!(est.duration_secs, Some((600, 1800)));
+        assert_eq!(est.open_blockers, vec!["blocker"]);
+    }
+}
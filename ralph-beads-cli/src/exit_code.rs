@@ -0,0 +1,160 @@
+//! Exit status catalogue
+//!
+//! Every subcommand exits with one of these codes so wrapping scripts can
+//! branch on the class of failure instead of parsing stderr. The numbers are
+//! stable; new classes are only ever appended. Plugins are the exception:
+//! their exit code is passed through unchanged.
+
+use serde::Serialize;
+use std::fmt;
+
+use crate::beads::BdError;
+
+/// Class of outcome, mapped to a process exit code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitCode {
+    /// Success
+    Ok,
+    /// Any failure without a more specific class
+    Error,
+    /// Invalid command-line arguments
+    Usage,
+    /// A check ran and rejected the input (lint errors, budget exceeded, invalid config)
+    Blocked,
+    /// A wait or bd call exceeded its timeout
+    Timeout,
+    /// bd is missing, unreachable, or too old
+    BdUnavailable,
+    /// Illegal state transition or unreadable session state
+    StateInvalid,
+    /// A bulk operation succeeded for some items and failed for others
+    Partial,
+    /// A configured hook command failed
+    HookFailed,
+    /// Session state changed since the caller read it
+    Conflict,
+}
+
+impl ExitCode {
+    pub const ALL: [ExitCode; 10] = [
+        ExitCode::Ok,
+        ExitCode::Error,
+        ExitCode::Usage,
+        ExitCode::Blocked,
+        ExitCode::Timeout,
+        ExitCode::BdUnavailable,
+        ExitCode::StateInvalid,
+        ExitCode::Partial,
+        ExitCode::HookFailed,
+        ExitCode::Conflict,
+    ];
+
+    /// Process exit code
+    pub fn code(self) -> i32 {
+        match self {
+            ExitCode::Ok => 0,
+            ExitCode::Error => 1,
+            ExitCode::Usage => 2,
+            ExitCode::Blocked => 3,
+            ExitCode::Timeout => 4,
+            ExitCode::BdUnavailable => 5,
+            ExitCode::StateInvalid => 6,
+            ExitCode::Partial => 7,
+            ExitCode::HookFailed => 8,
+            ExitCode::Conflict => 9,
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            ExitCode::Ok => "success",
+            ExitCode::Error => "general failure",
+            ExitCode::Usage => "invalid command-line arguments",
+            ExitCode::Blocked => "check rejected the input (lint errors, budget, config)",
+            ExitCode::Timeout => "wait or bd call timed out",
+            ExitCode::BdUnavailable => "bd missing, unreachable, or incompatible",
+            ExitCode::StateInvalid => "illegal state transition or invalid session state",
+            ExitCode::Partial => "bulk operation partially failed",
+            ExitCode::HookFailed => "a configured hook failed",
+            ExitCode::Conflict => "session state changed since it was read",
+        }
+    }
+}
+
+impl fmt::Display for ExitCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ExitCode::Ok => "ok",
+            ExitCode::Error => "error",
+            ExitCode::Usage => "usage",
+            ExitCode::Blocked => "blocked",
+            ExitCode::Timeout => "timeout",
+            ExitCode::BdUnavailable => "bd_unavailable",
+            ExitCode::StateInvalid => "state_invalid",
+            ExitCode::Partial => "partial",
+            ExitCode::HookFailed => "hook_failed",
+            ExitCode::Conflict => "conflict",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl From<&BdError> for ExitCode {
+    fn from(e: &BdError) -> Self {
+        match e {
+            BdError::BdUnavailable { .. } => ExitCode::BdUnavailable,
+            BdError::TimedOut { .. } => ExitCode::Timeout,
+            BdError::Failed { .. } | BdError::InvalidOutput { .. } => ExitCode::Error,
+        }
+    }
+}
+
+/// One catalogue entry, as listed by `info`
+#[derive(Debug, Clone, Serialize)]
+pub struct ExitCodeInfo {
+    pub code: i32,
+    pub name: String,
+    pub description: &'static str,
+}
+
+/// The full catalogue in code order
+pub fn catalogue() -> Vec<ExitCodeInfo> {
+    ExitCode::ALL
+        .iter()
+        .map(|c| ExitCodeInfo {
+            code: c.code(),
+            name: c.to_string(),
+            description: c.description(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_are_unique_and_ordered() {
+        let codes: Vec<i32> = ExitCode::ALL.iter().map(|c| c.code()).collect();
+        assert_eq!(codes, (0..ExitCode::ALL.len() as i32).collect::<Vec<_>>());
+        assert_eq!(
+            serde_json::to_value(ExitCode::BdUnavailable).unwrap(),
+            ExitCode::BdUnavailable.to_string()
+        );
+    }
+
+    #[test]
+    fn test_from_bd_error() {
+        let timed_out = BdError::TimedOut {
+            command: "bd list".to_string(),
+            timeout_secs: 30,
+        };
+        assert_eq!(ExitCode::from(&timed_out), ExitCode::Timeout);
+        let unavailable = BdError::BdUnavailable {
+            reason: "bd not found on PATH".to_string(),
+            hint: String::new(),
+        };
+        assert_eq!(ExitCode::from(&unavailable), ExitCode::BdUnavailable);
+    }
+}
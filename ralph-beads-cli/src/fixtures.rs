@@ -0,0 +1,276 @@
+//! Recorded `bd` invocations for tests
+//!
+//! With `--record-fixtures <dir>`, every `bd` call the CLI makes is written
+//! to `<dir>` as one JSON file: arguments, stdin, exit status, stdout, and
+//! stderr. Tests load a directory of fixtures into a [`Replay`] and run
+//! client code under [`with_replay`], which answers `bd` calls from the
+//! recordings instead of spawning it, so parsing can be exercised against
+//! real output without a beads installation.
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// One `bd` call and what it produced
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fixture {
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stdin: Option<String>,
+    /// Exit code (`None` when bd was killed by a signal)
+    pub status: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl Fixture {
+    pub fn success(&self) -> bool {
+        self.status == Some(0)
+    }
+
+    fn matches(&self, args: &[&str], stdin: Option<&str>) -> bool {
+        self.args
+            .iter()
+            .map(String::as_str)
+            .eq(args.iter().copied())
+            && self.stdin.as_deref() == stdin
+    }
+}
+
+/// Writes fixtures into a directory, numbered in call order
+#[derive(Debug)]
+pub struct Recorder {
+    dir: PathBuf,
+    next: AtomicUsize,
+}
+
+impl Recorder {
+    /// Record into `dir`, creating it
+    pub fn new(dir: &Path) -> Result<Self, String> {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        // Continue numbering after fixtures already in the directory
+        let existing = fs::read_dir(dir)
+            .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+            .filter(|entry| {
+                entry
+                    .as_ref()
+                    .is_ok_and(|e| e.path().extension().is_some_and(|x| x == "json"))
+            })
+            .count();
+        Ok(Recorder {
+            dir: dir.to_path_buf(),
+            next: AtomicUsize::new(existing + 1),
+        })
+    }
+
+    /// Write `fixture` as `<nnnn>-<subcommand>.json`, returning its path
+    pub fn record(&self, fixture: &Fixture) -> Result<PathBuf, String> {
+        let n = self.next.fetch_add(1, Ordering::SeqCst);
+        let name: String = fixture
+            .args
+            .iter()
+            .find(|a| !a.starts_with('-'))
+            .map_or("bd", String::as_str)
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        let path = self.dir.join(format!("{:04}-{}.json", n, name));
+        let json = serde_json::to_string_pretty(fixture).map_err(|e| e.to_string())?;
+        fs::write(&path, json + "\n")
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(path)
+    }
+}
+
+static RECORDER: OnceCell<Recorder> = OnceCell::new();
+
+/// Record every `bd` call in this process into `dir` (first call wins)
+pub fn record_to(dir: &Path) -> Result<(), String> {
+    let _ = RECORDER.set(Recorder::new(dir)?);
+    Ok(())
+}
+
+/// Record a call if `record_to` is active; failures only warn
+pub(crate) fn record(fixture: &Fixture) {
+    if let Some(recorder) = RECORDER.get() {
+        if let Err(e) = recorder.record(fixture) {
+            tracing::warn!("not recording bd fixture: {}", e);
+        }
+    }
+}
+
+/// Recorded calls served back in order
+///
+/// A call is answered by the first unused fixture with the same arguments
+/// and stdin, so a recorded retry sequence replays as it happened. Once
+/// every match has been used, the last one keeps answering.
+#[derive(Debug, Clone, Default)]
+pub struct Replay {
+    fixtures: Vec<Fixture>,
+    used: Vec<bool>,
+}
+
+impl Replay {
+    pub fn new(fixtures: Vec<Fixture>) -> Self {
+        let used = vec![false; fixtures.len()];
+        Replay { fixtures, used }
+    }
+
+    /// Load every `*.json` fixture in `dir`, in file name order
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+            .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|x| x == "json"))
+            .collect();
+        paths.sort();
+        let fixtures = paths
+            .iter()
+            .map(|path| {
+                let content = fs::read_to_string(path)
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                serde_json::from_str(&content)
+                    .map_err(|e| format!("Invalid fixture {}: {}", path.display(), e))
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self::new(fixtures))
+    }
+
+    /// The recording answering `bd <args>` fed `stdin`
+    pub fn answer(&mut self, args: &[&str], stdin: Option<&str>) -> Option<Fixture> {
+        let matching: Vec<usize> = (0..self.fixtures.len())
+            .filter(|&i| self.fixtures[i].matches(args, stdin))
+            .collect();
+        let i = matching
+            .iter()
+            .copied()
+            .find(|&i| !self.used[i])
+            .or(matching.last().copied())?;
+        self.used[i] = true;
+        Some(self.fixtures[i].clone())
+    }
+}
+
+thread_local! {
+    static REPLAY: RefCell<Option<Replay>> = const { RefCell::new(None) };
+}
+
+/// Run `f` with `bd` calls on this thread answered from `replay`
+///
+/// Calls without a recording fail as bd would, with stderr naming the
+/// missing fixture.
+pub fn with_replay<T>(replay: Replay, f: impl FnOnce() -> T) -> T {
+    let previous = REPLAY.with(|r| r.replace(Some(replay)));
+    let result = f();
+    REPLAY.with(|r| *r.borrow_mut() = previous);
+    result
+}
+
+/// Answer a call from the active replay, if one is set on this thread
+pub(crate) fn replayed(args: &[&str], stdin: Option<&str>) -> Option<Fixture> {
+    REPLAY.with(|r| {
+        let mut replay = r.borrow_mut();
+        let replay = replay.as_mut()?;
+        Some(replay.answer(args, stdin).unwrap_or_else(|| Fixture {
+            args: args.iter().map(|a| a.to_string()).collect(),
+            stdin: stdin.map(String::from),
+            status: Some(1),
+            stdout: String::new(),
+            stderr: format!("no recorded fixture for bd {}", args.join(" ")),
+        }))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beads::{self, BdError, ListFilter};
+
+    fn fixture(args: &[&str], status: i32, stdout: &str, stderr: &str) -> Fixture {
+        Fixture {
+            args: args.iter().map(|a| a.to_string()).collect(),
+            stdin: None,
+            status: Some(status),
+            stdout: stdout.to_string(),
+            stderr: stderr.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_record_then_load_round_trip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let recorder = Recorder::new(dir.path()).unwrap();
+        let list = fixture(&["list", "--json"], 0, "[]", "");
+        let path = recorder.record(&list).unwrap();
+        assert!(path.ends_with("0001-list.json"));
+        recorder
+            .record(&fixture(&["--version"], 0, "bd 0.30.0", ""))
+            .unwrap();
+
+        // A second session appends after the existing recordings
+        let again = Recorder::new(dir.path()).unwrap();
+        let path = again.record(&list).unwrap();
+        assert!(path.ends_with("0003-list.json"));
+
+        let mut replay = Replay::load(dir.path()).unwrap();
+        assert_eq!(replay.fixtures.len(), 3);
+        assert_eq!(
+            replay.answer(&["--version"], None).unwrap().stdout,
+            "bd 0.30.0"
+        );
+        assert!(replay.answer(&["show", "bd-1"], None).is_none());
+    }
+
+    #[test]
+    fn test_replay_serves_matches_in_order() {
+        let mut replay = Replay::new(vec![
+            fixture(&["ready"], 1, "", "database is locked"),
+            fixture(&["ready"], 0, "first", ""),
+            fixture(&["ready"], 0, "second", ""),
+        ]);
+
+        let outputs: Vec<String> = (0..4)
+            .map(|_| replay.answer(&["ready"], None).unwrap().stdout)
+            .collect();
+        assert_eq!(outputs, vec!["", "first", "second", "second"]);
+    }
+
+    #[test]
+    fn test_client_parses_replayed_output() {
+        let list = r#"[{"id":"bd-1.1","title":"Parse plans","status":"open","issue_type":"task",
+            "dependencies":[{"issue_id":"bd-1.1","depends_on_id":"bd-1","type":"parent-child"}]}]"#;
+        let replay = Replay::new(vec![
+            fixture(&["list", "--parent=bd-1", "--json"], 0, list, ""),
+            fixture(
+                &["show", "bd-2", "--json"],
+                1,
+                "",
+                "Error: issue bd-2 not found",
+            ),
+        ]);
+
+        with_replay(replay, || {
+            let filter = ListFilter {
+                parent: Some("bd-1".to_string()),
+                ..Default::default()
+            };
+            let issues = beads::list_issues(Path::new("."), &filter).unwrap();
+            assert_eq!(issues.len(), 1);
+            assert_eq!(issues[0].parent(), Some("bd-1"));
+
+            let err = beads::run_bd(Path::new("."), &["show", "bd-2", "--json"]).unwrap_err();
+            assert!(
+                matches!(err, BdError::Failed { ref stderr, .. } if stderr.contains("not found"))
+            );
+
+            let err = beads::run_bd(Path::new("."), &["close", "bd-9"]).unwrap_err();
+            assert!(err
+                .to_string()
+                .contains("no recorded fixture for bd close bd-9"));
+        });
+    }
+}
@@ -0,0 +1,436 @@
+//! GitHub integration via the `gh` CLI
+//!
+//! Wraps `gh pr view`, `gh run view`, and `gh pr create` with `--json`
+//! output and normalizes check results into a single [`CheckOutcome`], so
+//! CI gates can tell a failed run from a cancelled or still-pending one.
+
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+use std::path::Path;
+use std::process::Command;
+
+/// Normalized result of a check run, status context, or workflow run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckOutcome {
+    Success,
+    /// Completed without a verdict (skipped or neutral); does not block
+    Skipped,
+    Pending,
+    Cancelled,
+    Failure,
+}
+
+impl fmt::Display for CheckOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckOutcome::Success => write!(f, "success"),
+            CheckOutcome::Skipped => write!(f, "skipped"),
+            CheckOutcome::Pending => write!(f, "pending"),
+            CheckOutcome::Cancelled => write!(f, "cancelled"),
+            CheckOutcome::Failure => write!(f, "failure"),
+        }
+    }
+}
+
+impl CheckOutcome {
+    /// Classify a check run or workflow run from its status and conclusion
+    ///
+    /// Anything not yet completed is pending, whatever the conclusion says.
+    pub fn from_run(status: &str, conclusion: &str) -> Self {
+        if !status.is_empty() && !status.eq_ignore_ascii_case("completed") {
+            return CheckOutcome::Pending;
+        }
+        match conclusion.to_lowercase().as_str() {
+            "success" => CheckOutcome::Success,
+            "skipped" | "neutral" => CheckOutcome::Skipped,
+            "cancelled" => CheckOutcome::Cancelled,
+            "" => CheckOutcome::Pending,
+            // failure, timed_out, action_required, startup_failure, stale
+            _ => CheckOutcome::Failure,
+        }
+    }
+
+    /// Classify a commit status context state
+    pub fn from_status_state(state: &str) -> Self {
+        match state.to_lowercase().as_str() {
+            "success" => CheckOutcome::Success,
+            "pending" | "expected" => CheckOutcome::Pending,
+            _ => CheckOutcome::Failure,
+        }
+    }
+
+    /// Combine several outcomes: failure beats cancelled beats pending
+    pub fn combine(outcomes: impl IntoIterator<Item = CheckOutcome>) -> Self {
+        outcomes
+            .into_iter()
+            .max()
+            .map(|o| match o {
+                CheckOutcome::Skipped => CheckOutcome::Success,
+                other => other,
+            })
+            .unwrap_or(CheckOutcome::Success)
+    }
+}
+
+/// gh reports some unset fields as `null` rather than `""`
+fn null_as_empty<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Option::<String>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// One entry of a pull request's `statusCheckRollup`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct RollupEntry {
+    #[serde(rename = "__typename")]
+    typename: String,
+    name: String,
+    context: String,
+    #[serde(deserialize_with = "null_as_empty")]
+    status: String,
+    #[serde(deserialize_with = "null_as_empty")]
+    conclusion: String,
+    #[serde(deserialize_with = "null_as_empty")]
+    state: String,
+    details_url: String,
+    target_url: String,
+}
+
+/// A single check on a pull request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Check {
+    pub name: String,
+    pub outcome: CheckOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+impl From<RollupEntry> for Check {
+    fn from(entry: RollupEntry) -> Self {
+        let non_empty = |s: String| (!s.is_empty()).then_some(s);
+        if entry.typename == "StatusContext" {
+            Check {
+                name: entry.context,
+                outcome: CheckOutcome::from_status_state(&entry.state),
+                url: non_empty(entry.target_url),
+            }
+        } else {
+            Check {
+                name: entry.name,
+                outcome: CheckOutcome::from_run(&entry.status, &entry.conclusion),
+                url: non_empty(entry.details_url),
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct RawPr {
+    number: u64,
+    title: String,
+    state: String,
+    is_draft: bool,
+    url: String,
+    head_ref_name: String,
+    base_ref_name: String,
+    mergeable: String,
+    review_decision: Option<String>,
+    status_check_rollup: Option<Vec<RollupEntry>>,
+}
+
+/// Pull request state with normalized checks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PrStatus {
+    pub number: u64,
+    pub title: String,
+    /// OPEN, CLOSED, or MERGED
+    pub state: String,
+    pub draft: bool,
+    pub url: String,
+    pub head: String,
+    pub base: String,
+    pub mergeable: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub review_decision: Option<String>,
+    /// Combined outcome of all checks
+    pub checks_outcome: CheckOutcome,
+    pub checks: Vec<Check>,
+}
+
+/// A job within a workflow run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RunJob {
+    pub name: String,
+    pub outcome: CheckOutcome,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct RawJob {
+    name: String,
+    #[serde(deserialize_with = "null_as_empty")]
+    status: String,
+    #[serde(deserialize_with = "null_as_empty")]
+    conclusion: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct RawRun {
+    database_id: u64,
+    name: String,
+    #[serde(deserialize_with = "null_as_empty")]
+    status: String,
+    #[serde(deserialize_with = "null_as_empty")]
+    conclusion: String,
+    head_branch: String,
+    head_sha: String,
+    url: String,
+    jobs: Vec<RawJob>,
+}
+
+/// Workflow run state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RunStatus {
+    pub id: u64,
+    pub name: String,
+    pub outcome: CheckOutcome,
+    /// Raw gh status (queued, in_progress, completed, ...)
+    pub status: String,
+    /// Raw gh conclusion, empty while running
+    pub conclusion: String,
+    pub branch: String,
+    pub sha: String,
+    pub url: String,
+    pub jobs: Vec<RunJob>,
+}
+
+/// Options for `gh pr create`
+#[derive(Debug, Clone, Default)]
+pub struct NewPr {
+    pub title: String,
+    pub body: String,
+    pub base: Option<String>,
+    pub head: Option<String>,
+    pub draft: bool,
+}
+
+const PR_FIELDS: &str = "number,title,state,isDraft,url,headRefName,baseRefName,mergeable,reviewDecision,statusCheckRollup";
+const RUN_FIELDS: &str = "databaseId,name,status,conclusion,headBranch,headSha,url,jobs";
+
+/// Run `gh` with `args` in `dir`, returning stdout on success
+pub fn run_gh(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let _span = tracing::debug_span!("gh", args = %args.join(" ")).entered();
+    let output = Command::new("gh")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| format!("Failed to run gh: {}", e))?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        tracing::debug!(status = %output.status, stderr = %stderr.trim(), "gh failed");
+        return Err(format!("gh {} failed: {}", args.join(" "), stderr.trim()));
+    }
+
+    tracing::debug!(bytes = output.stdout.len(), "gh succeeded");
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Parse `gh pr view --json` output
+pub fn parse_pr_status(json: &str) -> Result<PrStatus, String> {
+    let raw: RawPr =
+        serde_json::from_str(json).map_err(|e| format!("Failed to parse gh output: {}", e))?;
+    let checks: Vec<Check> = raw
+        .status_check_rollup
+        .unwrap_or_default()
+        .into_iter()
+        .map(Check::from)
+        .collect();
+
+    Ok(PrStatus {
+        number: raw.number,
+        title: raw.title,
+        state: raw.state,
+        draft: raw.is_draft,
+        url: raw.url,
+        head: raw.head_ref_name,
+        base: raw.base_ref_name,
+        mergeable: raw.mergeable,
+        review_decision: raw.review_decision.filter(|d| !d.is_empty()),
+        checks_outcome: CheckOutcome::combine(checks.iter().map(|c| c.outcome)),
+        checks,
+    })
+}
+
+/// Parse `gh run view --json` output
+pub fn parse_run_status(json: &str) -> Result<RunStatus, String> {
+    let raw: RawRun =
+        serde_json::from_str(json).map_err(|e| format!("Failed to parse gh output: {}", e))?;
+
+    Ok(RunStatus {
+        id: raw.database_id,
+        name: raw.name,
+        outcome: CheckOutcome::from_run(&raw.status, &raw.conclusion),
+        status: raw.status,
+        conclusion: raw.conclusion,
+        branch: raw.head_branch,
+        sha: raw.head_sha,
+        url: raw.url,
+        jobs: raw
+            .jobs
+            .into_iter()
+            .map(|j| RunJob {
+                outcome: CheckOutcome::from_run(&j.status, &j.conclusion),
+                name: j.name,
+            })
+            .collect(),
+    })
+}
+
+/// Status of a pull request, by number, URL, or branch
+pub fn pr_status(dir: &Path, pr: &str) -> Result<PrStatus, String> {
+    parse_pr_status(&run_gh(dir, &["pr", "view", pr, "--json", PR_FIELDS])?)
+}
+
+/// Status of a workflow run
+pub fn run_status(dir: &Path, run_id: &str) -> Result<RunStatus, String> {
+    parse_run_status(&run_gh(
+        dir,
+        &["run", "view", run_id, "--json", RUN_FIELDS],
+    )?)
+}
+
+/// Open a pull request, returning its URL
+pub fn create_pr(dir: &Path, new: &NewPr) -> Result<String, String> {
+    let mut args = vec![
+        "pr".to_string(),
+        "create".to_string(),
+        format!("--title={}", new.title),
+        format!("--body={}", new.body),
+    ];
+    if let Some(ref base) = new.base {
+        args.push(format!("--base={}", base));
+    }
+    if let Some(ref head) = new.head {
+        args.push(format!("--head={}", head));
+    }
+    if new.draft {
+        args.push("--draft".to_string());
+    }
+
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let output = run_gh(dir, &args)?;
+    // gh prints progress lines before the PR URL
+    output
+        .lines()
+        .rev()
+        .map(str::trim)
+        .find(|l| l.starts_with("http"))
+        .map(str::to_string)
+        .ok_or_else(|| format!("gh pr create returned no URL: {}", output.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_run() {
+        assert_eq!(
+            CheckOutcome::from_run("COMPLETED", "SUCCESS"),
+            CheckOutcome::Success
+        );
+        assert_eq!(
+            CheckOutcome::from_run("completed", "cancelled"),
+            CheckOutcome::Cancelled
+        );
+        assert_eq!(
+            CheckOutcome::from_run("completed", "timed_out"),
+            CheckOutcome::Failure
+        );
+        assert_eq!(
+            CheckOutcome::from_run("in_progress", ""),
+            CheckOutcome::Pending
+        );
+        assert_eq!(CheckOutcome::from_run("queued", ""), CheckOutcome::Pending);
+        assert_eq!(
+            CheckOutcome::from_run("completed", "neutral"),
+            CheckOutcome::Skipped
+        );
+    }
+
+    #[test]
+    fn test_combine() {
+        use CheckOutcome::*;
+        assert_eq!(CheckOutcome::combine([Success, Skipped]), Success);
+        assert_eq!(CheckOutcome::combine([Success, Pending]), Pending);
+        assert_eq!(CheckOutcome::combine([Pending, Cancelled]), Cancelled);
+        assert_eq!(
+            CheckOutcome::combine([Cancelled, Failure, Pending]),
+            Failure
+        );
+        assert_eq!(CheckOutcome::combine([]), Success);
+    }
+
+    #[test]
+    fn test_parse_pr_status() {
+        let json = r#"{
+            "number": 42, "title": "Add login", "state": "OPEN", "isDraft": false,
+            "url": "https://github.com/o/r/pull/42",
+            "headRefName": "feature/login", "baseRefName": "main",
+            "mergeable": "MERGEABLE", "reviewDecision": "",
+            "statusCheckRollup": [
+                {"__typename": "CheckRun", "name": "test", "status": "COMPLETED",
+                 "conclusion": "SUCCESS", "detailsUrl": "https://ci/1"},
+                {"__typename": "CheckRun", "name": "lint", "status": "IN_PROGRESS",
+                 "conclusion": null},
+                {"__typename": "StatusContext", "context": "ci/legacy",
+                 "state": "PENDING", "targetUrl": ""}
+            ]
+        }"#;
+
+        let pr = parse_pr_status(json).unwrap();
+
+        assert_eq!(pr.number, 42);
+        assert_eq!(pr.head, "feature/login");
+        assert_eq!(pr.review_decision, None);
+        assert_eq!(pr.checks.len(), 3);
+        assert_eq!(pr.checks[0].url.as_deref(), Some("https://ci/1"));
+        assert_eq!(pr.checks[2].name, "ci/legacy");
+        assert_eq!(pr.checks[2].url, None);
+        assert_eq!(pr.checks_outcome, CheckOutcome::Pending);
+    }
+
+    #[test]
+    fn test_parse_pr_without_checks() {
+        let pr = parse_pr_status(r#"{"number": 1, "statusCheckRollup": null}"#).unwrap();
+        assert!(pr.checks.is_empty());
+        assert_eq!(pr.checks_outcome, CheckOutcome::Success);
+    }
+
+    #[test]
+    fn test_parse_run_status() {
+        let json = r#"{
+            "databaseId": 9001, "name": "CI", "status": "completed",
+            "conclusion": "cancelled", "headBranch": "main", "headSha": "abc",
+            "url": "https://github.com/o/r/actions/runs/9001",
+            "jobs": [
+                {"name": "build", "status": "completed", "conclusion": "success"},
+                {"name": "test", "status": "completed", "conclusion": "cancelled"}
+            ]
+        }"#;
+
+        let run = parse_run_status(json).unwrap();
+
+        assert_eq!(run.id, 9001);
+        assert_eq!(run.outcome, CheckOutcome::Cancelled);
+        assert_eq!(run.jobs[0].outcome, CheckOutcome::Success);
+        assert_eq!(run.jobs[1].outcome, CheckOutcome::Cancelled);
+        assert!(parse_run_status("not json").is_err());
+    }
+}
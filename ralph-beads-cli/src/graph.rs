@@ -0,0 +1,894 @@
+//! Dependency graph analysis for beads epics
+//!
+//! Builds the blocking graph between an epic's child issues and derives
+//! the planning views the swarm validator only summarizes: parallel waves,
+//! the critical path, bottlenecks, orphaned tasks, and cycles. Graphs can
+//! be rendered as DOT or Mermaid.
+//!
+//! Structural validation flags shapes that slow a swarm down (diamonds,
+//! heavy fan-in, waves wider than the worker pool, tasks without acceptance
+//! criteria) with a restructuring suggestion for each.
+
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+use crate::beads::Issue;
+use crate::lint::{self, LintConfig, Severity};
+
+/// Strongly connected components of the blocking graph that form cycles
+///
+/// Uses Tarjan's algorithm; edges to issues outside `issues` are ignored.
+pub fn find_cycles(issues: &[Issue]) -> Vec<Vec<String>> {
+    struct Tarjan<'a> {
+        edges: HashMap<&'a str, Vec<&'a str>>,
+        index: HashMap<&'a str, usize>,
+        lowlink: HashMap<&'a str, usize>,
+        stack: Vec<&'a str>,
+        on_stack: HashMap<&'a str, bool>,
+        next_index: usize,
+        cycles: Vec<Vec<String>>,
+    }
+
+    impl<'a> Tarjan<'a> {
+        fn visit(&mut self, node: &'a str) {
+            self.index.insert(node, self.next_index);
+            self.lowlink.insert(node, self.next_index);
+            self.next_index += 1;
+            self.stack.push(node);
+            self.on_stack.insert(node, true);
+
+            let successors = self.edges.get(node).cloned().unwrap_or_default();
+            for next in successors {
+                if !self.index.contains_key(next) {
+                    self.visit(next);
+                    let low = self.lowlink[node].min(self.lowlink[next]);
+                    self.lowlink.insert(node, low);
+                } else if self.on_stack.get(next).copied().unwrap_or(false) {
+                    let low = self.lowlink[node].min(self.index[next]);
+                    self.lowlink.insert(node, low);
+                }
+            }
+
+            if self.lowlink[node] == self.index[node] {
+                let mut component = Vec::new();
+                while let Some(member) = self.stack.pop() {
+                    self.on_stack.insert(member, false);
+                    component.push(member.to_string());
+                    if member == node {
+                        break;
+                    }
+                }
+                let self_loop = self
+                    .edges
+                    .get(node)
+                    .is_some_and(|succ| succ.contains(&node));
+                if component.len() > 1 || self_loop {
+                    component.sort();
+                    self.cycles.push(component);
+                }
+            }
+        }
+    }
+
+    let known: HashMap<&str, ()> = issues.iter().map(|i| (i.id.as_str(), ())).collect();
+    let edges = issues
+        .iter()
+        .map(|i| {
+            let blockers = i.blockers().filter(|b| known.contains_key(b)).collect();
+            (i.id.as_str(), blockers)
+        })
+        .collect();
+
+    let mut tarjan = Tarjan {
+        edges,
+        index: HashMap::new(),
+        lowlink: HashMap::new(),
+        stack: Vec::new(),
+        on_stack: HashMap::new(),
+        next_index: 0,
+        cycles: Vec::new(),
+    };
+    for issue in issues {
+        if !tarjan.index.contains_key(issue.id.as_str()) {
+            tarjan.visit(&issue.id);
+        }
+    }
+
+    tarjan.cycles.sort();
+    tarjan.cycles
+}
+
+/// A task whose completion unblocks many others
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Bottleneck {
+    pub id: String,
+    pub title: String,
+    /// Issues transitively blocked by this one
+    pub dependents: usize,
+}
+
+/// A blocking edge to an issue outside the analyzed set
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExternalBlocker {
+    pub id: String,
+    pub blocked_by: String,
+}
+
+/// Blocking graph between a set of issues
+///
+/// Edges point from a blocker to the issue it blocks.
+#[derive(Debug, Clone)]
+pub struct DepGraph {
+    pub issues: Vec<Issue>,
+    /// Indexes of the issues each issue blocks
+    pub dependents: Vec<Vec<usize>>,
+    /// Indexes of the issues each issue is blocked by
+    pub blockers: Vec<Vec<usize>>,
+    pub external: Vec<ExternalBlocker>,
+}
+
+impl DepGraph {
+    /// Build the graph; blockers outside `issues` are recorded as external
+    pub fn new(issues: Vec<Issue>) -> Self {
+        let index: HashMap<&str, usize> = issues
+            .iter()
+            .enumerate()
+            .map(|(i, issue)| (issue.id.as_str(), i))
+            .collect();
+        let mut dependents = vec![Vec::new(); issues.len()];
+        let mut blockers = vec![Vec::new(); issues.len()];
+        let mut external = Vec::new();
+
+        for (i, issue) in issues.iter().enumerate() {
+            for blocker in issue.blockers().collect::<BTreeSet<_>>() {
+                match index.get(blocker) {
+                    Some(&b) => {
+                        dependents[b].push(i);
+                        blockers[i].push(b);
+                    }
+                    None => external.push(ExternalBlocker {
+                        id: issue.id.clone(),
+                        blocked_by: blocker.to_string(),
+                    }),
+                }
+            }
+        }
+
+        DepGraph {
+            issues,
+            dependents,
+            blockers,
+            external,
+        }
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.blockers.iter().map(Vec::len).sum()
+    }
+
+    /// Group issues into waves that can run in parallel
+    ///
+    /// An issue's wave is one past the latest wave of its blockers. Issues
+    /// on or behind a cycle never become ready and are left out.
+    pub fn waves(&self) -> Vec<Vec<usize>> {
+        let mut remaining: Vec<usize> = self.blockers.iter().map(Vec::len).collect();
+        let mut level = vec![0; self.issues.len()];
+        let mut queue: VecDeque<usize> = (0..self.issues.len())
+            .filter(|&i| remaining[i] == 0)
+            .collect();
+        let mut waves: Vec<Vec<usize>> = Vec::new();
+
+        while let Some(i) = queue.pop_front() {
+            if waves.len() <= level[i] {
+                waves.resize(level[i] + 1, Vec::new());
+            }
+            waves[level[i]].push(i);
+            for &next in &self.dependents[i] {
+                level[next] = level[next].max(level[i] + 1);
+                remaining[next] -= 1;
+                if remaining[next] == 0 {
+                    queue.push_back(next);
+                }
+            }
+        }
+        waves
+    }
+
+    /// Longest chain of blocking dependencies, first blocker first
+    pub fn critical_path(&self) -> Vec<usize> {
+        let order: Vec<usize> = self.waves().into_iter().flatten().collect();
+        let mut length = vec![0usize; self.issues.len()];
+        let mut previous: Vec<Option<usize>> = vec![None; self.issues.len()];
+
+        // Waves are a topological order, so blockers are settled first
+        for &i in &order {
+            length[i] = length[i].max(1);
+            for &next in &self.dependents[i] {
+                if length[i] + 1 > length[next] {
+                    length[next] = length[i] + 1;
+                    previous[next] = Some(i);
+                }
+            }
+        }
+
+        let Some(mut end) = order
+            .iter()
+            .copied()
+            .max_by_key(|&i| (length[i], usize::MAX - i))
+        else {
+            return Vec::new();
+        };
+        let mut path = vec![end];
+        while let Some(p) = previous[end] {
+            path.push(p);
+            end = p;
+        }
+        path.reverse();
+        path
+    }
+
+    /// Number of issues transitively blocked by issue `i`
+    pub fn transitive_dependents(&self, i: usize) -> usize {
+        let mut seen = BTreeSet::new();
+        let mut stack = self.dependents[i].clone();
+        while let Some(next) = stack.pop() {
+            if seen.insert(next) {
+                stack.extend(&self.dependents[next]);
+            }
+        }
+        seen.remove(&i);
+        seen.len()
+    }
+
+    /// Indexes of every issue that transitively blocks issue `i`
+    pub fn ancestors(&self, i: usize) -> BTreeSet<usize> {
+        let mut seen = BTreeSet::new();
+        let mut stack = self.blockers[i].clone();
+        while let Some(next) = stack.pop() {
+            if seen.insert(next) {
+                stack.extend(&self.blockers[next]);
+            }
+        }
+        seen.remove(&i);
+        seen
+    }
+
+    /// Issues with no dependencies of any kind in a graph of several
+    pub fn orphans(&self) -> Vec<usize> {
+        if self.issues.len() < 2 {
+            return Vec::new();
+        }
+        (0..self.issues.len())
+            .filter(|&i| self.blockers[i].is_empty() && self.dependents[i].is_empty())
+            .filter(|&i| !self.external.iter().any(|e| e.id == self.issues[i].id))
+            .collect()
+    }
+}
+
+/// Drop closed issues and any dependencies on them
+///
+/// A closed blocker no longer blocks, so it should not surface as an
+/// external blocker once removed from the graph.
+pub fn open_issues(issues: Vec<Issue>) -> Vec<Issue> {
+    let closed: BTreeSet<String> = issues
+        .iter()
+        .filter(|i| i.is_closed())
+        .map(|i| i.id.clone())
+        .collect();
+    issues
+        .into_iter()
+        .filter(|i| !i.is_closed())
+        .map(|mut issue| {
+            issue
+                .dependencies
+                .retain(|d| !closed.contains(&d.depends_on_id));
+            issue
+        })
+        .collect()
+}
+
+/// Full analysis of an epic's dependency graph
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GraphAnalysis {
+    pub epic: String,
+    pub nodes: usize,
+    pub edges: usize,
+    pub critical_path: Vec<String>,
+    pub waves: Vec<Vec<String>>,
+    /// Widest wave, i.e. the most tasks that can run at once
+    pub max_parallelism: usize,
+    pub bottlenecks: Vec<Bottleneck>,
+    pub orphans: Vec<String>,
+    pub external_blockers: Vec<ExternalBlocker>,
+    pub cycles: Vec<Vec<String>>,
+}
+
+/// Analyze the graph between `issues`
+///
+/// Issues blocking at least `bottleneck_threshold` others (transitively)
+/// are reported as bottlenecks.
+pub fn analyze(epic: &str, graph: &DepGraph, bottleneck_threshold: usize) -> GraphAnalysis {
+    let ids = |indexes: &[usize]| -> Vec<String> {
+        indexes
+            .iter()
+            .map(|&i| graph.issues[i].id.clone())
+            .collect()
+    };
+    let waves = graph.waves();
+
+    let mut bottlenecks: Vec<Bottleneck> = (0..graph.issues.len())
+        .map(|i| Bottleneck {
+            id: graph.issues[i].id.clone(),
+            title: graph.issues[i].title.clone(),
+            dependents: graph.transitive_dependents(i),
+        })
+        .filter(|b| b.dependents >= bottleneck_threshold.max(1))
+        .collect();
+    bottlenecks.sort_by(|a, b| b.dependents.cmp(&a.dependents).then(a.id.cmp(&b.id)));
+
+    GraphAnalysis {
+        epic: epic.to_string(),
+        nodes: graph.issues.len(),
+        edges: graph.edge_count(),
+        critical_path: ids(&graph.critical_path()),
+        max_parallelism: waves.iter().map(Vec::len).max().unwrap_or(0),
+        waves: waves.iter().map(|w| ids(w)).collect(),
+        bottlenecks,
+        orphans: ids(&graph.orphans()),
+        external_blockers: graph.external.clone(),
+        cycles: find_cycles(&graph.issues),
+    }
+}
+
+/// Limits applied by structural validation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidateOptions {
+    /// Workers available; waves wider than this are flagged
+    pub workers: Option<usize>,
+    /// Direct blockers above which a task is flagged as a fan-in point
+    pub max_fan_in: usize,
+}
+
+impl Default for ValidateOptions {
+    fn default() -> Self {
+        ValidateOptions {
+            workers: None,
+            max_fan_in: 4,
+        }
+    }
+}
+
+/// A structural problem in an epic's graph
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StructuralFinding {
+    /// Check that produced the finding, e.g. `diamond` or `fan-in`
+    pub check: String,
+    pub severity: Severity,
+    /// Issues involved, most relevant first
+    pub issues: Vec<String>,
+    pub message: String,
+    pub suggestion: String,
+}
+
+/// Outcome of structural validation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StructureReport {
+    pub epic: String,
+    pub nodes: usize,
+    pub workers: Option<usize>,
+    pub findings: Vec<StructuralFinding>,
+}
+
+impl StructureReport {
+    /// Whether any error-severity findings were reported
+    pub fn has_errors(&self) -> bool {
+        self.findings.iter().any(|f| f.severity == Severity::Error)
+    }
+}
+
+fn finding(
+    check: &str,
+    severity: Severity,
+    issues: Vec<String>,
+    message: String,
+    suggestion: String,
+) -> StructuralFinding {
+    StructuralFinding {
+        check: check.to_string(),
+        severity,
+        issues,
+        message,
+        suggestion,
+    }
+}
+
+/// Diamonds: a task whose blockers split from a shared ancestor
+///
+/// Only the nearest shared ancestor is reported for each join.
+fn diamonds(graph: &DepGraph) -> Vec<StructuralFinding> {
+    let mut findings = Vec::new();
+    for join in 0..graph.issues.len() {
+        let blockers = &graph.blockers[join];
+        if blockers.len() < 2 {
+            continue;
+        }
+        let lineage: Vec<BTreeSet<usize>> = blockers
+            .iter()
+            .map(|&b| {
+                let mut set = graph.ancestors(b);
+                set.insert(b);
+                set
+            })
+            .collect();
+
+        let mut tops = BTreeSet::new();
+        for (x, a) in lineage.iter().enumerate() {
+            for b in &lineage[x + 1..] {
+                tops.extend(a.intersection(b).copied());
+            }
+        }
+        // Keep the lowest shared ancestors; their own ancestors add nothing
+        let covered: BTreeSet<usize> = tops.iter().flat_map(|&t| graph.ancestors(t)).collect();
+
+        for &top in tops.difference(&covered) {
+            let branches: Vec<String> = blockers
+                .iter()
+                .zip(&lineage)
+                .filter(|(&b, set)| b != top && set.contains(&top))
+                .map(|(&b, _)| graph.issues[b].id.clone())
+                .collect();
+            if branches.len() < 2 {
+                continue;
+            }
+            let (top_id, join_id) = (&graph.issues[top].id, &graph.issues[join].id);
+            let mut issues = vec![top_id.clone(), join_id.clone()];
+            issues.extend(branches.iter().cloned());
+            findings.push(finding(
+                "diamond",
+                Severity::Warning,
+                issues,
+                format!(
+                    "{} fans out to {} which all rejoin at {}",
+                    top_id,
+                    branches.join(", "),
+                    join_id
+                ),
+                format!(
+                    "Check that {} needs every branch; dropping an unneeded edge lets it start sooner",
+                    join_id
+                ),
+            ));
+        }
+    }
+    findings
+}
+
+fn fan_in(graph: &DepGraph, max: usize) -> Vec<StructuralFinding> {
+    (0..graph.issues.len())
+        .filter(|&i| graph.blockers[i].len() > max)
+        .map(|i| {
+            let id = &graph.issues[i].id;
+            let mut issues = vec![id.clone()];
+            issues.extend(
+                graph.blockers[i]
+                    .iter()
+                    .map(|&b| graph.issues[b].id.clone()),
+            );
+            finding(
+                "fan-in",
+                Severity::Warning,
+                issues,
+                format!(
+                    "{} waits on {} tasks (max {})",
+                    id,
+                    graph.blockers[i].len(),
+                    max
+                ),
+                format!(
+                    "Split {} so each part depends only on the tasks it uses",
+                    id
+                ),
+            )
+        })
+        .collect()
+}
+
+fn front_size(graph: &DepGraph, workers: usize) -> Vec<StructuralFinding> {
+    let workers = workers.max(1);
+    graph
+        .waves()
+        .iter()
+        .enumerate()
+        .filter(|(_, wave)| wave.len() > workers)
+        .map(|(n, wave)| {
+            finding(
+                "front-size",
+                Severity::Warning,
+                wave.iter().map(|&i| graph.issues[i].id.clone()).collect(),
+                format!(
+                    "Wave {} has {} ready tasks for {} workers ({} rounds)",
+                    n + 1,
+                    wave.len(),
+                    workers,
+                    wave.len().div_ceil(workers)
+                ),
+                "Add workers, or make low-priority tasks in this wave depend on earlier ones"
+                    .to_string(),
+            )
+        })
+        .collect()
+}
+
+fn missing_acceptance_criteria(graph: &DepGraph, config: &LintConfig) -> Vec<StructuralFinding> {
+    graph
+        .issues
+        .iter()
+        .filter(|issue| lint::requires_acceptance_criteria(config, issue))
+        .filter(|issue| issue.acceptance_criteria.trim().is_empty())
+        .map(|issue| {
+            finding(
+                "acceptance-criteria",
+                Severity::Warning,
+                vec![issue.id.clone()],
+                format!("{} has no acceptance criteria", issue.id),
+                format!(
+                    "Add '- [ ]' criteria so workers know when {} is done",
+                    issue.id
+                ),
+            )
+        })
+        .collect()
+}
+
+/// Check the graph between an epic's issues for structural problems
+///
+/// Everything is computed from the issues themselves; cycles are errors,
+/// the remaining checks are warnings.
+pub fn validate_structure(
+    epic: &str,
+    graph: &DepGraph,
+    lint_config: &LintConfig,
+    options: &ValidateOptions,
+) -> StructureReport {
+    let mut findings: Vec<StructuralFinding> = find_cycles(&graph.issues)
+        .into_iter()
+        .map(|cycle| {
+            finding(
+                "cycle",
+                Severity::Error,
+                cycle.clone(),
+                format!("Dependency cycle: {}", cycle.join(" -> ")),
+                format!(
+                    "Remove one blocking edge, e.g. bd dep remove {} <id>",
+                    cycle[0]
+                ),
+            )
+        })
+        .collect();
+    findings.extend(diamonds(graph));
+    findings.extend(fan_in(graph, options.max_fan_in));
+    if let Some(workers) = options.workers {
+        findings.extend(front_size(graph, workers));
+    }
+    findings.extend(missing_acceptance_criteria(graph, lint_config));
+
+    StructureReport {
+        epic: epic.to_string(),
+        nodes: graph.issues.len(),
+        workers: options.workers,
+        findings,
+    }
+}
+
+/// Render a structure report as text
+pub fn render_structure(report: &StructureReport) -> String {
+    let mut out = format!("Epic {}: {} tasks\n", report.epic, report.nodes);
+    if report.findings.is_empty() {
+        out.push_str("No structural problems found\n");
+    }
+    for f in &report.findings {
+        out.push_str(&format!("\n{} [{}] {}\n", f.severity, f.check, f.message));
+        out.push_str(&format!("    fix: {}\n", f.suggestion));
+    }
+    out
+}
+
+/// Render an analysis as text
+pub fn render_text(analysis: &GraphAnalysis) -> String {
+    let mut out = format!(
+        "Epic {}: {} tasks, {} dependencies\n",
+        analysis.epic, analysis.nodes, analysis.edges
+    );
+    out.push_str(&format!(
+        "\nCritical path ({}): {}\n",
+        analysis.critical_path.len(),
+        analysis.critical_path.join(" -> ")
+    ));
+    out.push_str(&format!(
+        "\nWaves (max parallelism {}):\n",
+        analysis.max_parallelism
+    ));
+    for (n, wave) in analysis.waves.iter().enumerate() {
+        out.push_str(&format!("  {}: {}\n", n + 1, wave.join(", ")));
+    }
+    if !analysis.bottlenecks.is_empty() {
+        out.push_str("\nBottlenecks:\n");
+        for b in &analysis.bottlenecks {
+            out.push_str(&format!(
+                "  {} blocks {} ({})\n",
+                b.id, b.dependents, b.title
+            ));
+        }
+    }
+    if !analysis.orphans.is_empty() {
+        out.push_str(&format!("\nOrphans: {}\n", analysis.orphans.join(", ")));
+    }
+    if !analysis.external_blockers.is_empty() {
+        out.push_str("\nExternal blockers:\n");
+        for e in &analysis.external_blockers {
+            out.push_str(&format!("  {} <- {}\n", e.id, e.blocked_by));
+        }
+    }
+    for cycle in &analysis.cycles {
+        out.push_str(&format!("\nCycle: {}\n", cycle.join(" -> ")));
+    }
+    out
+}
+
+fn escape_label(title: &str) -> String {
+    title.replace('"', "'")
+}
+
+/// Render the graph in Graphviz DOT, highlighting the critical path
+pub fn render_dot(graph: &DepGraph, analysis: &GraphAnalysis) -> String {
+    let critical: BTreeSet<&str> = analysis.critical_path.iter().map(String::as_str).collect();
+    let mut out = format!("digraph \"{}\" {{\n  rankdir=LR;\n", analysis.epic);
+    for issue in &graph.issues {
+        let style = if critical.contains(issue.id.as_str()) {
+            ", color=red"
+        } else {
+            ""
+        };
+        out.push_str(&format!(
+            "  \"{}\" [label=\"{}\\n{}\"{}];\n",
+            issue.id,
+            issue.id,
+            escape_label(&issue.title),
+            style
+        ));
+    }
+    for (i, dependents) in graph.dependents.iter().enumerate() {
+        for &d in dependents {
+            out.push_str(&format!(
+                "  \"{}\" -> \"{}\";\n",
+                graph.issues[i].id, graph.issues[d].id
+            ));
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Mermaid node identifiers may not contain dots or dashes
+fn mermaid_id(id: &str) -> String {
+    id.chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Render the graph as a Mermaid flowchart, highlighting the critical path
+pub fn render_mermaid(graph: &DepGraph, analysis: &GraphAnalysis) -> String {
+    let mut out = String::from("flowchart LR\n");
+    for issue in &graph.issues {
+        out.push_str(&format!(
+            "    {}[\"{}: {}\"]\n",
+            mermaid_id(&issue.id),
+            issue.id,
+            escape_label(&issue.title)
+        ));
+    }
+    for (i, dependents) in graph.dependents.iter().enumerate() {
+        for &d in dependents {
+            out.push_str(&format!(
+                "    {} --> {}\n",
+                mermaid_id(&graph.issues[i].id),
+                mermaid_id(&graph.issues[d].id)
+            ));
+        }
+    }
+    if !analysis.critical_path.is_empty() {
+        out.push_str("    classDef critical stroke:#d33,stroke-width:3px\n");
+        let ids: Vec<String> = analysis
+            .critical_path
+            .iter()
+            .map(|id| mermaid_id(id))
+            .collect();
+        out.push_str(&format!("    class {} critical\n", ids.join(",")));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beads::test_issue;
+
+    //   a -> b -> d -> e
+    //   a -> c -> d
+    //   f (orphan), g blocked by external x
+    fn sample() -> DepGraph {
+        DepGraph::new(vec![
+            test_issue("a", "open", &[]),
+            test_issue("b", "open", &["a"]),
+            test_issue("c", "open", &["a"]),
+            test_issue("d", "open", &["b", "c"]),
+            test_issue("e", "open", &["d"]),
+            test_issue("f", "open", &[]),
+            test_issue("g", "open", &["x"]),
+        ])
+    }
+
+    #[test]
+    fn test_waves() {
+        let analysis = analyze("epic", &sample(), 3);
+
+        assert_eq!(
+            analysis.waves,
+            vec![vec!["a", "f", "g"], vec!["b", "c"], vec!["d"], vec!["e"],]
+        );
+        assert_eq!(analysis.max_parallelism, 3);
+        assert_eq!(analysis.edges, 5);
+    }
+
+    #[test]
+    fn test_critical_path() {
+        let analysis = analyze("epic", &sample(), 3);
+        assert_eq!(analysis.critical_path, vec!["a", "b", "d", "e"]);
+    }
+
+    #[test]
+    fn test_bottlenecks_orphans_and_external() {
+        let analysis = analyze("epic", &sample(), 2);
+
+        let bottlenecks: Vec<(&str, usize)> = analysis
+            .bottlenecks
+            .iter()
+            .map(|b| (b.id.as_str(), b.dependents))
+            .collect();
+        assert_eq!(bottlenecks, vec![("a", 4), ("b", 2), ("c", 2)]);
+        assert_eq!(analysis.orphans, vec!["f"]);
+        assert_eq!(
+            analysis.external_blockers,
+            vec![ExternalBlocker {
+                id: "g".to_string(),
+                blocked_by: "x".to_string()
+            }]
+        );
+        assert!(analysis.cycles.is_empty());
+    }
+
+    #[test]
+    fn test_cycle_members_excluded_from_waves() {
+        let graph = DepGraph::new(vec![
+            test_issue("a", "open", &[]),
+            test_issue("b", "open", &["a", "c"]),
+            test_issue("c", "open", &["b"]),
+            test_issue("d", "open", &["c"]),
+        ]);
+
+        let analysis = analyze("epic", &graph, 3);
+
+        assert_eq!(analysis.waves, vec![vec!["a"]]);
+        assert_eq!(analysis.cycles, vec![vec!["b", "c"]]);
+        assert_eq!(analysis.critical_path, vec!["a"]);
+    }
+
+    #[test]
+    fn test_open_issues_drops_closed_blockers() {
+        let mut done = test_issue("a", "open", &[]);
+        done.status = "closed".to_string();
+
+        let open = open_issues(vec![
+            done,
+            test_issue("b", "open", &["a"]),
+            test_issue("c", "open", &["b"]),
+        ]);
+        let graph = DepGraph::new(open);
+
+        assert_eq!(graph.issues.len(), 2);
+        assert!(graph.external.is_empty());
+        assert_eq!(graph.edge_count(), 1);
+    }
+
+    #[test]
+    fn test_validate_structure() {
+        let mut graph = sample();
+        for issue in graph.issues.iter_mut().filter(|i| i.id != "b") {
+            issue.acceptance_criteria = "- [ ] Done".to_string();
+        }
+        let report = validate_structure(
+            "epic",
+            &graph,
+            &LintConfig::default(),
+            &ValidateOptions {
+                workers: Some(2),
+                max_fan_in: 1,
+            },
+        );
+
+        let checks: Vec<(&str, &[String])> = report
+            .findings
+            .iter()
+            .map(|f| (f.check.as_str(), f.issues.as_slice()))
+            .collect();
+        assert_eq!(
+            checks,
+            vec![
+                ("diamond", &["a", "d", "b", "c"].map(String::from)[..]),
+                ("fan-in", &["d", "b", "c"].map(String::from)[..]),
+                ("front-size", &["a", "f", "g"].map(String::from)[..]),
+                ("acceptance-criteria", &["b"].map(String::from)[..]),
+            ]
+        );
+        assert!(report.findings[2].message.contains("(2 rounds)"));
+        assert!(!report.has_errors());
+    }
+
+    #[test]
+    fn test_validate_reports_nearest_diamond_and_cycles() {
+        // a -> b -> {c, d} -> e: only b is the split point
+        let graph = DepGraph::new(vec![
+            test_issue("a", "open", &[]),
+            test_issue("b", "open", &["a"]),
+            test_issue("c", "open", &["b"]),
+            test_issue("d", "open", &["b"]),
+            test_issue("e", "open", &["c", "d"]),
+            test_issue("x", "open", &["y"]),
+            test_issue("y", "open", &["x"]),
+        ]);
+
+        let report = validate_structure(
+            "epic",
+            &graph,
+            &LintConfig::default(),
+            &ValidateOptions::default(),
+        );
+
+        let diamonds: Vec<&StructuralFinding> = report
+            .findings
+            .iter()
+            .filter(|f| f.check == "diamond")
+            .collect();
+        assert_eq!(diamonds.len(), 1);
+        assert_eq!(diamonds[0].issues[0], "b");
+        assert!(report.has_errors());
+        assert!(render_structure(&report).contains("error [cycle]"));
+    }
+
+    #[test]
+    fn test_empty_graph() {
+        let analysis = analyze("epic", &DepGraph::new(Vec::new()), 3);
+        assert!(analysis.critical_path.is_empty());
+        assert!(analysis.waves.is_empty());
+        assert_eq!(analysis.max_parallelism, 0);
+    }
+
+    #[test]
+    fn test_render_dot_and_mermaid() {
+        let graph = DepGraph::new(vec![
+            test_issue("rb-1.1", "open", &[]),
+            test_issue("rb-1.2", "open", &["rb-1.1"]),
+        ]);
+        let analysis = analyze("rb-1", &graph, 3);
+
+        let dot = render_dot(&graph, &analysis);
+        assert!(dot.starts_with("digraph \"rb-1\" {"));
+        assert!(dot.contains("  \"rb-1.1\" -> \"rb-1.2\";\n"));
+        assert!(dot.contains("color=red"));
+
+        let mermaid = render_mermaid(&graph, &analysis);
+        assert!(mermaid.starts_with("flowchart LR\n"));
+        assert!(mermaid.contains("    rb_1_1 --> rb_1_2\n"));
+        assert!(mermaid.contains("    class rb_1_1,rb_1_2 critical\n"));
+    }
+}
@@ -0,0 +1,437 @@
+//! Project health checks
+//!
+//! A broken beads setup rarely fails loudly: a worktree whose
+//! `.beads/redirect` points at a moved database, or a lock left behind by a
+//! crashed daemon, makes every later `bd` call fail with an unrelated error.
+//! `health` checks for these up front:
+//!
+//! - `beads-dir`: the project has a `.beads` directory
+//! - `redirect`: a `.beads/redirect` (used by worktrees) points at an
+//!   existing beads directory with a database, without chaining further
+//! - `locks`: no lock or pid file names a process that has exited
+//! - `daemon`: bd answers `bd info` within the configured timeout
+//! - `write-probe` (opt-in): a scratch bead can be created, read back, and
+//!   deleted
+//!
+//! The `health` command adds the toolchain pin checks of
+//! [`crate::toolchain`] after these.
+
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::beads::{create_issue, delete_issue, run_bd, show_issue, NewIssue};
+
+/// Beads directory of a project
+pub const BEADS_DIR: &str = ".beads";
+
+/// File in a worktree's beads directory naming the shared database
+pub const REDIRECT_FILE: &str = "redirect";
+
+/// Title of the scratch bead created by the write probe
+const PROBE_TITLE: &str = "ralph-beads health probe (safe to delete)";
+
+/// Outcome of one health check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    /// Not applicable, or not requested
+    Skipped,
+    Warn,
+    Fail,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CheckStatus::Pass => "pass",
+            CheckStatus::Skipped => "skipped",
+            CheckStatus::Warn => "warn",
+            CheckStatus::Fail => "fail",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// One health check and what it found
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HealthCheck {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl HealthCheck {
+    pub(crate) fn new(name: &'static str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        HealthCheck {
+            name,
+            status,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    pub(crate) fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+}
+
+/// Which optional checks to run
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HealthOptions {
+    /// Create, read, and delete a scratch bead
+    pub probe: bool,
+}
+
+/// Worst status among `checks`
+pub fn overall_status(checks: &[HealthCheck]) -> CheckStatus {
+    checks
+        .iter()
+        .map(|c| c.status)
+        .max()
+        .unwrap_or(CheckStatus::Pass)
+}
+
+/// Target of a redirect file, resolved against the project directory
+pub fn read_redirect(beads_dir: &Path) -> Result<Option<PathBuf>, String> {
+    let path = beads_dir.join(REDIRECT_FILE);
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    let target = content.trim();
+    if target.is_empty() {
+        return Err(format!("{} is empty", path.display()));
+    }
+    let target = PathBuf::from(target);
+    if target.is_absolute() {
+        return Ok(Some(target));
+    }
+    let project = beads_dir.parent().unwrap_or(beads_dir);
+    Ok(Some(project.join(target)))
+}
+
+/// Whether `dir` holds a beads database (SQLite file or JSONL export)
+fn has_database(dir: &Path) -> bool {
+    fs::read_dir(dir).is_ok_and(|entries| {
+        entries.flatten().any(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            name.ends_with(".db") || name.ends_with(".jsonl")
+        })
+    })
+}
+
+fn check_beads_dir(beads_dir: &Path) -> HealthCheck {
+    if beads_dir.is_dir() {
+        HealthCheck::new(
+            "beads-dir",
+            CheckStatus::Pass,
+            beads_dir.display().to_string(),
+        )
+    } else {
+        HealthCheck::new(
+            "beads-dir",
+            CheckStatus::Fail,
+            format!("{} not found", beads_dir.display()),
+        )
+        .with_hint("Run `ralph-beads-cli init` or `bd init`")
+    }
+}
+
+/// Check the redirect and return the directory holding the database
+fn check_redirect(beads_dir: &Path) -> (HealthCheck, Option<PathBuf>) {
+    let target = match read_redirect(beads_dir) {
+        Ok(None) => {
+            let check = if has_database(beads_dir) {
+                HealthCheck::new("redirect", CheckStatus::Pass, "no redirect; local database")
+            } else {
+                HealthCheck::new("redirect", CheckStatus::Warn, "no redirect and no database")
+                    .with_hint("bd creates the database on first write")
+            };
+            return (check, Some(beads_dir.to_path_buf()));
+        }
+        Ok(Some(target)) => target,
+        Err(e) => {
+            let check = HealthCheck::new("redirect", CheckStatus::Fail, e)
+                .with_hint("Write the path of the main repository's .beads directory into it");
+            return (check, None);
+        }
+    };
+
+    let hint = "Point .beads/redirect at the main repository's .beads directory";
+    let check = if !target.is_dir() {
+        HealthCheck::new(
+            "redirect",
+            CheckStatus::Fail,
+            format!("redirect target {} does not exist", target.display()),
+        )
+        .with_hint(hint)
+    } else if target.join(REDIRECT_FILE).exists() {
+        HealthCheck::new(
+            "redirect",
+            CheckStatus::Fail,
+            format!("redirect target {} is itself redirected", target.display()),
+        )
+        .with_hint(hint)
+    } else if !has_database(&target) {
+        HealthCheck::new(
+            "redirect",
+            CheckStatus::Fail,
+            format!("redirect target {} has no database", target.display()),
+        )
+        .with_hint(hint)
+    } else {
+        HealthCheck::new(
+            "redirect",
+            CheckStatus::Pass,
+            format!("-> {}", target.display()),
+        )
+    };
+    let usable = check.status == CheckStatus::Pass;
+    (check, usable.then_some(target))
+}
+
+/// Whether a process with `pid` is running
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    let proc_dir = Path::new("/proc");
+    if proc_dir.is_dir() {
+        return proc_dir.join(pid.to_string()).exists();
+    }
+    std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    // Can't tell; never report a lock as orphaned
+    true
+}
+
+/// Lock and pid files in `dir` whose recorded process has exited
+pub fn orphan_locks(dir: &Path, alive: impl Fn(u32) -> bool) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut orphans: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext == "lock" || ext == "pid")
+        })
+        .filter(|path| {
+            fs::read_to_string(path)
+                .ok()
+                .and_then(|content| {
+                    content
+                        .split(|c: char| !c.is_ascii_digit())
+                        .find(|s| !s.is_empty())
+                        .and_then(|s| s.parse::<u32>().ok())
+                })
+                .is_some_and(|pid| !alive(pid))
+        })
+        .collect();
+    orphans.sort();
+    orphans
+}
+
+fn check_locks(db_dir: &Path) -> HealthCheck {
+    let orphans = orphan_locks(db_dir, process_alive);
+    if orphans.is_empty() {
+        return HealthCheck::new("locks", CheckStatus::Pass, "no orphaned locks");
+    }
+    let names: Vec<String> = orphans.iter().map(|p| p.display().to_string()).collect();
+    HealthCheck::new(
+        "locks",
+        CheckStatus::Fail,
+        format!("held by exited processes: {}", names.join(", ")),
+    )
+    .with_hint("Stop any bd daemon for this project, then remove the listed files")
+}
+
+fn check_daemon(dir: &Path) -> HealthCheck {
+    match run_bd(dir, &["info", "--json"]) {
+        Ok(_) => HealthCheck::new("daemon", CheckStatus::Pass, "bd info responded"),
+        Err(e) => {
+            let hint = match &e {
+                crate::beads::BdError::BdUnavailable { hint, .. } => hint.clone(),
+                _ => "Restart the daemon with `bd daemon --stop` and retry".to_string(),
+            };
+            HealthCheck::new("daemon", CheckStatus::Fail, e.to_string()).with_hint(hint)
+        }
+    }
+}
+
+fn check_write_probe(dir: &Path) -> HealthCheck {
+    let new = NewIssue {
+        title: PROBE_TITLE.to_string(),
+        issue_type: "task".to_string(),
+        priority: Some(4),
+        ..Default::default()
+    };
+    let hint = "Check that the database is writable and not locked by another process";
+    let created = match create_issue(dir, &new) {
+        Ok(issue) => issue,
+        Err(e) => {
+            return HealthCheck::new("write-probe", CheckStatus::Fail, format!("create: {}", e))
+                .with_hint(hint)
+        }
+    };
+    let read = show_issue(dir, &created.id);
+    let deleted = delete_issue(dir, &created.id);
+    match (read, deleted) {
+        (Ok(issue), Ok(_)) if issue.title == PROBE_TITLE => HealthCheck::new(
+            "write-probe",
+            CheckStatus::Pass,
+            format!("created, read, and deleted {}", created.id),
+        ),
+        (Ok(issue), Ok(_)) => HealthCheck::new(
+            "write-probe",
+            CheckStatus::Fail,
+            format!("{} read back with title '{}'", created.id, issue.title),
+        )
+        .with_hint(hint),
+        (Err(e), _) => HealthCheck::new(
+            "write-probe",
+            CheckStatus::Fail,
+            format!("read {}: {}", created.id, e),
+        )
+        .with_hint(hint),
+        (Ok(_), Err(e)) => HealthCheck::new(
+            "write-probe",
+            CheckStatus::Warn,
+            format!("could not delete {}: {}", created.id, e),
+        )
+        .with_hint(format!("Delete the scratch bead: bd delete {}", created.id)),
+    }
+}
+
+/// Check the beads setup of the project in `dir`
+///
+/// Later checks are skipped when the beads directory or redirect is
+/// unusable, since they would only repeat that failure.
+pub fn check_beads(dir: &Path, options: HealthOptions) -> Vec<HealthCheck> {
+    let beads_dir = dir.join(BEADS_DIR);
+    let mut checks = vec![check_beads_dir(&beads_dir)];
+    if checks[0].status == CheckStatus::Fail {
+        for name in ["redirect", "locks", "daemon", "write-probe"] {
+            checks.push(HealthCheck::new(name, CheckStatus::Skipped, "no .beads"));
+        }
+        return checks;
+    }
+
+    let (redirect, db_dir) = check_redirect(&beads_dir);
+    checks.push(redirect);
+    match db_dir {
+        Some(ref db_dir) => checks.push(check_locks(db_dir)),
+        None => checks.push(HealthCheck::new(
+            "locks",
+            CheckStatus::Skipped,
+            "redirect unusable",
+        )),
+    }
+    checks.push(check_daemon(dir));
+
+    let write_probe = if !options.probe {
+        HealthCheck::new("write-probe", CheckStatus::Skipped, "pass --probe to run")
+    } else if checks.iter().any(|c| c.status == CheckStatus::Fail) {
+        HealthCheck::new("write-probe", CheckStatus::Skipped, "earlier checks failed")
+    } else {
+        check_write_probe(dir)
+    };
+    checks.push(write_probe);
+    checks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn project_with_db(root: &Path, name: &str) -> PathBuf {
+        let beads = root.join(name).join(BEADS_DIR);
+        fs::create_dir_all(&beads).unwrap();
+        fs::write(beads.join("beads.db"), "").unwrap();
+        beads
+    }
+
+    #[test]
+    fn test_redirect_resolution() {
+        let root = TempDir::new().unwrap();
+        let main = project_with_db(root.path(), "main");
+        let wt = root.path().join("wt").join(BEADS_DIR);
+        fs::create_dir_all(&wt).unwrap();
+
+        assert_eq!(read_redirect(&wt).unwrap(), None);
+        fs::write(wt.join(REDIRECT_FILE), "../main/.beads\n").unwrap();
+        assert_eq!(
+            read_redirect(&wt).unwrap(),
+            Some(root.path().join("wt").join("../main/.beads"))
+        );
+
+        let (check, db_dir) = check_redirect(&wt);
+        assert_eq!(check.status, CheckStatus::Pass);
+        assert_eq!(
+            db_dir.unwrap().canonicalize().unwrap(),
+            main.canonicalize().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_broken_redirects() {
+        let root = TempDir::new().unwrap();
+        let wt = root.path().join("wt").join(BEADS_DIR);
+        fs::create_dir_all(&wt).unwrap();
+
+        fs::write(wt.join(REDIRECT_FILE), "../gone/.beads").unwrap();
+        let (check, db_dir) = check_redirect(&wt);
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert!(check.detail.contains("does not exist"));
+        assert!(db_dir.is_none());
+
+        let empty = root.path().join("empty").join(BEADS_DIR);
+        fs::create_dir_all(&empty).unwrap();
+        fs::write(wt.join(REDIRECT_FILE), "../empty/.beads").unwrap();
+        assert!(check_redirect(&wt).0.detail.contains("has no database"));
+
+        let chained = project_with_db(root.path(), "chained");
+        fs::write(chained.join(REDIRECT_FILE), "../main/.beads").unwrap();
+        fs::write(wt.join(REDIRECT_FILE), "../chained/.beads").unwrap();
+        assert!(check_redirect(&wt).0.detail.contains("itself redirected"));
+    }
+
+    #[test]
+    fn test_orphan_locks() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("daemon.lock"), "pid=41\n").unwrap();
+        fs::write(dir.path().join("daemon.pid"), "42").unwrap();
+        fs::write(dir.path().join("other.lock"), "").unwrap();
+        fs::write(dir.path().join("beads.db"), "123").unwrap();
+
+        let orphans = orphan_locks(dir.path(), |pid| pid == 42);
+
+        assert_eq!(orphans, vec![dir.path().join("daemon.lock")]);
+    }
+
+    #[test]
+    fn test_missing_beads_dir_skips_the_rest() {
+        let dir = TempDir::new().unwrap();
+
+        let checks = check_beads(dir.path(), HealthOptions { probe: true });
+
+        assert_eq!(checks.len(), 5);
+        assert_eq!(checks[0].status, CheckStatus::Fail);
+        assert!(checks[1..].iter().all(|c| c.status == CheckStatus::Skipped));
+        assert_eq!(overall_status(&checks), CheckStatus::Fail);
+    }
+}
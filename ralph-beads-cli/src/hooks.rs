@@ -0,0 +1,318 @@
+//! Lifecycle hooks
+//!
+//! User commands configured in the `[hooks]` section of `.ralph-beads.toml`
+//! run at fixed lifecycle points. Each command runs through `sh -c` with
+//! the hook payload as JSON on stdin and `RALPH_HOOK` naming the point, and
+//! is killed once it runs past `timeout_secs`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::beads::run_with_timeout;
+
+/// Lifecycle points that can trigger hooks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HookPoint {
+    PreIteration,
+    PostIteration,
+    OnFailure,
+    OnGateCreated,
+    OnSwarmComplete,
+    OnPreflightFail,
+}
+
+impl HookPoint {
+    pub const ALL: [HookPoint; 6] = [
+        HookPoint::PreIteration,
+        HookPoint::PostIteration,
+        HookPoint::OnFailure,
+        HookPoint::OnGateCreated,
+        HookPoint::OnSwarmComplete,
+        HookPoint::OnPreflightFail,
+    ];
+}
+
+impl fmt::Display for HookPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HookPoint::PreIteration => write!(f, "pre-iteration"),
+            HookPoint::PostIteration => write!(f, "post-iteration"),
+            HookPoint::OnFailure => write!(f, "on-failure"),
+            HookPoint::OnGateCreated => write!(f, "on-gate-created"),
+            HookPoint::OnSwarmComplete => write!(f, "on-swarm-complete"),
+            HookPoint::OnPreflightFail => write!(f, "on-preflight-fail"),
+        }
+    }
+}
+
+impl FromStr for HookPoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.to_lowercase().replace('_', "-");
+        HookPoint::ALL
+            .iter()
+            .copied()
+            .find(|p| p.to_string() == normalized)
+            .ok_or_else(|| {
+                let valid: Vec<String> = HookPoint::ALL.iter().map(|p| p.to_string()).collect();
+                format!("Unknown hook point: {} (valid: {})", s, valid.join(", "))
+            })
+    }
+}
+
+/// The `[hooks]` configuration section: commands per lifecycle point
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct HooksConfig {
+    pub pre_iteration: Vec<String>,
+    pub post_iteration: Vec<String>,
+    pub on_failure: Vec<String>,
+    pub on_gate_created: Vec<String>,
+    pub on_swarm_complete: Vec<String>,
+    pub on_preflight_fail: Vec<String>,
+    /// Seconds a hook command may run before it is killed
+    #[serde(rename = "timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl Default for HooksConfig {
+    fn default() -> Self {
+        HooksConfig {
+            pre_iteration: Vec::new(),
+            post_iteration: Vec::new(),
+            on_failure: Vec::new(),
+            on_gate_created: Vec::new(),
+            on_swarm_complete: Vec::new(),
+            on_preflight_fail: Vec::new(),
+            timeout_secs: 60,
+        }
+    }
+}
+
+impl HooksConfig {
+    /// Commands configured for `point`, in order
+    pub fn commands(&self, point: HookPoint) -> &[String] {
+        match point {
+            HookPoint::PreIteration => &self.pre_iteration,
+            HookPoint::PostIteration => &self.post_iteration,
+            HookPoint::OnFailure => &self.on_failure,
+            HookPoint::OnGateCreated => &self.on_gate_created,
+            HookPoint::OnSwarmComplete => &self.on_swarm_complete,
+            HookPoint::OnPreflightFail => &self.on_preflight_fail,
+        }
+    }
+
+    /// Reject empty commands and a zero timeout
+    pub fn validate(&self) -> Result<(), String> {
+        if self.timeout_secs == 0 {
+            return Err("hooks.timeout_secs must be at least 1".to_string());
+        }
+        for point in HookPoint::ALL {
+            if self.commands(point).iter().any(|c| c.trim().is_empty()) {
+                return Err(format!("hooks.{} contains an empty command", point));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of one hook command
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HookResult {
+    pub command: String,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Payload sent to hooks: `payload` with the hook point added
+pub fn hook_payload(point: HookPoint, payload: &Value) -> Result<Value, String> {
+    let mut payload = match payload {
+        Value::Null => Value::Object(Default::default()),
+        Value::Object(_) => payload.clone(),
+        _ => return Err("Hook payload must be a JSON object".to_string()),
+    };
+    payload["hook"] = Value::String(point.to_string());
+    Ok(payload)
+}
+
+fn run_hook_command(
+    dir: &Path,
+    point: HookPoint,
+    command: &str,
+    payload: &str,
+    timeout: Duration,
+) -> HookResult {
+    let _span = tracing::debug_span!("hook", %point, command).entered();
+    let failed = |error: String| HookResult {
+        command: command.to_string(),
+        success: false,
+        exit_code: None,
+        error: Some(error),
+    };
+    let output = run_with_timeout(
+        Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .current_dir(dir)
+            .env("RALPH_HOOK", point.to_string()),
+        Some(payload),
+        timeout,
+    );
+    let output = match output {
+        Ok(Some(output)) => output,
+        Ok(None) => {
+            tracing::debug!(timeout_secs = timeout.as_secs(), "hook timed out");
+            return failed(format!("timed out after {}s", timeout.as_secs()));
+        }
+        Err(e) => return failed(format!("Failed to run hook: {}", e)),
+    };
+
+    // Keep the firing command's own stdout parseable
+    let mut stderr = std::io::stderr();
+    let _ = stderr.write_all(&output.stdout);
+    let _ = stderr.write_all(&output.stderr);
+
+    let result = HookResult {
+        command: command.to_string(),
+        success: output.status.success(),
+        exit_code: output.status.code(),
+        error: None,
+    };
+    tracing::debug!(success = result.success, exit_code = ?result.exit_code, "hook finished");
+    result
+}
+
+/// Run every hook configured for `point`, continuing past failures
+pub fn run_hooks(
+    dir: &Path,
+    config: &HooksConfig,
+    point: HookPoint,
+    payload: &Value,
+) -> Result<Vec<HookResult>, String> {
+    let payload =
+        serde_json::to_string(&hook_payload(point, payload)?).map_err(|e| e.to_string())?;
+    let timeout = Duration::from_secs(config.timeout_secs);
+    Ok(config
+        .commands(point)
+        .iter()
+        .map(|command| run_hook_command(dir, point, command, &payload, timeout))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_hook_point_round_trip() {
+        for point in HookPoint::ALL {
+            assert_eq!(point.to_string().parse::<HookPoint>().unwrap(), point);
+        }
+        assert_eq!(
+            "ON_FAILURE".parse::<HookPoint>().unwrap(),
+            HookPoint::OnFailure
+        );
+        let err = "on-success".parse::<HookPoint>().unwrap_err();
+        assert!(err.contains("pre-iteration"));
+    }
+
+    #[test]
+    fn test_hook_payload() {
+        let payload = hook_payload(HookPoint::OnFailure, &json!({"issue": "bd-1"})).unwrap();
+        assert_eq!(payload, json!({"issue": "bd-1", "hook": "on-failure"}));
+
+        let empty = hook_payload(HookPoint::PreIteration, &Value::Null).unwrap();
+        assert_eq!(empty, json!({"hook": "pre-iteration"}));
+
+        assert!(hook_payload(HookPoint::PreIteration, &json!([1])).is_err());
+    }
+
+    #[test]
+    fn test_validate_rejects_empty_command() {
+        let config = HooksConfig {
+            on_failure: vec!["  ".to_string()],
+            ..Default::default()
+        };
+        assert!(config.validate().unwrap_err().contains("hooks.on-failure"));
+    }
+
+    #[test]
+    fn test_run_hooks_passes_payload_and_continues_after_failure() {
+        let dir = TempDir::new().unwrap();
+        let config = HooksConfig {
+            post_iteration: vec![
+                "cat > payload.json; echo $RALPH_HOOK > point.txt".to_string(),
+                "exit 3".to_string(),
+                "touch ran-after-failure".to_string(),
+            ],
+            ..Default::default()
+        };
+
+        let results = run_hooks(
+            dir.path(),
+            &config,
+            HookPoint::PostIteration,
+            &json!({"iteration": 2}),
+        )
+        .unwrap();
+
+        assert_eq!(results.len(), 3);
+        assert!(results[0].success);
+        assert!(!results[1].success);
+        assert_eq!(results[1].exit_code, Some(3));
+        assert!(results[2].success);
+
+        let payload: Value = serde_json::from_str(
+            &std::fs::read_to_string(dir.path().join("payload.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(payload, json!({"iteration": 2, "hook": "post-iteration"}));
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("point.txt")).unwrap(),
+            "post-iteration\n"
+        );
+        assert!(dir.path().join("ran-after-failure").exists());
+    }
+
+    #[test]
+    fn test_hook_past_timeout_is_killed() {
+        let dir = TempDir::new().unwrap();
+        let config = HooksConfig {
+            on_failure: vec!["sleep 5".to_string(), "touch ran-next".to_string()],
+            timeout_secs: 1,
+            ..Default::default()
+        };
+
+        let results = run_hooks(dir.path(), &config, HookPoint::OnFailure, &Value::Null).unwrap();
+
+        assert!(!results[0].success);
+        assert_eq!(results[0].error.as_deref(), Some("timed out after 1s"));
+        assert!(results[1].success);
+        assert!(dir.path().join("ran-next").exists());
+    }
+
+    #[test]
+    fn test_run_hooks_without_commands() {
+        let results = run_hooks(
+            Path::new("."),
+            &HooksConfig::default(),
+            HookPoint::OnGateCreated,
+            &Value::Null,
+        )
+        .unwrap();
+        assert!(results.is_empty());
+    }
+}
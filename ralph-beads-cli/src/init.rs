@@ -0,0 +1,259 @@
+//! Project bootstrap
+//!
+//! `init` replaces the manual setup steps for a new project. Each step is
+//! idempotent and reports what it did, so re-running `init` on a configured
+//! project only fills in what is missing:
+//!
+//! - `.ralph-beads.toml` with every setting at its default
+//! - the beads database (`bd init` when `.beads/` is missing)
+//! - optionally, a git pre-commit hook that lints open issues
+
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::beads::run_bd;
+use crate::config::{Config, CONFIG_FILE};
+
+/// Marker identifying a git hook written by `init`
+const HOOK_MARKER: &str = "# Installed by ralph-beads-cli init";
+
+/// Outcome of one bootstrap step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StepStatus {
+    /// The step made a change
+    Created,
+    /// Already set up; left unchanged
+    Exists,
+    /// Not applicable or not requested
+    Skipped,
+    Failed,
+}
+
+impl fmt::Display for StepStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            StepStatus::Created => "created",
+            StepStatus::Exists => "exists",
+            StepStatus::Skipped => "skipped",
+            StepStatus::Failed => "failed",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// One bootstrap step and what came of it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InitStep {
+    pub name: &'static str,
+    pub status: StepStatus,
+    pub detail: String,
+}
+
+impl InitStep {
+    fn new(name: &'static str, status: StepStatus, detail: impl Into<String>) -> Self {
+        InitStep {
+            name,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Which optional steps to run
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InitOptions {
+    /// Overwrite an existing config file and pre-commit hook
+    pub force: bool,
+    /// Leave the beads database alone
+    pub skip_beads: bool,
+    /// Install the git pre-commit hook
+    pub git_hooks: bool,
+}
+
+/// Default configuration, with a header pointing at the docs
+pub fn default_config() -> String {
+    let body = toml::to_string_pretty(&Config::default()).unwrap_or_default();
+    format!(
+        "# Ralph-Beads project configuration (generated by `ralph-beads-cli init`)\n\
+         # Every value below is the built-in default; delete what you don't change.\n\
+         # `ralph-beads-cli config check` validates this file.\n\n{}",
+        body
+    )
+}
+
+fn write_config(dir: &Path, force: bool) -> InitStep {
+    let path = dir.join(CONFIG_FILE);
+    if path.exists() && !force {
+        return InitStep::new("config", StepStatus::Exists, path.display().to_string());
+    }
+    match fs::write(&path, default_config()) {
+        Ok(()) => InitStep::new("config", StepStatus::Created, path.display().to_string()),
+        Err(e) => InitStep::new(
+            "config",
+            StepStatus::Failed,
+            format!("Failed to write {}: {}", path.display(), e),
+        ),
+    }
+}
+
+fn init_beads(dir: &Path) -> InitStep {
+    let beads = dir.join(".beads");
+    if beads.is_dir() {
+        return InitStep::new("beads", StepStatus::Exists, beads.display().to_string());
+    }
+    match run_bd(dir, &["init"]) {
+        Ok(_) => InitStep::new("beads", StepStatus::Created, "bd init"),
+        Err(e) => InitStep::new("beads", StepStatus::Failed, e.to_string()),
+    }
+}
+
+/// Pre-commit hook script: blocks the commit when open issues have lint errors
+pub fn pre_commit_hook() -> String {
+    format!(
+        "#!/bin/sh\n{}\nexec ralph-beads-cli lint all\n",
+        HOOK_MARKER
+    )
+}
+
+fn install_git_hook(dir: &Path, force: bool) -> InitStep {
+    let hooks = dir.join(".git").join("hooks");
+    if !dir.join(".git").is_dir() {
+        return InitStep::new("git-hook", StepStatus::Skipped, "not a git repository");
+    }
+    let path = hooks.join("pre-commit");
+    if let Ok(existing) = fs::read_to_string(&path) {
+        if existing.contains(HOOK_MARKER) {
+            return InitStep::new("git-hook", StepStatus::Exists, path.display().to_string());
+        }
+        if !force {
+            return InitStep::new(
+                "git-hook",
+                StepStatus::Skipped,
+                format!("{} exists (use --force to replace)", path.display()),
+            );
+        }
+    }
+    let result = fs::create_dir_all(&hooks)
+        .and_then(|_| fs::write(&path, pre_commit_hook()))
+        .and_then(|_| make_executable(&path));
+    match result {
+        Ok(()) => InitStep::new("git-hook", StepStatus::Created, path.display().to_string()),
+        Err(e) => InitStep::new(
+            "git-hook",
+            StepStatus::Failed,
+            format!("Failed to write {}: {}", path.display(), e),
+        ),
+    }
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Bootstrap `dir`, running every step even when an earlier one fails
+pub fn init(dir: &Path, options: InitOptions) -> Vec<InitStep> {
+    let config = write_config(dir, options.force);
+    let beads = if options.skip_beads {
+        InitStep::new("beads", StepStatus::Skipped, "--no-beads")
+    } else {
+        init_beads(dir)
+    };
+    let git_hook = if options.git_hooks {
+        install_git_hook(dir, options.force)
+    } else {
+        InitStep::new(
+            "git-hook",
+            StepStatus::Skipped,
+            "pass --git-hooks to install",
+        )
+    };
+    vec![config, beads, git_hook]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{load_config, parse_config};
+    use tempfile::TempDir;
+
+    fn options() -> InitOptions {
+        InitOptions {
+            skip_beads: true,
+            git_hooks: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_default_config_round_trips() {
+        assert_eq!(parse_config(&default_config()).unwrap(), Config::default());
+    }
+
+    #[test]
+    fn test_init_is_idempotent() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join(".git")).unwrap();
+
+        let steps = init(dir.path(), options());
+        let statuses: Vec<StepStatus> = steps.iter().map(|s| s.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                StepStatus::Created,
+                StepStatus::Skipped,
+                StepStatus::Created
+            ]
+        );
+        assert!(load_config(dir.path()).unwrap().source.is_some());
+
+        let again: Vec<StepStatus> = init(dir.path(), options())
+            .iter()
+            .map(|s| s.status)
+            .collect();
+        assert_eq!(
+            again,
+            vec![StepStatus::Exists, StepStatus::Skipped, StepStatus::Exists]
+        );
+    }
+
+    #[test]
+    fn test_foreign_hook_needs_force() {
+        let dir = TempDir::new().unwrap();
+        let hooks = dir.path().join(".git").join("hooks");
+        fs::create_dir_all(&hooks).unwrap();
+        fs::write(hooks.join("pre-commit"), "#!/bin/sh\nmake check\n").unwrap();
+
+        let step = install_git_hook(dir.path(), false);
+        assert_eq!(step.status, StepStatus::Skipped);
+        assert!(fs::read_to_string(hooks.join("pre-commit"))
+            .unwrap()
+            .contains("make check"));
+
+        assert_eq!(
+            install_git_hook(dir.path(), true).status,
+            StepStatus::Created
+        );
+        assert!(fs::read_to_string(hooks.join("pre-commit"))
+            .unwrap()
+            .contains(HOOK_MARKER));
+    }
+
+    #[test]
+    fn test_git_hook_outside_repo() {
+        let dir = TempDir::new().unwrap();
+        assert_eq!(
+            install_git_hook(dir.path(), false).status,
+            StepStatus::Skipped
+        );
+    }
+}
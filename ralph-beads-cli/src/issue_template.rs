@@ -0,0 +1,293 @@
+//! Issue templates for `issue create`
+//!
+//! A new issue's description is rendered from a minijinja template.
+//! `--template NAME` loads `.ralph-beads/templates/NAME.md` (or a file
+//! path); otherwise `issue-<type>.md` from the same directory is used when
+//! present, then a built-in skeleton. Templates see:
+//!
+//! - `title`, `type`, `parent`, `priority`
+//! - `description`: the text given with `--description`, if any
+//! - `headings`: `{name, text}` for every heading section `[lint]` requires
+//!   for the type, then every other `--section`; `text` is missing when
+//!   none was given
+//!
+//! The built-in skeleton fills sections without text with a `TODO:`
+//! placeholder, and a required acceptance criteria field gets one too, so
+//! the issue has the structure lint expects from the start. The draft is
+//! linted before anything is created.
+
+use minijinja::syntax::SyntaxConfig;
+use minijinja::value::Serde;
+use minijinja::Environment;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::beads::{Dependency, Issue, NewIssue};
+use crate::context::TEMPLATE_DIR;
+use crate::lint::section_field;
+
+/// ID the draft is linted under, before bd assigns one
+pub const DRAFT_ID: &str = "(new)";
+
+/// Marks text the author still has to write
+pub const PLACEHOLDER: &str = "TODO:";
+
+const DEFAULT_TEMPLATE: &str = r#"{{ description or ("TODO: what this " ~ type ~ " is for and why") }}
+{% for heading in headings %}
+
+## {{ heading.name }}
+
+{{ heading.text or ("TODO: " ~ heading.name) }}
+{% endfor %}
+"#;
+
+/// What `issue create` was asked for
+#[derive(Debug, Clone, Default)]
+pub struct IssueRequest {
+    pub issue_type: String,
+    pub title: String,
+    pub parent: Option<String>,
+    pub priority: Option<u8>,
+    pub description: Option<String>,
+    pub acceptance_criteria: Option<String>,
+    /// Text for headings, in the order given
+    pub sections: Vec<(String, String)>,
+}
+
+/// A section heading of the description
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Heading {
+    pub name: String,
+    pub text: Option<String>,
+}
+
+#[derive(Serialize)]
+struct TemplateVars<'a> {
+    title: &'a str,
+    #[serde(rename = "type")]
+    issue_type: &'a str,
+    parent: Option<&'a str>,
+    priority: Option<u8>,
+    description: Option<&'a str>,
+    headings: Vec<Heading>,
+}
+
+/// Template source: `name` as a file or project template, else the
+/// project's `issue-<type>` template, else the built-in skeleton
+pub fn load_template(dir: &Path, issue_type: &str, name: Option<&str>) -> Result<String, String> {
+    let candidates: Vec<PathBuf> = match name {
+        Some(name) if name.contains('/') || Path::new(name).extension().is_some() => {
+            vec![PathBuf::from(name), dir.join(name)]
+        }
+        Some(name) => vec![dir.join(TEMPLATE_DIR).join(format!("{}.md", name))],
+        None => vec![dir
+            .join(TEMPLATE_DIR)
+            .join(format!("issue-{}.md", issue_type))],
+    };
+    match candidates.iter().find(|p| p.is_file()) {
+        Some(path) => fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e)),
+        None if name.is_none() => Ok(DEFAULT_TEMPLATE.to_string()),
+        None => Err(format!(
+            "Unknown issue template: {} (add {}/{}.md)",
+            name.unwrap_or_default(),
+            TEMPLATE_DIR,
+            name.unwrap_or_default()
+        )),
+    }
+}
+
+/// Render `template` for `request` into the fields of a new issue
+///
+/// `required` is the `[lint]` section list for the issue type.
+pub fn render(
+    template: &str,
+    request: &IssueRequest,
+    required: &[String],
+) -> Result<NewIssue, String> {
+    let given = |name: &str| {
+        request
+            .sections
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, text)| text.clone())
+    };
+    let mut headings: Vec<Heading> = required
+        .iter()
+        .filter(|s| section_field(s).is_none())
+        .map(|name| Heading {
+            name: name.clone(),
+            text: given(name),
+        })
+        .collect();
+    for (name, text) in &request.sections {
+        if !headings.iter().any(|h| h.name.eq_ignore_ascii_case(name)) {
+            headings.push(Heading {
+                name: name.clone(),
+                text: Some(text.clone()),
+            });
+        }
+    }
+
+    let vars = TemplateVars {
+        title: &request.title,
+        issue_type: &request.issue_type,
+        parent: request.parent.as_deref(),
+        priority: request.priority,
+        description: request.description.as_deref(),
+        headings,
+    };
+    let mut env = Environment::new();
+    env.set_syntax(
+        SyntaxConfig::builder()
+            .trim_blocks(true)
+            .lstrip_blocks(true)
+            .build()
+            .map_err(|e| e.to_string())?,
+    );
+    let description = env
+        .render_str(template, Serde(&vars))
+        .map_err(|e| format!("Template error: {}", e))?;
+
+    let needs_criteria = required
+        .iter()
+        .any(|s| section_field(s) == Some("acceptance_criteria"));
+    let acceptance_criteria = request.acceptance_criteria.clone().or_else(|| {
+        needs_criteria.then(|| {
+            format!(
+                "{} how to tell this {} is done",
+                PLACEHOLDER, request.issue_type
+            )
+        })
+    });
+
+    Ok(NewIssue {
+        title: request.title.clone(),
+        issue_type: request.issue_type.clone(),
+        priority: request.priority,
+        parent: request.parent.clone(),
+        description: Some(description.trim().to_string()),
+        acceptance_criteria,
+    })
+}
+
+/// The issue `new` would create, as lint sees it
+pub fn draft(new: &NewIssue) -> Issue {
+    Issue {
+        id: DRAFT_ID.to_string(),
+        title: new.title.clone(),
+        description: new.description.clone().unwrap_or_default(),
+        acceptance_criteria: new.acceptance_criteria.clone().unwrap_or_default(),
+        status: "open".to_string(),
+        priority: new.priority,
+        issue_type: new.issue_type.clone(),
+        dependencies: new
+            .parent
+            .iter()
+            .map(|parent| Dependency {
+                issue_id: DRAFT_ID.to_string(),
+                depends_on_id: parent.clone(),
+                dep_type: "parent-child".to_string(),
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lint::{build_report, LintConfig, Related};
+    use tempfile::TempDir;
+
+    fn request() -> IssueRequest {
+        IssueRequest {
+            issue_type: "bug".to_string(),
+            title: "Login fails on Safari".to_string(),
+            priority: Some(1),
+            sections: vec![
+                (
+                    "steps to reproduce".to_string(),
+                    "1. Open Safari".to_string(),
+                ),
+                ("Logs".to_string(), "none".to_string()),
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn required() -> Vec<String> {
+        [
+            "description",
+            "acceptance_criteria",
+            "Steps to reproduce",
+            "Expected",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+    }
+
+    #[test]
+    fn test_default_template_prefills_required_sections() {
+        let new = render(DEFAULT_TEMPLATE, &request(), &required()).unwrap();
+
+        assert_eq!(
+            new.description.as_deref(),
+            Some(
+                "TODO: what this bug is for and why\n\n\
+                 ## Steps to reproduce\n\n1. Open Safari\n\n\
+                 ## Expected\n\nTODO: Expected\n\n\
+                 ## Logs\n\nnone"
+            )
+        );
+        assert_eq!(
+            new.acceptance_criteria.as_deref(),
+            Some("TODO: how to tell this bug is done")
+        );
+    }
+
+    #[test]
+    fn test_project_templates_take_precedence() {
+        let tmp = TempDir::new().unwrap();
+        let templates = tmp.path().join(TEMPLATE_DIR);
+        fs::create_dir_all(&templates).unwrap();
+        fs::write(templates.join("issue-bug.md"), "Bug: {{ title }}").unwrap();
+        fs::write(templates.join("triage.md"), "Triage {{ type }}").unwrap();
+
+        let by_type = load_template(tmp.path(), "bug", None).unwrap();
+        let by_name = load_template(tmp.path(), "bug", Some("triage")).unwrap();
+
+        assert_eq!(by_type, "Bug: {{ title }}");
+        assert_eq!(by_name, "Triage {{ type }}");
+        assert_eq!(
+            load_template(tmp.path(), "task", None).unwrap(),
+            DEFAULT_TEMPLATE
+        );
+        assert!(load_template(tmp.path(), "bug", Some("missing")).is_err());
+    }
+
+    #[test]
+    fn test_draft_lints_like_the_created_issue() {
+        let config = LintConfig {
+            required_sections: [("bug".to_string(), required())].into_iter().collect(),
+            ..Default::default()
+        };
+        let full = render(DEFAULT_TEMPLATE, &request(), &required()).unwrap();
+        let bare = render("{{ title }}", &request(), &required()).unwrap();
+
+        let passing = build_report(&[draft(&full)], &Related::default(), &config, "test", 0);
+        let failing = build_report(&[draft(&bare)], &Related::default(), &config, "test", 0);
+
+        assert!(!passing.has_errors());
+        let missing: Vec<&str> = failing
+            .findings
+            .iter()
+            .map(|f| f.message.as_str())
+            .collect();
+        assert_eq!(failing.errors, 2);
+        assert!(missing[0].ends_with("'Steps to reproduce'"));
+        assert!(missing[1].ends_with("'Expected'"));
+    }
+}
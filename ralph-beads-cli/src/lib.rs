@@ -0,0 +1,48 @@
+//! Ralph-Beads CLI library
+//!
+//! Shared implementation behind the `ralph-beads-cli` binary. Each module
+//! is usable on its own so the plugin and tests can call it directly.
+
+pub mod activity;
+pub mod bd_version;
+pub mod beads;
+pub mod beads_state;
+pub mod bootstrap;
+pub mod build_cache;
+pub mod bundle;
+pub mod complexity;
+pub mod config;
+pub mod context;
+pub mod cost;
+pub mod data_dirs;
+pub mod diff;
+pub mod epic;
+pub mod estimate;
+pub mod exit_code;
+pub mod fixtures;
+pub mod framework;
+pub mod github;
+pub mod graph;
+pub mod health;
+pub mod hooks;
+pub mod init;
+pub mod issue_template;
+pub mod limits;
+pub mod lint;
+pub mod logging;
+pub mod metrics;
+pub mod plan_repl;
+pub mod plugins;
+pub mod report;
+pub mod retention;
+pub mod schema;
+pub mod split;
+pub mod sse;
+pub mod state;
+pub mod status;
+pub mod stop;
+pub mod timeline;
+pub mod toolchain;
+pub mod velocity;
+pub mod workspace;
+pub mod worktree;
@@ -0,0 +1,366 @@
+//! Subprocess limits shared across processes
+//!
+//! Dashboards, followers, and agents each run their own `ralph-beads-cli`
+//! processes, so limits on `bd` and `git` spawns are enforced through lock
+//! files rather than in memory:
+//!
+//! - concurrency: one lock file per slot; a spawn holds a slot's exclusive
+//!   lock until the subprocess exits
+//! - quota: a ledger of spawn times from the last minute, rewritten under
+//!   its own lock
+//!
+//! Callers that find no free slot or spent quota wait (polling) up to the
+//! queue timeout before giving up.
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::data_dirs;
+
+/// Environment variable overriding the directory holding the lock files
+pub const LOCK_DIR_ENV: &str = "RALPH_BEADS_LOCK_DIR";
+
+/// How often a queued spawn rechecks for a free slot
+const POLL_INTERVAL: Duration = Duration::from_millis(25);
+
+/// Width of the quota window
+const QUOTA_WINDOW_MS: u64 = 60_000;
+
+/// The `[limits]` configuration section
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    /// Most `bd`/`git` subprocesses running at once, across the user's
+    /// processes
+    pub max_concurrent: usize,
+    /// Most spawns started in any 60-second window (unlimited when unset)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spawns_per_minute: Option<u32>,
+    /// Seconds a spawn may wait for a slot or quota before failing
+    pub queue_timeout_secs: u64,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        LimitsConfig {
+            max_concurrent: 16,
+            spawns_per_minute: None,
+            queue_timeout_secs: 60,
+        }
+    }
+}
+
+impl LimitsConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_concurrent == 0 {
+            return Err("limits.max_concurrent must be at least 1".to_string());
+        }
+        if self.spawns_per_minute == Some(0) {
+            return Err("limits.spawns_per_minute must be at least 1".to_string());
+        }
+        if self.queue_timeout_secs == 0 {
+            return Err("limits.queue_timeout_secs must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Why a spawn was refused
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LimitError {
+    /// No slot or quota freed up before the queue timeout
+    Queued(String),
+    /// The lock files couldn't be used
+    Io(String),
+}
+
+impl fmt::Display for LimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LimitError::Queued(message) | LimitError::Io(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<LimitError> for String {
+    fn from(e: LimitError) -> Self {
+        e.to_string()
+    }
+}
+
+/// A held concurrency slot; released when dropped
+#[derive(Debug)]
+pub struct Permit {
+    _slot: File,
+}
+
+/// Slot and quota usage, as reported by `limits status`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LimitsStatus {
+    pub lock_dir: PathBuf,
+    pub max_concurrent: usize,
+    pub running: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spawns_per_minute: Option<u32>,
+    pub spawns_last_minute: usize,
+}
+
+/// Limits enforced through lock files in `dir`
+#[derive(Debug, Clone)]
+pub struct Limiter {
+    dir: PathBuf,
+    config: LimitsConfig,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn open_rw(path: &Path) -> Result<File, String> {
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))
+}
+
+/// Spawn times still inside the quota window
+fn recent_spawns(content: &str, now: u64) -> Vec<u64> {
+    content
+        .lines()
+        .filter_map(|l| l.trim().parse::<u64>().ok())
+        .filter(|t| now.saturating_sub(*t) < QUOTA_WINDOW_MS)
+        .collect()
+}
+
+impl Limiter {
+    pub fn new(dir: PathBuf, config: LimitsConfig) -> Self {
+        Limiter { dir, config }
+    }
+
+    pub fn queue_timeout_secs(&self) -> u64 {
+        self.config.queue_timeout_secs
+    }
+
+    fn slot_path(&self, slot: usize) -> PathBuf {
+        self.dir.join(format!("slot-{}.lock", slot))
+    }
+
+    fn ledger_path(&self) -> PathBuf {
+        self.dir.join("spawns.log")
+    }
+
+    /// Lock the first free slot, if any
+    fn try_slot(&self) -> Result<Option<Permit>, String> {
+        for slot in 0..self.config.max_concurrent {
+            let file = open_rw(&self.slot_path(slot))?;
+            if file.try_lock().is_ok() {
+                return Ok(Some(Permit { _slot: file }));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Record a spawn at `now` if the quota allows, else how long until it does
+    fn try_quota(&self, quota: u32, now: u64) -> Result<Option<Duration>, String> {
+        let path = self.ledger_path();
+        let mut ledger = open_rw(&path)?;
+        ledger
+            .lock()
+            .map_err(|e| format!("Failed to lock {}: {}", path.display(), e))?;
+        let mut content = String::new();
+        ledger
+            .read_to_string(&mut content)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let mut spawns = recent_spawns(&content, now);
+        if spawns.len() >= quota as usize {
+            let oldest = spawns.iter().min().copied().unwrap_or(now);
+            let wait = (oldest + QUOTA_WINDOW_MS).saturating_sub(now).max(1);
+            return Ok(Some(Duration::from_millis(wait)));
+        }
+        spawns.push(now);
+        let content: String = spawns.iter().map(|t| format!("{}\n", t)).collect();
+        ledger
+            .set_len(0)
+            .and_then(|_| ledger.seek(SeekFrom::Start(0)))
+            .and_then(|_| ledger.write_all(content.as_bytes()))
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(None)
+    }
+
+    /// Wait for a free slot and spawn quota, then hold the slot until the
+    /// permit is dropped
+    pub fn acquire(&self, what: &str) -> Result<Permit, LimitError> {
+        data_dirs::create_private_dir(&self.dir).map_err(|e| {
+            LimitError::Io(format!("Failed to create {}: {}", self.dir.display(), e))
+        })?;
+        let start = Instant::now();
+        let deadline = start + Duration::from_secs(self.config.queue_timeout_secs);
+
+        let permit = loop {
+            if let Some(permit) = self.try_slot().map_err(LimitError::Io)? {
+                break permit;
+            }
+            if Instant::now() >= deadline {
+                return Err(LimitError::Queued(format!(
+                    "{}: all {} subprocess slots busy for {}s (limits.max_concurrent)",
+                    what, self.config.max_concurrent, self.config.queue_timeout_secs
+                )));
+            }
+            thread::sleep(POLL_INTERVAL);
+        };
+
+        if let Some(quota) = self.config.spawns_per_minute {
+            while let Some(wait) = self.try_quota(quota, now_ms()).map_err(LimitError::Io)? {
+                if Instant::now() + wait > deadline {
+                    return Err(LimitError::Queued(format!(
+                        "{}: spawn quota of {} per minute exhausted (limits.spawns_per_minute)",
+                        what, quota
+                    )));
+                }
+                thread::sleep(wait);
+            }
+        }
+
+        let waited = start.elapsed();
+        if waited >= POLL_INTERVAL {
+            tracing::info!(
+                what,
+                waited_ms = waited.as_millis() as u64,
+                "subprocess queued"
+            );
+        }
+        Ok(permit)
+    }
+
+    /// Current slot and quota usage
+    pub fn status(&self) -> Result<LimitsStatus, String> {
+        let mut running = 0;
+        for slot in 0..self.config.max_concurrent {
+            let path = self.slot_path(slot);
+            if !path.exists() {
+                continue;
+            }
+            // A slot we can lock is free; the probe lock is dropped at once
+            if open_rw(&path)?.try_lock().is_err() {
+                running += 1;
+            }
+        }
+        let ledger = fs::read_to_string(self.ledger_path()).unwrap_or_default();
+        Ok(LimitsStatus {
+            lock_dir: self.dir.clone(),
+            max_concurrent: self.config.max_concurrent,
+            running,
+            spawns_per_minute: self.config.spawns_per_minute,
+            spawns_last_minute: recent_spawns(&ledger, now_ms()).len(),
+        })
+    }
+}
+
+/// Lock directory: `RALPH_BEADS_LOCK_DIR`, else the data directories' one
+pub fn lock_dir() -> PathBuf {
+    env::var_os(LOCK_DIR_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| data_dirs::dirs().lock_dir())
+}
+
+static LIMITER: OnceCell<Limiter> = OnceCell::new();
+
+/// Set the limits for every subprocess this process spawns (first call wins)
+pub fn configure(config: LimitsConfig) {
+    let _ = LIMITER.set(Limiter::new(lock_dir(), config));
+}
+
+/// The process-wide limiter
+pub fn limiter() -> &'static Limiter {
+    LIMITER.get_or_init(|| Limiter::new(lock_dir(), LimitsConfig::default()))
+}
+
+/// Wait for permission to spawn `what` under the configured limits
+pub fn acquire(what: &str) -> Result<Permit, LimitError> {
+    limiter().acquire(what)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn limiter(dir: &TempDir, max_concurrent: usize, quota: Option<u32>) -> Limiter {
+        Limiter {
+            dir: dir.path().to_path_buf(),
+            config: LimitsConfig {
+                max_concurrent,
+                spawns_per_minute: quota,
+                queue_timeout_secs: 0,
+            },
+        }
+    }
+
+    #[test]
+    fn test_concurrency_slots() {
+        let dir = TempDir::new().unwrap();
+        let limiter = limiter(&dir, 2, None);
+
+        let first = limiter.acquire("bd list").unwrap();
+        let _second = limiter.acquire("bd list").unwrap();
+        assert_eq!(limiter.status().unwrap().running, 2);
+        let err = limiter.acquire("bd list").unwrap_err();
+        assert!(matches!(err, LimitError::Queued(ref m) if m.contains("max_concurrent")));
+
+        drop(first);
+        assert!(limiter.acquire("bd list").is_ok());
+    }
+
+    #[test]
+    fn test_spawn_quota() {
+        let dir = TempDir::new().unwrap();
+        let limiter = limiter(&dir, 4, Some(2));
+
+        drop(limiter.acquire("git status").unwrap());
+        drop(limiter.acquire("git status").unwrap());
+        let err = limiter.acquire("git status").unwrap_err();
+        assert!(matches!(err, LimitError::Queued(ref m) if m.contains("spawns_per_minute")));
+        assert_eq!(limiter.status().unwrap().spawns_last_minute, 2);
+    }
+
+    #[test]
+    fn test_unusable_lock_dir_is_not_a_timeout() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("not-a-dir");
+        fs::write(&file, "").unwrap();
+        let limiter = Limiter::new(file.join("limits"), LimitsConfig::default());
+
+        let err = limiter.acquire("bd list").unwrap_err();
+        assert!(matches!(err, LimitError::Io(ref m) if m.contains("Failed to create")));
+    }
+
+    #[test]
+    fn test_recent_spawns_window() {
+        let now = 1_000_000;
+        let content = format!("{}\n{}\ngarbage\n{}\n", now - 70_000, now - 59_000, now);
+        assert_eq!(recent_spawns(&content, now), vec![now - 59_000, now]);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(LimitsConfig::default().validate().is_ok());
+        let zero = LimitsConfig {
+            max_concurrent: 0,
+            ..Default::default()
+        };
+        assert!(zero.validate().is_err());
+    }
+}
@@ -0,0 +1,1568 @@
+//! Issue linting for Ralph-Beads
+//!
+//! Checks beads issues for the structure the loop depends on (descriptions,
+//! acceptance criteria, freshness, dependency hygiene). Rules can be
+//! disabled or re-leveled per project through the `[lint]` section of
+//! `.ralph-beads.toml`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::activity::{emit_event, parse_timestamp, ActivityEvent, EventKind};
+use crate::beads::{add_comment, list_comments, show_issue, BdError, Comment, Issue};
+use crate::graph::find_cycles;
+
+/// Severity of a lint finding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "info" => Ok(Severity::Info),
+            "warning" | "warn" => Ok(Severity::Warning),
+            "error" => Ok(Severity::Error),
+            _ => Err(format!("Unknown severity: {}", s)),
+        }
+    }
+}
+
+/// Static description of a lint rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RuleInfo {
+    pub id: &'static str,
+    pub default_severity: Severity,
+    pub description: &'static str,
+}
+
+/// All lint rules known to this crate
+pub const RULES: &[RuleInfo] = &[
+    RuleInfo {
+        id: "required-sections",
+        default_severity: Severity::Error,
+        description: "Issue is missing a section required for its type",
+    },
+    RuleInfo {
+        id: "stale",
+        default_severity: Severity::Warning,
+        description: "Open issue has not been updated within the staleness threshold",
+    },
+    RuleInfo {
+        id: "circular-dependency",
+        default_severity: Severity::Error,
+        description: "Issues block each other in a cycle and can never become ready",
+    },
+    RuleInfo {
+        id: "priority-missing",
+        default_severity: Severity::Warning,
+        description: "Open issue has no priority set",
+    },
+    RuleInfo {
+        id: "oversized-epic",
+        default_severity: Severity::Warning,
+        description: "Open epic has more open children than max_epic_children",
+    },
+    RuleInfo {
+        id: "duplicate-title",
+        default_severity: Severity::Warning,
+        description: "Two open issues have near-identical titles",
+    },
+    RuleInfo {
+        id: "ac-quality",
+        default_severity: Severity::Warning,
+        description: "Acceptance criteria score below min_ac_score",
+    },
+    RuleInfo {
+        id: "stale-dependency",
+        default_severity: Severity::Warning,
+        description: "Open issue still has a blocking edge to a closed issue",
+    },
+    RuleInfo {
+        id: "long-dependency-chain",
+        default_severity: Severity::Warning,
+        description: "Chain of blocking dependencies longer than max_chain_length",
+    },
+    RuleInfo {
+        id: "cross-epic-dependency",
+        default_severity: Severity::Warning,
+        description: "Issue depends on another epic's issue without a gate in between",
+    },
+    RuleInfo {
+        id: "blocked-without-reason",
+        default_severity: Severity::Warning,
+        description: "Blocked issue has no comment explaining what it waits on",
+    },
+];
+
+/// Look up a rule by id
+pub fn rule_info(id: &str) -> Option<&'static RuleInfo> {
+    RULES.iter().find(|r| r.id == id)
+}
+
+/// Per-rule overrides
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuleConfig {
+    pub enabled: Option<bool>,
+    pub severity: Option<Severity>,
+}
+
+/// The `[lint]` configuration section
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LintConfig {
+    /// Days without update after which an open issue is stale
+    pub stale_days: u32,
+    /// Direct children above which an epic should be split
+    pub max_epic_children: usize,
+    /// Title similarity (percent) at which issues look like duplicates
+    pub duplicate_similarity: u8,
+    /// Acceptance criteria quality score (0-100) below which to warn
+    pub min_ac_score: u8,
+    /// Open issues a blocking chain may hold before it should be split
+    pub max_chain_length: usize,
+    /// Overrides keyed by rule id
+    pub rules: BTreeMap<String, RuleConfig>,
+    /// Required sections keyed by issue type; replaces the default list
+    /// for that type
+    pub required_sections: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        LintConfig {
+            stale_days: 14,
+            max_epic_children: 15,
+            duplicate_similarity: 85,
+            min_ac_score: 60,
+            max_chain_length: 5,
+            rules: BTreeMap::new(),
+            required_sections: BTreeMap::new(),
+        }
+    }
+}
+
+impl LintConfig {
+    /// Reject overrides for rules that don't exist
+    pub fn validate(&self) -> Result<(), String> {
+        if self.duplicate_similarity > 100 {
+            return Err(format!(
+                "lint.duplicate_similarity must be 0-100, got {}",
+                self.duplicate_similarity
+            ));
+        }
+        if self.min_ac_score > 100 {
+            return Err(format!(
+                "lint.min_ac_score must be 0-100, got {}",
+                self.min_ac_score
+            ));
+        }
+        if self.max_chain_length < 2 {
+            return Err(format!(
+                "lint.max_chain_length must be at least 2, got {}",
+                self.max_chain_length
+            ));
+        }
+        for id in self.rules.keys() {
+            if rule_info(id).is_none() {
+                let known: Vec<&str> = RULES.iter().map(|r| r.id).collect();
+                return Err(format!(
+                    "Unknown lint rule '{}' (known rules: {})",
+                    id,
+                    known.join(", ")
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether a rule is enabled
+    pub fn is_enabled(&self, id: &str) -> bool {
+        self.rules.get(id).and_then(|r| r.enabled).unwrap_or(true)
+    }
+
+    /// Effective severity of a rule
+    pub fn severity(&self, id: &str) -> Severity {
+        self.rules
+            .get(id)
+            .and_then(|r| r.severity)
+            .or_else(|| rule_info(id).map(|r| r.default_severity))
+            .unwrap_or(Severity::Warning)
+    }
+
+    /// Sections required for an issue type
+    pub fn sections_for(&self, issue_type: &str) -> Vec<String> {
+        if let Some(sections) = self.required_sections.get(issue_type) {
+            return sections.clone();
+        }
+        let defaults: &[&str] = match issue_type {
+            "epic" => &["description"],
+            "task" | "feature" | "bug" => &["description", "acceptance_criteria"],
+            _ => &["description"],
+        };
+        defaults.iter().map(|s| s.to_string()).collect()
+    }
+}
+
+/// A single lint finding
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LintFinding {
+    pub issue_id: String,
+    pub rule: String,
+    pub severity: Severity,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+}
+
+/// Result of linting a set of issues
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LintReport {
+    /// Configuration file applied, or "built-in defaults"
+    pub config: String,
+    pub issues_checked: usize,
+    pub errors: usize,
+    pub warnings: usize,
+    pub findings: Vec<LintFinding>,
+}
+
+impl LintReport {
+    /// Whether any error-severity findings were reported
+    pub fn has_errors(&self) -> bool {
+        self.errors > 0
+    }
+
+    /// The report narrowed to findings on `issue_id`, counted afresh
+    pub fn for_issue(self, issue_id: &str) -> Self {
+        self.for_issues(&[issue_id.to_string()])
+    }
+
+    /// The report narrowed to findings on `issue_ids`, counted afresh
+    pub fn for_issues(mut self, issue_ids: &[String]) -> Self {
+        self.findings.retain(|f| issue_ids.contains(&f.issue_id));
+        self.issues_checked = issue_ids.len();
+        self.errors = count(&self.findings, Severity::Error);
+        self.warnings = count(&self.findings, Severity::Warning);
+        self
+    }
+}
+
+/// What dependency rules look up beyond the linted issues
+#[derive(Debug, Clone, Default)]
+pub struct Related {
+    /// Issues outside the linted set: closed blockers, other epics' issues
+    pub issues: Vec<Issue>,
+    /// Comments on blocked issues, keyed by issue ID; `None` when not
+    /// fetched, which skips `blocked-without-reason`
+    pub comments: Option<BTreeMap<String, Vec<Comment>>>,
+}
+
+/// Everything a rule can look at
+pub struct LintContext<'a> {
+    pub issues: &'a [Issue],
+    pub related: &'a Related,
+    pub config: &'a LintConfig,
+    /// Current Unix time, for age-based rules
+    pub now: u64,
+}
+
+impl LintContext<'_> {
+    /// A linted or related issue by ID
+    fn find(&self, id: &str) -> Option<&Issue> {
+        self.issues
+            .iter()
+            .chain(&self.related.issues)
+            .find(|i| i.id == id)
+    }
+}
+
+/// Finding produced by a rule before severity is applied
+struct RawFinding {
+    issue_id: String,
+    message: String,
+    suggestion: Option<String>,
+}
+
+type RuleFn = fn(&LintContext) -> Vec<RawFinding>;
+
+/// Rule implementations, keyed by rule id
+const RULE_FNS: &[(&str, RuleFn)] = &[
+    ("required-sections", check_required_sections),
+    ("stale", check_stale),
+    ("circular-dependency", check_circular_dependencies),
+    ("priority-missing", check_priority_missing),
+    ("oversized-epic", check_oversized_epics),
+    ("duplicate-title", check_duplicate_titles),
+    ("ac-quality", check_ac_quality),
+    ("stale-dependency", check_stale_dependencies),
+    ("long-dependency-chain", check_dependency_chains),
+    ("cross-epic-dependency", check_cross_epic_dependencies),
+    ("blocked-without-reason", check_blocked_without_reason),
+];
+
+/// The issue field a required section names, or `None` for a heading
+pub(crate) fn section_field(section: &str) -> Option<&'static str> {
+    match section.to_lowercase().replace(' ', "_").as_str() {
+        "description" => Some("description"),
+        "acceptance_criteria" | "ac" => Some("acceptance_criteria"),
+        "design" => Some("design"),
+        "notes" => Some("notes"),
+        _ => None,
+    }
+}
+
+/// Field names that map to dedicated issue fields rather than headings
+fn field_content<'a>(issue: &'a Issue, section: &str) -> Option<&'a str> {
+    match section_field(section)? {
+        "description" => Some(&issue.description),
+        "acceptance_criteria" => Some(&issue.acceptance_criteria),
+        "design" => Some(&issue.design),
+        _ => Some(&issue.notes),
+    }
+}
+
+/// Whether any markdown heading in the issue's text matches `section`
+fn has_heading(issue: &Issue, section: &str) -> bool {
+    [
+        &issue.description,
+        &issue.acceptance_criteria,
+        &issue.design,
+        &issue.notes,
+    ]
+    .iter()
+    .flat_map(|text| text.lines())
+    .filter(|line| line.trim_start().starts_with('#'))
+    .any(|line| {
+        line.trim_start()
+            .trim_start_matches('#')
+            .trim()
+            .trim_end_matches(':')
+            .eq_ignore_ascii_case(section)
+    })
+}
+
+fn check_required_sections(ctx: &LintContext) -> Vec<RawFinding> {
+    let mut findings = Vec::new();
+    for issue in ctx.issues {
+        for section in ctx.config.sections_for(&issue.issue_type) {
+            let present = match field_content(issue, &section) {
+                Some(content) => !content.trim().is_empty(),
+                None => has_heading(issue, &section),
+            };
+            if !present {
+                let suggestion = match field_content(issue, &section) {
+                    Some(_) => format!("Fill in the {} field of {}", section, issue.id),
+                    None => format!("Add a '## {}' heading to the description", section),
+                };
+                findings.push(RawFinding {
+                    issue_id: issue.id.clone(),
+                    message: format!(
+                        "{} '{}' is missing required section '{}'",
+                        issue.issue_type, issue.title, section
+                    ),
+                    suggestion: Some(suggestion),
+                });
+            }
+        }
+    }
+    findings
+}
+
+fn check_stale(ctx: &LintContext) -> Vec<RawFinding> {
+    let threshold = u64::from(ctx.config.stale_days) * 86_400;
+    ctx.issues
+        .iter()
+        .filter(|issue| !issue.is_closed())
+        .filter_map(|issue| {
+            let updated = issue
+                .updated_at
+                .as_deref()
+                .or(issue.created_at.as_deref())
+                .and_then(parse_timestamp)?;
+            let age = ctx.now.saturating_sub(updated);
+            (age > threshold).then(|| RawFinding {
+                issue_id: issue.id.clone(),
+                message: format!(
+                    "'{}' has not been updated in {} days",
+                    issue.title,
+                    age / 86_400
+                ),
+                suggestion: Some(format!(
+                    "Update, re-prioritize, or close it: bd close {} --reason=\"stale\"",
+                    issue.id
+                )),
+            })
+        })
+        .collect()
+}
+
+fn check_circular_dependencies(ctx: &LintContext) -> Vec<RawFinding> {
+    find_cycles(ctx.issues)
+        .into_iter()
+        .map(|cycle| RawFinding {
+            issue_id: cycle[0].clone(),
+            message: format!("Dependency cycle: {}", cycle.join(" -> ")),
+            suggestion: Some(format!(
+                "Break the cycle, e.g. bd dep remove {} <dependency-id>",
+                cycle[0]
+            )),
+        })
+        .collect()
+}
+
+fn check_priority_missing(ctx: &LintContext) -> Vec<RawFinding> {
+    ctx.issues
+        .iter()
+        .filter(|issue| !issue.is_closed() && issue.priority.is_none())
+        .map(|issue| RawFinding {
+            issue_id: issue.id.clone(),
+            message: format!("'{}' has no priority", issue.title),
+            suggestion: Some(format!("bd update {} --priority=2", issue.id)),
+        })
+        .collect()
+}
+
+fn check_oversized_epics(ctx: &LintContext) -> Vec<RawFinding> {
+    let mut children: BTreeMap<&str, usize> = BTreeMap::new();
+    for issue in ctx.issues.iter().filter(|i| !i.is_closed()) {
+        if let Some(parent) = issue.parent() {
+            *children.entry(parent).or_insert(0) += 1;
+        }
+    }
+
+    ctx.issues
+        .iter()
+        .filter(|issue| issue.issue_type == "epic" && !issue.is_closed())
+        .filter_map(|epic| {
+            let count = children.get(epic.id.as_str()).copied().unwrap_or(0);
+            (count > ctx.config.max_epic_children).then(|| RawFinding {
+                issue_id: epic.id.clone(),
+                message: format!(
+                    "Epic '{}' has {} open children (max {})",
+                    epic.title, count, ctx.config.max_epic_children
+                ),
+                suggestion: Some("Split into smaller epics or sub-epics".to_string()),
+            })
+        })
+        .collect()
+}
+
+/// Character bigrams of a title, normalized to lowercase alphanumerics
+fn title_bigrams(title: &str) -> Vec<(char, char)> {
+    let normalized: Vec<char> = title
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .collect();
+    normalized.windows(2).map(|w| (w[0], w[1])).collect()
+}
+
+/// Sørensen–Dice similarity of two titles, as a percentage
+pub fn title_similarity(a: &str, b: &str) -> u8 {
+    let a = title_bigrams(a);
+    let mut b = title_bigrams(b);
+    if a.is_empty() || b.is_empty() {
+        return 0;
+    }
+
+    let total = a.len() + b.len();
+    let mut shared = 0;
+    for bigram in &a {
+        if let Some(pos) = b.iter().position(|x| x == bigram) {
+            b.swap_remove(pos);
+            shared += 1;
+        }
+    }
+    ((200 * shared) / total) as u8
+}
+
+fn check_duplicate_titles(ctx: &LintContext) -> Vec<RawFinding> {
+    let open: Vec<&Issue> = ctx.issues.iter().filter(|i| !i.is_closed()).collect();
+    let mut findings = Vec::new();
+    for (i, a) in open.iter().enumerate() {
+        for b in &open[i + 1..] {
+            let similarity = title_similarity(&a.title, &b.title);
+            if similarity >= ctx.config.duplicate_similarity {
+                findings.push(RawFinding {
+                    issue_id: a.id.clone(),
+                    message: format!(
+                        "Title looks like a duplicate of {} ({}% similar): '{}'",
+                        b.id, similarity, b.title
+                    ),
+                    suggestion: Some(format!(
+                        "If duplicated, close one: bd close {} --reason=\"duplicate of {}\"",
+                        b.id, a.id
+                    )),
+                });
+            }
+        }
+    }
+    findings
+}
+
+/// Phrases that describe a feeling rather than an observable outcome
+const NON_TESTABLE_PHRASES: &[&str] = &[
+    "works well",
+    "work well",
+    "as expected",
+    "properly",
+    "correctly",
+    "user friendly",
+    "user-friendly",
+    "intuitive",
+    "seamless",
+    "seamlessly",
+    "robust",
+    "good",
+    "nice",
+    "fast",
+    "etc",
+];
+
+/// Verbs that name an activity without saying when it is done
+const VAGUE_VERBS: &[&str] = &[
+    "handle",
+    "handles",
+    "support",
+    "supports",
+    "improve",
+    "improves",
+    "optimize",
+    "optimizes",
+    "enhance",
+    "enhances",
+    "manage",
+    "manages",
+    "ensure",
+    "ensures",
+];
+
+/// Content analysis of an issue's acceptance criteria
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AcQuality {
+    /// 0 (unusable) to 100 (every criterion is a testable checkbox)
+    pub score: u8,
+    /// Number of criteria found
+    pub criteria: usize,
+    /// Criteria written as plain text or bullets instead of `- [ ]`
+    pub missing_checkboxes: usize,
+    /// Non-testable phrases found, in order of appearance
+    pub non_testable: Vec<String>,
+    /// Vague verbs found, in order of appearance
+    pub vague_verbs: Vec<String>,
+}
+
+/// Strip a list marker, returning the item text and whether it had a checkbox
+///
+/// Returns `None` for lines that aren't list items.
+fn list_item(line: &str) -> Option<(&str, bool)> {
+    let line = line.trim_start();
+    let rest = if let Some(rest) = line
+        .strip_prefix("- ")
+        .or_else(|| line.strip_prefix("* "))
+        .or_else(|| line.strip_prefix("+ "))
+    {
+        rest
+    } else {
+        let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if digits == 0 {
+            return None;
+        }
+        line[digits..]
+            .strip_prefix(". ")
+            .or_else(|| line[digits..].strip_prefix(") "))?
+    };
+    let rest = rest.trim_start();
+    for checkbox in ["[ ]", "[x]", "[X]"] {
+        if let Some(text) = rest.strip_prefix(checkbox) {
+            return Some((text.trim(), true));
+        }
+    }
+    Some((rest.trim(), false))
+}
+
+/// The criteria in acceptance criteria text, and whether each has a checkbox
+///
+/// Criteria are list items, or non-heading lines when the text has no list.
+pub fn criteria(text: &str) -> Vec<(&str, bool)> {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+    let items: Vec<(&str, bool)> = lines.iter().filter_map(|line| list_item(line)).collect();
+    if items.is_empty() {
+        lines.iter().map(|line| (*line, false)).collect()
+    } else {
+        items
+    }
+}
+
+/// Score acceptance criteria text from 0 to 100
+///
+/// A single criterion, criteria without checkboxes, non-testable phrases,
+/// and vague verbs each cost points.
+pub fn ac_quality(text: &str) -> AcQuality {
+    let criteria = criteria(text);
+
+    let mut non_testable = Vec::new();
+    let mut vague_verbs = Vec::new();
+    for (criterion, _) in &criteria {
+        let words: Vec<String> = criterion
+            .to_lowercase()
+            .split(|c: char| !(c.is_alphanumeric() || c == '-'))
+            .filter(|w| !w.is_empty())
+            .map(str::to_string)
+            .collect();
+        let padded = format!(" {} ", words.join(" "));
+        non_testable.extend(
+            NON_TESTABLE_PHRASES
+                .iter()
+                .filter(|phrase| padded.contains(&format!(" {} ", phrase)))
+                .map(|phrase| phrase.to_string()),
+        );
+        vague_verbs.extend(
+            words
+                .iter()
+                .filter(|w| VAGUE_VERBS.contains(&w.as_str()))
+                .cloned(),
+        );
+    }
+    let missing_checkboxes = criteria.iter().filter(|(_, checkbox)| !checkbox).count();
+
+    let score = if criteria.is_empty() {
+        0
+    } else {
+        let mut penalty = 0usize;
+        if criteria.len() == 1 {
+            penalty += 15;
+        }
+        penalty += 25 * missing_checkboxes / criteria.len();
+        penalty += 15 * non_testable.len();
+        penalty += 10 * vague_verbs.len();
+        100usize.saturating_sub(penalty) as u8
+    };
+
+    AcQuality {
+        score,
+        criteria: criteria.len(),
+        missing_checkboxes,
+        non_testable,
+        vague_verbs,
+    }
+}
+
+/// Whether a required section name refers to the acceptance criteria field
+fn is_acceptance_criteria(section: &str) -> bool {
+    matches!(
+        section.to_lowercase().replace(' ', "_").as_str(),
+        "acceptance_criteria" | "ac"
+    )
+}
+
+/// Whether `config` requires acceptance criteria for `issue`'s type
+pub fn requires_acceptance_criteria(config: &LintConfig, issue: &Issue) -> bool {
+    config
+        .sections_for(&issue.issue_type)
+        .iter()
+        .any(|s| is_acceptance_criteria(s))
+}
+
+fn check_ac_quality(ctx: &LintContext) -> Vec<RawFinding> {
+    ctx.issues
+        .iter()
+        .filter(|issue| requires_acceptance_criteria(ctx.config, issue))
+        // Empty criteria are reported by required-sections
+        .filter(|issue| !issue.acceptance_criteria.trim().is_empty())
+        .filter_map(|issue| {
+            let quality = ac_quality(&issue.acceptance_criteria);
+            if quality.score >= ctx.config.min_ac_score {
+                return None;
+            }
+            let mut problems = Vec::new();
+            if quality.criteria == 1 {
+                problems.push("only 1 criterion".to_string());
+            }
+            if quality.missing_checkboxes > 0 {
+                problems.push(format!("{} without checkboxes", quality.missing_checkboxes));
+            }
+            if !quality.non_testable.is_empty() {
+                problems.push(format!("non-testable: {}", quality.non_testable.join(", ")));
+            }
+            if !quality.vague_verbs.is_empty() {
+                problems.push(format!("vague verbs: {}", quality.vague_verbs.join(", ")));
+            }
+            Some(RawFinding {
+                issue_id: issue.id.clone(),
+                message: format!(
+                    "Acceptance criteria of '{}' score {}/100 ({})",
+                    issue.title,
+                    quality.score,
+                    problems.join("; ")
+                ),
+                suggestion: Some(
+                    "Write each criterion as a '- [ ]' item with an observable outcome".to_string(),
+                ),
+            })
+        })
+        .collect()
+}
+
+fn check_stale_dependencies(ctx: &LintContext) -> Vec<RawFinding> {
+    let mut findings = Vec::new();
+    for issue in ctx.issues.iter().filter(|i| !i.is_closed()) {
+        for blocker in issue.blockers() {
+            let Some(closed) = ctx.find(blocker).filter(|b| b.is_closed()) else {
+                continue;
+            };
+            findings.push(RawFinding {
+                issue_id: issue.id.clone(),
+                message: format!(
+                    "'{}' still depends on closed {} ('{}')",
+                    issue.title, closed.id, closed.title
+                ),
+                suggestion: Some(format!("bd dep remove {} {}", issue.id, closed.id)),
+            });
+        }
+    }
+    findings
+}
+
+/// Longest chain of open blockers ending at `id`, outermost blocker first
+fn longest_chain<'a>(
+    ctx: &'a LintContext,
+    id: &'a str,
+    memo: &mut BTreeMap<&'a str, Vec<&'a str>>,
+    visiting: &mut BTreeSet<&'a str>,
+) -> Vec<&'a str> {
+    if let Some(chain) = memo.get(id) {
+        return chain.clone();
+    }
+    // Cycles are circular-dependency's to report
+    if !visiting.insert(id) {
+        return Vec::new();
+    }
+    let mut best = Vec::new();
+    if let Some(issue) = ctx.find(id) {
+        for blocker in issue.blockers() {
+            if ctx.find(blocker).is_some_and(|b| !b.is_closed()) {
+                let chain = longest_chain(ctx, blocker, memo, visiting);
+                if chain.len() > best.len() {
+                    best = chain;
+                }
+            }
+        }
+    }
+    visiting.remove(id);
+    best.push(id);
+    memo.insert(id, best.clone());
+    best
+}
+
+fn check_dependency_chains(ctx: &LintContext) -> Vec<RawFinding> {
+    let blocking: BTreeSet<&str> = ctx.issues.iter().flat_map(|i| i.blockers()).collect();
+    let mut memo = BTreeMap::new();
+    ctx.issues
+        .iter()
+        // Report each chain once, at the issue it ends in
+        .filter(|issue| !blocking.contains(issue.id.as_str()))
+        .filter_map(|issue| {
+            let chain = longest_chain(ctx, &issue.id, &mut memo, &mut BTreeSet::new());
+            (chain.len() > ctx.config.max_chain_length).then(|| RawFinding {
+                issue_id: issue.id.clone(),
+                message: format!(
+                    "'{}' ends a chain of {} dependent issues (max {}): {}",
+                    issue.title,
+                    chain.len(),
+                    ctx.config.max_chain_length,
+                    chain.join(" -> ")
+                ),
+                suggestion: Some(format!(
+                    "Drop an ordering that isn't real, e.g. bd dep remove {} {}",
+                    issue.id,
+                    chain[chain.len() - 2]
+                )),
+            })
+        })
+        .collect()
+}
+
+fn check_cross_epic_dependencies(ctx: &LintContext) -> Vec<RawFinding> {
+    let mut findings = Vec::new();
+    for issue in ctx.issues.iter().filter(|i| i.issue_type != "gate") {
+        let Some(epic) = issue.parent() else {
+            continue;
+        };
+        // A closed blocker is already satisfied; stale-dependency covers it
+        let blockers = issue.blockers().filter_map(|b| ctx.find(b));
+        for blocker in blockers.filter(|b| !b.is_closed()) {
+            let other = match blocker.parent() {
+                Some(other) if other != epic && blocker.issue_type != "gate" => other,
+                _ => continue,
+            };
+            findings.push(RawFinding {
+                issue_id: issue.id.clone(),
+                message: format!(
+                    "'{}' in {} depends on {} in {} without a gate",
+                    issue.title, epic, blocker.id, other
+                ),
+                suggestion: Some(format!(
+                    "bd create \"{} ready for {}\" -t gate --deps blocks:{}, then \
+                     bd dep add {} <gate-id> and bd dep remove {} {}",
+                    other, issue.id, blocker.id, issue.id, issue.id, blocker.id
+                )),
+            });
+        }
+    }
+    findings
+}
+
+fn check_blocked_without_reason(ctx: &LintContext) -> Vec<RawFinding> {
+    let Some(ref comments) = ctx.related.comments else {
+        return Vec::new();
+    };
+    ctx.issues
+        .iter()
+        .filter(|issue| issue.status == "blocked")
+        .filter(|issue| comments.get(&issue.id).is_none_or(|c| c.is_empty()))
+        .map(|issue| RawFinding {
+            issue_id: issue.id.clone(),
+            message: format!("'{}' is blocked with no comment saying why", issue.title),
+            suggestion: Some(format!(
+                "bd comments add {} \"Blocked on: <what it waits for>\"",
+                issue.id
+            )),
+        })
+        .collect()
+}
+
+/// Run all enabled rules over `ctx.issues`
+pub fn lint_issues(ctx: &LintContext) -> Vec<LintFinding> {
+    let mut findings = Vec::new();
+    for (id, check) in RULE_FNS {
+        if !ctx.config.is_enabled(id) {
+            continue;
+        }
+        let severity = ctx.config.severity(id);
+        findings.extend(check(ctx).into_iter().map(|f| LintFinding {
+            issue_id: f.issue_id,
+            rule: id.to_string(),
+            severity,
+            message: f.message,
+            suggestion: f.suggestion,
+        }));
+    }
+    findings.sort_by(|a, b| {
+        b.severity
+            .cmp(&a.severity)
+            .then_with(|| a.issue_id.cmp(&b.issue_id))
+    });
+    findings
+}
+
+/// Fetch what dependency rules need beyond `issues`: blockers outside the
+/// set (with their parents) and comments on blocked issues
+pub fn gather_related(dir: &Path, issues: &[Issue]) -> Result<Related, BdError> {
+    let known: BTreeSet<&str> = issues.iter().map(|i| i.id.as_str()).collect();
+    let outside: BTreeSet<&str> = issues
+        .iter()
+        .filter(|i| !i.is_closed())
+        .flat_map(|i| i.blockers())
+        .filter(|b| !known.contains(b))
+        .collect();
+    let mut related = Related::default();
+    // A blocker bd can't show (deleted, or an `external:` reference) only
+    // leaves the rules that need it without it
+    for id in outside
+        .into_iter()
+        .filter(|id| !id.starts_with("external:"))
+    {
+        match show_issue(dir, id) {
+            Ok(issue) => related.issues.push(issue),
+            Err(e) => tracing::warn!("skipping blocker {}: {}", id, e),
+        }
+    }
+    let mut comments = BTreeMap::new();
+    for issue in issues.iter().filter(|i| i.status == "blocked") {
+        comments.insert(issue.id.clone(), list_comments(dir, &issue.id)?);
+    }
+    related.comments = Some(comments);
+    Ok(related)
+}
+
+/// Lint issues and summarize the results
+///
+/// Closed issues are skipped, though dependency rules still see them;
+/// `config_source` is recorded in the report so
+/// callers can see which configuration was applied.
+pub fn build_report(
+    issues: &[Issue],
+    related: &Related,
+    config: &LintConfig,
+    config_source: &str,
+    now: u64,
+) -> LintReport {
+    let (closed, open): (Vec<Issue>, Vec<Issue>) =
+        issues.iter().cloned().partition(|i| i.is_closed());
+    let mut related = related.clone();
+    related.issues.extend(closed);
+    let ctx = LintContext {
+        issues: &open,
+        related: &related,
+        config,
+        now,
+    };
+    let findings = lint_issues(&ctx);
+
+    LintReport {
+        config: config_source.to_string(),
+        issues_checked: open.len(),
+        errors: count(&findings, Severity::Error),
+        warnings: count(&findings, Severity::Warning),
+        findings,
+    }
+}
+
+fn count(findings: &[LintFinding], severity: Severity) -> usize {
+    findings.iter().filter(|f| f.severity == severity).count()
+}
+
+/// Per-issue lint outcome, as published to the activity feed
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GateResult {
+    pub issue_id: String,
+    pub passed: bool,
+    pub errors: usize,
+    pub warnings: usize,
+    pub findings: Vec<LintFinding>,
+}
+
+/// Split a report into one gate result per open issue in `issues`
+pub fn gate_results(report: &LintReport, issues: &[Issue]) -> Vec<GateResult> {
+    issues
+        .iter()
+        .filter(|i| !i.is_closed())
+        .map(|issue| {
+            let findings: Vec<LintFinding> = report
+                .findings
+                .iter()
+                .filter(|f| f.issue_id == issue.id)
+                .cloned()
+                .collect();
+            let count = |severity| findings.iter().filter(|f| f.severity == severity).count();
+            let errors = count(Severity::Error);
+            GateResult {
+                issue_id: issue.id.clone(),
+                passed: errors == 0,
+                errors,
+                warnings: count(Severity::Warning),
+                findings,
+            }
+        })
+        .collect()
+}
+
+/// Emit a `gate` event per linted issue into the activity feed
+pub fn record_activity(
+    feed_file: &Path,
+    report: &LintReport,
+    issues: &[Issue],
+) -> Result<Vec<ActivityEvent>, String> {
+    gate_results(report, issues)
+        .into_iter()
+        .map(|result| {
+            let message = if result.passed {
+                format!("lint passed ({} warnings)", result.warnings)
+            } else {
+                format!(
+                    "lint failed ({} errors, {} warnings)",
+                    result.errors, result.warnings
+                )
+            };
+            let mut data = serde_json::to_value(&result).map_err(|e| e.to_string())?;
+            data["gate"] = "lint".into();
+            emit_event(
+                feed_file,
+                &result.issue_id,
+                EventKind::Gate,
+                &message,
+                None,
+                Some(data),
+            )
+        })
+        .collect()
+}
+
+/// Comment body summarizing one issue's lint outcome
+pub fn comment_text(result: &GateResult) -> String {
+    let mut text = if result.passed {
+        format!("Lint passed ({} warnings)", result.warnings)
+    } else {
+        format!(
+            "Lint failed ({} errors, {} warnings)",
+            result.errors, result.warnings
+        )
+    };
+    for finding in &result.findings {
+        text.push_str(&format!(
+            "\n- {} {}: {}",
+            finding.severity, finding.rule, finding.message
+        ));
+    }
+    text
+}
+
+/// Post the lint outcome as a comment on each linted issue
+///
+/// Returns the IDs of the issues that were commented on.
+pub fn record_comments(
+    dir: &Path,
+    report: &LintReport,
+    issues: &[Issue],
+) -> Result<Vec<String>, BdError> {
+    gate_results(report, issues)
+        .into_iter()
+        .map(|result| {
+            add_comment(dir, &result.issue_id, &comment_text(&result))?;
+            Ok(result.issue_id)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 86_400;
+    // 2026-01-12T00:00:00Z
+    const NOW: u64 = 1_768_176_000;
+
+    fn issue(id: &str, issue_type: &str) -> Issue {
+        Issue {
+            id: id.to_string(),
+            // Single-token titles keep the duplicate-title rule quiet
+            title: id.to_uppercase(),
+            description: "Does a thing".to_string(),
+            acceptance_criteria: "- [ ] Thing done".to_string(),
+            status: "open".to_string(),
+            priority: Some(2),
+            issue_type: issue_type.to_string(),
+            updated_at: Some("2026-01-11T00:00:00Z".to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn run(issues: &[Issue], config: &LintConfig) -> Vec<LintFinding> {
+        lint_issues(&LintContext {
+            issues,
+            related: &Related::default(),
+            config,
+            now: NOW,
+        })
+    }
+
+    #[test]
+    fn test_severity_from_str() {
+        assert_eq!("warn".parse::<Severity>().unwrap(), Severity::Warning);
+        assert_eq!("ERROR".parse::<Severity>().unwrap(), Severity::Error);
+        assert!("fatal".parse::<Severity>().is_err());
+    }
+
+    #[test]
+    fn test_clean_issue_has_no_findings() {
+        assert!(run(&[issue("a", "task")], &LintConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn test_missing_acceptance_criteria() {
+        let mut task = issue("a", "task");
+        task.acceptance_criteria.clear();
+
+        let findings = run(&[task], &LintConfig::default());
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, "required-sections");
+        assert_eq!(findings[0].severity, Severity::Error);
+        assert!(findings[0].message.contains("acceptance_criteria"));
+    }
+
+    #[test]
+    fn test_epic_does_not_require_acceptance_criteria() {
+        let mut epic = issue("e", "epic");
+        epic.acceptance_criteria.clear();
+
+        assert!(run(&[epic], &LintConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn test_custom_heading_section() {
+        let mut config = LintConfig::default();
+        config
+            .required_sections
+            .insert("bug".to_string(), vec!["Steps to Reproduce".to_string()]);
+        let mut bug = issue("b", "bug");
+        assert_eq!(run(&[bug.clone()], &config).len(), 1);
+
+        bug.description = "Crash\n\n## Steps to reproduce:\n1. Click".to_string();
+        assert!(run(&[bug], &config).is_empty());
+    }
+
+    #[test]
+    fn test_stale_issue() {
+        let mut old = issue("old", "task");
+        old.updated_at = Some("2025-12-01T00:00:00Z".to_string());
+
+        let findings = run(&[old.clone()], &LintConfig::default());
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, "stale");
+        assert!(findings[0].message.contains("42 days"));
+
+        let config = LintConfig {
+            stale_days: 60,
+            ..Default::default()
+        };
+        assert!(run(&[old], &config).is_empty());
+    }
+
+    fn blocked_by(mut issue: Issue, blocker: &str) -> Issue {
+        issue.dependencies.push(crate::beads::Dependency {
+            issue_id: issue.id.clone(),
+            depends_on_id: blocker.to_string(),
+            dep_type: "blocks".to_string(),
+        });
+        issue
+    }
+
+    fn child_of(mut issue: Issue, parent: &str) -> Issue {
+        issue.dependencies.push(crate::beads::Dependency {
+            issue_id: issue.id.clone(),
+            depends_on_id: parent.to_string(),
+            dep_type: "parent-child".to_string(),
+        });
+        issue
+    }
+
+    #[test]
+    fn test_circular_dependency() {
+        let issues = vec![
+            blocked_by(issue("a", "task"), "b"),
+            blocked_by(issue("b", "task"), "c"),
+            blocked_by(issue("c", "task"), "a"),
+            blocked_by(issue("d", "task"), "a"),
+            blocked_by(issue("e", "task"), "e"),
+        ];
+
+        let cycles = find_cycles(&issues);
+
+        assert_eq!(
+            cycles,
+            vec![
+                vec!["a".to_string(), "b".to_string(), "c".to_string()],
+                vec!["e".to_string()],
+            ]
+        );
+        let findings = run(&issues, &LintConfig::default());
+        assert_eq!(findings.len(), 2);
+        assert!(findings.iter().all(|f| f.rule == "circular-dependency"));
+    }
+
+    #[test]
+    fn test_acyclic_graph_has_no_cycles() {
+        let issues = vec![
+            blocked_by(issue("a", "task"), "b"),
+            blocked_by(issue("b", "task"), "c"),
+            issue("c", "task"),
+            blocked_by(issue("d", "task"), "missing"),
+        ];
+
+        assert!(find_cycles(&issues).is_empty());
+    }
+
+    #[test]
+    fn test_priority_missing() {
+        let mut a = issue("a", "task");
+        a.priority = None;
+        let mut done = issue("done", "task");
+        done.priority = None;
+        done.status = "closed".to_string();
+
+        let findings = run(&[a, issue("b", "task"), done], &LintConfig::default());
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, "priority-missing");
+        assert_eq!(findings[0].issue_id, "a");
+    }
+
+    #[test]
+    fn test_oversized_epic() {
+        let mut issues = vec![issue("epic", "epic")];
+        for i in 0..5 {
+            issues.push(child_of(issue(&format!("c{}", i), "task"), "epic"));
+        }
+        issues[1].status = "closed".to_string();
+        let config = LintConfig {
+            max_epic_children: 3,
+            ..Default::default()
+        };
+
+        let findings = run(&issues, &config);
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, "oversized-epic");
+        assert!(findings[0].message.contains("4 open children"));
+    }
+
+    #[test]
+    fn test_title_similarity() {
+        assert_eq!(title_similarity("Add login page", "Add login page"), 100);
+        assert!(title_similarity("Add login page", "add Login-page!") >= 90);
+        assert!(title_similarity("Add login page", "Refactor payment service") < 40);
+        assert_eq!(title_similarity("", "anything"), 0);
+    }
+
+    #[test]
+    fn test_duplicate_titles() {
+        let mut a = issue("a", "task");
+        a.title = "Implement user login page".to_string();
+        let mut b = issue("b", "task");
+        b.title = "Implement user login pages".to_string();
+        let mut c = issue("c", "task");
+        c.title = "Write migration guide".to_string();
+        let mut d = issue("d", "task");
+        d.title = "Write migration guides".to_string();
+        d.status = "closed".to_string();
+
+        let findings = run(&[a, b, c, d], &LintConfig::default());
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, "duplicate-title");
+        assert!(findings[0].message.contains("b"));
+    }
+
+    #[test]
+    fn test_ac_quality_score() {
+        let good = ac_quality("- [ ] Returns 404 for unknown ids\n- [x] Logs the request id");
+        assert_eq!(good.score, 100);
+        assert_eq!(good.criteria, 2);
+
+        let vague = ac_quality("Login works well\nHandle errors properly");
+        assert_eq!(vague.criteria, 2);
+        assert_eq!(vague.missing_checkboxes, 2);
+        assert_eq!(vague.non_testable, vec!["works well", "properly"]);
+        assert_eq!(vague.vague_verbs, vec!["handle"]);
+        assert_eq!(vague.score, 35);
+
+        assert_eq!(ac_quality("## Acceptance Criteria\n").score, 0);
+        assert_eq!(ac_quality("1. [ ] Exit code is 3").criteria, 1);
+    }
+
+    #[test]
+    fn test_low_ac_quality_is_a_warning() {
+        let mut task = issue("a", "task");
+        task.acceptance_criteria = "- It works well and is fast".to_string();
+        let mut epic = issue("e", "epic");
+        epic.acceptance_criteria = task.acceptance_criteria.clone();
+
+        let findings = run(&[task, epic], &LintConfig::default());
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, "ac-quality");
+        assert_eq!(findings[0].issue_id, "a");
+        assert_eq!(findings[0].severity, Severity::Warning);
+        assert!(findings[0].message.contains("score 30/100"));
+
+        let config = LintConfig {
+            min_ac_score: 30,
+            ..Default::default()
+        };
+        let mut task = issue("a", "task");
+        task.acceptance_criteria = "- It works well and is fast".to_string();
+        assert!(run(&[task], &config).is_empty());
+    }
+
+    #[test]
+    fn test_record_activity() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let feed = dir.path().join("activity.jsonl");
+        let mut bad = issue("bad", "task");
+        bad.description.clear();
+        let mut closed = issue("closed", "task");
+        closed.status = "closed".to_string();
+        let issues = vec![issue("good", "task"), bad, closed];
+        let report = build_report(
+            &issues,
+            &Related::default(),
+            &LintConfig::default(),
+            "defaults",
+            NOW,
+        );
+
+        let events = record_activity(&feed, &report, &issues).unwrap();
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].issue, "good");
+        assert_eq!(events[0].kind, EventKind::Gate);
+        assert_eq!(events[0].data.as_ref().unwrap()["passed"], true);
+        assert_eq!(events[1].issue, "bad");
+        assert!(events[1].message.starts_with("lint failed (1 errors"));
+        assert_eq!(events[1].data.as_ref().unwrap()["gate"], "lint");
+        assert_eq!(crate::activity::read_events(&feed).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_comment_text() {
+        let mut bad = issue("bad", "task");
+        bad.description.clear();
+        let issues = vec![issue("good", "task"), bad];
+        let report = build_report(
+            &issues,
+            &Related::default(),
+            &LintConfig::default(),
+            "defaults",
+            NOW,
+        );
+        let results = gate_results(&report, &issues);
+
+        assert_eq!(comment_text(&results[0]), "Lint passed (0 warnings)");
+        let failed = comment_text(&results[1]);
+        assert!(
+            failed.starts_with("Lint failed (1 errors, 0 warnings)\n- error required-sections:")
+        );
+    }
+
+    #[test]
+    fn test_invalid_duplicate_similarity() {
+        let config = LintConfig {
+            duplicate_similarity: 101,
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+
+        let config = LintConfig {
+            min_ac_score: 101,
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_rule_overrides() {
+        let mut task = issue("a", "task");
+        task.description.clear();
+        task.updated_at = Some("2025-01-01T00:00:00Z".to_string());
+
+        let mut config = LintConfig::default();
+        config.rules.insert(
+            "required-sections".to_string(),
+            RuleConfig {
+                enabled: Some(false),
+                severity: None,
+            },
+        );
+        config.rules.insert(
+            "stale".to_string(),
+            RuleConfig {
+                enabled: None,
+                severity: Some(Severity::Info),
+            },
+        );
+
+        let findings = run(&[task], &config);
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, "stale");
+        assert_eq!(findings[0].severity, Severity::Info);
+    }
+
+    #[test]
+    fn test_build_report_skips_closed() {
+        let mut closed = issue("c", "task");
+        closed.status = "closed".to_string();
+        closed.description.clear();
+        let mut broken = issue("b", "task");
+        broken.description.clear();
+
+        let report = build_report(
+            &[closed, broken],
+            &Related::default(),
+            &LintConfig::default(),
+            "built-in defaults",
+            NOW + DAY,
+        );
+
+        assert_eq!(report.issues_checked, 1);
+        assert_eq!(report.errors, 1);
+        assert!(report.has_errors());
+        assert_eq!(report.config, "built-in defaults");
+    }
+
+    fn run_with(issues: &[Issue], related: &Related, config: &LintConfig) -> Vec<(String, String)> {
+        lint_issues(&LintContext {
+            issues,
+            related,
+            config,
+            now: NOW,
+        })
+        .into_iter()
+        .map(|f| (f.rule, f.issue_id))
+        .collect()
+    }
+
+    fn pair(rule: &str, id: &str) -> (String, String) {
+        (rule.to_string(), id.to_string())
+    }
+
+    #[test]
+    fn test_stale_dependency() {
+        let mut done = issue("done", "task");
+        done.status = "closed".to_string();
+        let related = Related {
+            issues: vec![done],
+            comments: None,
+        };
+        let mut finished = blocked_by(issue("c", "task"), "done");
+        finished.status = "closed".to_string();
+        let issues = vec![
+            blocked_by(issue("a", "task"), "done"),
+            blocked_by(issue("b", "task"), "a"),
+            finished,
+        ];
+
+        let findings = lint_issues(&LintContext {
+            issues: &issues,
+            related: &related,
+            config: &LintConfig::default(),
+            now: NOW,
+        });
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, "stale-dependency");
+        assert_eq!(findings[0].issue_id, "a");
+        assert_eq!(
+            findings[0].suggestion.as_deref(),
+            Some("bd dep remove a done")
+        );
+    }
+
+    #[test]
+    fn test_long_dependency_chain() {
+        let issues = vec![
+            issue("a", "task"),
+            blocked_by(issue("b", "task"), "a"),
+            blocked_by(issue("c", "task"), "b"),
+            blocked_by(issue("d", "task"), "c"),
+            blocked_by(issue("e", "task"), "a"),
+        ];
+        let config = LintConfig {
+            max_chain_length: 3,
+            ..Default::default()
+        };
+
+        let findings = lint_issues(&LintContext {
+            issues: &issues,
+            related: &Related::default(),
+            config: &config,
+            now: NOW,
+        });
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].issue_id, "d");
+        assert!(findings[0].message.ends_with("(max 3): a -> b -> c -> d"));
+        assert_eq!(
+            findings[0].suggestion.as_deref(),
+            Some("Drop an ordering that isn't real, e.g. bd dep remove d c")
+        );
+        let relaxed = LintConfig {
+            max_chain_length: 4,
+            ..Default::default()
+        };
+        assert!(run_with(&issues, &Related::default(), &relaxed).is_empty());
+    }
+
+    #[test]
+    fn test_cross_epic_dependency() {
+        let gate = child_of(issue("g", "gate"), "e2");
+        let mut done = child_of(issue("d", "task"), "e2");
+        done.status = "closed".to_string();
+        let issues = vec![
+            blocked_by(child_of(issue("x", "task"), "e1"), "y"),
+            child_of(issue("y", "task"), "e2"),
+            blocked_by(child_of(issue("z", "task"), "e1"), "g"),
+            blocked_by(child_of(issue("w", "task"), "e1"), "x"),
+            blocked_by(child_of(issue("v", "task"), "e1"), "d"),
+        ];
+        let related = Related {
+            issues: vec![gate, done],
+            comments: None,
+        };
+
+        let findings = run_with(&issues, &related, &LintConfig::default());
+
+        // The satisfied dependency on d is only stale, not missing a gate
+        assert_eq!(
+            findings,
+            vec![
+                pair("stale-dependency", "v"),
+                pair("cross-epic-dependency", "x")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_blocked_without_reason() {
+        let mut blocked = issue("a", "task");
+        blocked.status = "blocked".to_string();
+        let mut explained = issue("b", "task");
+        explained.status = "blocked".to_string();
+        let issues = vec![blocked, explained];
+        let comment = Comment {
+            author: "w1".to_string(),
+            text: "Waiting on the API key".to_string(),
+            created_at: None,
+        };
+        let fetched = Related {
+            issues: Vec::new(),
+            comments: Some(BTreeMap::from([("b".to_string(), vec![comment])])),
+        };
+
+        let without = run_with(&issues, &Related::default(), &LintConfig::default());
+        let with = run_with(&issues, &fetched, &LintConfig::default());
+
+        assert!(without.is_empty());
+        assert_eq!(with, vec![pair("blocked-without-reason", "a")]);
+    }
+
+    #[test]
+    fn test_invalid_max_chain_length() {
+        let config = LintConfig {
+            max_chain_length: 1,
+            ..Default::default()
+        };
+        assert!(config.validate().unwrap_err().contains("max_chain_length"));
+    }
+}
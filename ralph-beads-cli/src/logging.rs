@@ -0,0 +1,84 @@
+//! Structured logging
+//!
+//! Diagnostics go through `tracing`. Stderr shows warnings by default and
+//! more with each `-v`; `--diag-log` additionally appends JSON lines with
+//! every span (command, bd/gh subprocess calls, hooks) and its duration, so
+//! a failed run can be reconstructed after the fact.
+
+use std::fs::{self, OpenOptions};
+use std::io::IsTerminal;
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::level_filters::LevelFilter;
+use tracing::Level;
+use tracing_subscriber::filter::filter_fn;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::prelude::*;
+
+/// Target for command outcome events, which go to the log file only
+/// (the error itself is already printed to stderr)
+pub const OUTCOME_TARGET: &str = "ralph_beads_cli::outcome";
+
+/// Stderr level for a `-v` count: warn, info, debug, then trace
+pub fn stderr_level(verbose: u8) -> Level {
+    match verbose {
+        0 => Level::WARN,
+        1 => Level::INFO,
+        2 => Level::DEBUG,
+        _ => Level::TRACE,
+    }
+}
+
+/// Install the global subscriber
+pub fn init(verbose: u8, diag_log: Option<&Path>) -> Result<(), String> {
+    let level = stderr_level(verbose);
+    let stderr = tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_target(false)
+        .with_ansi(std::io::stderr().is_terminal())
+        .without_time()
+        .with_filter(filter_fn(move |meta| {
+            *meta.level() <= level && meta.target() != OUTCOME_TARGET
+        }));
+
+    let file = match diag_log {
+        Some(path) => {
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+            }
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+            Some(
+                tracing_subscriber::fmt::layer()
+                    .json()
+                    .with_writer(Mutex::new(file))
+                    .with_span_events(FmtSpan::CLOSE)
+                    .with_filter(LevelFilter::from_level(level.max(Level::DEBUG))),
+            )
+        }
+        None => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(stderr)
+        .with(file)
+        .try_init()
+        .map_err(|e| format!("Failed to initialize logging: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stderr_level() {
+        assert_eq!(stderr_level(0), Level::WARN);
+        assert_eq!(stderr_level(1), Level::INFO);
+        assert_eq!(stderr_level(2), Level::DEBUG);
+        assert_eq!(stderr_level(7), Level::TRACE);
+    }
+}
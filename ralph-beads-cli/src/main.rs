@@ -4,23 +4,139 @@
 //! - Complexity detection from task descriptions
 //! - Test framework detection
 //! - Iteration calculation based on mode and complexity
+//! - Conflict forecasting and health reports across git worktrees
+//! - Per-issue worktree creation with an issue registry
+//! - Local activity feed for progress events, tagged by session run ID
+//! - Issue linting with per-project rule configuration
+//! - Configuration validation with per-key provenance
+//! - Typed beads state dimensions with transition validation
+//! - Epic scaffolding from markdown plans or an interactive planning prompt
+//! - Dependency graph analysis (critical path, waves, bottlenecks) and
+//!   structural validation
+//! - Lifecycle hooks configured per project
+//! - GitHub pull request and workflow run status via `gh`
+//! - Workflow metrics with Prometheus export
+//! - Token cost ledger with per-epic reports and budgets
+//! - Structured logging to stderr and JSON-lines log files
+//! - External plugins (`ralph-beads-<name>` on PATH) as subcommands
+//! - Agent context compilation from templates
+//! - Effort estimates from complexity and task history
+//! - Stable exit codes per failure class
+//! - Machine-wide limits on concurrent bd/git subprocesses
+//! - Project bootstrap (`init`): config, beads database, git hooks
+//! - Multi-repo workspaces with aggregated lint reports
+//! - Beads database health checks (redirects, orphaned locks, daemon)
+//! - JSON Schemas for structured command output
+//! - Epic status reports in Markdown, HTML, or JSON
+//! - Session state updates with compare-and-swap on a revision counter
+//! - Recording of bd calls as replayable test fixtures
+//! - Activity feed retention with daily compaction of old events
+//! - Per-task context bundles (issue, epic, blocker outputs, test command)
+//! - Structured git diff summaries, attachable to activity events
+//! - Plan/approval linkage gating the move into building mode
+//! - Bootstrap recipes run on each new issue worktree
+//! - XDG-aware data directories with a `--data-dir` override
+//! - One-call situational overview (`status`) for the start of an iteration
+//! - Epic velocity: completions per day, task durations, projected finish
+//! - Issue creation from templates, refused when the draft fails lint
+//! - Configurable loop stop conditions with a reason for each stop
+//! - Task splitting by acceptance criteria, linted before creation
+//! - Toolchain version pin checks in health
+//! - Worktree guard against working on an issue in another issue's worktree
+//! - Build caches shared between worktrees
 
-mod complexity;
-mod framework;
-mod state;
-
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use regex::Regex;
 use serde_json::json;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use complexity::{calculate_max_iterations, detect_complexity, Complexity};
-use framework::detect_framework;
-use state::WorkflowMode;
+use ralph_beads_cli::activity::{
+    self, emit_event, follow_events, format_duration, format_event, format_timestamp, now_secs,
+    paginate, parse_cursor, parse_duration, read_events, run_event_hook, EventFilter, EventKind,
+    Followed, DEFAULT_MAX_BACKLOG, DEFAULT_PAGE_SIZE,
+};
+use ralph_beads_cli::beads::{
+    self, list_comments, list_issues, show_issue, BdError, Issue, ListFilter,
+};
+use ralph_beads_cli::beads_state::{
+    bulk_exit_code, get_state, render_transitions, set_state, set_state_bulk, watch_state,
+    StateSchema, WatchOutcome,
+};
+use ralph_beads_cli::bootstrap::{self, BootstrapConfig, TemplateVars};
+use ralph_beads_cli::build_cache;
+use ralph_beads_cli::bundle;
+use ralph_beads_cli::complexity::Complexity;
+use ralph_beads_cli::config::{check_config, load_config, CONFIG_FILE};
+use ralph_beads_cli::context::{self, ContextData};
+use ralph_beads_cli::cost::{self, CostFilter, NewCost};
+use ralph_beads_cli::data_dirs::{self, DataDirs};
+use ralph_beads_cli::diff;
+use ralph_beads_cli::epic::{self, parse_plan, render_graph, scaffold};
+use ralph_beads_cli::estimate;
+use ralph_beads_cli::exit_code::{self, ExitCode};
+use ralph_beads_cli::fixtures;
+use ralph_beads_cli::framework::detect_framework;
+use ralph_beads_cli::github::{self, NewPr};
+use ralph_beads_cli::graph::{self, DepGraph, ValidateOptions};
+use ralph_beads_cli::health::{self, CheckStatus, HealthOptions};
+use ralph_beads_cli::hooks::{run_hooks, HookPoint, HooksConfig};
+use ralph_beads_cli::init::{self, InitOptions, StepStatus};
+use ralph_beads_cli::issue_template::{self, IssueRequest, DRAFT_ID};
+use ralph_beads_cli::limits;
+use ralph_beads_cli::lint::{self, LintReport};
+use ralph_beads_cli::logging::{self, OUTCOME_TARGET};
+use ralph_beads_cli::metrics;
+use ralph_beads_cli::plan_repl::{self, PlanSession};
+use ralph_beads_cli::plugins::{self, PluginContext};
+use ralph_beads_cli::report;
+use ralph_beads_cli::retention::{self, ActivityConfig};
+use ralph_beads_cli::schema;
+use ralph_beads_cli::split;
+use ralph_beads_cli::sse::serve;
+use ralph_beads_cli::state::{self, SessionState, UpdateError, WorkflowMode};
+use ralph_beads_cli::status::{
+    self, HealthSummary, IssueStatus, LintSummary, PendingApproval, Status,
+};
+use ralph_beads_cli::stop::{self, StopSignals};
+use ralph_beads_cli::timeline::{build_report, render_mermaid, render_text};
+use ralph_beads_cli::toolchain;
+use ralph_beads_cli::velocity;
+use ralph_beads_cli::workspace::{self, WorkspaceLint};
+use ralph_beads_cli::worktree::{
+    create_issue_worktree, current_worktree, forecast_worktree_conflicts, format_size, guard_issue,
+    list_issue_worktrees, read_registry, registry_path, remove_issue_worktree, repo_root,
+    sort_reports, worktree_reports, ConflictRisk, ReportFilter, ReportSort,
+};
 
 #[derive(Parser)]
 #[command(name = "ralph-beads-cli")]
 #[command(about = "Rust CLI helper for ralph-beads plugin", long_about = None)]
 #[command(version)]
 struct Cli {
+    /// Show more diagnostics on stderr (-v info, -vv debug, -vvv trace)
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Append JSON-lines diagnostics (spans, subprocess calls, outcomes) to
+    /// a file; not the activity feed, which subcommands take as --log-file
+    #[arg(long, value_name = "PATH")]
+    diag_log: Option<String>,
+
+    /// Seconds before any bd call is killed (default: bd.timeout_secs, 30)
+    #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    bd_timeout: Option<u64>,
+
+    /// Developer mode: write every bd call and its output to DIR as test fixtures
+    #[arg(long, value_name = "DIR")]
+    record_fixtures: Option<String>,
+
+    /// Keep all state under DIR instead of .beads/ and the XDG directories
+    /// (also RALPH_BEADS_DATA_DIR)
+    #[arg(long, value_name = "DIR")]
+    data_dir: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -36,6 +152,10 @@ enum Commands {
         /// Output format: text or json
         #[arg(short, long, default_value = "text")]
         format: String,
+
+        /// Project directory whose [complexity] taxonomy to use (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
     },
 
     /// Detect test framework from current directory
@@ -44,32 +164,1969 @@ enum Commands {
         #[arg(short, long)]
         dir: Option<String>,
 
-        /// Output format: text or json
-        #[arg(short, long, default_value = "text")]
-        format: String,
-    },
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// Calculate max iterations based on mode and complexity
+    CalcIterations {
+        /// Workflow mode: planning or building
+        #[arg(short, long)]
+        mode: String,
+
+        /// Complexity level: trivial, simple, standard, critical, or a level
+        /// of the configured [complexity] taxonomy
+        #[arg(short, long)]
+        complexity: String,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Show the table row used and the [iterations] overrides applied
+        #[arg(long)]
+        explain: bool,
+
+        /// Project directory whose configuration to use (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+    },
+
+    /// Inspect git worktrees used for parallel execution
+    Worktree {
+        #[command(subcommand)]
+        command: WorktreeCommands,
+    },
+
+    /// Publish and read events on the local activity feed
+    Activity {
+        #[command(subcommand)]
+        command: ActivityCommands,
+    },
+
+    /// Lint beads issues for structure and freshness
+    Lint {
+        #[command(subcommand)]
+        command: LintCommands,
+    },
+
+    /// Read and change typed state dimensions on beads issues
+    BeadsState {
+        #[command(subcommand)]
+        command: BeadsStateCommands,
+    },
+
+    /// Create epics and their tasks from plans
+    Epic {
+        #[command(subcommand)]
+        command: EpicCommands,
+    },
+
+    /// Create issues from templates, refusing ones that fail lint
+    Issue {
+        #[command(subcommand)]
+        command: IssueCommands,
+    },
+
+    /// Draft an epic's tasks interactively and create them in one step
+    Plan {
+        #[command(subcommand)]
+        command: PlanCommands,
+    },
+
+    /// Analyze beads dependency graphs
+    Graph {
+        #[command(subcommand)]
+        command: GraphCommands,
+    },
+
+    /// Inspect and trigger lifecycle hooks from .ralph-beads.toml
+    Hooks {
+        #[command(subcommand)]
+        command: HooksCommands,
+    },
+
+    /// Query and create GitHub pull requests and runs via gh
+    Gh {
+        #[command(subcommand)]
+        command: GhCommands,
+    },
+
+    /// Record and export workflow metrics
+    Metrics {
+        #[command(subcommand)]
+        command: MetricsCommands,
+    },
+
+    /// Track token costs per session, task, and worker
+    Cost {
+        #[command(subcommand)]
+        command: CostCommands,
+    },
+
+    /// Summarize an epic's progress for sharing
+    Report {
+        #[command(subcommand)]
+        command: ReportCommands,
+    },
+
+    /// Summarize git changes for reviewers
+    Diff {
+        #[command(subcommand)]
+        command: DiffCommands,
+    },
+
+    /// Assemble what a worker needs to start on a task
+    Task {
+        #[command(subcommand)]
+        command: TaskCommands,
+    },
+
+    /// Reshape work for parallel workers
+    Swarm {
+        #[command(subcommand)]
+        command: SwarmCommands,
+    },
+
+    /// Print the JSON Schema of a command's JSON output, or list them
+    Schema {
+        /// Command whose output schema to print (omit to list)
+        command: Option<String>,
+    },
+
+    /// Output information about CLI capabilities
+    Info {
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Also probe the installed bd (exits 5 if unavailable or incompatible)
+        #[arg(long)]
+        check_bd: bool,
+
+        /// Project directory whose configuration, hooks, and templates to report
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Also list every file and directory the CLI reads or writes
+        #[arg(long)]
+        paths: bool,
+    },
+
+    /// Workflow mode of the current session
+    State {
+        #[command(subcommand)]
+        command: StateCommands,
+    },
+
+    /// Compile prompt-ready agent context from templates
+    Context {
+        #[command(subcommand)]
+        command: ContextCommands,
+    },
+
+    /// Inspect and validate .ralph-beads.toml
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+
+    /// Repositories of the multi-repo workspace
+    Workspace {
+        #[command(subcommand)]
+        command: WorkspaceCommands,
+    },
+
+    /// Machine-wide limits on bd and git subprocesses
+    Limits {
+        #[command(subcommand)]
+        command: LimitsCommands,
+    },
+
+    /// Estimate iterations and wall-clock time for an issue from similar past work
+    Estimate {
+        /// Issue ID
+        #[arg(short, long)]
+        issue: String,
+
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Path to the activity feed file (defaults to activity.jsonl in the data directory)
+        #[arg(short, long)]
+        log_file: Option<String>,
+
+        /// Path to the metrics log (defaults to metrics.jsonl in the data directory)
+        #[arg(short, long)]
+        metrics_file: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// Check the beads setup (directory, redirect, locks, daemon) and toolchain
+    /// pins; exits 3 on failure
+    Health {
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Also create, read back, and delete a scratch bead
+        #[arg(long)]
+        probe: bool,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// Everything to know at the start of an iteration: worktree, issue,
+    /// session, health, epic progress, open gates, failures, and lint errors
+    Status {
+        /// Issue to report on (defaults to the one the worktree serves)
+        #[arg(short, long)]
+        issue: Option<String>,
+
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Path to the activity feed file (defaults to activity.jsonl in the data directory)
+        #[arg(short, long)]
+        log_file: Option<String>,
+
+        /// Path to the session state file (defaults to session.json in the data directory)
+        #[arg(short, long)]
+        session_file: Option<String>,
+
+        /// Most recent failures to show
+        #[arg(long, default_value_t = 5)]
+        failures: usize,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// Bootstrap a project: config file, beads database, and git hooks
+    Init {
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Overwrite an existing config file and pre-commit hook
+        #[arg(long)]
+        force: bool,
+
+        /// Don't run `bd init` when .beads/ is missing
+        #[arg(long)]
+        no_beads: bool,
+
+        /// Install a git pre-commit hook that runs `lint all`
+        #[arg(long)]
+        git_hooks: bool,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// List plugins (ralph-beads-<name> executables on PATH)
+    Plugins {
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// Run a plugin subcommand
+    #[command(external_subcommand)]
+    External(Vec<String>),
+}
+
+#[derive(Subcommand)]
+enum WorktreeCommands {
+    /// List worktrees and the issue each one serves
+    List {
+        /// Repository directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// Create a worktree for an issue and mark the issue in progress
+    CreateForIssue {
+        /// Issue the worktree serves
+        id: String,
+
+        /// Repository directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Branch to fork the new branch from
+        #[arg(short, long, default_value = "main")]
+        base: String,
+
+        /// Directory to create the worktree in (defaults to <repo>-worktrees/
+        /// next to the repository)
+        #[arg(long)]
+        root: Option<String>,
+
+        /// Skip the `[worktree.bootstrap]` recipe
+        #[arg(long)]
+        no_bootstrap: bool,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// Show the current worktree, its issue, and its shared build caches
+    Info {
+        /// Worktree directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: text, json, or env (cache variables as exports)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// Refuse (exit 3) unless this worktree was created for the given issue
+    Guard {
+        /// Issue about to be worked on
+        #[arg(short, long)]
+        issue: String,
+
+        /// Let worktrees not made by create-for-issue (e.g. the main checkout) pass
+        #[arg(long)]
+        allow_unregistered: bool,
+
+        /// Worktree directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// Forecast merge conflicts between active worktree branches and main
+    Conflicts {
+        /// Repository directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Base branch that worktrees merge back into
+        #[arg(short, long, default_value = "main")]
+        base: String,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// Disk usage, commit age, ahead/behind, dirtiness, and worker per worktree
+    Report {
+        /// Repository directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Base branch for ahead/behind counts
+        #[arg(short, long, default_value = "main")]
+        base: String,
+
+        /// Sort by: path, disk, age, or behind
+        #[arg(short, long, default_value = "path")]
+        sort: String,
+
+        /// Only worktrees with uncommitted changes
+        #[arg(long)]
+        dirty: bool,
+
+        /// Only worktrees whose last commit is older than this (e.g. 7d)
+        #[arg(long)]
+        older_than: Option<String>,
+
+        /// Leave out the main worktree
+        #[arg(long)]
+        no_main: bool,
+
+        /// Activity feed used to find each task's worker (defaults to activity.jsonl in the data directory)
+        #[arg(short, long)]
+        log_file: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+}
+
+/// Activity feed subcommands
+#[derive(Subcommand)]
+enum ActivityCommands {
+    /// Publish an event to the activity feed
+    Emit {
+        /// Issue the event relates to
+        #[arg(short, long)]
+        issue: String,
+
+        /// Event kind: claimed, progress, preflight, completed, failed, blocked, gate, ci
+        #[arg(short, long)]
+        kind: String,
+
+        /// Human-readable event message
+        #[arg(short, long)]
+        message: String,
+
+        /// Worker publishing the event
+        #[arg(short, long)]
+        worker: Option<String>,
+
+        /// Additional event data as a JSON object
+        #[arg(short, long)]
+        data: Option<String>,
+
+        /// Attach a summary of the diff against this revision as `data.diff`
+        /// (default HEAD: uncommitted changes)
+        #[arg(long, num_args = 0..=1, default_missing_value = "HEAD")]
+        diff: Option<String>,
+
+        /// Path to the activity feed file (defaults to activity.jsonl in the data directory)
+        #[arg(short, long)]
+        log_file: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// List events from the activity feed
+    List {
+        /// Only show events for this issue
+        #[arg(short, long)]
+        issue: Option<String>,
+
+        /// Only show events of this kind
+        #[arg(short, long)]
+        kind: Option<String>,
+
+        /// Show at most this many (most recent) events
+        #[arg(short = 'n', long, conflicts_with_all = ["cursor", "page_size"])]
+        limit: Option<usize>,
+
+        /// Only show events from this run (see `state new`)
+        #[arg(long)]
+        run: Option<String>,
+
+        /// Page through the feed oldest first, starting after this cursor
+        /// (a `next_cursor` from the previous page)
+        #[arg(long)]
+        cursor: Option<String>,
+
+        /// Events per page when paging (default 100 with --cursor)
+        #[arg(long)]
+        page_size: Option<usize>,
+
+        /// Path to the activity feed file (defaults to activity.jsonl in the data directory)
+        #[arg(short, long)]
+        log_file: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// Stream new events from the activity feed as they are published
+    Follow {
+        /// Only show events for this issue
+        #[arg(short, long)]
+        issue: Option<String>,
+
+        /// Only show events of this kind
+        #[arg(short, long)]
+        kind: Option<String>,
+
+        /// Only show events published by this worker
+        #[arg(short, long)]
+        worker: Option<String>,
+
+        /// Only show events from this run (see `state new`)
+        #[arg(long)]
+        run: Option<String>,
+
+        /// Only show events whose issue, message, or data match this regex
+        #[arg(short, long)]
+        grep: Option<String>,
+
+        /// Shell command to run per matching event (event JSON on stdin)
+        #[arg(short, long)]
+        exec: Option<String>,
+
+        /// Replay existing events before following new ones
+        #[arg(long)]
+        from_start: bool,
+
+        /// Poll interval in milliseconds
+        #[arg(long, default_value = "500")]
+        interval_ms: u64,
+
+        /// Matching events handled per poll (and 4 KiB of feed read per
+        /// event); a follower further behind skips ahead with a notice
+        #[arg(long, default_value_t = DEFAULT_MAX_BACKLOG)]
+        max_backlog: usize,
+
+        /// Path to the activity feed file (defaults to activity.jsonl in the data directory)
+        #[arg(short, long)]
+        log_file: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// Serve the activity feed to web clients over Server-Sent Events
+    Serve {
+        /// Port to listen on
+        #[arg(short, long, default_value = "8787")]
+        port: u16,
+
+        /// Address to bind (use 0.0.0.0 to expose beyond localhost)
+        #[arg(short, long, default_value = "127.0.0.1")]
+        bind: String,
+
+        /// Feed poll interval in milliseconds
+        #[arg(long, default_value = "500")]
+        interval_ms: u64,
+
+        /// Browser origin allowed to read the stream (repeatable), e.g. http://localhost:3000
+        #[arg(long = "allow-origin")]
+        allow_origins: Vec<String>,
+
+        /// Path to the activity feed file (defaults to activity.jsonl in the data directory)
+        #[arg(short, long)]
+        log_file: Option<String>,
+    },
+
+    /// Apply retention limits, rolling retired events into daily summaries
+    ///
+    /// Limits default to the `[activity]` section of `.ralph-beads.toml`;
+    /// flags override them.
+    Prune {
+        /// Retire events older than this many days
+        #[arg(long)]
+        max_age_days: Option<u64>,
+
+        /// Keep at most this many events (newest first)
+        #[arg(long)]
+        max_events: Option<usize>,
+
+        /// Keep at most this many events per issue (newest first)
+        #[arg(long)]
+        max_per_issue: Option<usize>,
+
+        /// Drop retired events instead of compacting them
+        #[arg(long)]
+        no_compact: bool,
+
+        /// Report what would be retired without rewriting the feed
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Path to the activity feed file (defaults to activity.jsonl in the data directory)
+        #[arg(short, long)]
+        log_file: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// Aggregate events into per-task timelines
+    Report {
+        /// Only include the epic and its child issues
+        #[arg(short, long)]
+        epic: Option<String>,
+
+        /// Only include events newer than this (e.g. 24h, 7d)
+        #[arg(short, long)]
+        since: Option<String>,
+
+        /// Minimum silence reported as an idle gap (e.g. 30m)
+        #[arg(long, default_value = "1h")]
+        idle_threshold: String,
+
+        /// Path to the activity feed file (defaults to activity.jsonl in the data directory)
+        #[arg(short, long)]
+        log_file: Option<String>,
+
+        /// Output format: text, json, or mermaid
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum LintCommands {
+    /// Lint all open issues
+    All {
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Record a gate event per issue in the activity feed (defaults to
+        /// activity.jsonl in the data directory)
+        #[arg(long, value_name = "FEED", num_args = 0..=1)]
+        emit: Option<Option<String>>,
+
+        /// Post the outcome as a comment on each linted issue
+        #[arg(long)]
+        record: bool,
+
+        /// Lint every repo of the [workspace] and aggregate the reports
+        #[arg(long, conflicts_with_all = ["emit", "record"])]
+        workspace: bool,
+    },
+
+    /// Lint a single issue
+    Issue {
+        /// Issue ID
+        id: String,
+
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Record a gate event per issue in the activity feed (defaults to
+        /// activity.jsonl in the data directory)
+        #[arg(long, value_name = "FEED", num_args = 0..=1)]
+        emit: Option<Option<String>>,
+
+        /// Post the outcome as a comment on each linted issue
+        #[arg(long)]
+        record: bool,
+    },
+
+    /// Lint an epic and its child issues
+    Epic {
+        /// Epic ID
+        id: String,
+
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Record a gate event per issue in the activity feed (defaults to
+        /// activity.jsonl in the data directory)
+        #[arg(long, value_name = "FEED", num_args = 0..=1)]
+        emit: Option<Option<String>>,
+
+        /// Post the outcome as a comment on each linted issue
+        #[arg(long)]
+        record: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum BeadsStateCommands {
+    /// Show the current value of a dimension
+    Get {
+        /// Issue ID
+        id: String,
+
+        /// State dimension, e.g. mode or health
+        dimension: String,
+
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// Set a dimension, rejecting values and transitions the schema forbids
+    Set {
+        /// Issue ID
+        id: String,
+
+        /// State dimension, e.g. mode or health
+        dimension: String,
+
+        /// New value
+        value: String,
+
+        /// Reason recorded with the state change
+        #[arg(short, long)]
+        reason: Option<String>,
+
+        /// Skip transition checks (the value must still be valid)
+        #[arg(long)]
+        force: bool,
+
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// Set a dimension on many issues, reporting each result
+    ///
+    /// Exits 0 when all succeed, 7 on partial failure, 1 when all fail.
+    SetBulk {
+        /// State dimension, e.g. mode or health
+        dimension: String,
+
+        /// New value
+        value: String,
+
+        /// Issue IDs (epic IDs with --children)
+        #[arg(required = true)]
+        ids: Vec<String>,
+
+        /// Apply to the children of the given epics instead
+        #[arg(long)]
+        children: bool,
+
+        /// Reason recorded with each state change
+        #[arg(short, long)]
+        reason: Option<String>,
+
+        /// Skip transition checks (the value must still be valid)
+        #[arg(long)]
+        force: bool,
+
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// Poll a dimension, printing each change, until it reaches a value
+    Watch {
+        /// Issue ID
+        id: String,
+
+        /// State dimension to watch
+        #[arg(long, default_value = "mode")]
+        dimension: String,
+
+        /// Stop once the dimension has this value
+        #[arg(short, long)]
+        until: Option<String>,
+
+        /// Give up after this long (e.g. 30s, 10m); exits 4
+        #[arg(short, long)]
+        timeout: Option<String>,
+
+        /// Poll interval in milliseconds
+        #[arg(long, default_value = "2000")]
+        interval_ms: u64,
+
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: text or json (one object per change)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// Show the state machine of one or all dimensions
+    Transitions {
+        /// State dimension (all dimensions when omitted)
+        dimension: Option<String>,
+
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum EpicCommands {
+    /// Create an epic, child tasks, and dependencies from a markdown plan
+    Scaffold {
+        /// Plan file (reads stdin when omitted or "-")
+        #[arg(long)]
+        file: Option<String>,
+
+        /// Print the task graph without creating anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum IssueCommands {
+    /// Render the issue template, lint the draft, and create it unless lint errors
+    Create {
+        /// Issue type (task, bug, feature, epic)
+        #[arg(long = "type", default_value = "task")]
+        issue_type: String,
+
+        /// Issue title
+        #[arg(long)]
+        title: String,
+
+        /// Template name in .ralph-beads/templates or a file path
+        /// (default: issue-<type>.md there, then the built-in skeleton)
+        #[arg(long)]
+        template: Option<String>,
+
+        /// Parent epic ID
+        #[arg(long)]
+        parent: Option<String>,
+
+        /// Priority (0-4)
+        #[arg(long)]
+        priority: Option<u8>,
+
+        /// Opening text of the description
+        #[arg(long)]
+        description: Option<String>,
+
+        /// Acceptance criteria
+        #[arg(long)]
+        acceptance: Option<String>,
+
+        /// Text for a section heading, as NAME=TEXT (repeatable)
+        #[arg(long = "section", value_name = "NAME=TEXT")]
+        sections: Vec<String>,
+
+        /// Render and lint without creating
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum PlanCommands {
+    /// Interactive prompt to add, edit, split, and link tasks, then commit
+    Repl {
+        /// Existing epic to add tasks to (omit to draft a new epic)
+        #[arg(short, long)]
+        epic: Option<String>,
+
+        /// Title of the new epic (or set it with `title` in the session)
+        #[arg(short, long)]
+        title: Option<String>,
+
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum GraphCommands {
+    /// Critical path, parallel waves, bottlenecks, and orphans of an epic
+    Analyze {
+        /// Epic ID
+        #[arg(short, long)]
+        epic: String,
+
+        /// Minimum transitive dependents for a task to count as a bottleneck
+        #[arg(long, default_value = "3")]
+        bottleneck_threshold: usize,
+
+        /// Keep closed issues in the graph
+        #[arg(long)]
+        include_closed: bool,
+
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: text, json (with diagrams), dot, or mermaid
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// Structural checks with restructuring suggestions; exits 3 on cycles
+    Validate {
+        /// Epic ID
+        #[arg(short, long)]
+        epic: String,
+
+        /// Workers available; waves wider than this are flagged
+        #[arg(short, long)]
+        workers: Option<usize>,
+
+        /// Direct blockers above which a task is flagged
+        #[arg(long, default_value = "4")]
+        max_fan_in: usize,
+
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum HooksCommands {
+    /// List configured hooks per lifecycle point
+    List {
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// Run the hooks for a lifecycle point; exits 8 if any hook fails
+    Run {
+        /// Lifecycle point, e.g. post-iteration or on-failure
+        point: String,
+
+        /// JSON object passed to each hook on stdin
+        #[arg(short, long)]
+        payload: Option<String>,
+
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum GhCommands {
+    /// Pull request state with normalized check outcomes
+    PrStatus {
+        /// PR number, URL, or branch
+        pr: String,
+
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// Workflow run state: success, failure, cancelled, or pending
+    RunStatus {
+        /// Workflow run ID
+        run_id: String,
+
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// Open a pull request and print its URL
+    CreatePr {
+        /// PR title
+        #[arg(short, long)]
+        title: String,
+
+        /// PR body
+        #[arg(short, long, default_value = "")]
+        body: String,
+
+        /// Base branch (defaults to the repository default)
+        #[arg(long)]
+        base: Option<String>,
+
+        /// Head branch (defaults to the current branch)
+        #[arg(long)]
+        head: Option<String>,
+
+        /// Open as a draft
+        #[arg(long)]
+        draft: bool,
+
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum MetricsCommands {
+    /// Record a counter increment or histogram observation
+    Record {
+        /// Metric name, e.g. iterations_total or gate_wait_seconds
+        name: String,
+
+        /// Increment (counters) or observed value (histograms)
+        #[arg(default_value = "1")]
+        value: f64,
+
+        /// Label as key=value (repeatable)
+        #[arg(long = "label")]
+        labels: Vec<String>,
+
+        /// Path to the metrics file (defaults to metrics.jsonl in the data directory)
+        #[arg(short, long)]
+        metrics_file: Option<String>,
+    },
+
+    /// Summarize recorded metrics
+    Show {
+        /// Only show this metric
+        #[arg(short, long)]
+        name: Option<String>,
+
+        /// Only include samples newer than this (e.g. 24h, 7d)
+        #[arg(short, long)]
+        since: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Path to the metrics file (defaults to metrics.jsonl in the data directory)
+        #[arg(short, long)]
+        metrics_file: Option<String>,
+    },
+
+    /// Export all metrics in the Prometheus text format
+    Export {
+        /// Export format (only prometheus is supported)
+        #[arg(short, long, default_value = "prometheus")]
+        format: String,
+
+        /// Path to the metrics file (defaults to metrics.jsonl in the data directory)
+        #[arg(short, long)]
+        metrics_file: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum StateCommands {
+    /// Start a new session with a fresh run ID
+    New {
+        /// Starting mode: planning or building
+        #[arg(short, long, default_value = "planning")]
+        mode: String,
+
+        /// Why the session is starting
+        #[arg(short, long)]
+        reason: Option<String>,
+
+        /// Replace a session that hasn't completed
+        #[arg(long)]
+        force: bool,
+
+        /// Output format: text, json, or env (an `export` line for the shell)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Path to the session state file (defaults to session.json in the data directory)
+        #[arg(short, long)]
+        session_file: Option<String>,
+    },
+
+    /// Show the session's mode and transition history
+    Show {
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Path to the session state file (defaults to session.json in the data directory)
+        #[arg(short, long)]
+        session_file: Option<String>,
+    },
+
+    /// Move the session to another mode, rejecting illegal transitions
+    Transition {
+        /// Target mode: planning, building, paused, or complete
+        #[arg(long)]
+        to: String,
+
+        /// Why the mode is changing
+        #[arg(short, long)]
+        reason: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Path to the session state file (defaults to session.json in the data directory)
+        #[arg(short, long)]
+        session_file: Option<String>,
+    },
+
+    /// Link the plan document and approval gate the session builds from
+    ///
+    /// Once linked, the session (and any session that replaces it) cannot
+    /// enter building mode until the approval gate bead is closed.
+    Link {
+        /// Bead holding the plan document (usually the epic)
+        #[arg(long)]
+        plan: String,
+
+        /// Gate bead that must be closed before building starts
+        #[arg(long)]
+        approval_gate: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Path to the session state file (defaults to session.json in the data directory)
+        #[arg(short, long)]
+        session_file: Option<String>,
+    },
+
+    /// Record loop iterations, refusing to overwrite a newer session
+    Update {
+        /// Set iteration_count (it never goes down)
+        #[arg(long, conflicts_with = "add_iterations")]
+        iterations: Option<u32>,
+
+        /// Add to iteration_count
+        #[arg(long)]
+        add_iterations: Option<u32>,
+
+        /// Revision the change was based on (from `state show`); exits 9 if
+        /// the file has moved on
+        #[arg(long)]
+        expect_revision: Option<u64>,
+
+        /// On a revision mismatch, merge into the newer session instead of
+        /// failing (iteration_count keeps the higher value)
+        #[arg(long)]
+        retry: bool,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Path to the session state file (defaults to session.json in the data directory)
+        #[arg(short, long, visible_alias = "file")]
+        session_file: Option<String>,
+    },
+
+    /// Whether the loop should run another iteration; exits 3 when it should stop
+    ShouldContinue {
+        /// Issue being worked on, for failures and open gates (defaults to
+        /// the session's run on the activity feed, without gates)
+        #[arg(short, long)]
+        issue: Option<String>,
+
+        /// Iteration limit for the session
+        #[arg(long)]
+        max_iterations: Option<u32>,
+
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Path to the session state file (defaults to session.json in the data directory)
+        #[arg(long)]
+        session_file: Option<String>,
+
+        /// Path to the activity feed file (defaults to activity.jsonl in the data directory)
+        #[arg(long)]
+        log_file: Option<String>,
+
+        /// Path to the cost ledger (defaults to costs.jsonl in the data directory)
+        #[arg(long)]
+        cost_file: Option<String>,
+    },
+
+    /// Ask the loop to stop at its next check (or withdraw the request)
+    Stop {
+        /// Why the loop should stop
+        #[arg(short, long)]
+        reason: Option<String>,
+
+        /// Withdraw an earlier stop request
+        #[arg(long)]
+        clear: bool,
+
+        /// Path to the session state file (defaults to session.json in the data directory)
+        #[arg(long)]
+        session_file: Option<String>,
+    },
+
+    /// Upgrade saved session files to the current schema version
+    Migrate {
+        /// Session file to upgrade (repeatable; defaults to session.json in
+        /// the data directory)
+        #[arg(long = "file", value_name = "FILE")]
+        files: Vec<String>,
+
+        /// Report what would change without rewriting any file
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum WorkspaceCommands {
+    /// List the repos of the [workspace] section
+    List {
+        /// Directory to find the workspace config from (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum LimitsCommands {
+    /// Show running subprocesses and spawns in the last minute against the limits
+    Status {
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Validate every section and show effective settings with their source (exits 3 if invalid)
+    Check {
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ContextCommands {
+    /// Render an issue's context (body, state, gates, activity) as markdown
+    Compile {
+        /// Issue ID
+        #[arg(short, long)]
+        issue: String,
+
+        /// Template name (built-in: builder, planner) or path to a template file
+        #[arg(short, long, default_value = "builder")]
+        template: String,
+
+        /// Number of recent activity events to include
+        #[arg(long, default_value = "10")]
+        activity_limit: usize,
+
+        /// Drop the oldest activity and gate results until the context fits this many tokens
+        #[arg(long)]
+        max_tokens: Option<usize>,
+
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Path to the activity feed file (defaults to activity.jsonl in the data directory)
+        #[arg(short, long)]
+        log_file: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum CostCommands {
+    /// Append token usage for a model call to the cost ledger
+    Record {
+        /// Session (loop run) the call belongs to
+        #[arg(long)]
+        session: String,
+
+        /// Input tokens
+        #[arg(long)]
+        tokens_in: u64,
+
+        /// Output tokens
+        #[arg(long)]
+        tokens_out: u64,
+
+        /// Model name, priced via [cost.models]
+        #[arg(long)]
+        model: String,
+
+        /// Issue the call was made for
+        #[arg(long)]
+        issue: Option<String>,
+
+        /// Worker that made the call
+        #[arg(long)]
+        worker: Option<String>,
+
+        /// Explicit cost in USD (overrides the configured price)
+        #[arg(long)]
+        cost_usd: Option<f64>,
+
+        /// Project directory (for configuration)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Path to the cost ledger (defaults to costs.jsonl in the data directory)
+        #[arg(short, long)]
+        cost_file: Option<String>,
+    },
+
+    /// Aggregate costs per task, worker, and model
+    Report {
+        /// Only include an epic and its children
+        #[arg(short, long)]
+        epic: Option<String>,
+
+        /// Only include one session
+        #[arg(long)]
+        session: Option<String>,
+
+        /// Only include entries newer than this (e.g. 24h, 7d)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Path to the cost ledger (defaults to costs.jsonl in the data directory)
+        #[arg(short, long)]
+        cost_file: Option<String>,
+    },
+
+    /// Check spend against the budget; exits 3 when it is exceeded
+    Check {
+        /// Only count an epic and its children
+        #[arg(short, long)]
+        epic: Option<String>,
+
+        /// Only count one session
+        #[arg(long)]
+        session: Option<String>,
+
+        /// Budget in USD (default: cost.max_cost_usd from configuration)
+        #[arg(long)]
+        max_cost: Option<f64>,
+
+        /// Project directory (for configuration)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Path to the cost ledger (defaults to costs.jsonl in the data directory)
+        #[arg(short, long)]
+        cost_file: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ReportCommands {
+    /// Status document: progress, task states, blockers, open gates, failures, ETA
+    Status {
+        /// Epic ID
+        #[arg(short, long)]
+        epic: String,
+
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Number of recent failures to include
+        #[arg(long, default_value_t = 5)]
+        failures: usize,
+
+        /// Path to the activity feed file (defaults to activity.jsonl in the data directory)
+        #[arg(short, long)]
+        log_file: Option<String>,
+
+        /// Path to the metrics log (defaults to metrics.jsonl in the data directory)
+        #[arg(short, long)]
+        metrics_file: Option<String>,
+
+        /// Output format: md, html, or json
+        #[arg(short, long, default_value = "md")]
+        format: String,
+    },
+
+    /// Completions per day, task durations, projected finish, and per-worker throughput
+    Velocity {
+        /// Epic ID
+        #[arg(short, long)]
+        epic: String,
+
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Days of completions to average over
+        #[arg(long, default_value_t = 14)]
+        days: u64,
+
+        /// Path to the activity feed file (defaults to activity.jsonl in the data directory)
+        #[arg(short, long)]
+        log_file: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum DiffCommands {
+    /// Files, hunks, lines by language, renames, and risky paths of a diff
+    Summarize {
+        /// Revision or range to diff against (e.g. main...HEAD; default HEAD)
+        #[arg(short, long)]
+        base: Option<String>,
+
+        /// Only staged changes
+        #[arg(long)]
+        staged: bool,
+
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum TaskCommands {
+    /// Bundle a task with its epic, blocker outputs, and test command
+    Context {
+        /// Task ID
+        id: String,
+
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: md or json
+        #[arg(short, long, default_value = "md")]
+        format: String,
+    },
+
+    /// Same as `swarm split`
+    Split {
+        /// Task ID
+        id: String,
+
+        /// Number of parts
+        #[arg(long)]
+        into: usize,
+
+        /// Skip the shared setup task the parts depend on
+        #[arg(long)]
+        no_setup: bool,
+
+        /// Lint and print the parts without creating anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum SwarmCommands {
+    /// Split an oversized task into parts under its epic by its acceptance criteria
+    Split {
+        /// Task ID
+        id: String,
+
+        /// Number of parts
+        #[arg(long)]
+        into: usize,
+
+        /// Skip the shared setup task the parts depend on
+        #[arg(long)]
+        no_setup: bool,
+
+        /// Lint and print the parts without creating anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Project directory (defaults to current)
+        #[arg(short, long)]
+        dir: Option<String>,
+
+        /// Output format: text or json
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+}
+
+/// An error message with the exit code it is reported under
+struct Failure {
+    code: ExitCode,
+    message: String,
+}
+
+impl From<&str> for Failure {
+    fn from(message: &str) -> Self {
+        Failure {
+            code: ExitCode::Error,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<&String> for Failure {
+    fn from(message: &String) -> Self {
+        Failure::from(message.as_str())
+    }
+}
+
+impl From<&BdError> for Failure {
+    fn from(e: &BdError) -> Self {
+        Failure {
+            code: ExitCode::from(e),
+            message: e.to_string(),
+        }
+    }
+}
+
+/// Print an error and exit, reporting bd outages and timeouts by their own code
+fn fail(error: impl Into<Failure>) -> ! {
+    let failure = error.into();
+    fail_with(failure.code, &failure.message);
+}
+
+/// Print an error and exit with `code`
+fn fail_with(code: ExitCode, message: &str) -> ! {
+    eprintln!("Error: {}", message);
+    tracing::error!(target: OUTCOME_TARGET, error = message, code = code.code(), "command failed");
+    std::process::exit(code.code());
+}
+
+/// Report a failed session update by its class
+fn fail_update(e: UpdateError) -> ! {
+    let code = match e {
+        UpdateError::Conflict { .. } => ExitCode::Conflict,
+        UpdateError::Invalid(_) => ExitCode::StateInvalid,
+        UpdateError::Io(_) => ExitCode::Error,
+    };
+    fail_with(code, &e.to_string());
+}
+
+/// Refuse to enter `to` while the session's approval gate is still open
+fn check_approval(session: &SessionState, to: WorkflowMode) {
+    let Some(gate) = session.approval_required(to) else {
+        return;
+    };
+    let issue = show_issue(Path::new("."), gate).unwrap_or_else(|e| fail(&e));
+    if !issue.is_closed() {
+        fail_with(
+            ExitCode::Blocked,
+            &format!(
+                "Approval gate {} is {}; building waits until it is closed",
+                gate, issue.status
+            ),
+        );
+    }
+}
+
+/// Exit with `code`, recording the outcome in the log
+fn exit(code: ExitCode) -> ! {
+    exit_raw(code.code());
+}
+
+/// Exit with a code from outside the catalogue (a plugin's status)
+fn exit_raw(code: i32) -> ! {
+    tracing::info!(target: OUTCOME_TARGET, code, "command exited");
+    std::process::exit(code);
+}
+
+/// Parse an event kind argument, exiting on invalid input
+fn parse_kind(kind: &str) -> EventKind {
+    kind.parse::<EventKind>().unwrap_or_else(|e| fail(&e))
+}
+
+/// Print a lint report and exit non-zero if it contains errors
+fn output_lint_report(report: &LintReport, format: &str) {
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(report).unwrap());
+    } else {
+        println!("Config: {}", report.config);
+        for finding in &report.findings {
+            println!(
+                "{} [{}] {}: {}",
+                finding.severity, finding.rule, finding.issue_id, finding.message
+            );
+            if let Some(ref suggestion) = finding.suggestion {
+                println!("    fix: {}", suggestion);
+            }
+        }
+        println!(
+            "\n{} issues checked: {} errors, {} warnings",
+            report.issues_checked, report.errors, report.warnings
+        );
+    }
+
+    if report.has_errors() {
+        exit(ExitCode::Blocked);
+    }
+}
+
+/// Print a workspace lint report; lint errors block, unlintable repos are partial
+fn output_workspace_lint(lint: &WorkspaceLint, format: &str) {
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(lint).unwrap());
+    } else {
+        for repo in &lint.repos {
+            println!("== {} ({})", repo.repo, repo.path.display());
+            if let Some(ref error) = repo.error {
+                println!("  failed: {}", error);
+            }
+            for finding in repo.report.iter().flat_map(|r| &r.findings) {
+                println!(
+                    "  {} [{}] {}: {}",
+                    finding.severity, finding.rule, finding.issue_id, finding.message
+                );
+            }
+        }
+        println!(
+            "\n{} repos, {} issues checked: {} errors, {} warnings",
+            lint.repos.len(),
+            lint.issues_checked,
+            lint.errors,
+            lint.warnings
+        );
+        if lint.failed > 0 {
+            println!("{} repos could not be linted", lint.failed);
+        }
+    }
+
+    if lint.errors > 0 {
+        exit(ExitCode::Blocked);
+    }
+    if lint.failed > 0 {
+        exit(ExitCode::Partial);
+    }
+}
+
+/// State schema with the dimensions configured for `dir`
+fn load_state_schema(dir: &Path) -> StateSchema {
+    let loaded = load_config(dir).unwrap_or_else(|e| fail(&e));
+    StateSchema::with_config(&loaded.config.state)
+}
+
+/// The epic `id` summarized with its open and closed children
+fn epic_summary(dir: &Path, id: &str) -> Result<bundle::EpicSummary, String> {
+    let epic = show_issue(dir, id)?;
+    let children = epic_tasks(dir, &epic.id)?;
+    Ok(bundle::EpicSummary::new(&epic, &children))
+}
+
+/// Children of epic `id`, open and closed
+fn epic_tasks(dir: &Path, id: &str) -> Result<Vec<Issue>, String> {
+    let mut children = Vec::new();
+    for status in [None, Some("closed".to_string())] {
+        let filter = ListFilter {
+            parent: Some(id.to_string()),
+            status,
+            ..Default::default()
+        };
+        children.extend(list_issues(dir, &filter)?);
+    }
+    Ok(children)
+}
+
+/// `path` when given, else the data directory's `default`
+fn path_or(path: Option<String>, default: PathBuf) -> PathBuf {
+    path.map(PathBuf::from).unwrap_or(default)
+}
+
+/// Everything `info --paths` lists: the data directories plus the
+/// project's config file, templates, and worktree registry
+fn path_locations(dir: &Path, dirs: &DataDirs) -> Vec<data_dirs::Location> {
+    let absolute = |path: PathBuf| std::path::absolute(&path).unwrap_or(path);
+    let mut locations = dirs.locations();
+    for location in &mut locations {
+        // RALPH_BEADS_LOCK_DIR moves the locks out of the runtime directory
+        if location.name == "locks" {
+            location.path = limits::lock_dir();
+        }
+        location.path = absolute(std::mem::take(&mut location.path));
+    }
+    let config = load_config(dir)
+        .ok()
+        .and_then(|loaded| loaded.source)
+        .unwrap_or_else(|| dir.join(CONFIG_FILE));
+    locations.push(data_dirs::Location {
+        name: "config",
+        path: absolute(config),
+        purpose: "project configuration",
+    });
+    locations.push(data_dirs::Location {
+        name: "templates",
+        path: absolute(dir.join(context::TEMPLATE_DIR)),
+        purpose: "context templates",
+    });
+    if let Ok(registry) = registry_path(dir) {
+        locations.push(data_dirs::Location {
+            name: "worktrees",
+            path: absolute(registry),
+            purpose: "issue worktree registry",
+        });
+    }
+    locations
+}
+
+/// What `info` reports about the project in `dir`: the config file in
+/// effect, configured hooks, state dimensions, templates, and plugins
+fn project_descriptor(dir: &Path) -> serde_json::Value {
+    // A broken config shouldn't hide the rest of the descriptor
+    let (loaded, config_error) = match load_config(dir) {
+        Ok(loaded) => (loaded, None),
+        Err(e) => (Default::default(), Some(e)),
+    };
+    let hooks: serde_json::Map<String, serde_json::Value> = HookPoint::ALL
+        .iter()
+        .filter_map(|point| {
+            let count = loaded.config.hooks.commands(*point).len();
+            (count > 0).then(|| (point.to_string(), json!(count)))
+        })
+        .collect();
+    let schema = StateSchema::with_config(&loaded.config.state);
+    json!({
+        "config_file": loaded.source,
+        "config_error": config_error,
+        "hooks": hooks,
+        "state_dimensions": schema.dimensions.keys().collect::<Vec<_>>(),
+        "templates": context::available_templates(dir),
+        "plugins": plugins::installed_plugins()
+            .into_iter()
+            .map(|p| p.name)
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// Lint `issues` with the configuration that applies to `dir`, optionally
+/// recording the results in the activity feed and as issue comments
+fn run_lint(
+    dir: &Path,
+    issues: &[Issue],
+    format: &str,
+    emit: Option<Option<String>>,
+    record: bool,
+) {
+    let loaded = load_config(dir).unwrap_or_else(|e| fail(&e));
+    let related = lint::gather_related(dir, issues).unwrap_or_else(|e| fail(&e));
+    let report = lint::build_report(
+        issues,
+        &related,
+        &loaded.config.lint,
+        &loaded.source_description(),
+        now_secs(),
+    );
+    if let Some(feed) = emit {
+        let feed = path_or(feed, data_dirs::dirs().feed_file());
+        let events = lint::record_activity(&feed, &report, issues).unwrap_or_else(|e| fail(&e));
+        auto_prune_feed(&feed, &loaded.config.activity);
+        for event in &events {
+            let payload = serde_json::to_value(event).unwrap();
+            fire_hooks(
+                dir,
+                &loaded.config.hooks,
+                HookPoint::OnGateCreated,
+                &payload,
+            );
+        }
+    }
+    if record {
+        lint::record_comments(dir, &report, issues).unwrap_or_else(|e| fail(&e));
+    }
+    output_lint_report(&report, format);
+}
+
+/// Run the hooks a command fires at `point`; failures only warn, since
+/// they don't change the command's outcome
+fn fire_hooks(dir: &Path, config: &HooksConfig, point: HookPoint, payload: &serde_json::Value) {
+    let results = match run_hooks(dir, config, point, payload) {
+        Ok(results) => results,
+        Err(e) => return tracing::warn!("not running {} hooks: {}", point, e),
+    };
+    for result in results.iter().filter(|r| !r.success) {
+        let status = match (result.exit_code, &result.error) {
+            (Some(code), _) => format!("exit {}", code),
+            (None, error) => error.clone().unwrap_or_default(),
+        };
+        tracing::warn!("{} hook failed [{}]: {}", point, status, result.command);
+    }
+}
 
-    /// Calculate max iterations based on mode and complexity
-    CalcIterations {
-        /// Workflow mode: planning or building
-        #[arg(short, long)]
-        mode: String,
+/// Prune `feed` once it outgrows `[activity] auto_prune_bytes`; failures
+/// only warn, since the event itself was already recorded
+fn auto_prune_feed(feed: &Path, config: &ActivityConfig) {
+    if let Err(e) = retention::auto_prune(feed, config, now_secs()) {
+        tracing::warn!("not pruning {}: {}", feed.display(), e);
+    }
+}
 
-        /// Complexity level: trivial, simple, standard, critical
-        #[arg(short, long)]
-        complexity: String,
+/// `swarm split` (and `task split`): divide a task into parts by its
+/// acceptance criteria
+fn split_task(id: &str, into: usize, no_setup: bool, dry_run: bool, dir: &Path, format: &str) {
+    let task = show_issue(dir, id).unwrap_or_else(|e| fail(&e));
+    let Some(epic) = task.parent().map(str::to_string) else {
+        fail(&format!("{} has no parent epic to split under", id));
+    };
+    let plan = split::split_plan(&task, into, !no_setup).unwrap_or_else(|e| fail(&e));
 
-        /// Output format: text or json
-        #[arg(short, long, default_value = "text")]
-        format: String,
-    },
+    // The parts are linted among the open issues, without the task they replace
+    let loaded = load_config(dir).unwrap_or_else(|e| fail(&e));
+    let drafts = split::draft_ids(&plan);
+    let mut issues = split::drafts(&plan, &epic);
+    issues.extend(
+        list_issues(dir, &ListFilter::default())
+            .unwrap_or_else(|e| fail(&e))
+            .into_iter()
+            .filter(|i| i.id != task.id),
+    );
+    let mut report = lint::build_report(
+        &issues,
+        &lint::Related::default(),
+        &loaded.config.lint,
+        &loaded.source_description(),
+        now_secs(),
+    )
+    .for_issues(&drafts);
 
-    /// Output information about CLI capabilities
-    Info {
-        /// Output format: text or json
-        #[arg(short, long, default_value = "text")]
-        format: String,
-    },
+    let created = !dry_run && !report.has_errors();
+    let labels = if created {
+        let result =
+            split::apply_split(dir, &task.id, &plan, &epic, !no_setup).unwrap_or_else(|e| fail(&e));
+        for finding in &mut report.findings {
+            if let Some(i) = drafts.iter().position(|d| *d == finding.issue_id) {
+                finding.issue_id = result.task_ids[i].clone();
+            }
+        }
+        result.task_ids
+    } else {
+        drafts
+    };
+
+    if format == "json" {
+        let tasks: Vec<_> = plan
+            .tasks
+            .iter()
+            .zip(&labels)
+            .map(|(part, label)| {
+                let deps: Vec<&String> = part.depends_on.iter().map(|&d| &labels[d]).collect();
+                json!({
+                    "id": label,
+                    "title": part.title,
+                    "acceptance_criteria": part.acceptance_criteria,
+                    "depends_on": deps
+                })
+            })
+            .collect();
+        let out = json!({
+            "created": created,
+            "task": task.id,
+            "epic": epic,
+            "tasks": tasks,
+            "lint": report
+        });
+        println!("{}", serde_json::to_string_pretty(&out).unwrap());
+    } else {
+        match (created, report.has_errors()) {
+            (true, _) => println!("Split {} into:", task.id),
+            (false, true) => println!("Not split: the parts of {} fail lint", task.id),
+            (false, false) => println!("Draft split of {}:", task.id),
+        }
+        print!("{}", render_graph(&plan, &epic, &labels));
+        for finding in &report.findings {
+            println!(
+                "{} [{}] {}",
+                finding.severity, finding.rule, finding.message
+            );
+        }
+    }
+    if report.has_errors() {
+        exit(ExitCode::Blocked);
+    }
 }
 
 /// Helper function to output a key-value result in the specified format
@@ -81,13 +2138,50 @@ fn output_result(format: &str, key: &str, value: &str) {
     }
 }
 
+/// `--help` footer listing installed plugins
+fn plugins_help() -> Option<String> {
+    let plugins = plugins::installed_plugins();
+    if plugins.is_empty() {
+        return None;
+    }
+    let lines: Vec<String> = plugins
+        .iter()
+        .map(|p| format!("  {:<18} {}", p.name, p.path.display()))
+        .collect();
+    Some(format!("Plugins:\n{}", lines.join("\n")))
+}
+
 fn main() {
-    let cli = Cli::parse();
+    let mut command = Cli::command();
+    if let Some(help) = plugins_help() {
+        command = command.after_help(help);
+    }
+    let matches = command.get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    logging::init(cli.verbose, cli.diag_log.as_deref().map(Path::new)).unwrap_or_else(|e| fail(&e));
+    data_dirs::configure(DataDirs::resolve(cli.data_dir.as_deref().map(Path::new)));
+    let dirs = data_dirs::dirs();
+    let config = load_config(Path::new("."))
+        .map(|loaded| loaded.config)
+        .unwrap_or_default();
+    let mut bd_config = config.bd;
+    if let Some(timeout) = cli.bd_timeout {
+        bd_config.timeout_secs = timeout;
+    }
+    beads::configure(bd_config);
+    limits::configure(config.limits);
+    if let Some(ref dir) = cli.record_fixtures {
+        fixtures::record_to(Path::new(dir)).unwrap_or_else(|e| fail(&e));
+    }
+    let command = matches.subcommand_name().unwrap_or_default();
+    let _span = tracing::info_span!("command", subcommand = command).entered();
 
     match cli.command {
-        Commands::DetectComplexity { task, format } => {
-            let complexity = detect_complexity(&task);
-            output_result(&format, "complexity", &complexity.to_string());
+        Commands::DetectComplexity { task, format, dir } => {
+            let directory = dir.unwrap_or_else(|| ".".to_string());
+            let loaded = load_config(Path::new(&directory)).unwrap_or_else(|e| fail(&e));
+            let complexity = loaded.config.complexity.detect(&task);
+            output_result(&format, "complexity", &complexity);
         }
 
         Commands::DetectFramework { dir, format } => {
@@ -109,28 +2203,2433 @@ fn main() {
             mode,
             complexity,
             format,
+            explain,
+            dir,
         } => {
             let wf_mode = mode
                 .parse::<WorkflowMode>()
                 .unwrap_or(WorkflowMode::Building);
-            let cx = complexity
-                .parse::<Complexity>()
-                .unwrap_or(Complexity::Standard);
-            let iterations = calculate_max_iterations(&wf_mode, &cx);
-            output_result(&format, "max_iterations", &iterations.to_string());
+            let directory = dir.unwrap_or_else(|| ".".to_string());
+            let loaded = load_config(Path::new(&directory)).unwrap_or_else(|e| fail(&e));
+            let table = &loaded.config.iterations;
+            let taxonomy = &loaded.config.complexity;
+            if taxonomy.is_custom() {
+                let explanation = taxonomy
+                    .explain(table, &wf_mode, &complexity)
+                    .unwrap_or_else(|e| fail_with(ExitCode::Usage, &e));
+                if !explain {
+                    output_result(
+                        &format,
+                        "max_iterations",
+                        &explanation.max_iterations.to_string(),
+                    );
+                } else if format == "json" {
+                    let mut value = serde_json::to_value(&explanation).unwrap();
+                    value["config"] = json!(loaded.source_description());
+                    println!("{}", serde_json::to_string_pretty(&value).unwrap());
+                } else {
+                    println!("max_iterations={}", explanation.max_iterations);
+                    println!("config: {}", loaded.source_description());
+                    println!(
+                        "level: {}  multiplier={} x {}.standard={}",
+                        explanation.level, explanation.multiplier, wf_mode, explanation.base
+                    );
+                }
+            } else {
+                let cx = complexity
+                    .parse::<Complexity>()
+                    .unwrap_or(Complexity::Standard);
+                if !explain {
+                    let iterations = table.max_iterations(&wf_mode, &cx);
+                    output_result(&format, "max_iterations", &iterations.to_string());
+                } else {
+                    let explanation = table.explain(&wf_mode, &cx);
+                    if format == "json" {
+                        let mut value = serde_json::to_value(&explanation).unwrap();
+                        value["config"] = json!(loaded.source_description());
+                        println!("{}", serde_json::to_string_pretty(&value).unwrap());
+                    } else {
+                        println!("max_iterations={}", explanation.max_iterations);
+                        println!("config: {}", loaded.source_description());
+                        println!(
+                            "row: {}  planning={}  building={}  (using {})",
+                            cx, explanation.row.0, explanation.row.1, wf_mode
+                        );
+                        if explanation.max_iterations != explanation.default {
+                            println!("built-in value: {}", explanation.default);
+                        }
+                        if explanation.overrides.is_empty() {
+                            println!("overrides: none");
+                        } else {
+                            println!("overrides:");
+                            for o in &explanation.overrides {
+                                println!("  {} = {} (built-in {})", o.cell, o.value, o.default);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Commands::Worktree { command } => match command {
+            WorktreeCommands::List { dir, format } => {
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let worktrees =
+                    list_issue_worktrees(Path::new(&directory)).unwrap_or_else(|e| fail(&e));
+
+                if format == "json" {
+                    println!("{}", serde_json::to_string_pretty(&worktrees).unwrap());
+                } else {
+                    println!("{:<50} {:<40} ISSUE", "PATH", "BRANCH");
+                    for wt in &worktrees {
+                        println!(
+                            "{:<50} {:<40} {}",
+                            wt.worktree.path,
+                            wt.worktree.branch.as_deref().unwrap_or("(detached)"),
+                            wt.issue.as_deref().unwrap_or("-")
+                        );
+                    }
+                }
+            }
+
+            WorktreeCommands::Info { dir, format } => {
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let dir = Path::new(&directory);
+                let config = load_config(dir)
+                    .unwrap_or_else(|e| fail(&e))
+                    .config
+                    .worktree;
+                let current = current_worktree(dir).unwrap_or_else(|e| fail(&e));
+                let cache =
+                    build_cache::cache_info(dir, &config.cache).unwrap_or_else(|e| fail(&e));
+
+                match format.as_str() {
+                    "json" => {
+                        let result = json!({
+                            "root": current.root,
+                            "branch": current.branch,
+                            "issue": current.context.as_ref().map(|c| &c.issue),
+                            "cache": cache,
+                        });
+                        println!("{}", serde_json::to_string_pretty(&result).unwrap());
+                    }
+                    "env" => {
+                        for line in cache.iter().flat_map(|c| c.exports()) {
+                            println!("{}", line);
+                        }
+                    }
+                    _ => {
+                        println!("Worktree: {}", current.root.display());
+                        println!(
+                            "Branch:   {}",
+                            current.branch.as_deref().unwrap_or("(detached)")
+                        );
+                        println!(
+                            "Issue:    {}",
+                            current.context.as_ref().map_or("(none)", |c| &c.issue)
+                        );
+                        match cache {
+                            Some(cache) => {
+                                println!("Cache:    {} in {}", cache.mode, cache.root.display());
+                                for (key, value) in &cache.env {
+                                    println!("  {}={}", key, value);
+                                }
+                            }
+                            None => println!("Cache:    off"),
+                        }
+                    }
+                }
+            }
+
+            WorktreeCommands::Guard {
+                issue,
+                allow_unregistered,
+                dir,
+                format,
+            } => {
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let dir = Path::new(&directory);
+                let current = current_worktree(dir).unwrap_or_else(|e| fail(&e));
+                let registry = registry_path(dir)
+                    .and_then(|path| read_registry(&path))
+                    .unwrap_or_else(|e| fail(&e));
+                let guard = guard_issue(&current, &registry, &issue, allow_unregistered);
+
+                if format == "json" {
+                    println!("{}", serde_json::to_string_pretty(&guard).unwrap());
+                    if !guard.allowed {
+                        exit(ExitCode::Blocked);
+                    }
+                } else if guard.allowed {
+                    println!("{}", guard.detail);
+                } else {
+                    fail_with(ExitCode::Blocked, &guard.detail);
+                }
+            }
+
+            WorktreeCommands::CreateForIssue {
+                id,
+                dir,
+                base,
+                root,
+                no_bootstrap,
+                format,
+            } => {
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let dir = Path::new(&directory);
+                let config = load_config(dir)
+                    .unwrap_or_else(|e| fail(&e))
+                    .config
+                    .worktree;
+                let recipe = if no_bootstrap {
+                    BootstrapConfig::default()
+                } else {
+                    config.bootstrap
+                };
+                let cache =
+                    build_cache::cache_info(dir, &config.cache).unwrap_or_else(|e| fail(&e));
+                if let Some(ref cache) = cache {
+                    cache.prepare().unwrap_or_else(|e| fail(&e));
+                }
+                let cache_env = cache.as_ref().map(|c| c.env.clone()).unwrap_or_default();
+                let issue = show_issue(dir, &id).unwrap_or_else(|e| fail(&e));
+                let (path, context) = create_issue_worktree(
+                    dir,
+                    &issue.id,
+                    &issue.title,
+                    &base,
+                    root.as_deref().map(Path::new),
+                )
+                .unwrap_or_else(|e| fail(&e));
+
+                let report = if recipe.is_empty() {
+                    None
+                } else {
+                    let root = repo_root(dir).unwrap_or_else(|e| fail(&e));
+                    let vars = TemplateVars::new(&context, &path, &root);
+                    Some(bootstrap::bootstrap(
+                        &recipe, &vars, &cache_env, &root, &path,
+                    ))
+                };
+                if let Some(failure) = report.as_ref().and_then(|r| r.failure()) {
+                    let rollback = remove_issue_worktree(dir, &path, &context.branch);
+                    if format == "json" {
+                        let result = json!({
+                            "path": path,
+                            "context": context,
+                            "bootstrap": report,
+                            "cache": cache,
+                            "rolled_back": rollback.is_ok(),
+                        });
+                        println!("{}", serde_json::to_string_pretty(&result).unwrap());
+                    }
+                    let error = failure.error.as_deref().unwrap_or("failed");
+                    match rollback {
+                        Ok(()) => fail(&format!(
+                            "Bootstrap {} `{}` failed, removed {}: {}",
+                            failure.kind,
+                            failure.target,
+                            path.display(),
+                            error
+                        )),
+                        Err(e) => fail(&format!(
+                            "Bootstrap {} `{}` failed ({}), and removing {} failed too: {}",
+                            failure.kind,
+                            failure.target,
+                            error,
+                            path.display(),
+                            e
+                        )),
+                    }
+                }
+                beads::update_status(dir, &issue.id, "in_progress").unwrap_or_else(|e| fail(&e));
+
+                if format == "json" {
+                    let result = json!({
+                        "path": path,
+                        "context": context,
+                        "bootstrap": report,
+                        "cache": cache,
+                    });
+                    println!("{}", serde_json::to_string_pretty(&result).unwrap());
+                } else {
+                    println!("Created {} on {}", path.display(), context.branch);
+                    if let Some(ref cache) = cache {
+                        println!("  cache    {} in {}", cache.mode, cache.root.display());
+                    }
+                    if let Some(ref report) = report {
+                        for step in &report.steps {
+                            println!("  {:<8} {}", step.kind, step.target);
+                        }
+                    }
+                    println!("{} is now in_progress", issue.id);
+                }
+            }
+
+            WorktreeCommands::Conflicts { dir, base, format } => {
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let pairs = forecast_worktree_conflicts(Path::new(&directory), &base)
+                    .unwrap_or_else(|e| fail(&e));
+
+                if format == "json" {
+                    let result = json!({
+                        "base": base,
+                        "conflicts": pairs,
+                        "serialize": pairs
+                            .iter()
+                            .filter(|p| p.risk == ConflictRisk::High)
+                            .filter_map(|p| Some([p.task_a.clone()?, p.task_b.clone()?]))
+                            .collect::<Vec<_>>()
+                    });
+                    println!("{}", serde_json::to_string_pretty(&result).unwrap());
+                } else if pairs.is_empty() {
+                    println!("No overlapping changes between worktrees");
+                } else {
+                    for pair in &pairs {
+                        let risk = match pair.risk {
+                            ConflictRisk::High => "HIGH",
+                            ConflictRisk::Low => "low",
+                        };
+                        println!("[{}] {} <-> {}", risk, pair.a, pair.b);
+                        for file in &pair.files {
+                            if file.overlapping_ranges.is_empty() {
+                                println!("  - {}", file.path);
+                            } else {
+                                let ranges: Vec<String> = file
+                                    .overlapping_ranges
+                                    .iter()
+                                    .map(|(start, end)| format!("{}-{}", start, end))
+                                    .collect();
+                                println!("  - {} (lines {})", file.path, ranges.join(", "));
+                            }
+                        }
+                    }
+                }
+            }
+
+            WorktreeCommands::Report {
+                dir,
+                base,
+                sort,
+                dirty,
+                older_than,
+                no_main,
+                log_file,
+                format,
+            } => {
+                let log_file = path_or(log_file, dirs.feed_file());
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let sort = ReportSort::parse(&sort).unwrap_or_else(|e| fail(&e));
+                let filter = ReportFilter {
+                    dirty,
+                    older_than: older_than.map(|d| parse_duration(&d).unwrap_or_else(|e| fail(&e))),
+                    skip_main: no_main,
+                };
+                let events = read_events(&log_file).unwrap_or_else(|e| fail(&e));
+                let now = now_secs();
+                let mut reports: Vec<_> = worktree_reports(Path::new(&directory), &base, &events)
+                    .unwrap_or_else(|e| fail(&e))
+                    .into_iter()
+                    .filter(|r| filter.matches(r, now))
+                    .collect();
+                sort_reports(&mut reports, sort);
+
+                if format == "json" {
+                    println!("{}", serde_json::to_string_pretty(&reports).unwrap());
+                } else if reports.is_empty() {
+                    println!("No matching worktrees");
+                } else {
+                    println!(
+                        "{:<40} {:<24} {:>10} {:>8} {:>14} {:<5} WORKER",
+                        "PATH", "BRANCH", "SIZE", "AGE", "+AHEAD/-BEHIND", "DIRTY"
+                    );
+                    for r in &reports {
+                        let age = r
+                            .last_commit
+                            .map_or("-".to_string(), |t| format_duration(now.saturating_sub(t)));
+                        let divergence = match (r.ahead, r.behind) {
+                            (Some(a), Some(b)) => format!("+{}/-{}", a, b),
+                            _ => "-".to_string(),
+                        };
+                        let path = if r.exists {
+                            r.path.clone()
+                        } else {
+                            format!("{} (missing)", r.path)
+                        };
+                        println!(
+                            "{:<40} {:<24} {:>10} {:>8} {:>14} {:<5} {}",
+                            path,
+                            r.branch.as_deref().unwrap_or("(detached)"),
+                            format_size(r.disk_bytes),
+                            age,
+                            divergence,
+                            if r.dirty { "yes" } else { "no" },
+                            r.worker.as_deref().unwrap_or("-")
+                        );
+                    }
+                }
+            }
+        },
+
+        Commands::Activity { command } => match command {
+            ActivityCommands::Emit {
+                issue,
+                kind,
+                message,
+                worker,
+                data,
+                diff,
+                log_file,
+                format,
+            } => {
+                let log_file = path_or(log_file, dirs.feed_file());
+                let kind = parse_kind(&kind);
+                let mut data = data.map(|d| {
+                    serde_json::from_str::<serde_json::Value>(&d)
+                        .unwrap_or_else(|e| fail(&format!("Invalid --data JSON: {}", e)))
+                });
+                if let Some(base) = diff {
+                    let summary = diff::summarize(Path::new("."), Some(&base), false)
+                        .unwrap_or_else(|e| fail(&e));
+                    let data = data.get_or_insert_with(|| json!({}));
+                    if let Some(object) = data.as_object_mut() {
+                        object.insert("diff".to_string(), serde_json::to_value(&summary).unwrap());
+                    }
+                }
+                let event = emit_event(&log_file, &issue, kind, &message, worker, data)
+                    .unwrap_or_else(|e| fail(&e));
+                auto_prune_feed(&log_file, &config.activity);
+                if format == "json" {
+                    println!("{}", serde_json::to_string(&event).unwrap());
+                } else {
+                    println!("{}", format_event(&event));
+                }
+            }
+
+            ActivityCommands::List {
+                issue,
+                kind,
+                limit,
+                run,
+                cursor,
+                page_size,
+                log_file,
+                format,
+            } => {
+                let log_file = path_or(log_file, dirs.feed_file());
+                let filter = EventFilter {
+                    issue,
+                    kind: kind.as_deref().map(parse_kind),
+                    run,
+                    ..Default::default()
+                };
+                let mut events: Vec<_> = read_events(&log_file)
+                    .unwrap_or_else(|e| fail(&e))
+                    .into_iter()
+                    .filter(|e| filter.matches(e))
+                    .collect();
+                if cursor.is_some() || page_size.is_some() {
+                    let after = cursor
+                        .as_deref()
+                        .map(|c| parse_cursor(c).unwrap_or_else(|e| fail(&e)));
+                    let page = paginate(events, after, page_size.unwrap_or(DEFAULT_PAGE_SIZE));
+                    if format == "json" {
+                        println!("{}", serde_json::to_string_pretty(&page).unwrap());
+                    } else {
+                        for event in &page.events {
+                            println!("{}", format_event(event));
+                        }
+                        if let Some(ref next) = page.next_cursor {
+                            eprintln!("More events: --cursor {}", next);
+                        }
+                    }
+                    return;
+                }
+                if let Some(limit) = limit {
+                    let skip = events.len().saturating_sub(limit);
+                    events.drain(..skip);
+                }
+
+                if format == "json" {
+                    println!("{}", serde_json::to_string_pretty(&events).unwrap());
+                } else {
+                    for event in &events {
+                        println!("{}", format_event(event));
+                    }
+                }
+            }
+
+            ActivityCommands::Follow {
+                issue,
+                kind,
+                worker,
+                run,
+                grep,
+                exec,
+                from_start,
+                interval_ms,
+                max_backlog,
+                log_file,
+                format,
+            } => {
+                let log_file = path_or(log_file, dirs.feed_file());
+                let filter = EventFilter {
+                    issue,
+                    kind: kind.as_deref().map(parse_kind),
+                    worker,
+                    run,
+                    pattern: grep.map(|g| {
+                        Regex::new(&g).unwrap_or_else(|e| fail(&format!("Invalid --grep: {}", e)))
+                    }),
+                };
+
+                let stop = activity::stop_on_interrupt();
+                let summary = follow_events(
+                    &log_file,
+                    &filter,
+                    from_start,
+                    Duration::from_millis(interval_ms),
+                    max_backlog.max(1),
+                    stop,
+                    |followed| {
+                        let event = match followed {
+                            Followed::Event(event) => event,
+                            Followed::Skipped(notice) => {
+                                if format == "json" {
+                                    println!("{}", serde_json::to_string(&notice).unwrap());
+                                } else {
+                                    let resume = notice
+                                        .resume_at
+                                        .map(|id| format!(" (resuming at #{})", id))
+                                        .unwrap_or_default();
+                                    println!(
+                                        "warning: skipped {} events to catch up{}",
+                                        notice.skipped, resume
+                                    );
+                                }
+                                return true;
+                            }
+                        };
+                        if format == "json" {
+                            println!("{}", serde_json::to_string(event).unwrap());
+                        } else {
+                            println!("{}", format_event(event));
+                        }
+                        if let Some(ref command) = exec {
+                            match run_event_hook(command, event) {
+                                Ok(true) => {}
+                                Ok(false) => {
+                                    tracing::warn!("hook failed for event #{}", event.id)
+                                }
+                                Err(e) => tracing::warn!("{}", e),
+                            }
+                        }
+                        true
+                    },
+                )
+                .unwrap_or_else(|e| fail(&e));
+
+                let last = summary
+                    .last_id
+                    .map(|id| format!(", last #{}", id))
+                    .unwrap_or_default();
+                eprintln!(
+                    "Followed {} events ({} skipped){}",
+                    summary.delivered, summary.skipped, last
+                );
+            }
+
+            ActivityCommands::Serve {
+                port,
+                bind,
+                interval_ms,
+                allow_origins,
+                log_file,
+            } => {
+                let log_file = path_or(log_file, dirs.feed_file());
+                let addr = format!("{}:{}", bind, port);
+                eprintln!("Serving activity feed on http://{}/events", addr);
+                serve(
+                    &log_file,
+                    &addr,
+                    Duration::from_millis(interval_ms),
+                    &allow_origins,
+                )
+                .unwrap_or_else(|e| fail(&e));
+            }
+
+            ActivityCommands::Prune {
+                max_age_days,
+                max_events,
+                max_per_issue,
+                no_compact,
+                dry_run,
+                log_file,
+                format,
+            } => {
+                let log_file = path_or(log_file, dirs.feed_file());
+                let mut limits = config.activity;
+                limits.max_age_days = max_age_days.or(limits.max_age_days);
+                limits.max_events = max_events.or(limits.max_events);
+                limits.max_per_issue = max_per_issue.or(limits.max_per_issue);
+                limits.compact &= !no_compact;
+                if !limits.has_limits() {
+                    fail_with(
+                        ExitCode::Usage,
+                        "No retention limits: pass --max-age-days, --max-events, or --max-per-issue, or set them under [activity]",
+                    );
+                }
+                limits
+                    .validate()
+                    .unwrap_or_else(|e| fail_with(ExitCode::Usage, &e));
+                let report = retention::prune_feed(&log_file, &limits, now_secs(), dry_run)
+                    .unwrap_or_else(|e| fail(&e));
+
+                if format == "json" {
+                    println!("{}", serde_json::to_string_pretty(&report).unwrap());
+                } else {
+                    println!("before={}", report.before);
+                    println!("after={}", report.after);
+                    println!("retired={}", report.retired);
+                    println!("summaries={}", report.summaries);
+                    if dry_run {
+                        println!("dry_run=true");
+                    }
+                }
+            }
+
+            ActivityCommands::Report {
+                epic,
+                since,
+                idle_threshold,
+                log_file,
+                format,
+            } => {
+                let log_file = path_or(log_file, dirs.feed_file());
+                let now = now_secs();
+                let since = since
+                    .map(|s| now.saturating_sub(parse_duration(&s).unwrap_or_else(|e| fail(&e))));
+                let idle = parse_duration(&idle_threshold).unwrap_or_else(|e| fail(&e));
+                let events = read_events(&log_file).unwrap_or_else(|e| fail(&e));
+                let report = build_report(&events, epic.as_deref(), since, idle, now);
+
+                match format.as_str() {
+                    "json" => println!("{}", serde_json::to_string_pretty(&report).unwrap()),
+                    "mermaid" => print!("{}", render_mermaid(&report)),
+                    _ => print!("{}", render_text(&report)),
+                }
+            }
+        },
+
+        Commands::Lint { command } => match command {
+            LintCommands::All {
+                dir,
+                format,
+                emit,
+                record,
+                workspace,
+            } => {
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let dir = Path::new(&directory);
+                if workspace {
+                    let loaded = load_config(dir).unwrap_or_else(|e| fail(&e));
+                    let repos = workspace::workspace_repos(&loaded).unwrap_or_else(|e| fail(&e));
+                    output_workspace_lint(&workspace::lint_workspace(&repos, now_secs()), &format);
+                } else {
+                    let issues =
+                        list_issues(dir, &ListFilter::default()).unwrap_or_else(|e| fail(&e));
+                    run_lint(dir, &issues, &format, emit, record);
+                }
+            }
+
+            LintCommands::Issue {
+                id,
+                dir,
+                format,
+                emit,
+                record,
+            } => {
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let dir = Path::new(&directory);
+                let issue = show_issue(dir, &id).unwrap_or_else(|e| fail(&e));
+                run_lint(dir, &[issue], &format, emit, record);
+            }
+
+            LintCommands::Epic {
+                id,
+                dir,
+                format,
+                emit,
+                record,
+            } => {
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let dir = Path::new(&directory);
+                let mut issues = vec![show_issue(dir, &id).unwrap_or_else(|e| fail(&e))];
+                let children = ListFilter {
+                    parent: Some(id),
+                    ..Default::default()
+                };
+                issues.extend(list_issues(dir, &children).unwrap_or_else(|e| fail(&e)));
+                run_lint(dir, &issues, &format, emit, record);
+            }
+        },
+
+        Commands::BeadsState { command } => match command {
+            BeadsStateCommands::Get {
+                id,
+                dimension,
+                dir,
+                format,
+            } => {
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let dir = Path::new(&directory);
+                load_state_schema(dir)
+                    .dimension(&dimension)
+                    .unwrap_or_else(|e| fail(&e));
+                let value = get_state(dir, &id, &dimension).unwrap_or_else(|e| fail(&e));
+                if format == "json" {
+                    println!(
+                        "{}",
+                        json!({ "id": id, "dimension": dimension, "value": value })
+                    );
+                } else {
+                    println!("{}", value.as_deref().unwrap_or("(unset)"));
+                }
+            }
+
+            BeadsStateCommands::Set {
+                id,
+                dimension,
+                value,
+                reason,
+                force,
+                dir,
+                format,
+            } => {
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let dir = Path::new(&directory);
+                let schema = load_state_schema(dir);
+                let previous = set_state(
+                    dir,
+                    &schema,
+                    &id,
+                    &dimension,
+                    &value,
+                    reason.as_deref(),
+                    force,
+                )
+                .unwrap_or_else(|e| fail(&e));
+                if format == "json" {
+                    println!(
+                        "{}",
+                        json!({
+                            "id": id,
+                            "dimension": dimension,
+                            "from": previous,
+                            "to": value
+                        })
+                    );
+                } else {
+                    println!(
+                        "{} {}: {} -> {}",
+                        id,
+                        dimension,
+                        previous.as_deref().unwrap_or("(unset)"),
+                        value
+                    );
+                }
+            }
+
+            BeadsStateCommands::SetBulk {
+                dimension,
+                value,
+                ids,
+                children,
+                reason,
+                force,
+                dir,
+                format,
+            } => {
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let dir = Path::new(&directory);
+                let schema = load_state_schema(dir);
+                let targets: Vec<String> = if children {
+                    ids.iter()
+                        .flat_map(|epic| {
+                            let filter = ListFilter {
+                                parent: Some(epic.clone()),
+                                ..Default::default()
+                            };
+                            list_issues(dir, &filter).unwrap_or_else(|e| fail(&e))
+                        })
+                        .map(|issue| issue.id)
+                        .collect()
+                } else {
+                    ids
+                };
+
+                let results = set_state_bulk(
+                    dir,
+                    &schema,
+                    &targets,
+                    &dimension,
+                    &value,
+                    reason.as_deref(),
+                    force,
+                );
+                let succeeded = results.iter().filter(|r| r.ok).count();
+                if format == "json" {
+                    let summary = json!({
+                        "dimension": dimension,
+                        "value": value,
+                        "succeeded": succeeded,
+                        "failed": results.len() - succeeded,
+                        "results": results
+                    });
+                    println!("{}", serde_json::to_string_pretty(&summary).unwrap());
+                } else {
+                    for result in &results {
+                        match result.error {
+                            Some(ref error) => println!("FAIL {}: {}", result.id, error),
+                            None => println!(
+                                "ok   {}: {} -> {}",
+                                result.id,
+                                result.from.as_deref().unwrap_or("(unset)"),
+                                value
+                            ),
+                        }
+                    }
+                    println!(
+                        "\n{} of {} issues set {}={}",
+                        succeeded,
+                        results.len(),
+                        dimension,
+                        value
+                    );
+                }
+                exit(bulk_exit_code(&results));
+            }
+
+            BeadsStateCommands::Watch {
+                id,
+                dimension,
+                until,
+                timeout,
+                interval_ms,
+                dir,
+                format,
+            } => {
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let dir = Path::new(&directory);
+                let schema = load_state_schema(dir);
+                let target = schema.dimension(&dimension).unwrap_or_else(|e| fail(&e));
+                if let Some(ref value) = until {
+                    if !target.has_value(value) {
+                        fail(&format!(
+                            "Invalid value '{}' for {} (valid values: {})",
+                            value,
+                            dimension,
+                            target.values.join(", ")
+                        ));
+                    }
+                }
+                let timeout = timeout
+                    .map(|t| Duration::from_secs(parse_duration(&t).unwrap_or_else(|e| fail(&e))));
+
+                let outcome = watch_state(
+                    || get_state(dir, &id, &dimension),
+                    until.as_deref(),
+                    timeout,
+                    Duration::from_millis(interval_ms),
+                    |change| {
+                        if format == "json" {
+                            println!(
+                                "{}",
+                                json!({
+                                    "id": id,
+                                    "dimension": dimension,
+                                    "from": change.from,
+                                    "to": change.to,
+                                    "initial": change.initial,
+                                    "observed_at": format_timestamp(change.observed_at)
+                                })
+                            );
+                        } else if change.initial {
+                            println!(
+                                "{} {} {}: {}",
+                                format_timestamp(change.observed_at),
+                                id,
+                                dimension,
+                                change.to.as_deref().unwrap_or("(unset)")
+                            );
+                        } else {
+                            println!(
+                                "{} {} {}: {} -> {}",
+                                format_timestamp(change.observed_at),
+                                id,
+                                dimension,
+                                change.from.as_deref().unwrap_or("(unset)"),
+                                change.to.as_deref().unwrap_or("(unset)")
+                            );
+                        }
+                    },
+                )
+                .unwrap_or_else(|e| fail(&e));
+
+                if let WatchOutcome::TimedOut(last) = outcome {
+                    fail_with(
+                        ExitCode::Timeout,
+                        &format!(
+                            "Timed out waiting for {} {}; last value: {}",
+                            id,
+                            dimension,
+                            last.as_deref().unwrap_or("(unset)")
+                        ),
+                    );
+                }
+            }
+
+            BeadsStateCommands::Transitions {
+                dimension,
+                dir,
+                format,
+            } => {
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let schema = load_state_schema(Path::new(&directory));
+                let names: Vec<String> = match dimension {
+                    Some(name) => {
+                        schema.dimension(&name).unwrap_or_else(|e| fail(&e));
+                        vec![name]
+                    }
+                    None => schema.dimensions.keys().cloned().collect(),
+                };
+                if format == "json" {
+                    let selected: serde_json::Map<String, serde_json::Value> = names
+                        .iter()
+                        .map(|n| (n.clone(), json!(schema.dimensions[n])))
+                        .collect();
+                    println!("{}", serde_json::to_string_pretty(&selected).unwrap());
+                } else {
+                    for name in &names {
+                        print!("{}", render_transitions(name, &schema.dimensions[name]));
+                    }
+                }
+            }
+        },
+
+        Commands::Epic { command } => match command {
+            EpicCommands::Scaffold {
+                file,
+                dry_run,
+                dir,
+                format,
+            } => {
+                let content = match file.as_deref() {
+                    None | Some("-") => {
+                        let mut buf = String::new();
+                        std::io::Read::read_to_string(&mut std::io::stdin(), &mut buf)
+                            .unwrap_or_else(|e| fail(&format!("Failed to read stdin: {}", e)));
+                        buf
+                    }
+                    Some(path) => std::fs::read_to_string(path)
+                        .unwrap_or_else(|e| fail(&format!("Failed to read {}: {}", path, e))),
+                };
+                let plan = parse_plan(&content).unwrap_or_else(|e| fail(&e));
+
+                let (epic_label, labels) = if dry_run {
+                    let keys = plan.tasks.iter().map(|t| t.key.clone()).collect();
+                    ("(epic)".to_string(), keys)
+                } else {
+                    let directory = dir.unwrap_or_else(|| ".".to_string());
+                    let result =
+                        scaffold(Path::new(&directory), &plan).unwrap_or_else(|e| fail(&e));
+                    (result.epic_id, result.task_ids)
+                };
+
+                if format == "json" {
+                    let tasks: Vec<_> = plan
+                        .tasks
+                        .iter()
+                        .enumerate()
+                        .map(|(i, task)| {
+                            let deps: Vec<&String> =
+                                task.depends_on.iter().map(|&d| &labels[d]).collect();
+                            json!({
+                                "id": labels[i],
+                                "key": task.key,
+                                "title": task.title,
+                                "phase": task.phase,
+                                "depends_on": deps
+                            })
+                        })
+                        .collect();
+                    let out = json!({
+                        "dry_run": dry_run,
+                        "epic": { "id": epic_label, "title": plan.title },
+                        "tasks": tasks
+                    });
+                    println!("{}", serde_json::to_string_pretty(&out).unwrap());
+                } else {
+                    print!("{}", render_graph(&plan, &epic_label, &labels));
+                }
+            }
+        },
+
+        Commands::Issue { command } => match command {
+            IssueCommands::Create {
+                issue_type,
+                title,
+                template,
+                parent,
+                priority,
+                description,
+                acceptance,
+                sections,
+                dry_run,
+                dir,
+                format,
+            } => {
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let dir = Path::new(&directory);
+                let sections = sections
+                    .iter()
+                    .map(|s| match s.split_once('=') {
+                        Some((name, text)) => (name.trim().to_string(), text.to_string()),
+                        None => fail_with(
+                            ExitCode::Usage,
+                            &format!("Invalid --section '{}' (expected NAME=TEXT)", s),
+                        ),
+                    })
+                    .collect();
+                let request = IssueRequest {
+                    issue_type,
+                    title,
+                    parent,
+                    priority,
+                    description,
+                    acceptance_criteria: acceptance,
+                    sections,
+                };
+                let loaded = load_config(dir).unwrap_or_else(|e| fail(&e));
+                let source =
+                    issue_template::load_template(dir, &request.issue_type, template.as_deref())
+                        .unwrap_or_else(|e| fail(&e));
+                let required = loaded.config.lint.sections_for(&request.issue_type);
+                let new = issue_template::render(&source, &request, &required)
+                    .unwrap_or_else(|e| fail(&e));
+
+                // Lint the draft among the open issues so duplicates show up;
+                // first, so pairwise findings land on the draft
+                let mut issues = vec![issue_template::draft(&new)];
+                issues
+                    .extend(list_issues(dir, &ListFilter::default()).unwrap_or_else(|e| fail(&e)));
+                let mut report = lint::build_report(
+                    &issues,
+                    &lint::Related::default(),
+                    &loaded.config.lint,
+                    &loaded.source_description(),
+                    now_secs(),
+                )
+                .for_issue(DRAFT_ID);
+
+                let issue = if dry_run || report.has_errors() {
+                    issue_template::draft(&new)
+                } else {
+                    let issue = beads::create_issue(dir, &new).unwrap_or_else(|e| fail(&e));
+                    for finding in &mut report.findings {
+                        finding.issue_id = issue.id.clone();
+                    }
+                    issue
+                };
+                let created = issue.id != DRAFT_ID;
+                if format == "json" {
+                    let out = json!({ "created": created, "issue": issue, "lint": report });
+                    println!("{}", serde_json::to_string_pretty(&out).unwrap());
+                } else {
+                    match (created, report.has_errors()) {
+                        (true, _) => println!("Created {}: {}", issue.id, issue.title),
+                        (false, true) => println!("Not created: {} fails lint", issue.title),
+                        (false, false) => println!("Draft: {}", issue.title),
+                    }
+                    if !created {
+                        println!("\n{}\n", issue.description);
+                        if !issue.acceptance_criteria.is_empty() {
+                            println!("Acceptance criteria:\n{}\n", issue.acceptance_criteria);
+                        }
+                    }
+                    for finding in &report.findings {
+                        println!(
+                            "{} [{}] {}",
+                            finding.severity, finding.rule, finding.message
+                        );
+                        if let Some(ref suggestion) = finding.suggestion {
+                            println!("    fix: {}", suggestion);
+                        }
+                    }
+                }
+                if report.has_errors() {
+                    exit(ExitCode::Blocked);
+                }
+            }
+        },
+
+        Commands::Plan { command } => match command {
+            PlanCommands::Repl { epic, title, dir } => {
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let dir = Path::new(&directory);
+                let loaded = load_config(dir).unwrap_or_else(|e| fail(&e));
+                let title = match epic {
+                    Some(ref id) => show_issue(dir, id).unwrap_or_else(|e| fail(&e)).title,
+                    None => title.unwrap_or_default(),
+                };
+                let mut session = PlanSession::new(&title, epic.clone(), loaded.config.lint);
+
+                let stdin = std::io::stdin();
+                let interactive = stdin.is_terminal();
+                if interactive {
+                    println!(
+                        "Planning {} (type help for commands)",
+                        epic.as_deref().unwrap_or("a new epic")
+                    );
+                }
+                let commit = plan_repl::run(
+                    &mut session,
+                    stdin.lock(),
+                    &mut std::io::stdout(),
+                    interactive,
+                )
+                .unwrap_or_else(|e| fail(&e));
+                if !commit {
+                    println!("Nothing created");
+                    return;
+                }
+
+                let result = epic::scaffold_into(dir, &session.plan, epic.as_deref())
+                    .unwrap_or_else(|e| fail(&e));
+                print!(
+                    "{}",
+                    render_graph(&session.plan, &result.epic_id, &result.task_ids)
+                );
+            }
+        },
+
+        Commands::Graph { command } => match command {
+            GraphCommands::Analyze {
+                epic,
+                bottleneck_threshold,
+                include_closed,
+                dir,
+                format,
+            } => {
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let filter = ListFilter {
+                    parent: Some(epic.clone()),
+                    ..Default::default()
+                };
+                let mut issues =
+                    list_issues(Path::new(&directory), &filter).unwrap_or_else(|e| fail(&e));
+                if !include_closed {
+                    issues = graph::open_issues(issues);
+                }
+                let dep_graph = DepGraph::new(issues);
+                let analysis = graph::analyze(&epic, &dep_graph, bottleneck_threshold);
+
+                match format.as_str() {
+                    "json" => {
+                        let mut out = serde_json::to_value(&analysis).unwrap();
+                        out["diagrams"] = json!({
+                            "dot": graph::render_dot(&dep_graph, &analysis),
+                            "mermaid": graph::render_mermaid(&dep_graph, &analysis)
+                        });
+                        println!("{}", serde_json::to_string_pretty(&out).unwrap());
+                    }
+                    "dot" => print!("{}", graph::render_dot(&dep_graph, &analysis)),
+                    "mermaid" => print!("{}", graph::render_mermaid(&dep_graph, &analysis)),
+                    _ => print!("{}", graph::render_text(&analysis)),
+                }
+            }
+
+            GraphCommands::Validate {
+                epic,
+                workers,
+                max_fan_in,
+                dir,
+                format,
+            } => {
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let dir = Path::new(&directory);
+                let loaded = load_config(dir).unwrap_or_else(|e| fail(&e));
+                let filter = ListFilter {
+                    parent: Some(epic.clone()),
+                    ..Default::default()
+                };
+                let issues = list_issues(dir, &filter).unwrap_or_else(|e| fail(&e));
+                let dep_graph = DepGraph::new(graph::open_issues(issues));
+                let options = ValidateOptions {
+                    workers,
+                    max_fan_in,
+                };
+                let report =
+                    graph::validate_structure(&epic, &dep_graph, &loaded.config.lint, &options);
+
+                if format == "json" {
+                    println!("{}", serde_json::to_string_pretty(&report).unwrap());
+                } else {
+                    print!("{}", graph::render_structure(&report));
+                }
+                if report.has_errors() {
+                    exit(ExitCode::Blocked);
+                }
+            }
+        },
+
+        Commands::Hooks { command } => match command {
+            HooksCommands::List { dir, format } => {
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let loaded = load_config(Path::new(&directory)).unwrap_or_else(|e| fail(&e));
+                if format == "json" {
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&loaded.config.hooks).unwrap()
+                    );
+                } else {
+                    println!("Config: {}", loaded.source_description());
+                    for point in HookPoint::ALL {
+                        let commands = loaded.config.hooks.commands(point);
+                        if commands.is_empty() {
+                            println!("{}: (none)", point);
+                        }
+                        for command in commands {
+                            println!("{}: {}", point, command);
+                        }
+                    }
+                }
+            }
+
+            HooksCommands::Run {
+                point,
+                payload,
+                dir,
+                format,
+            } => {
+                let point = point.parse::<HookPoint>().unwrap_or_else(|e| fail(&e));
+                let payload = match payload {
+                    Some(raw) => serde_json::from_str(&raw)
+                        .unwrap_or_else(|e| fail(&format!("Invalid --payload JSON: {}", e))),
+                    None => serde_json::Value::Null,
+                };
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let dir = Path::new(&directory);
+                let loaded = load_config(dir).unwrap_or_else(|e| fail(&e));
+                let results = run_hooks(dir, &loaded.config.hooks, point, &payload)
+                    .unwrap_or_else(|e| fail(&e));
+
+                if format == "json" {
+                    let out = json!({ "hook": point.to_string(), "results": results });
+                    println!("{}", serde_json::to_string_pretty(&out).unwrap());
+                } else {
+                    for result in &results {
+                        let status = match (result.success, result.exit_code) {
+                            (true, _) => "ok".to_string(),
+                            (false, Some(code)) => format!("exit {}", code),
+                            (false, None) => result.error.clone().unwrap_or_default(),
+                        };
+                        println!("{} [{}] {}", point, status, result.command);
+                    }
+                }
+                if results.iter().any(|r| !r.success) {
+                    exit(ExitCode::HookFailed);
+                }
+            }
+        },
+
+        Commands::Gh { command } => match command {
+            GhCommands::PrStatus { pr, dir, format } => {
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let status =
+                    github::pr_status(Path::new(&directory), &pr).unwrap_or_else(|e| fail(&e));
+                if format == "json" {
+                    println!("{}", serde_json::to_string_pretty(&status).unwrap());
+                } else {
+                    println!(
+                        "#{} {} [{}{}] {} -> {}",
+                        status.number,
+                        status.title,
+                        status.state,
+                        if status.draft { ", draft" } else { "" },
+                        status.head,
+                        status.base
+                    );
+                    println!("checks: {}", status.checks_outcome);
+                    for check in &status.checks {
+                        println!("  {:<9} {}", check.outcome, check.name);
+                    }
+                }
+            }
+
+            GhCommands::RunStatus {
+                run_id,
+                dir,
+                format,
+            } => {
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let status =
+                    github::run_status(Path::new(&directory), &run_id).unwrap_or_else(|e| fail(&e));
+                if format == "json" {
+                    println!("{}", serde_json::to_string_pretty(&status).unwrap());
+                } else {
+                    println!(
+                        "{} {} on {}: {}",
+                        status.id, status.name, status.branch, status.outcome
+                    );
+                    for job in &status.jobs {
+                        println!("  {:<9} {}", job.outcome, job.name);
+                    }
+                }
+            }
+
+            GhCommands::CreatePr {
+                title,
+                body,
+                base,
+                head,
+                draft,
+                dir,
+                format,
+            } => {
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let new = NewPr {
+                    title,
+                    body,
+                    base,
+                    head,
+                    draft,
+                };
+                let url =
+                    github::create_pr(Path::new(&directory), &new).unwrap_or_else(|e| fail(&e));
+                output_result(&format, "url", &url);
+            }
+        },
+
+        Commands::Metrics { command } => match command {
+            MetricsCommands::Record {
+                name,
+                value,
+                labels,
+                metrics_file,
+            } => {
+                let metrics_file = path_or(metrics_file, dirs.metrics_file());
+                let labels = labels
+                    .iter()
+                    .map(|l| metrics::parse_label(l))
+                    .collect::<Result<_, _>>()
+                    .unwrap_or_else(|e| fail(&e));
+                metrics::record_metric(&metrics_file, &name, value, labels)
+                    .unwrap_or_else(|e| fail(&e));
+            }
+
+            MetricsCommands::Show {
+                name,
+                since,
+                format,
+                metrics_file,
+            } => {
+                let metrics_file = path_or(metrics_file, dirs.metrics_file());
+                if let Some(ref name) = name {
+                    metrics::metric_info(name).unwrap_or_else(|e| fail(&e));
+                }
+                let cutoff = since.map(|s| {
+                    now_secs().saturating_sub(parse_duration(&s).unwrap_or_else(|e| fail(&e)))
+                });
+                let samples: Vec<_> = metrics::read_samples(&metrics_file)
+                    .unwrap_or_else(|e| fail(&e))
+                    .into_iter()
+                    .filter(|s| name.as_ref().is_none_or(|n| &s.name == n))
+                    .filter(|s| cutoff.is_none_or(|c| s.timestamp >= c))
+                    .collect();
+                let series = metrics::aggregate(&samples);
+
+                if format == "json" {
+                    println!("{}", serde_json::to_string_pretty(&series).unwrap());
+                } else if series.is_empty() {
+                    println!("No metrics recorded");
+                } else {
+                    for s in &series {
+                        let labels: Vec<String> = s
+                            .labels
+                            .iter()
+                            .map(|(k, v)| format!("{}={}", k, v))
+                            .collect();
+                        let name = if labels.is_empty() {
+                            s.name.clone()
+                        } else {
+                            format!("{}{{{}}}", s.name, labels.join(","))
+                        };
+                        match s.kind {
+                            metrics::MetricKind::Counter => println!("{}  total={}", name, s.sum),
+                            metrics::MetricKind::Histogram => println!(
+                                "{}  count={} mean={:.2} min={} max={}",
+                                name,
+                                s.count,
+                                s.mean(),
+                                s.min,
+                                s.max
+                            ),
+                        }
+                    }
+                }
+            }
+
+            MetricsCommands::Export {
+                format,
+                metrics_file,
+            } => {
+                let metrics_file = path_or(metrics_file, dirs.metrics_file());
+                if format != "prometheus" {
+                    fail(&format!(
+                        "Unsupported export format: {} (supported: prometheus)",
+                        format
+                    ));
+                }
+                let samples = metrics::read_samples(&metrics_file).unwrap_or_else(|e| fail(&e));
+                print!(
+                    "{}",
+                    metrics::render_prometheus(&metrics::aggregate(&samples))
+                );
+            }
+        },
+
+        Commands::Cost { command } => match command {
+            CostCommands::Record {
+                session,
+                tokens_in,
+                tokens_out,
+                model,
+                issue,
+                worker,
+                cost_usd,
+                dir,
+                format,
+                cost_file,
+            } => {
+                let cost_file = path_or(cost_file, dirs.cost_file());
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let loaded = load_config(Path::new(&directory)).unwrap_or_else(|e| fail(&e));
+                let entry = cost::record_cost(
+                    &cost_file,
+                    &loaded.config.cost,
+                    NewCost {
+                        session,
+                        issue,
+                        worker,
+                        model,
+                        tokens_in,
+                        tokens_out,
+                        cost_usd,
+                    },
+                )
+                .unwrap_or_else(|e| fail(&e));
+
+                if format == "json" {
+                    println!("{}", serde_json::to_string(&entry).unwrap());
+                } else {
+                    match entry.cost_usd {
+                        Some(c) => println!("cost_usd={:.4}", c),
+                        None => println!("cost_usd=unpriced"),
+                    }
+                }
+            }
+
+            CostCommands::Report {
+                epic,
+                session,
+                since,
+                format,
+                cost_file,
+            } => {
+                let cost_file = path_or(cost_file, dirs.cost_file());
+                let since = since.map(|s| {
+                    now_secs().saturating_sub(parse_duration(&s).unwrap_or_else(|e| fail(&e)))
+                });
+                let entries = cost::read_costs(&cost_file).unwrap_or_else(|e| fail(&e));
+                let report = cost::build_report(
+                    &entries,
+                    &CostFilter {
+                        session,
+                        epic,
+                        since,
+                    },
+                );
+
+                if format == "json" {
+                    println!("{}", serde_json::to_string_pretty(&report).unwrap());
+                } else {
+                    print!("{}", cost::render_text(&report));
+                }
+            }
+
+            CostCommands::Check {
+                epic,
+                session,
+                max_cost,
+                dir,
+                format,
+                cost_file,
+            } => {
+                let cost_file = path_or(cost_file, dirs.cost_file());
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let loaded = load_config(Path::new(&directory)).unwrap_or_else(|e| fail(&e));
+                let Some(budget) = max_cost.or(loaded.config.cost.max_cost_usd) else {
+                    fail("No budget: pass --max-cost or set cost.max_cost_usd");
+                };
+                let entries = cost::read_costs(&cost_file).unwrap_or_else(|e| fail(&e));
+                let spent = cost::build_report(
+                    &entries,
+                    &CostFilter {
+                        session,
+                        epic,
+                        since: None,
+                    },
+                )
+                .total
+                .cost_usd;
+                let within = spent <= budget;
+
+                if format == "json" {
+                    println!(
+                        "{}",
+                        json!({
+                            "within_budget": within,
+                            "spent_usd": spent,
+                            "max_cost_usd": budget,
+                        })
+                    );
+                } else {
+                    println!(
+                        "{}: ${:.4} of ${:.4}",
+                        if within {
+                            "within budget"
+                        } else {
+                            "over budget"
+                        },
+                        spent,
+                        budget
+                    );
+                }
+                if !within {
+                    exit(ExitCode::Blocked);
+                }
+            }
+        },
+
+        Commands::State { command } => match command {
+            StateCommands::New {
+                mode,
+                reason,
+                force,
+                format,
+                session_file,
+            } => {
+                let session_file = path_or(session_file, dirs.session_file());
+                let mode: WorkflowMode = mode.parse().unwrap_or_else(|e: String| fail(&e));
+                let path = &session_file;
+                let current = SessionState::load(path)
+                    .unwrap_or_else(|e| fail_with(ExitCode::StateInvalid, &e));
+                check_approval(&current, mode);
+                let session = SessionState::update(path, Some(current.revision), false, |s| {
+                    if s.is_active() && !force {
+                        return Err(format!(
+                            "Session {} is still {} (use --force to replace it)",
+                            s.run_id.as_deref().unwrap_or("(no run id)"),
+                            s.mode.unwrap_or_default()
+                        ));
+                    }
+                    *s = s.succeed(state::new_run_id(), mode, reason, now_secs())?;
+                    Ok(())
+                })
+                .unwrap_or_else(|e| fail_update(e));
+
+                let run_id = session.run_id.as_deref().unwrap_or_default();
+                match format.as_str() {
+                    "json" => println!("{}", serde_json::to_string_pretty(&session).unwrap()),
+                    "env" => println!("export {}={}", state::RUN_ENV, run_id),
+                    _ => {
+                        println!("run={}", run_id);
+                        println!("mode={}", mode);
+                    }
+                }
+            }
+
+            StateCommands::Show {
+                format,
+                session_file,
+            } => {
+                let session_file = path_or(session_file, dirs.session_file());
+                let session = SessionState::load(&session_file)
+                    .unwrap_or_else(|e| fail_with(ExitCode::StateInvalid, &e));
+                if format == "json" {
+                    println!("{}", serde_json::to_string_pretty(&session).unwrap());
+                } else {
+                    if let Some(ref run_id) = session.run_id {
+                        println!("run={}", run_id);
+                    }
+                    match session.mode {
+                        Some(mode) => println!("mode={}", mode),
+                        None => println!("mode=(none)"),
+                    }
+                    println!("revision={}", session.revision);
+                    println!("iterations={}", session.iteration_count);
+                    if let Some(ref plan) = session.plan {
+                        println!("plan={}", plan.plan_id);
+                        if let Some(ref gate) = plan.approval_gate {
+                            println!("approval_gate={}", gate);
+                        }
+                    }
+                    for t in &session.transitions {
+                        let from = t.from.map_or("(start)".to_string(), |m| m.to_string());
+                        println!(
+                            "{}  {} -> {}{}",
+                            format_timestamp(t.timestamp),
+                            from,
+                            t.to,
+                            t.reason
+                                .as_ref()
+                                .map_or(String::new(), |r| format!("  ({})", r))
+                        );
+                    }
+                }
+            }
+
+            StateCommands::Link {
+                plan,
+                approval_gate,
+                format,
+                session_file,
+            } => {
+                let session_file = path_or(session_file, dirs.session_file());
+                let session = SessionState::update(&session_file, None, false, |s| {
+                    s.link_plan(&plan, approval_gate.as_deref())
+                })
+                .unwrap_or_else(|e| fail_update(e));
+
+                if format == "json" {
+                    println!("{}", serde_json::to_string_pretty(&session).unwrap());
+                } else {
+                    println!("plan={}", plan);
+                    if let Some(gate) = approval_gate {
+                        println!("approval_gate={}", gate);
+                    }
+                }
+            }
+
+            StateCommands::Update {
+                iterations,
+                add_iterations,
+                expect_revision,
+                retry,
+                format,
+                session_file,
+            } => {
+                let session_file = path_or(session_file, dirs.session_file());
+                let session = SessionState::update(&session_file, expect_revision, retry, |s| {
+                    let count = match (iterations, add_iterations) {
+                        (Some(n), _) => n,
+                        (None, Some(n)) => s.iteration_count.saturating_add(n),
+                        (None, None) => s.iteration_count,
+                    };
+                    s.record_iterations(count, retry)
+                })
+                .unwrap_or_else(|e| fail_update(e));
+
+                if format == "json" {
+                    println!("{}", serde_json::to_string_pretty(&session).unwrap());
+                } else {
+                    println!("revision={}", session.revision);
+                    println!("iterations={}", session.iteration_count);
+                }
+            }
+
+            StateCommands::ShouldContinue {
+                issue,
+                max_iterations,
+                dir,
+                format,
+                session_file,
+                log_file,
+                cost_file,
+            } => {
+                let session_file = path_or(session_file, dirs.session_file());
+                let log_file = path_or(log_file, dirs.feed_file());
+                let cost_file = path_or(cost_file, dirs.cost_file());
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let dir = Path::new(&directory);
+                let config = load_config(dir).unwrap_or_else(|e| fail(&e)).config;
+                let mut session = SessionState::load(&session_file)
+                    .unwrap_or_else(|e| fail_with(ExitCode::StateInvalid, &e));
+
+                let mut signals = StopSignals {
+                    max_iterations,
+                    ..Default::default()
+                };
+                if session.is_active() && config.stop.no_progress_iterations > 0 {
+                    if let Ok(fingerprint) = stop::tree_fingerprint(dir) {
+                        let idle = session.clone().mark_progress(&fingerprint);
+                        // Only a changed tree is saved, so checks don't bump the revision
+                        if idle == 0 {
+                            session = SessionState::update(&session_file, None, true, |s| {
+                                s.mark_progress(&fingerprint);
+                                Ok(())
+                            })
+                            .unwrap_or_else(|e| fail_update(e));
+                        }
+                        signals.idle_iterations = Some(idle);
+                    }
+                }
+
+                let events = read_events(&log_file).unwrap_or_else(|e| fail(&e));
+                let events: Vec<&_> = events
+                    .iter()
+                    .filter(|e| match issue {
+                        Some(ref id) => e.issue == *id,
+                        None => session.run_id.is_some() && e.run == session.run_id,
+                    })
+                    .collect();
+                signals.consecutive_failures = stop::trailing_failures(&events);
+                if let (Some(ref id), true) = (&issue, config.stop.open_gates) {
+                    let current = show_issue(dir, id).unwrap_or_else(|e| fail(&e));
+                    let tasks = std::collections::BTreeMap::from([(id.as_str(), &current)]);
+                    signals.open_gates = report::open_gates(&events, &tasks)
+                        .into_iter()
+                        .map(|g| g.gate)
+                        .collect();
+                }
+                if let (Some(budget), true) = (config.cost.max_cost_usd, config.stop.budget) {
+                    let entries = cost::read_costs(&cost_file).unwrap_or_else(|e| fail(&e));
+                    signals.spent_usd = cost::build_report(&entries, &CostFilter::default())
+                        .total
+                        .cost_usd;
+                    signals.budget_usd = Some(budget);
+                }
+
+                let verdict = session.should_continue(&config.stop, &signals);
+                if format == "json" {
+                    println!("{}", serde_json::to_string_pretty(&verdict).unwrap());
+                } else {
+                    println!("should_continue={}", verdict.should_continue);
+                    println!("continuation_reason={}", verdict.continuation_reason);
+                    println!("detail={}", verdict.detail);
+                }
+                if verdict.continuation_reason.is_failure() {
+                    let mut payload = serde_json::to_value(&verdict).unwrap();
+                    payload["issue"] = json!(issue);
+                    payload["iteration"] = json!(session.iteration_count);
+                    fire_hooks(dir, &config.hooks, HookPoint::OnFailure, &payload);
+                }
+                if !verdict.should_continue {
+                    exit(ExitCode::Blocked);
+                }
+            }
+
+            StateCommands::Stop {
+                reason,
+                clear,
+                session_file,
+            } => {
+                let session_file = path_or(session_file, dirs.session_file());
+                let session = SessionState::update(&session_file, None, true, |s| {
+                    s.request_stop(!clear, reason, now_secs());
+                    Ok(())
+                })
+                .unwrap_or_else(|e| fail_update(e));
+                match session.stop_requested {
+                    Some(_) => println!("Stop requested; the loop ends at its next check"),
+                    None => println!("Stop request withdrawn"),
+                }
+            }
+
+            StateCommands::Migrate {
+                files,
+                dry_run,
+                format,
+            } => {
+                let files = if files.is_empty() {
+                    vec![dirs.session_file()]
+                } else {
+                    files.into_iter().map(PathBuf::from).collect()
+                };
+                let results: Vec<serde_json::Value> = files
+                    .iter()
+                    .map(|file| {
+                        let path = file.as_path();
+                        let migrated = SessionState::load_versioned(path).and_then(|(_, from)| {
+                            if from < state::SCHEMA_VERSION && !dry_run {
+                                // Under the session lock, so a concurrent update isn't lost
+                                SessionState::update(path, None, false, |_| Ok(()))
+                                    .map_err(|e| e.to_string())?;
+                            }
+                            Ok(from)
+                        });
+                        match migrated {
+                            Ok(from) => json!({
+                                "file": file,
+                                "from_version": from,
+                                "to_version": state::SCHEMA_VERSION,
+                                "migrated": from < state::SCHEMA_VERSION && !dry_run,
+                            }),
+                            Err(e) => json!({ "file": file, "error": e }),
+                        }
+                    })
+                    .collect();
+                if format == "json" {
+                    println!("{}", serde_json::to_string_pretty(&results).unwrap());
+                } else {
+                    for result in &results {
+                        let file = result["file"].as_str().unwrap_or_default();
+                        if let Some(error) = result["error"].as_str() {
+                            println!("{}: failed: {}", file, error);
+                        } else if result["from_version"] == result["to_version"] {
+                            println!("{}: already v{}", file, state::SCHEMA_VERSION);
+                        } else {
+                            println!(
+                                "{}: v{} -> v{}{}",
+                                file,
+                                result["from_version"],
+                                state::SCHEMA_VERSION,
+                                if dry_run { " (dry run)" } else { "" }
+                            );
+                        }
+                    }
+                }
+                let failed = results.iter().filter(|r| r.get("error").is_some()).count();
+                if failed == results.len() && failed > 0 {
+                    exit(ExitCode::StateInvalid);
+                } else if failed > 0 {
+                    exit(ExitCode::Partial);
+                }
+            }
+
+            StateCommands::Transition {
+                to,
+                reason,
+                format,
+                session_file,
+            } => {
+                let session_file = path_or(session_file, dirs.session_file());
+                let to: WorkflowMode = to.parse().unwrap_or_else(|e: String| fail(&e));
+                let path = &session_file;
+                let current = SessionState::load(path)
+                    .unwrap_or_else(|e| fail_with(ExitCode::StateInvalid, &e));
+                check_approval(&current, to);
+                // The approval was checked against this revision's plan link
+                let session = SessionState::update(path, Some(current.revision), false, |s| {
+                    s.transition(to, reason, now_secs()).map(|_| ())
+                })
+                .unwrap_or_else(|e| fail_update(e));
+                let transition = session.transitions.last().unwrap();
+
+                if format == "json" {
+                    println!("{}", serde_json::to_string(&transition).unwrap());
+                } else {
+                    println!("mode={}", transition.to);
+                }
+            }
+        },
+
+        Commands::Context { command } => match command {
+            ContextCommands::Compile {
+                issue,
+                template,
+                activity_limit,
+                max_tokens,
+                dir,
+                log_file,
+            } => {
+                let log_file = path_or(log_file, dirs.feed_file());
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let dir = Path::new(&directory);
+                let source = context::load_template(dir, &template).unwrap_or_else(|e| fail(&e));
+                let issue = show_issue(dir, &issue).unwrap_or_else(|e| fail(&e));
+                let schema = load_state_schema(dir);
+                // State is best-effort context; an older bd without dimensions
+                // still gets the rest
+                let state = schema
+                    .dimensions
+                    .keys()
+                    .filter_map(|name| match get_state(dir, &issue.id, name) {
+                        Ok(value) => value.map(|v| (name.clone(), v)),
+                        Err(e) => {
+                            tracing::debug!(dimension = %name, "state unavailable: {}", e);
+                            None
+                        }
+                    })
+                    .collect();
+                let events = read_events(&log_file).unwrap_or_else(|e| fail(&e));
+                let data = ContextData::new(issue, state, &events, activity_limit);
+                if let Some(max_tokens) = max_tokens {
+                    let budgeted = context::render_within(&source, &data, max_tokens)
+                        .unwrap_or_else(|e| fail(&e));
+                    print!("{}", budgeted.output);
+                    // The budget report goes to stderr so stdout stays prompt-ready
+                    eprintln!(
+                        "context: ~{} tokens (budget {}){}; left out {} activity events, {} gate results",
+                        budgeted.tokens,
+                        max_tokens,
+                        if budgeted.fits { "" } else { ", over budget" },
+                        budgeted.excluded_activity.len(),
+                        budgeted.excluded_gates.len()
+                    );
+                    tracing::info!(
+                        tokens = budgeted.tokens,
+                        excluded_activity = ?budgeted.excluded_activity,
+                        excluded_gates = ?budgeted.excluded_gates,
+                        "context budget applied"
+                    );
+                } else {
+                    print!(
+                        "{}",
+                        context::render(&source, &data).unwrap_or_else(|e| fail(&e))
+                    );
+                }
+            }
+        },
+
+        Commands::Config { command } => match command {
+            ConfigCommands::Check { dir, format } => {
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let mut check = check_config(Path::new(&directory));
+                if let Some(timeout) = cli.bd_timeout {
+                    check.apply_override("bd.timeout_secs", json!(timeout));
+                }
+                if format == "json" {
+                    println!("{}", serde_json::to_string_pretty(&check).unwrap());
+                } else {
+                    match check.file {
+                        Some(ref file) => println!("Config: {}", file.display()),
+                        None => println!("Config: built-in defaults (no {})", CONFIG_FILE),
+                    }
+                    println!();
+                    for entry in &check.entries {
+                        println!("  {} = {}  ({})", entry.key, entry.value, entry.source);
+                    }
+                    if check.valid {
+                        println!("\nConfiguration is valid");
+                    } else {
+                        println!("\nErrors:");
+                        for error in &check.errors {
+                            println!("  - {}", error);
+                        }
+                    }
+                }
+                if !check.valid {
+                    exit(ExitCode::Blocked);
+                }
+            }
+        },
+
+        Commands::Workspace { command } => match command {
+            WorkspaceCommands::List { dir, format } => {
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let loaded = load_config(Path::new(&directory)).unwrap_or_else(|e| fail(&e));
+                let repos = workspace::workspace_repos(&loaded).unwrap_or_else(|e| fail(&e));
+                if format == "json" {
+                    println!("{}", serde_json::to_string_pretty(&repos).unwrap());
+                } else {
+                    for repo in &repos {
+                        let status = match (repo.exists, repo.beads) {
+                            (false, _) => "missing",
+                            (true, false) => "no beads",
+                            (true, true) => "ok",
+                        };
+                        println!("{:<20} {:<9} {}", repo.name, status, repo.path.display());
+                    }
+                }
+            }
+        },
+
+        Commands::Limits { command } => match command {
+            LimitsCommands::Status { format } => {
+                let status = limits::limiter().status().unwrap_or_else(|e| fail(&e));
+                if format == "json" {
+                    println!("{}", serde_json::to_string_pretty(&status).unwrap());
+                } else {
+                    println!("lock_dir={}", status.lock_dir.display());
+                    println!("running={}/{}", status.running, status.max_concurrent);
+                    match status.spawns_per_minute {
+                        Some(quota) => {
+                            println!("spawns_last_minute={}/{}", status.spawns_last_minute, quota)
+                        }
+                        None => println!("spawns_last_minute=(unlimited)"),
+                    }
+                }
+            }
+        },
+
+        Commands::Init {
+            dir,
+            force,
+            no_beads,
+            git_hooks,
+            format,
+        } => {
+            let directory = dir.unwrap_or_else(|| ".".to_string());
+            let options = InitOptions {
+                force,
+                skip_beads: no_beads,
+                git_hooks,
+            };
+            let steps = init::init(Path::new(&directory), options);
+            if format == "json" {
+                println!("{}", serde_json::to_string_pretty(&steps).unwrap());
+            } else {
+                for step in &steps {
+                    println!(
+                        "{:<9} {:<8} {}",
+                        step.name,
+                        step.status.to_string(),
+                        step.detail
+                    );
+                }
+            }
+            if steps.iter().any(|s| s.status == StepStatus::Failed) {
+                exit(ExitCode::Error);
+            }
+        }
+
+        Commands::Health { dir, probe, format } => {
+            let directory = dir.unwrap_or_else(|| ".".to_string());
+            let dir = Path::new(&directory);
+            let loaded = load_config(dir).unwrap_or_else(|e| fail(&e));
+            let mut checks = health::check_beads(dir, HealthOptions { probe });
+            checks.extend(toolchain::check_toolchain(dir, &loaded.config.toolchain));
+            checks.extend(build_cache::check_repo_cache(
+                dir,
+                &loaded.config.worktree.cache,
+            ));
+            let status = health::overall_status(&checks);
+            if format == "json" {
+                let result = json!({ "status": status, "checks": checks });
+                println!("{}", serde_json::to_string_pretty(&result).unwrap());
+            } else {
+                for check in &checks {
+                    println!(
+                        "{:<12} {:<8} {}",
+                        check.name,
+                        check.status.to_string(),
+                        check.detail
+                    );
+                    if let Some(ref hint) = check.hint {
+                        println!("{:<21} hint: {}", "", hint);
+                    }
+                }
+            }
+            if status == CheckStatus::Fail {
+                exit(ExitCode::Blocked);
+            }
+        }
+
+        Commands::Status {
+            issue,
+            dir,
+            log_file,
+            session_file,
+            failures,
+            format,
+        } => {
+            let log_file = path_or(log_file, dirs.feed_file());
+            let session_file = path_or(session_file, dirs.session_file());
+            let directory = dir.unwrap_or_else(|| ".".to_string());
+            let dir = Path::new(&directory);
+            let mut status = Status::new(now_secs());
+
+            status.worktree = status.gather("worktree", current_worktree(dir));
+            let id = issue.or_else(|| {
+                let context = status.worktree.as_ref()?.context.as_ref()?;
+                Some(context.issue.clone())
+            });
+            let issue = id.and_then(|id| status.gather("issue", show_issue(dir, &id)));
+            if let Some(ref issue) = issue {
+                let blockers: Result<Vec<_>, BdError> =
+                    issue.blockers().map(|b| show_issue(dir, b)).collect();
+                if let Some(blockers) = status.gather("issue", blockers) {
+                    status.issue = Some(IssueStatus::new(issue, &blockers));
+                }
+                if let Some(parent) = issue.parent() {
+                    status.epic = status.gather("epic", epic_summary(dir, parent));
+                }
+                let issues = std::slice::from_ref(issue);
+                let inputs = load_config(dir)
+                    .and_then(|loaded| Ok((lint::gather_related(dir, issues)?, loaded)));
+                if let Some((related, loaded)) = status.gather("lint", inputs) {
+                    let report = lint::build_report(
+                        issues,
+                        &related,
+                        &loaded.config.lint,
+                        &loaded.source_description(),
+                        now_secs(),
+                    );
+                    status.lint = Some(LintSummary::new(report));
+                }
+            }
+
+            if session_file.exists() {
+                status.session = status.gather("session", SessionState::load(&session_file));
+            }
+            let link = status.session.as_ref().and_then(|s| s.plan.clone());
+            if let Some((plan, Some(gate))) = link.map(|l| (l.plan_id, l.approval_gate)) {
+                if let Some(found) = status.gather("approval", show_issue(dir, &gate)) {
+                    if !found.is_closed() {
+                        status.approval = Some(PendingApproval {
+                            plan,
+                            gate,
+                            status: found.status,
+                        });
+                    }
+                }
+            }
+            let mut checks = health::check_beads(dir, HealthOptions::default());
+            if let Some(loaded) = status.gather("health", load_config(dir)) {
+                checks.extend(toolchain::check_toolchain(dir, &loaded.config.toolchain));
+                checks.extend(build_cache::check_repo_cache(
+                    dir,
+                    &loaded.config.worktree.cache,
+                ));
+            }
+            status.health = Some(HealthSummary::new(checks));
+            if let Some(events) = status.gather("activity", read_events(&log_file)) {
+                status.add_feed(&events, issue.as_ref(), failures);
+            }
+
+            if format == "json" {
+                println!("{}", serde_json::to_string_pretty(&status).unwrap());
+            } else {
+                print!("{}", status::render_text(&status));
+            }
+        }
+
+        Commands::Estimate {
+            issue,
+            dir,
+            log_file,
+            metrics_file,
+            format,
+        } => {
+            let log_file = path_or(log_file, dirs.feed_file());
+            let metrics_file = path_or(metrics_file, dirs.metrics_file());
+            let directory = dir.unwrap_or_else(|| ".".to_string());
+            let dir = Path::new(&directory);
+            let issue = show_issue(dir, &issue).unwrap_or_else(|e| fail(&e));
+            // Open issues give the epic position, closed ones the history
+            let mut issues = list_issues(dir, &ListFilter::default()).unwrap_or_else(|e| fail(&e));
+            let closed = ListFilter {
+                status: Some("closed".to_string()),
+                ..Default::default()
+            };
+            issues.extend(list_issues(dir, &closed).unwrap_or_else(|e| fail(&e)));
+            let events = read_events(&log_file).unwrap_or_else(|e| fail(&e));
+            let samples = metrics::read_samples(&metrics_file).unwrap_or_else(|e| fail(&e));
+            let loaded = load_config(dir).unwrap_or_else(|e| fail(&e));
+            let est = estimate::estimate(
+                &issue,
+                &issues,
+                &estimate::task_durations(&events),
+                &estimate::task_iterations(&samples),
+                &loaded.config.iterations,
+            );
+            if format == "json" {
+                println!("{}", serde_json::to_string_pretty(&est).unwrap());
+            } else {
+                println!("Estimate for {}: {}", est.issue_id, issue.title);
+                println!("  complexity:  {}", est.complexity);
+                println!(
+                    "  iterations:  {} (limit {})",
+                    est.iterations, est.max_iterations
+                );
+                match est.duration_secs {
+                    Some((low, high)) => println!(
+                        "  duration:    {} - {}",
+                        format_duration(low),
+                        format_duration(high)
+                    ),
+                    None => println!("  duration:    unknown (no completed similar tasks)"),
+                }
+                println!(
+                    "  confidence:  {} ({} similar)",
+                    est.confidence,
+                    est.similar.len()
+                );
+                if !est.open_blockers.is_empty() {
+                    println!("  waits on:    {}", est.open_blockers.join(", "));
+                }
+            }
+        }
+
+        Commands::Plugins { format } => {
+            let plugins = plugins::installed_plugins();
+            if format == "json" {
+                println!("{}", serde_json::to_string_pretty(&plugins).unwrap());
+            } else if plugins.is_empty() {
+                println!("No plugins found (install ralph-beads-<name> executables on PATH)");
+            } else {
+                for plugin in &plugins {
+                    println!("{:<18} {}", plugin.name, plugin.path.display());
+                }
+            }
+        }
+
+        Commands::External(args) => {
+            let (name, args) = args
+                .split_first()
+                .unwrap_or_else(|| fail("Missing plugin name"));
+            let Some(plugin) = plugins::find_plugin(name) else {
+                fail(&format!(
+                    "Unknown command '{}' (no {}{} on PATH; see --help)",
+                    name,
+                    plugins::PLUGIN_PREFIX,
+                    name
+                ));
+            };
+            let loaded = load_config(Path::new(".")).unwrap_or_else(|e| fail(&e));
+            let context = PluginContext::new(name, Path::new("."), loaded);
+            let code = plugins::run_plugin(&plugin, args, &context).unwrap_or_else(|e| fail(&e));
+            exit_raw(code);
         }
 
-        Commands::Info { format } => {
-            let info = json!({
+        Commands::Report { command } => match command {
+            ReportCommands::Status {
+                epic,
+                dir,
+                failures,
+                log_file,
+                metrics_file,
+                format,
+            } => {
+                let log_file = path_or(log_file, dirs.feed_file());
+                let metrics_file = path_or(metrics_file, dirs.metrics_file());
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let dir = Path::new(&directory);
+                let epic = show_issue(dir, &epic).unwrap_or_else(|e| fail(&e));
+                let tasks = epic_tasks(dir, &epic.id).unwrap_or_else(|e| fail(&e));
+                // Closed issues across the project are the estimates' history
+                let closed = ListFilter {
+                    status: Some("closed".to_string()),
+                    ..Default::default()
+                };
+                let mut history = list_issues(dir, &closed).unwrap_or_else(|e| fail(&e));
+                history.extend(tasks.iter().filter(|t| !t.is_closed()).cloned());
+                let events = read_events(&log_file).unwrap_or_else(|e| fail(&e));
+                let samples = metrics::read_samples(&metrics_file).unwrap_or_else(|e| fail(&e));
+                let loaded = load_config(dir).unwrap_or_else(|e| fail(&e));
+                let durations = estimate::task_durations(&events);
+                let iterations = estimate::task_iterations(&samples);
+                let estimates = tasks
+                    .iter()
+                    .filter(|t| !t.is_closed())
+                    .filter_map(|t| {
+                        let est = estimate::estimate(
+                            t,
+                            &history,
+                            &durations,
+                            &iterations,
+                            &loaded.config.iterations,
+                        );
+                        est.duration_secs.map(|range| (t.id.clone(), range))
+                    })
+                    .collect();
+
+                let status =
+                    report::build_status(&epic, &tasks, &events, &estimates, failures, now_secs());
+                match format.as_str() {
+                    "json" => println!("{}", serde_json::to_string_pretty(&status).unwrap()),
+                    "html" => print!("{}", report::render_html(&status)),
+                    _ => print!("{}", report::render_markdown(&status)),
+                }
+            }
+
+            ReportCommands::Velocity {
+                epic,
+                dir,
+                days,
+                log_file,
+                format,
+            } => {
+                let log_file = path_or(log_file, dirs.feed_file());
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let dir = Path::new(&directory);
+                let epic = show_issue(dir, &epic).unwrap_or_else(|e| fail(&e));
+                let tasks = epic_tasks(dir, &epic.id).unwrap_or_else(|e| fail(&e));
+                let events = read_events(&log_file).unwrap_or_else(|e| fail(&e));
+
+                let velocity = velocity::build_velocity(&epic, &tasks, &events, days, now_secs());
+                if format == "json" {
+                    println!("{}", serde_json::to_string_pretty(&velocity).unwrap());
+                } else {
+                    print!("{}", velocity::render_text(&velocity));
+                }
+            }
+        },
+
+        Commands::Diff { command } => match command {
+            DiffCommands::Summarize {
+                base,
+                staged,
+                dir,
+                format,
+            } => {
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let summary = diff::summarize(Path::new(&directory), base.as_deref(), staged)
+                    .unwrap_or_else(|e| fail(&e));
+                if format == "json" {
+                    println!("{}", serde_json::to_string_pretty(&summary).unwrap());
+                } else {
+                    print!("{}", diff::render_text(&summary));
+                }
+            }
+        },
+
+        Commands::Task { command } => match command {
+            TaskCommands::Context { id, dir, format } => {
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                let dir = Path::new(&directory);
+                let task = show_issue(dir, &id).unwrap_or_else(|e| fail(&e));
+                let epic = task
+                    .parent()
+                    .map(|parent| epic_summary(dir, parent).unwrap_or_else(|e| fail(&e)));
+                let blockers = task
+                    .blockers()
+                    .map(|blocker| {
+                        let issue = show_issue(dir, blocker).unwrap_or_else(|e| fail(&e));
+                        // Only a closed blocker's comments describe what it produced
+                        let comments = if issue.is_closed() {
+                            list_comments(dir, blocker).unwrap_or_else(|e| fail(&e))
+                        } else {
+                            Vec::new()
+                        };
+                        (issue, comments)
+                    })
+                    .collect();
+                let (framework, test_command) = detect_framework(&directory);
+                let framework = bundle::FrameworkCommands {
+                    framework,
+                    test_command,
+                };
+                let bundle = bundle::TaskBundle::new(task, epic, blockers, framework, now_secs());
+                if format == "json" {
+                    println!("{}", serde_json::to_string_pretty(&bundle).unwrap());
+                } else {
+                    print!("{}", bundle::render_markdown(&bundle));
+                }
+            }
+
+            TaskCommands::Split {
+                id,
+                into,
+                no_setup,
+                dry_run,
+                dir,
+                format,
+            } => {
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                split_task(&id, into, no_setup, dry_run, Path::new(&directory), &format);
+            }
+        },
+
+        Commands::Swarm { command } => match command {
+            SwarmCommands::Split {
+                id,
+                into,
+                no_setup,
+                dry_run,
+                dir,
+                format,
+            } => {
+                let directory = dir.unwrap_or_else(|| ".".to_string());
+                split_task(&id, into, no_setup, dry_run, Path::new(&directory), &format);
+            }
+        },
+
+        Commands::Schema { command } => match command {
+            Some(command) => {
+                let schema = schema::schema_for(&command).unwrap_or_else(|e| fail(&e));
+                println!("{}", serde_json::to_string_pretty(&schema).unwrap());
+            }
+            None => {
+                for info in schema::SCHEMAS {
+                    println!("{:<16} {}", info.command, info.description);
+                }
+            }
+        },
+
+        Commands::Info {
+            format,
+            check_bd,
+            dir,
+            paths,
+        } => {
+            let directory = dir.unwrap_or_else(|| ".".to_string());
+            let dir = Path::new(&directory);
+            let bd = check_bd.then(|| beads::check_bd(dir));
+            let project = project_descriptor(dir);
+            let mut info = json!({
                 "version": env!("CARGO_PKG_VERSION"),
                 "capabilities": [
                     "detect-complexity",
                     "detect-framework",
-                    "calc-iterations"
+                    "calc-iterations",
+                    "worktree-conflicts",
+                    "activity",
+                    "lint",
+                    "beads-state",
+                    "epic-scaffold",
+                    "graph-analyze",
+                    "hooks",
+                    "github",
+                    "metrics",
+                    "cost",
+                    "plugins",
+                    "context-compile",
+                    "state-transition",
+                    "estimate",
+                    "worktree-report",
+                    "config-check",
+                    "subprocess-limits",
+                    "init",
+                    "workspace",
+                    "state-migrate",
+                    "worktree-issues",
+                    "graph-validate",
+                    "health",
+                    "schema",
+                    "plan-repl",
+                    "report-status",
+                    "state-cas",
+                    "bd-fixtures",
+                    "activity-retention",
+                    "task-context",
+                    "diff-summarize",
+                    "plan-approval",
+                    "worktree-bootstrap",
+                    "data-dirs",
+                    "status",
+                    "report-velocity",
+                    "issue-create",
+                    "stop-conditions",
+                    "swarm-split",
+                    "toolchain-pins",
+                    "worktree-cache",
+                    "worktree-guard"
                 ],
+                "exit_codes": exit_code::catalogue(),
+                "project": project,
                 "complexity_levels": ["trivial", "simple", "standard", "critical"],
                 "workflow_modes": ["planning", "building", "paused", "complete"]
             });
+            if let Some(ref bd) = bd {
+                info["bd"] = serde_json::to_value(bd).unwrap();
+            }
+            let locations = paths.then(|| path_locations(dir, dirs));
+            if let Some(ref locations) = locations {
+                info["paths"] = serde_json::to_value(locations).unwrap();
+            }
             if format == "json" {
                 println!("{}", serde_json::to_string_pretty(&info).unwrap());
             } else {
@@ -139,6 +4638,132 @@ fn main() {
                 println!("  - detect-complexity: Analyze task description for complexity");
                 println!("  - detect-framework: Detect test framework from project files");
                 println!("  - calc-iterations: Calculate max iterations for mode/complexity");
+                println!("  - worktree-conflicts: Forecast conflicts between worktree branches");
+                println!("  - activity: Publish and read events on the local activity feed");
+                println!("  - lint: Lint beads issues with configurable rules");
+                println!("  - beads-state: Typed state dimensions with transition checks");
+                println!("  - epic-scaffold: Create epics and tasks from markdown plans");
+                println!("  - graph-analyze: Critical path, waves, and bottlenecks of an epic");
+                println!("  - hooks: Run configured commands at lifecycle points");
+                println!("  - github: PR and workflow run status via gh");
+                println!("  - metrics: Record workflow metrics and export to Prometheus");
+                println!("  - cost: Token cost ledger with per-epic reports and budgets");
+                println!("  - plugins: ralph-beads-<name> executables on PATH as subcommands");
+                println!("  - context-compile: Render issue context from prompt templates");
+                println!("  - state-transition: Validated workflow mode changes with history");
+                println!("  - estimate: Iterations and duration range from similar past tasks");
+                println!("  - worktree-report: Disk usage, age, and divergence per worktree");
+                println!(
+                    "  - config-check: Validate configuration and show where settings come from"
+                );
+                println!("  - subprocess-limits: Machine-wide cap and quota on bd/git spawns");
+                println!("  - init: Bootstrap config, beads database, and git hooks");
+                println!("  - workspace: Aggregate lint reports across a multi-repo workspace");
+                println!("  - state-migrate: Upgrade session files to the current schema");
+                println!(
+                    "  - worktree-issues: Create worktrees for issues and list who serves what"
+                );
+                println!("  - graph-validate: Structural checks on an epic's dependency graph");
+                println!("  - health: Check the beads directory, redirects, locks, and daemon");
+                println!("  - schema: JSON Schemas for structured command output");
+                println!("  - plan-repl: Draft an epic's tasks interactively, then create them");
+                println!("  - report-status: Epic status document in Markdown, HTML, or JSON");
+                println!("  - state-cas: Revisioned session updates that refuse stale writes");
+                println!("  - bd-fixtures: Record bd calls for replay in tests");
+                println!("  - activity-retention: Prune the feed, compacting old events by day");
+                println!("  - task-context: One bundle of everything a worker needs for a task");
+                println!("  - diff-summarize: Files, lines by language, renames, and risky paths");
+                println!("  - plan-approval: Building waits for the linked plan's approval gate");
+                println!("  - worktree-bootstrap: Copy, link, and set up each new issue worktree");
+                println!("  - data-dirs: XDG locations for state, one --data-dir to move them all");
+                println!("  - status: Worktree, issue, session, health, gates, failures, and lint");
+                println!("  - report-velocity: Epic completions per day and projected finish");
+                println!(
+                    "  - issue-create: Issues from templates, refused when the draft fails lint"
+                );
+                println!(
+                    "  - stop-conditions: Failures, no progress, budget, gates, or a human stop"
+                );
+                println!("  - swarm-split: Split an oversized task into parts by its criteria");
+                println!("  - toolchain-pins: rustc, node, python, and bd checked against pins");
+                println!("  - worktree-cache: Share build caches between worktrees");
+                println!("  - worktree-guard: Refuse work on an issue outside its worktree");
+                println!("\nProject:");
+                println!(
+                    "  config: {}",
+                    project["config_file"]
+                        .as_str()
+                        .unwrap_or("built-in defaults")
+                );
+                if let Some(error) = project["config_error"].as_str() {
+                    println!("  config error: {}", error);
+                }
+                for (key, label) in [
+                    ("hooks", "hooks"),
+                    ("state_dimensions", "state dimensions"),
+                    ("templates", "templates"),
+                    ("plugins", "plugins"),
+                ] {
+                    let names: Vec<String> = match &project[key] {
+                        serde_json::Value::Object(map) => map
+                            .iter()
+                            .map(|(name, count)| format!("{} ({})", name, count))
+                            .collect(),
+                        serde_json::Value::Array(items) => items
+                            .iter()
+                            .filter_map(|v| v.as_str().map(String::from))
+                            .collect(),
+                        _ => Vec::new(),
+                    };
+                    if !names.is_empty() {
+                        println!("  {}: {}", label, names.join(", "));
+                    }
+                }
+                if let Some(ref locations) = locations {
+                    println!(
+                        "\nPaths{}:",
+                        if dirs.overridden { " (--data-dir)" } else { "" }
+                    );
+                    for location in locations {
+                        println!("  {:<10} {}", location.name, location.path.display());
+                    }
+                }
+                println!("\nExit codes:");
+                for entry in exit_code::catalogue() {
+                    println!(
+                        "  {:>2} {:<15} {}",
+                        entry.code, entry.name, entry.description
+                    );
+                }
+                if let Some(ref bd) = bd {
+                    println!("\nbd:");
+                    match (bd.available, &bd.version) {
+                        (true, Some(version)) => println!("  available: yes ({})", version),
+                        (true, None) => println!("  available: yes"),
+                        (false, _) => println!("  available: no"),
+                    }
+                    if bd.available {
+                        println!(
+                            "  compatible: {}",
+                            if bd.compatible {
+                                "yes"
+                            } else {
+                                "no (needs bd mol support)"
+                            }
+                        );
+                    }
+                    if !bd.features.is_empty() {
+                        let features: Vec<String> =
+                            bd.features.iter().map(|f| f.to_string()).collect();
+                        println!("  features: {}", features.join(", "));
+                    }
+                    if let Some(ref e) = bd.error {
+                        println!("  {}", e.to_string().replace('\n', "\n  "));
+                    }
+                }
+            }
+            if bd.is_some_and(|bd| !bd.available || !bd.compatible) {
+                exit(ExitCode::BdUnavailable);
             }
         }
     }
@@ -159,4 +4784,19 @@ mod tests {
         // Just verify it doesn't panic
         output_result("json", "key", "value");
     }
+
+    #[test]
+    fn test_failure_keeps_bd_error_class() {
+        let timed_out = BdError::TimedOut {
+            command: "bd list".to_string(),
+            timeout_secs: 30,
+        };
+
+        let failure = Failure::from(&timed_out);
+        let plain = Failure::from(timed_out.to_string().as_str());
+
+        assert_eq!(failure.code, ExitCode::Timeout);
+        assert_eq!(failure.message, "bd list timed out after 30s");
+        assert_eq!(plain.code, ExitCode::Error);
+    }
 }
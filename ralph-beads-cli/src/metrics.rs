@@ -0,0 +1,407 @@
+//! Workflow metrics
+//!
+//! Counters and histograms are recorded as samples in a local JSONL file
+//! (`.beads/metrics.jsonl` by default), aggregated on demand, and exported
+//! in the Prometheus text exposition format. Only metrics listed in
+//! [`METRICS`] can be recorded, so dashboards see a stable schema.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::activity::{append_json_line, now_secs};
+
+/// Default location of the metrics file, relative to the project root
+/// (`--data-dir` moves it; see [`crate::data_dirs`])
+pub const DEFAULT_METRICS_FILE: &str = ".beads/metrics.jsonl";
+
+/// Prefix applied to exported metric names
+const PROMETHEUS_PREFIX: &str = "ralph_";
+
+/// Histogram bucket upper bounds in seconds
+const BUCKETS: &[f64] = &[
+    0.5, 1.0, 5.0, 15.0, 30.0, 60.0, 300.0, 900.0, 1800.0, 3600.0,
+];
+
+/// Kind of metric
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetricKind {
+    /// Monotonic count; samples are increments
+    Counter,
+    /// Distribution of observed values
+    Histogram,
+}
+
+impl fmt::Display for MetricKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetricKind::Counter => write!(f, "counter"),
+            MetricKind::Histogram => write!(f, "histogram"),
+        }
+    }
+}
+
+/// Schema entry for a known metric
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MetricInfo {
+    pub name: &'static str,
+    pub kind: MetricKind,
+    pub help: &'static str,
+}
+
+/// All metrics that can be recorded
+pub const METRICS: &[MetricInfo] = &[
+    MetricInfo {
+        name: "iterations_total",
+        kind: MetricKind::Counter,
+        help: "Loop iterations run",
+    },
+    MetricInfo {
+        name: "preflight_duration_seconds",
+        kind: MetricKind::Histogram,
+        help: "Time spent in preflight checks",
+    },
+    MetricInfo {
+        name: "gate_wait_seconds",
+        kind: MetricKind::Histogram,
+        help: "Time spent waiting for gates to resolve",
+    },
+    MetricInfo {
+        name: "task_claim_latency_seconds",
+        kind: MetricKind::Histogram,
+        help: "Time from a task becoming ready to being claimed",
+    },
+    MetricInfo {
+        name: "failure_patterns_total",
+        kind: MetricKind::Counter,
+        help: "Occurrences of known failure patterns",
+    },
+];
+
+/// Look up a metric by name
+pub fn metric_info(name: &str) -> Result<&'static MetricInfo, String> {
+    METRICS.iter().find(|m| m.name == name).ok_or_else(|| {
+        let known: Vec<&str> = METRICS.iter().map(|m| m.name).collect();
+        format!(
+            "Unknown metric '{}' (known metrics: {})",
+            name,
+            known.join(", ")
+        )
+    })
+}
+
+/// A single recorded sample
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricSample {
+    /// Unix timestamp (seconds)
+    pub timestamp: u64,
+    pub name: String,
+    pub value: f64,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+}
+
+/// Parse a `key=value` label argument
+pub fn parse_label(s: &str) -> Result<(String, String), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid label '{}': expected key=value", s))?;
+    let valid_key = !key.is_empty()
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !key.starts_with(|c: char| c.is_ascii_digit());
+    if !valid_key {
+        return Err(format!("Invalid label name '{}'", key));
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// Validate and append a sample to the metrics file
+pub fn record_metric(
+    metrics_file: &Path,
+    name: &str,
+    value: f64,
+    labels: BTreeMap<String, String>,
+) -> Result<MetricSample, String> {
+    let info = metric_info(name)?;
+    if !value.is_finite() || value < 0.0 {
+        return Err(format!(
+            "Metric value must be a non-negative number, got {}",
+            value
+        ));
+    }
+    if info.kind == MetricKind::Counter && value.fract() != 0.0 {
+        return Err(format!(
+            "Counter {} takes whole increments, got {}",
+            name, value
+        ));
+    }
+
+    let sample = MetricSample {
+        timestamp: now_secs(),
+        name: name.to_string(),
+        value,
+        labels,
+    };
+    append_json_line(metrics_file, &sample)?;
+    Ok(sample)
+}
+
+/// Read all samples (missing file = no samples, malformed lines skipped)
+pub fn read_samples(metrics_file: &Path) -> Result<Vec<MetricSample>, String> {
+    if !metrics_file.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(metrics_file)
+        .map_err(|e| format!("Failed to read {}: {}", metrics_file.display(), e))?;
+
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Aggregated samples of one metric and label set
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricSeries {
+    pub name: String,
+    pub kind: MetricKind,
+    pub labels: BTreeMap<String, String>,
+    pub count: u64,
+    pub sum: f64,
+    pub min: f64,
+    pub max: f64,
+    /// Cumulative counts per bucket upper bound (histograms only)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub buckets: Vec<(f64, u64)>,
+}
+
+impl MetricSeries {
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.sum / self.count as f64
+        }
+    }
+}
+
+/// Aggregate samples into series, ordered by name then labels
+///
+/// Samples for metrics no longer in the schema are ignored.
+pub fn aggregate(samples: &[MetricSample]) -> Vec<MetricSeries> {
+    let mut series: BTreeMap<(String, Vec<(String, String)>), MetricSeries> = BTreeMap::new();
+
+    for sample in samples {
+        let Ok(info) = metric_info(&sample.name) else {
+            continue;
+        };
+        let key = (
+            sample.name.clone(),
+            sample
+                .labels
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+        );
+        let entry = series.entry(key).or_insert_with(|| MetricSeries {
+            name: sample.name.clone(),
+            kind: info.kind,
+            labels: sample.labels.clone(),
+            count: 0,
+            sum: 0.0,
+            min: f64::INFINITY,
+            max: 0.0,
+            buckets: match info.kind {
+                MetricKind::Histogram => BUCKETS.iter().map(|&b| (b, 0)).collect(),
+                MetricKind::Counter => Vec::new(),
+            },
+        });
+
+        entry.count += 1;
+        entry.sum += sample.value;
+        entry.min = entry.min.min(sample.value);
+        entry.max = entry.max.max(sample.value);
+        for (bound, count) in entry.buckets.iter_mut() {
+            if sample.value <= *bound {
+                *count += 1;
+            }
+        }
+    }
+
+    series.into_values().collect()
+}
+
+fn format_labels(labels: &BTreeMap<String, String>, extra: Option<(&str, String)>) -> String {
+    let mut parts: Vec<String> = labels
+        .iter()
+        .map(|(k, v)| {
+            let escaped = v
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{}=\"{}\"", k, escaped)
+        })
+        .collect();
+    if let Some((k, v)) = extra {
+        parts.push(format!("{}=\"{}\"", k, v));
+    }
+    if parts.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", parts.join(","))
+    }
+}
+
+/// Render series in the Prometheus text exposition format
+pub fn render_prometheus(series: &[MetricSeries]) -> String {
+    let mut out = String::new();
+    let mut current: Option<&str> = None;
+
+    for s in series {
+        let name = format!("{}{}", PROMETHEUS_PREFIX, s.name);
+        if current != Some(s.name.as_str()) {
+            current = Some(s.name.as_str());
+            if let Ok(info) = metric_info(&s.name) {
+                out.push_str(&format!("# HELP {} {}\n", name, info.help));
+            }
+            out.push_str(&format!("# TYPE {} {}\n", name, s.kind));
+        }
+
+        match s.kind {
+            MetricKind::Counter => {
+                out.push_str(&format!(
+                    "{}{} {}\n",
+                    name,
+                    format_labels(&s.labels, None),
+                    s.sum
+                ));
+            }
+            MetricKind::Histogram => {
+                for (bound, count) in &s.buckets {
+                    out.push_str(&format!(
+                        "{}_bucket{} {}\n",
+                        name,
+                        format_labels(&s.labels, Some(("le", bound.to_string()))),
+                        count
+                    ));
+                }
+                out.push_str(&format!(
+                    "{}_bucket{} {}\n",
+                    name,
+                    format_labels(&s.labels, Some(("le", "+Inf".to_string()))),
+                    s.count
+                ));
+                let labels = format_labels(&s.labels, None);
+                out.push_str(&format!("{}_sum{} {}\n", name, labels, s.sum));
+                out.push_str(&format!("{}_count{} {}\n", name, labels, s.count));
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn sample(name: &str, value: f64, labels: &[(&str, &str)]) -> MetricSample {
+        MetricSample {
+            timestamp: 0,
+            name: name.to_string(),
+            value,
+            labels: labels
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_record_and_read() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("nested/metrics.jsonl");
+
+        record_metric(&file, "iterations_total", 1.0, BTreeMap::new()).unwrap();
+        record_metric(&file, "gate_wait_seconds", 12.5, BTreeMap::new()).unwrap();
+
+        let samples = read_samples(&file).unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[1].value, 12.5);
+    }
+
+    #[test]
+    fn test_record_validation() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("metrics.jsonl");
+
+        let err = record_metric(&file, "nope", 1.0, BTreeMap::new()).unwrap_err();
+        assert!(err.contains("iterations_total"));
+        assert!(record_metric(&file, "iterations_total", 1.5, BTreeMap::new()).is_err());
+        assert!(record_metric(&file, "gate_wait_seconds", -1.0, BTreeMap::new()).is_err());
+        assert!(record_metric(&file, "gate_wait_seconds", f64::NAN, BTreeMap::new()).is_err());
+        assert!(read_samples(&file).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_label() {
+        assert_eq!(
+            parse_label("pattern=timeout").unwrap(),
+            ("pattern".to_string(), "timeout".to_string())
+        );
+        assert_eq!(parse_label("k=a=b").unwrap().1, "a=b");
+        assert!(parse_label("novalue").is_err());
+        assert!(parse_label("bad-key=x").is_err());
+        assert!(parse_label("1st=x").is_err());
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let samples = vec![
+            sample("gate_wait_seconds", 2.0, &[]),
+            sample("gate_wait_seconds", 40.0, &[]),
+            sample("failure_patterns_total", 1.0, &[("pattern", "timeout")]),
+            sample("failure_patterns_total", 2.0, &[("pattern", "timeout")]),
+            sample("failure_patterns_total", 1.0, &[("pattern", "oom")]),
+            sample("retired_metric", 1.0, &[]),
+        ];
+
+        let series = aggregate(&samples);
+
+        assert_eq!(series.len(), 3);
+        assert_eq!(series[0].labels["pattern"], "oom");
+        assert_eq!(series[1].sum, 3.0);
+        let gate = &series[2];
+        assert_eq!(gate.count, 2);
+        assert_eq!(gate.mean(), 21.0);
+        assert_eq!((gate.min, gate.max), (2.0, 40.0));
+        assert_eq!(gate.buckets[1], (1.0, 0));
+        assert_eq!(gate.buckets[2], (5.0, 1));
+        assert_eq!(gate.buckets[5], (60.0, 2));
+    }
+
+    #[test]
+    fn test_render_prometheus() {
+        let samples = vec![
+            sample("iterations_total", 1.0, &[]),
+            sample("iterations_total", 1.0, &[]),
+            sample("gate_wait_seconds", 2.0, &[("gate", "ci \"main\"")]),
+        ];
+
+        let text = render_prometheus(&aggregate(&samples));
+
+        assert!(text.contains("# TYPE ralph_iterations_total counter\nralph_iterations_total 2\n"));
+        assert!(text.contains("# HELP ralph_gate_wait_seconds "));
+        assert!(
+            text.contains("ralph_gate_wait_seconds_bucket{gate=\"ci \\\"main\\\"\",le=\"5\"} 1\n")
+        );
+        assert!(text
+            .contains("ralph_gate_wait_seconds_bucket{gate=\"ci \\\"main\\\"\",le=\"+Inf\"} 1\n"));
+        assert!(text.contains("ralph_gate_wait_seconds_count{gate=\"ci \\\"main\\\"\"} 1\n"));
+    }
+}
@@ -0,0 +1,494 @@
+//! Interactive planning sessions
+//!
+//! `plan repl` builds an epic's task structure one line at a time instead
+//! of dozens of separate `bd` calls. Nothing touches beads until `commit`,
+//! which creates every task and dependency through `epic::scaffold_into`
+//! and rolls back if any call fails.
+//!
+//! Tasks use the bullet syntax of markdown plans (`Title {#key}
+//! (after: a, b) (priority: 1)`) and are referred to by key.
+
+use std::io::{BufRead, Write};
+
+use crate::beads::{Dependency, Issue};
+use crate::epic::{parse_task_line, render_graph, slugify, topological_order, Plan, PlanTask};
+use crate::graph::{self, DepGraph};
+use crate::lint::{self, LintConfig, LintContext};
+
+/// Help text listing the session commands
+pub const HELP: &str = "\
+Commands:
+  title <text>                        Set the epic title (new epics only)
+  add <title> [{#key}] [(after: k1, k2)] [(priority: N)]
+  edit <key> title|desc|ac|priority <value>
+  split <key> <title> | <title> ...   Replace a task with parallel parts
+  dep <key> <blocker-key>             <key> waits for <blocker-key>
+  undep <key> <blocker-key>
+  rm <key>
+  show                                Print the task graph
+  analyze                             Waves, critical path, bottlenecks
+  lint                                Lint the drafts with the project rules
+  commit                              Create everything in beads and exit
+  quit                                Exit without creating anything
+  help";
+
+/// What the driver should do after a command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// Print the text and read the next command
+    Continue(String),
+    Commit,
+    Quit,
+}
+
+/// A draft epic being edited
+pub struct PlanSession {
+    pub plan: Plan,
+    /// Existing epic the tasks will be created under
+    pub epic: Option<String>,
+    lint_config: LintConfig,
+}
+
+impl PlanSession {
+    /// Start a session for a new epic, or for tasks under `epic`
+    pub fn new(title: &str, epic: Option<String>, lint_config: LintConfig) -> Self {
+        PlanSession {
+            plan: Plan {
+                title: title.to_string(),
+                description: String::new(),
+                tasks: Vec::new(),
+            },
+            epic,
+            lint_config,
+        }
+    }
+
+    fn index(&self, key: &str) -> Result<usize, String> {
+        self.plan
+            .tasks
+            .iter()
+            .position(|t| t.key == key)
+            .ok_or_else(|| format!("No task with key '{}'", key))
+    }
+
+    /// Resolve a key or title reference, as `(after: ...)` does in plans
+    fn resolve(&self, reference: &str) -> Result<usize, String> {
+        self.index(reference).or_else(|_| {
+            self.plan
+                .tasks
+                .iter()
+                .position(|t| t.title.eq_ignore_ascii_case(reference))
+                .ok_or_else(|| format!("Unknown task '{}'", reference))
+        })
+    }
+
+    /// Run `change` on the plan, undoing it if it introduces a cycle
+    fn guarded(&mut self, change: impl FnOnce(&mut Plan)) -> Result<(), String> {
+        let before = self.plan.clone();
+        change(&mut self.plan);
+        if let Err(e) = topological_order(&self.plan) {
+            self.plan = before;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn add(&mut self, spec: &str) -> Result<String, String> {
+        let raw = parse_task_line(spec, None, 1).map_err(|e| e.replace("line 1: ", ""))?;
+        if self.index(&raw.key).is_ok() {
+            return Err(format!(
+                "Key '{}' is taken (add {{#key}} to pick another)",
+                raw.key
+            ));
+        }
+        let depends_on = raw
+            .after
+            .iter()
+            .flatten()
+            .map(|r| self.resolve(r))
+            .collect::<Result<Vec<_>, _>>()?;
+        let key = raw.key.clone();
+        self.plan.tasks.push(PlanTask {
+            key: raw.key,
+            title: raw.title,
+            description: String::new(),
+            phase: None,
+            priority: raw.priority,
+            acceptance_criteria: String::new(),
+            depends_on,
+        });
+        Ok(format!("Added {}", key))
+    }
+
+    fn edit(&mut self, args: &str) -> Result<String, String> {
+        let mut parts = args.splitn(3, ' ');
+        let (Some(key), Some(field), Some(value)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return Err("Usage: edit <key> title|desc|ac|priority <value>".to_string());
+        };
+        let i = self.index(key)?;
+        let value = value.trim();
+        let task = &mut self.plan.tasks[i];
+        match field {
+            "title" => task.title = value.to_string(),
+            "desc" => task.description = value.replace("\\n", "\n"),
+            "ac" => task.acceptance_criteria = value.replace("\\n", "\n"),
+            "priority" => {
+                task.priority = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("Invalid priority '{}'", value))?,
+                )
+            }
+            _ => return Err(format!("Unknown field '{}'", field)),
+        }
+        Ok(format!("Updated {} {}", key, field))
+    }
+
+    /// Replace a task with parts that share its blockers; its dependents
+    /// wait for every part
+    fn split(&mut self, args: &str) -> Result<String, String> {
+        let (key, rest) = args
+            .split_once(' ')
+            .ok_or("Usage: split <key> <title> | <title> ...")?;
+        let i = self.index(key)?;
+        let titles: Vec<&str> = rest
+            .split('|')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .collect();
+        if titles.len() < 2 {
+            return Err("Split needs at least two parts separated by '|'".to_string());
+        }
+        let keys: Vec<String> = titles
+            .iter()
+            .map(|t| format!("{}-{}", key, slugify(t, None)))
+            .collect();
+        if let Some(taken) = keys.iter().find(|k| self.index(k).is_ok()) {
+            return Err(format!("Key '{}' is taken", taken));
+        }
+
+        let original = self.plan.tasks.remove(i);
+        let first = self.plan.tasks.len();
+        for task in &mut self.plan.tasks {
+            let waits = task.depends_on.contains(&i);
+            task.depends_on.retain(|&d| d != i);
+            for d in &mut task.depends_on {
+                if *d > i {
+                    *d -= 1;
+                }
+            }
+            if waits {
+                task.depends_on.extend(first..first + titles.len());
+            }
+        }
+        let blockers: Vec<usize> = original
+            .depends_on
+            .iter()
+            .map(|&d| if d > i { d - 1 } else { d })
+            .collect();
+        for (title, key) in titles.iter().zip(&keys) {
+            self.plan.tasks.push(PlanTask {
+                key: key.clone(),
+                title: title.to_string(),
+                description: original.description.clone(),
+                phase: original.phase.clone(),
+                priority: original.priority,
+                acceptance_criteria: String::new(),
+                depends_on: blockers.clone(),
+            });
+        }
+        Ok(format!("Split {} into {}", key, keys.join(", ")))
+    }
+
+    fn dep(&mut self, args: &str, add: bool) -> Result<String, String> {
+        let usage = if add {
+            "Usage: dep <key> <blocker-key>"
+        } else {
+            "Usage: undep <key> <blocker-key>"
+        };
+        let (key, blocker) = args.split_once(' ').ok_or(usage)?;
+        let (i, b) = (self.index(key)?, self.index(blocker.trim())?);
+        if add {
+            if i == b {
+                return Err(format!("{} can't depend on itself", key));
+            }
+            self.guarded(|plan| {
+                if !plan.tasks[i].depends_on.contains(&b) {
+                    plan.tasks[i].depends_on.push(b);
+                }
+            })?;
+            Ok(format!("{} now waits for {}", key, blocker.trim()))
+        } else {
+            self.plan.tasks[i].depends_on.retain(|&d| d != b);
+            Ok(format!("{} no longer waits for {}", key, blocker.trim()))
+        }
+    }
+
+    fn remove(&mut self, key: &str) -> Result<String, String> {
+        let i = self.index(key)?;
+        self.plan.tasks.remove(i);
+        for task in &mut self.plan.tasks {
+            task.depends_on.retain(|&d| d != i);
+            for d in &mut task.depends_on {
+                if *d > i {
+                    *d -= 1;
+                }
+            }
+        }
+        Ok(format!("Removed {}", key))
+    }
+
+    /// The drafts as issues keyed by task key, for analysis and lint
+    pub fn draft_issues(&self) -> Vec<Issue> {
+        let tasks = &self.plan.tasks;
+        tasks
+            .iter()
+            .map(|task| Issue {
+                id: task.key.clone(),
+                title: task.title.clone(),
+                description: task.description.clone(),
+                acceptance_criteria: task.acceptance_criteria.clone(),
+                status: "open".to_string(),
+                priority: task.priority,
+                issue_type: "task".to_string(),
+                dependencies: task
+                    .depends_on
+                    .iter()
+                    .map(|&d| Dependency {
+                        issue_id: task.key.clone(),
+                        depends_on_id: tasks[d].key.clone(),
+                        dep_type: "blocks".to_string(),
+                    })
+                    .collect(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn analyze(&self) -> String {
+        let graph = DepGraph::new(self.draft_issues());
+        let label = self.epic.as_deref().unwrap_or("(draft)");
+        graph::render_text(&graph::analyze(label, &graph, 3))
+    }
+
+    fn lint(&self) -> String {
+        let issues = self.draft_issues();
+        let findings = lint::lint_issues(&LintContext {
+            issues: &issues,
+            related: &lint::Related::default(),
+            config: &self.lint_config,
+            now: 0,
+        });
+        if findings.is_empty() {
+            return "No lint findings".to_string();
+        }
+        findings
+            .iter()
+            .map(|f| format!("{} [{}] {}: {}", f.severity, f.rule, f.issue_id, f.message))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn show(&self) -> String {
+        let keys: Vec<String> = self.plan.tasks.iter().map(|t| t.key.clone()).collect();
+        let label = self.epic.as_deref().unwrap_or("(epic)");
+        render_graph(&self.plan, label, &keys)
+            .trim_end()
+            .to_string()
+    }
+
+    /// Whether the draft can be committed
+    fn ready(&self) -> Result<(), String> {
+        if self.epic.is_none() && self.plan.title.trim().is_empty() {
+            return Err("Set the epic title first: title <text>".to_string());
+        }
+        if self.plan.tasks.is_empty() {
+            return Err("Nothing to commit: add a task first".to_string());
+        }
+        topological_order(&self.plan).map(|_| ())
+    }
+
+    /// Run one command line
+    pub fn execute(&mut self, line: &str) -> Result<Step, String> {
+        let line = line.trim();
+        let (command, args) = line.split_once(' ').unwrap_or((line, ""));
+        let args = args.trim();
+        let text = match command {
+            "" => String::new(),
+            "help" | "?" => HELP.to_string(),
+            "title" => {
+                if self.epic.is_some() {
+                    return Err("The epic already exists; its title can't change here".into());
+                }
+                self.plan.title = args.to_string();
+                format!("Epic title: {}", args)
+            }
+            "add" => self.add(args)?,
+            "edit" => self.edit(args)?,
+            "split" => self.split(args)?,
+            "dep" => self.dep(args, true)?,
+            "undep" => self.dep(args, false)?,
+            "rm" => self.remove(args)?,
+            "show" => self.show(),
+            "analyze" => self.analyze(),
+            "lint" => self.lint(),
+            "commit" => {
+                self.ready()?;
+                return Ok(Step::Commit);
+            }
+            "quit" | "exit" => return Ok(Step::Quit),
+            _ => return Err(format!("Unknown command '{}' (try help)", command)),
+        };
+        Ok(Step::Continue(text))
+    }
+}
+
+/// Drive a session from `input`, echoing results to `output`
+///
+/// Returns `true` when the user asked to commit. End of input quits.
+pub fn run<R: BufRead, W: Write>(
+    session: &mut PlanSession,
+    input: R,
+    output: &mut W,
+    prompt: bool,
+) -> Result<bool, String> {
+    let io_err = |e: std::io::Error| e.to_string();
+    let mut lines = input.lines();
+    loop {
+        if prompt {
+            write!(output, "plan> ").map_err(io_err)?;
+            output.flush().map_err(io_err)?;
+        }
+        let Some(line) = lines.next() else {
+            return Ok(false);
+        };
+        match session.execute(&line.map_err(io_err)?) {
+            Ok(Step::Continue(text)) if text.is_empty() => {}
+            Ok(Step::Continue(text)) => writeln!(output, "{}", text).map_err(io_err)?,
+            Ok(Step::Commit) => return Ok(true),
+            Ok(Step::Quit) => return Ok(false),
+            Err(e) => writeln!(output, "error: {}", e).map_err(io_err)?,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(lines: &[&str]) -> PlanSession {
+        let mut session = PlanSession::new("", None, LintConfig::default());
+        for line in lines {
+            session.execute(line).unwrap();
+        }
+        session
+    }
+
+    fn deps(session: &PlanSession, key: &str) -> Vec<String> {
+        let i = session.index(key).unwrap();
+        session.plan.tasks[i]
+            .depends_on
+            .iter()
+            .map(|&d| session.plan.tasks[d].key.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_add_and_dependencies() {
+        let mut s = session(&[
+            "title Auth",
+            "add Create users table {#schema} (priority: 1)",
+            "add Login endpoint (after: schema)",
+            "add Logout endpoint",
+            "dep logout-endpoint login-endpoint",
+        ]);
+
+        assert_eq!(s.plan.title, "Auth");
+        assert_eq!(s.plan.tasks[0].priority, Some(1));
+        assert_eq!(deps(&s, "logout-endpoint"), vec!["login-endpoint"]);
+
+        let err = s.execute("dep schema logout-endpoint").unwrap_err();
+        assert!(err.contains("cycle"));
+        assert!(deps(&s, "schema").is_empty());
+        assert!(s
+            .execute("add Login endpoint")
+            .unwrap_err()
+            .contains("taken"));
+
+        s.execute("undep logout-endpoint login-endpoint").unwrap();
+        assert!(deps(&s, "logout-endpoint").is_empty());
+    }
+
+    #[test]
+    fn test_split_and_remove() {
+        let mut s = session(&[
+            "add Schema {#schema}",
+            "add API {#api} (after: schema)",
+            "add Docs {#docs} (after: api)",
+            "split api Login | Logout",
+        ]);
+
+        assert_eq!(deps(&s, "api-login"), vec!["schema"]);
+        assert_eq!(deps(&s, "api-logout"), vec!["schema"]);
+        assert_eq!(deps(&s, "docs"), vec!["api-login", "api-logout"]);
+        assert!(s.execute("edit api title X").is_err());
+
+        s.execute("rm schema").unwrap();
+        assert!(deps(&s, "api-login").is_empty());
+        assert_eq!(deps(&s, "docs"), vec!["api-login", "api-logout"]);
+    }
+
+    #[test]
+    fn test_lint_and_analyze_drafts() {
+        let mut s = session(&["add Schema {#schema} (priority: 1)"]);
+        let Step::Continue(text) = s.execute("lint").unwrap() else {
+            panic!("lint should continue");
+        };
+        assert!(text.contains("error [required-sections] schema"));
+
+        s.execute("edit schema desc Users table").unwrap();
+        s.execute("edit schema ac - [ ] Migration applies cleanly\\n- [ ] Rolls back")
+            .unwrap();
+        assert_eq!(
+            s.execute("lint").unwrap(),
+            Step::Continue("No lint findings".to_string())
+        );
+
+        s.execute("add API {#api} (after: schema) (priority: 2)")
+            .unwrap();
+        let Step::Continue(text) = s.execute("analyze").unwrap() else {
+            panic!("analyze should continue");
+        };
+        assert!(text.contains("Critical path (2): schema -> api"));
+    }
+
+    #[test]
+    fn test_commit_requires_title_and_tasks() {
+        let mut s = session(&[]);
+        assert!(s.execute("commit").unwrap_err().contains("title"));
+        s.execute("title Auth").unwrap();
+        assert!(s.execute("commit").unwrap_err().contains("add a task"));
+        s.execute("add Schema").unwrap();
+        assert_eq!(s.execute("commit").unwrap(), Step::Commit);
+
+        let mut existing =
+            PlanSession::new("Auth", Some("bd-1".to_string()), LintConfig::default());
+        assert!(existing.execute("title Other").is_err());
+    }
+
+    #[test]
+    fn test_run_reports_errors_and_continues() {
+        let mut s = session(&[]);
+        let input = "title Auth\nbogus\nadd Schema\nshow\ncommit\nadd Never\n";
+        let mut output = Vec::new();
+
+        let commit = run(&mut s, input.as_bytes(), &mut output, false).unwrap();
+
+        assert!(commit);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("error: Unknown command 'bogus'"));
+        assert!(output.contains("(epic) Auth\n  schema Schema\n"));
+        assert_eq!(s.plan.tasks.len(), 1);
+    }
+}
@@ -0,0 +1,194 @@
+//! External subcommand plugins
+//!
+//! Like cargo and git, any executable named `ralph-beads-<name>` on `PATH`
+//! becomes `ralph-beads-cli <name>`. The plugin receives the remaining
+//! arguments unchanged and the shared context (version, project directory,
+//! loaded `.ralph-beads.toml`) as JSON in `RALPH_BEADS_CONTEXT`.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::{Config, LoadedConfig};
+
+/// Executable name prefix that marks a plugin
+pub const PLUGIN_PREFIX: &str = "ralph-beads-";
+
+/// Environment variable carrying the plugin context
+pub const CONTEXT_ENV: &str = "RALPH_BEADS_CONTEXT";
+
+/// Names that can't be plugins: the CLI itself
+const RESERVED: &[&str] = &["cli"];
+
+/// A discovered plugin executable
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Plugin {
+    /// Subcommand name (executable name without the prefix)
+    pub name: String,
+    pub path: PathBuf,
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Plugins in the directories of `path_var`; earlier directories win
+pub fn discover_plugins(path_var: &OsStr) -> Vec<Plugin> {
+    let mut plugins: BTreeMap<String, PathBuf> = BTreeMap::new();
+    for dir in env::split_paths(path_var) {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let file_name = entry.file_name();
+            let Some(file_name) = file_name.to_str() else {
+                continue;
+            };
+            let stem = file_name.strip_suffix(".exe").unwrap_or(file_name);
+            let Some(name) = stem.strip_prefix(PLUGIN_PREFIX) else {
+                continue;
+            };
+            if name.is_empty() || RESERVED.contains(&name) || plugins.contains_key(name) {
+                continue;
+            }
+            let path = entry.path();
+            if is_executable(&path) {
+                plugins.insert(name.to_string(), path);
+            }
+        }
+    }
+    plugins
+        .into_iter()
+        .map(|(name, path)| Plugin { name, path })
+        .collect()
+}
+
+/// Plugins on the current `PATH`
+pub fn installed_plugins() -> Vec<Plugin> {
+    env::var_os("PATH")
+        .map(|path| discover_plugins(&path))
+        .unwrap_or_default()
+}
+
+/// Find the plugin providing subcommand `name`
+pub fn find_plugin(name: &str) -> Option<Plugin> {
+    installed_plugins().into_iter().find(|p| p.name == name)
+}
+
+/// Context handed to plugins
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginContext {
+    pub version: String,
+    pub plugin: String,
+    pub dir: PathBuf,
+    /// `.ralph-beads.toml` that applied, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config_file: Option<PathBuf>,
+    pub config: Config,
+}
+
+impl PluginContext {
+    pub fn new(plugin: &str, dir: &Path, loaded: LoadedConfig) -> Self {
+        PluginContext {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            plugin: plugin.to_string(),
+            dir: dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf()),
+            config_file: loaded.source,
+            config: loaded.config,
+        }
+    }
+}
+
+/// Run `plugin` with `args`, returning its exit code
+pub fn run_plugin(
+    plugin: &Plugin,
+    args: &[String],
+    context: &PluginContext,
+) -> Result<i32, String> {
+    let context = serde_json::to_string(context).map_err(|e| e.to_string())?;
+    let status = Command::new(&plugin.path)
+        .args(args)
+        .env(CONTEXT_ENV, context)
+        .status()
+        .map_err(|e| format!("Failed to run plugin {}: {}", plugin.path.display(), e))?;
+    // A plugin killed by a signal has no exit code
+    Ok(status.code().unwrap_or(1))
+}
+
+#[cfg(test)]
+#[cfg(unix)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+
+    fn write_script(dir: &Path, name: &str, body: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_discover_plugins() {
+        let first = TempDir::new().unwrap();
+        let second = TempDir::new().unwrap();
+        let deploy = write_script(first.path(), "ralph-beads-deploy", "true");
+        write_script(second.path(), "ralph-beads-deploy", "false");
+        write_script(second.path(), "ralph-beads-audit", "true");
+        write_script(second.path(), "ralph-beads-cli", "true");
+        write_script(second.path(), "unrelated", "true");
+        fs::write(second.path().join("ralph-beads-notes"), "not executable").unwrap();
+
+        let path = env::join_paths([first.path(), second.path()]).unwrap();
+        let plugins = discover_plugins(&path);
+
+        let names: Vec<&str> = plugins.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["audit", "deploy"]);
+        assert_eq!(
+            plugins.iter().find(|p| p.name == "deploy").unwrap().path,
+            deploy
+        );
+    }
+
+    #[test]
+    fn test_run_plugin_passes_args_and_context() {
+        let dir = TempDir::new().unwrap();
+        let out = dir.path().join("out");
+        let script = write_script(
+            dir.path(),
+            "ralph-beads-echo",
+            &format!(
+                "echo \"$@\" > {0}; echo \"${1}\" >> {0}; exit 4",
+                out.display(),
+                CONTEXT_ENV
+            ),
+        );
+        let plugin = Plugin {
+            name: "echo".to_string(),
+            path: script,
+        };
+        let context = PluginContext::new("echo", dir.path(), LoadedConfig::default());
+
+        let code = run_plugin(&plugin, &["--flag".to_string(), "x".to_string()], &context).unwrap();
+
+        assert_eq!(code, 4);
+        let written = fs::read_to_string(&out).unwrap();
+        let mut lines = written.lines();
+        assert_eq!(lines.next(), Some("--flag x"));
+        let context: serde_json::Value = serde_json::from_str(lines.next().unwrap()).unwrap();
+        assert_eq!(context["plugin"], "echo");
+        assert_eq!(context["config"]["lint"]["stale_days"], 14);
+    }
+}
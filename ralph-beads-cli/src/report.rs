@@ -0,0 +1,594 @@
+//! Epic status reports
+//!
+//! Condenses an epic's tasks, the activity feed, and effort estimates into
+//! one status document: a progress bar, each task's state with the reasons
+//! it is blocked, gates still open, recent failures, and an ETA. Rendered as
+//! Markdown for pasting into chat or PR descriptions, or as HTML.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::activity::{format_duration, format_timestamp, ActivityEvent, EventKind};
+use crate::beads::Issue;
+use crate::graph::DepGraph;
+
+/// Width of the text progress bar, in cells
+const BAR_WIDTH: usize = 20;
+
+/// Where a task stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskState {
+    InProgress,
+    Blocked,
+    Ready,
+    Done,
+}
+
+impl TaskState {
+    fn label(self) -> &'static str {
+        match self {
+            TaskState::InProgress => "in progress",
+            TaskState::Blocked => "blocked",
+            TaskState::Ready => "ready",
+            TaskState::Done => "done",
+        }
+    }
+}
+
+/// One task of the epic
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TaskReport {
+    pub id: String,
+    pub title: String,
+    pub state: TaskState,
+    /// Worker of the latest claim on the feed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub worker: Option<String>,
+    /// Why the task can't start, one entry per cause
+    pub blocked_by: Vec<String>,
+}
+
+/// Task counts and completion percentage
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Progress {
+    pub total: usize,
+    pub done: usize,
+    pub in_progress: usize,
+    pub blocked: usize,
+    pub ready: usize,
+    pub percent: u8,
+}
+
+/// A gate whose latest event on the feed did not pass
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OpenGate {
+    pub issue: String,
+    pub gate: String,
+    pub message: String,
+    pub timestamp: u64,
+}
+
+/// A `failed` event for one of the epic's tasks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Failure {
+    pub issue: String,
+    pub message: String,
+    pub timestamp: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub worker: Option<String>,
+}
+
+/// Remaining wall-clock time for the open tasks
+///
+/// `low_secs` sums the optimistic estimates along the longest blocking
+/// chain (enough workers to run everything else alongside it);
+/// `high_secs` sums the pessimistic estimates of every open task (a single
+/// worker). Tasks without recorded history count in `remaining` but not in
+/// `estimated`, so the range is a lower bound when the two differ.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Eta {
+    pub remaining: usize,
+    pub estimated: usize,
+    pub low_secs: u64,
+    pub high_secs: u64,
+}
+
+/// Status of an epic at `generated_at`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StatusReport {
+    pub epic: String,
+    pub title: String,
+    pub generated_at: u64,
+    pub progress: Progress,
+    pub tasks: Vec<TaskReport>,
+    pub open_gates: Vec<OpenGate>,
+    pub recent_failures: Vec<Failure>,
+    pub eta: Option<Eta>,
+}
+
+/// Build the status report for `epic` and its `tasks`
+///
+/// `events` is the whole activity feed; only events for the tasks are
+/// used. `estimates` maps open task IDs to their wall-clock range in
+/// seconds, for those with history. At most `max_failures` failures are
+/// kept, newest first.
+pub fn build_status(
+    epic: &Issue,
+    tasks: &[Issue],
+    events: &[ActivityEvent],
+    estimates: &BTreeMap<String, (u64, u64)>,
+    max_failures: usize,
+    now: u64,
+) -> StatusReport {
+    let ids: BTreeSet<&str> = tasks.iter().map(|t| t.id.as_str()).collect();
+    let events: Vec<&ActivityEvent> = events
+        .iter()
+        .filter(|e| ids.contains(e.issue.as_str()))
+        .collect();
+
+    let mut workers: BTreeMap<&str, &str> = BTreeMap::new();
+    let mut blocked_events: BTreeMap<&str, &str> = BTreeMap::new();
+    for event in &events {
+        match event.kind {
+            EventKind::Claimed => {
+                if let Some(ref worker) = event.worker {
+                    workers.insert(&event.issue, worker);
+                }
+            }
+            EventKind::Blocked => {
+                blocked_events.insert(&event.issue, &event.message);
+            }
+            // Any later progress means the task moved past what blocked it
+            EventKind::Progress | EventKind::Completed => {
+                blocked_events.remove(event.issue.as_str());
+            }
+            _ => {}
+        }
+    }
+
+    let by_id: BTreeMap<&str, &Issue> = tasks.iter().map(|t| (t.id.as_str(), t)).collect();
+    let mut reports: Vec<TaskReport> = tasks
+        .iter()
+        .map(|task| {
+            let mut blocked_by: Vec<String> = task
+                .blockers()
+                .filter_map(|b| match by_id.get(b) {
+                    Some(blocker) if blocker.is_closed() => None,
+                    Some(blocker) => Some(format!(
+                        "waits on {} ({}, {})",
+                        b, blocker.title, blocker.status
+                    )),
+                    None => Some(format!("waits on {} (outside the epic)", b)),
+                })
+                .collect();
+            if let Some(message) = blocked_events.get(task.id.as_str()) {
+                blocked_by.push(message.to_string());
+            }
+            let state = if task.is_closed() {
+                TaskState::Done
+            } else if task.status == "in_progress" {
+                TaskState::InProgress
+            } else if !blocked_by.is_empty() || task.status == "blocked" {
+                TaskState::Blocked
+            } else {
+                TaskState::Ready
+            };
+            if state == TaskState::Done {
+                blocked_by.clear();
+            }
+            TaskReport {
+                id: task.id.clone(),
+                title: task.title.clone(),
+                state,
+                worker: workers.get(task.id.as_str()).map(|w| w.to_string()),
+                blocked_by,
+            }
+        })
+        .collect();
+    reports.sort_by(|a, b| a.state.cmp(&b.state).then_with(|| a.id.cmp(&b.id)));
+
+    let count = |state: TaskState| reports.iter().filter(|t| t.state == state).count();
+    let done = count(TaskState::Done);
+    let progress = Progress {
+        total: reports.len(),
+        done,
+        in_progress: count(TaskState::InProgress),
+        blocked: count(TaskState::Blocked),
+        ready: count(TaskState::Ready),
+        percent: (done * 100).checked_div(reports.len()).unwrap_or(0) as u8,
+    };
+
+    StatusReport {
+        epic: epic.id.clone(),
+        title: epic.title.clone(),
+        generated_at: now,
+        progress,
+        open_gates: open_gates(&events, &by_id),
+        recent_failures: events
+            .iter()
+            .rev()
+            .filter(|e| e.kind == EventKind::Failed)
+            .take(max_failures)
+            .map(|e| Failure {
+                issue: e.issue.clone(),
+                message: e.message.clone(),
+                timestamp: e.timestamp,
+                worker: e.worker.clone(),
+            })
+            .collect(),
+        eta: eta(tasks, estimates),
+        tasks: reports,
+    }
+}
+
+/// Gates of open tasks whose latest event doesn't record `passed: true`
+///
+/// Gates are told apart by `data.gate`; events without one count as a
+/// single unnamed gate per task.
+pub fn open_gates(events: &[&ActivityEvent], tasks: &BTreeMap<&str, &Issue>) -> Vec<OpenGate> {
+    let mut latest: BTreeMap<(&str, String), &ActivityEvent> = BTreeMap::new();
+    for event in events.iter().filter(|e| e.kind == EventKind::Gate) {
+        let gate = event
+            .data
+            .as_ref()
+            .and_then(|d| d["gate"].as_str())
+            .unwrap_or("gate")
+            .to_string();
+        latest.insert((&event.issue, gate), event);
+    }
+
+    latest
+        .into_iter()
+        .filter(|((issue, _), _)| tasks.get(issue).is_some_and(|t| !t.is_closed()))
+        .filter(|(_, event)| event.data.as_ref().and_then(|d| d["passed"].as_bool()) != Some(true))
+        .map(|((issue, gate), event)| OpenGate {
+            issue: issue.to_string(),
+            gate,
+            message: event.message.clone(),
+            timestamp: event.timestamp,
+        })
+        .collect()
+}
+
+fn eta(tasks: &[Issue], estimates: &BTreeMap<String, (u64, u64)>) -> Option<Eta> {
+    let open: Vec<Issue> = tasks.iter().filter(|t| !t.is_closed()).cloned().collect();
+    let estimated = open
+        .iter()
+        .filter(|t| estimates.contains_key(&t.id))
+        .count();
+    if estimated == 0 {
+        return None;
+    }
+
+    let range = |id: &str| estimates.get(id).copied().unwrap_or((0, 0));
+    let high_secs = open.iter().map(|t| range(&t.id).1).sum();
+    let graph = DepGraph::new(open);
+    let low_secs = graph
+        .critical_path()
+        .into_iter()
+        .map(|i| range(&graph.issues[i].id).0)
+        .sum();
+
+    Some(Eta {
+        remaining: graph.issues.len(),
+        estimated,
+        low_secs,
+        high_secs,
+    })
+}
+
+/// `[#####---------------] 25%`
+fn progress_bar(percent: u8) -> String {
+    let filled = usize::from(percent) * BAR_WIDTH / 100;
+    format!(
+        "[{}{}] {}%",
+        "#".repeat(filled),
+        "-".repeat(BAR_WIDTH - filled),
+        percent
+    )
+}
+
+fn eta_line(eta: &Option<Eta>) -> String {
+    match eta {
+        Some(eta) => {
+            let mut line = format!(
+                "{} - {} for {} open tasks",
+                format_duration(eta.low_secs),
+                format_duration(eta.high_secs),
+                eta.remaining
+            );
+            if eta.estimated < eta.remaining {
+                line.push_str(&format!(
+                    " ({} without history, not counted)",
+                    eta.remaining - eta.estimated
+                ));
+            }
+            line
+        }
+        None => "unknown (no completed similar tasks)".to_string(),
+    }
+}
+
+/// Render the report as Markdown
+pub fn render_markdown(report: &StatusReport) -> String {
+    let p = &report.progress;
+    let mut out = format!("# {}: {}\n\n", report.epic, report.title);
+    out.push_str(&format!(
+        "`{}` {}/{} done, {} in progress, {} blocked, {} ready\n\n",
+        progress_bar(p.percent),
+        p.done,
+        p.total,
+        p.in_progress,
+        p.blocked,
+        p.ready
+    ));
+    out.push_str(&format!("**ETA:** {}\n", eta_line(&report.eta)));
+
+    out.push_str(
+        "\n## Tasks\n\n| Task | Title | State | Worker | Blocked by |\n|---|---|---|---|---|\n",
+    );
+    for task in &report.tasks {
+        out.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            task.id,
+            task.title.replace('|', "\\|"),
+            task.state.label(),
+            task.worker.as_deref().unwrap_or(""),
+            task.blocked_by.join("; ").replace('|', "\\|")
+        ));
+    }
+
+    if !report.open_gates.is_empty() {
+        out.push_str("\n## Open gates\n\n");
+        for gate in &report.open_gates {
+            out.push_str(&format!(
+                "- **{}** on {}: {} ({})\n",
+                gate.gate,
+                gate.issue,
+                gate.message,
+                format_timestamp(gate.timestamp)
+            ));
+        }
+    }
+
+    if !report.recent_failures.is_empty() {
+        out.push_str("\n## Recent failures\n\n");
+        for failure in &report.recent_failures {
+            out.push_str(&format!(
+                "- {} {}: {}\n",
+                format_timestamp(failure.timestamp),
+                failure.issue,
+                failure.message
+            ));
+        }
+    }
+
+    out.push_str(&format!(
+        "\n_Generated {}_\n",
+        format_timestamp(report.generated_at)
+    ));
+    out
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Render the report as a standalone HTML fragment
+pub fn render_html(report: &StatusReport) -> String {
+    let p = &report.progress;
+    let mut out = format!(
+        "<h1>{}: {}</h1>\n",
+        escape_html(&report.epic),
+        escape_html(&report.title)
+    );
+    out.push_str(&format!(
+        "<p><progress value=\"{}\" max=\"{}\"></progress> {}% &mdash; {}/{} done, {} in progress, {} blocked, {} ready</p>\n",
+        p.done, p.total, p.percent, p.done, p.total, p.in_progress, p.blocked, p.ready
+    ));
+    out.push_str(&format!(
+        "<p><strong>ETA:</strong> {}</p>\n",
+        escape_html(&eta_line(&report.eta))
+    ));
+
+    out.push_str("<h2>Tasks</h2>\n<table>\n<tr><th>Task</th><th>Title</th><th>State</th><th>Worker</th><th>Blocked by</th></tr>\n");
+    for task in &report.tasks {
+        out.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            escape_html(&task.id),
+            escape_html(&task.title),
+            task.state.label(),
+            escape_html(task.worker.as_deref().unwrap_or("")),
+            escape_html(&task.blocked_by.join("; "))
+        ));
+    }
+    out.push_str("</table>\n");
+
+    if !report.open_gates.is_empty() {
+        out.push_str("<h2>Open gates</h2>\n<ul>\n");
+        for gate in &report.open_gates {
+            out.push_str(&format!(
+                "<li><strong>{}</strong> on {}: {}</li>\n",
+                escape_html(&gate.gate),
+                escape_html(&gate.issue),
+                escape_html(&gate.message)
+            ));
+        }
+        out.push_str("</ul>\n");
+    }
+
+    if !report.recent_failures.is_empty() {
+        out.push_str("<h2>Recent failures</h2>\n<ul>\n");
+        for failure in &report.recent_failures {
+            out.push_str(&format!(
+                "<li>{} {}: {}</li>\n",
+                format_timestamp(failure.timestamp),
+                escape_html(&failure.issue),
+                escape_html(&failure.message)
+            ));
+        }
+        out.push_str("</ul>\n");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beads::test_issue;
+    use serde_json::json;
+
+    fn event(id: u64, issue: &str, kind: EventKind, message: &str) -> ActivityEvent {
+        ActivityEvent {
+            id,
+            timestamp: id * 100,
+            issue: issue.to_string(),
+            kind,
+            message: message.to_string(),
+            worker: Some("worker-a".to_string()),
+            run: None,
+            data: None,
+        }
+    }
+
+    fn epic() -> Issue {
+        Issue {
+            id: "bd-1".to_string(),
+            title: "Auth | login".to_string(),
+            issue_type: "epic".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn tasks() -> Vec<Issue> {
+        vec![
+            test_issue("bd-1.1", "closed", &[]),
+            test_issue("bd-1.2", "in_progress", &["bd-1.1"]),
+            test_issue("bd-1.3", "open", &["bd-1.2", "bd-9"]),
+            test_issue("bd-1.4", "open", &["bd-1.1"]),
+        ]
+    }
+
+    #[test]
+    fn test_task_states_and_progress() {
+        let events = vec![event(1, "bd-1.2", EventKind::Claimed, "start")];
+        let report = build_status(&epic(), &tasks(), &events, &BTreeMap::new(), 5, 0);
+
+        let states: Vec<(&str, TaskState)> = report
+            .tasks
+            .iter()
+            .map(|t| (t.id.as_str(), t.state))
+            .collect();
+        assert_eq!(
+            states,
+            vec![
+                ("bd-1.2", TaskState::InProgress),
+                ("bd-1.3", TaskState::Blocked),
+                ("bd-1.4", TaskState::Ready),
+                ("bd-1.1", TaskState::Done),
+            ]
+        );
+        assert_eq!(report.tasks[0].worker.as_deref(), Some("worker-a"));
+        assert_eq!(
+            report.tasks[1].blocked_by,
+            vec![
+                "waits on bd-1.2 (Title of bd-1.2, in_progress)".to_string(),
+                "waits on bd-9 (outside the epic)".to_string(),
+            ]
+        );
+        assert_eq!(report.progress.done, 1);
+        assert_eq!(report.progress.percent, 25);
+        assert!(report.eta.is_none());
+    }
+
+    #[test]
+    fn test_blocked_event_reason_until_progress() {
+        let mut events = vec![event(1, "bd-1.4", EventKind::Blocked, "needs API key")];
+        let report = build_status(&epic(), &tasks(), &events, &BTreeMap::new(), 5, 0);
+        let task = report.tasks.iter().find(|t| t.id == "bd-1.4").unwrap();
+        assert_eq!(task.state, TaskState::Blocked);
+        assert_eq!(task.blocked_by, vec!["needs API key".to_string()]);
+
+        events.push(event(2, "bd-1.4", EventKind::Progress, "key arrived"));
+        let report = build_status(&epic(), &tasks(), &events, &BTreeMap::new(), 5, 0);
+        let task = report.tasks.iter().find(|t| t.id == "bd-1.4").unwrap();
+        assert_eq!(task.state, TaskState::Ready);
+    }
+
+    #[test]
+    fn test_open_gates_and_failures() {
+        let mut failing = event(1, "bd-1.3", EventKind::Gate, "lint failed");
+        failing.data = Some(json!({ "gate": "lint", "passed": false }));
+        let mut passed = event(2, "bd-1.4", EventKind::Gate, "lint ok");
+        passed.data = Some(json!({ "gate": "lint", "passed": true }));
+        let pending = event(3, "bd-1.4", EventKind::Gate, "awaiting review");
+        let mut closed = event(4, "bd-1.1", EventKind::Gate, "lint failed");
+        closed.data = Some(json!({ "gate": "lint", "passed": false }));
+        let events = vec![
+            failing,
+            passed,
+            pending,
+            closed,
+            event(5, "bd-1.2", EventKind::Failed, "tests failed"),
+            event(6, "bd-2.1", EventKind::Failed, "other epic"),
+            event(7, "bd-1.2", EventKind::Failed, "tests failed again"),
+        ];
+
+        let report = build_status(&epic(), &tasks(), &events, &BTreeMap::new(), 1, 0);
+
+        let gates: Vec<(&str, &str)> = report
+            .open_gates
+            .iter()
+            .map(|g| (g.issue.as_str(), g.gate.as_str()))
+            .collect();
+        assert_eq!(gates, vec![("bd-1.3", "lint"), ("bd-1.4", "gate")]);
+        assert_eq!(report.recent_failures.len(), 1);
+        assert_eq!(report.recent_failures[0].message, "tests failed again");
+    }
+
+    #[test]
+    fn test_eta_spans_critical_path_to_serial() {
+        let estimates = BTreeMap::from([
+            ("bd-1.2".to_string(), (100, 200)),
+            ("bd-1.3".to_string(), (300, 600)),
+            ("bd-1.4".to_string(), (50, 80)),
+        ]);
+        let report = build_status(&epic(), &tasks(), &[], &estimates, 5, 0);
+
+        let eta = report.eta.unwrap();
+        assert_eq!(eta.remaining, 3);
+        assert_eq!(eta.estimated, 3);
+        assert_eq!(eta.low_secs, 400);
+        assert_eq!(eta.high_secs, 880);
+    }
+
+    #[test]
+    fn test_render_markdown_and_html() {
+        let events = vec![event(1, "bd-1.2", EventKind::Failed, "<panic> in auth")];
+        let report = build_status(&epic(), &tasks(), &events, &BTreeMap::new(), 5, 0);
+
+        let md = render_markdown(&report);
+        assert!(md.starts_with("# bd-1: Auth | login\n"));
+        assert!(md.contains("`[#####---------------] 25%` 1/4 done"));
+        assert!(md.contains("| bd-1.3 | Title of bd-1.3 | blocked |  | waits on bd-1.2"));
+        assert!(md.contains("## Recent failures"));
+        assert!(!md.contains("## Open gates"));
+
+        let html = render_html(&report);
+        assert!(html.contains("<progress value=\"1\" max=\"4\"></progress> 25%"));
+        assert!(html.contains("&lt;panic&gt; in auth"));
+    }
+
+    #[test]
+    fn test_progress_bar_bounds() {
+        assert_eq!(progress_bar(0), format!("[{}] 0%", "-".repeat(BAR_WIDTH)));
+        assert_eq!(
+            progress_bar(100),
+            format!("[{}] 100%", "#".repeat(BAR_WIDTH))
+        );
+    }
+}
@@ -0,0 +1,534 @@
+//! Activity feed retention and compaction
+//!
+//! The feed is append-only, so left alone it grows without bound. Retention
+//! limits (maximum age, total events, events per issue) select the events
+//! to retire; compaction folds each issue's retired events for a day into
+//! one `summary` record holding their counts, workers, and claim and
+//! completion times, so timelines and estimates over long horizons still
+//! see them without reading every line.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::Path;
+
+use crate::activity::{
+    format_timestamp, last_id, lock_feed, record_last_id, ActivityEvent, EventKind,
+};
+use crate::state::sidecar;
+
+/// The `[activity]` configuration section: how much of the feed to keep
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ActivityConfig {
+    /// Retire events older than this many days
+    pub max_age_days: Option<u64>,
+    /// Keep at most this many events in the feed (newest first)
+    pub max_events: Option<usize>,
+    /// Keep at most this many events per issue (newest first)
+    pub max_per_issue: Option<usize>,
+    /// Fold retired events into daily summaries instead of dropping them
+    pub compact: bool,
+    /// Prune automatically after an emit once the feed exceeds this size
+    pub auto_prune_bytes: Option<u64>,
+}
+
+impl Default for ActivityConfig {
+    fn default() -> Self {
+        ActivityConfig {
+            max_age_days: None,
+            max_events: None,
+            max_per_issue: None,
+            compact: true,
+            auto_prune_bytes: None,
+        }
+    }
+}
+
+impl ActivityConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        for (key, value) in [
+            ("max_age_days", self.max_age_days.map(|n| n as usize)),
+            ("max_events", self.max_events),
+            ("max_per_issue", self.max_per_issue),
+        ] {
+            if value == Some(0) {
+                return Err(format!("activity.{} must be at least 1", key));
+            }
+        }
+        if self.auto_prune_bytes.is_some() && !self.has_limits() {
+            return Err(
+                "activity.auto_prune_bytes needs max_age_days, max_events, or max_per_issue"
+                    .to_string(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Whether any retention limit is set
+    pub fn has_limits(&self) -> bool {
+        self.max_age_days.is_some() || self.max_events.is_some() || self.max_per_issue.is_some()
+    }
+}
+
+/// Everything known about an issue's compacted events for one UTC day,
+/// carried in the `data` of a `summary` record
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DailySummary {
+    /// `YYYY-MM-DD`
+    pub day: String,
+    pub events: u32,
+    /// Events per kind
+    pub counts: BTreeMap<String, u32>,
+    pub first: u64,
+    pub last: u64,
+    /// Earliest `claimed` event
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claimed_at: Option<u64>,
+    /// Latest `completed` event
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub workers: Vec<String>,
+}
+
+impl DailySummary {
+    /// Fold `event` into the summary
+    fn add(&mut self, event: &ActivityEvent) {
+        if let Some(other) = summary_of(event) {
+            self.merge(other);
+            return;
+        }
+        self.merge(DailySummary {
+            day: self.day.clone(),
+            events: 1,
+            counts: BTreeMap::from([(event.kind.to_string(), 1)]),
+            first: event.timestamp,
+            last: event.timestamp,
+            claimed_at: (event.kind == EventKind::Claimed).then_some(event.timestamp),
+            completed_at: (event.kind == EventKind::Completed).then_some(event.timestamp),
+            workers: event.worker.iter().cloned().collect(),
+        });
+    }
+
+    fn merge(&mut self, other: DailySummary) {
+        if self.events == 0 {
+            self.first = other.first;
+        }
+        self.first = self.first.min(other.first);
+        self.last = self.last.max(other.last);
+        self.events += other.events;
+        for (kind, n) in other.counts {
+            *self.counts.entry(kind).or_insert(0) += n;
+        }
+        self.claimed_at = match (self.claimed_at, other.claimed_at) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.completed_at = self.completed_at.max(other.completed_at);
+        for worker in other.workers {
+            if !self.workers.contains(&worker) {
+                self.workers.push(worker);
+            }
+        }
+    }
+}
+
+/// The summary carried by a `summary` record, if `event` is one
+pub fn summary_of(event: &ActivityEvent) -> Option<DailySummary> {
+    if event.kind != EventKind::Summary {
+        return None;
+    }
+    serde_json::from_value(event.data.clone()?).ok()
+}
+
+/// What a prune retired
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PruneReport {
+    /// Events in the feed before pruning, summaries included
+    pub before: usize,
+    /// Records left, summaries included
+    pub after: usize,
+    /// Events retired by the limits
+    pub retired: usize,
+    /// Summary records written (new or updated)
+    pub summaries: usize,
+}
+
+/// Apply `config`'s limits to `events` as of `now`
+///
+/// Returns the records to keep, in id order: events within every limit,
+/// plus (when compacting) one summary per issue and day for the rest.
+/// Existing summaries are kept, and merged with new ones for the same day.
+/// A summary takes the highest id of what it replaces, so the feed's last
+/// id, and with it the next id handed out, doesn't change.
+pub fn prune_events(
+    events: Vec<ActivityEvent>,
+    config: &ActivityConfig,
+    now: u64,
+) -> (Vec<ActivityEvent>, PruneReport) {
+    let before = events.len();
+    let cutoff = config
+        .max_age_days
+        .map(|days| now.saturating_sub(days.saturating_mul(86_400)));
+
+    // Walk newest first so the caps keep the most recent events
+    let mut per_issue: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut kept_events = 0;
+    let mut retire = vec![false; events.len()];
+    for (i, event) in events.iter().enumerate().rev() {
+        if event.kind == EventKind::Summary {
+            continue;
+        }
+        let seen = per_issue.entry(&event.issue).or_insert(0);
+        let too_old = cutoff.is_some_and(|cutoff| event.timestamp < cutoff);
+        let issue_full = config.max_per_issue.is_some_and(|max| *seen >= max);
+        let feed_full = config.max_events.is_some_and(|max| kept_events >= max);
+        if too_old || issue_full || feed_full {
+            retire[i] = true;
+        } else {
+            *seen += 1;
+            kept_events += 1;
+        }
+    }
+    let retired = retire.iter().filter(|r| **r).count();
+
+    let mut kept = Vec::new();
+    let mut groups: BTreeMap<(String, String), (u64, DailySummary, bool)> = BTreeMap::new();
+    for (event, retire) in events.into_iter().zip(retire) {
+        // Existing summaries are regrouped so new events for their day merge in
+        let is_summary = event.kind == EventKind::Summary;
+        if !(retire || is_summary && config.compact) {
+            kept.push(event);
+            continue;
+        }
+        if !config.compact {
+            continue;
+        }
+        let day = format_timestamp(event.timestamp)[..10].to_string();
+        let (id, summary, changed) = groups
+            .entry((event.issue.clone(), day.clone()))
+            .or_insert_with(|| {
+                (
+                    0,
+                    DailySummary {
+                        day,
+                        ..Default::default()
+                    },
+                    false,
+                )
+            });
+        *id = (*id).max(event.id);
+        *changed |= !is_summary;
+        summary.add(&event);
+    }
+
+    let summaries = groups.values().filter(|(_, _, changed)| *changed).count();
+    kept.extend(groups.into_iter().map(|((issue, _), (id, summary, _))| {
+        let kinds: Vec<String> = summary
+            .counts
+            .iter()
+            .map(|(kind, n)| format!("{}={}", kind, n))
+            .collect();
+        ActivityEvent {
+            id,
+            timestamp: summary.last,
+            issue,
+            kind: EventKind::Summary,
+            message: format!(
+                "{} events on {} ({})",
+                summary.events,
+                summary.day,
+                kinds.join(", ")
+            ),
+            worker: None,
+            run: None,
+            data: serde_json::to_value(&summary).ok(),
+        }
+    }));
+    kept.sort_by_key(|e| e.id);
+
+    let report = PruneReport {
+        before,
+        after: kept.len(),
+        retired,
+        summaries,
+    };
+    (kept, report)
+}
+
+/// Prune the feed file in place
+///
+/// The feed stays locked from the read to the rename, so concurrent
+/// writers and prunes wait rather than lose events. The new feed is written
+/// to a temp file unique to this process and renamed over the old one. The
+/// highest id used is recorded first, so ids keep counting up even when
+/// every event was retired. With `dry_run` nothing is written.
+pub fn prune_feed(
+    feed_file: &Path,
+    config: &ActivityConfig,
+    now: u64,
+    dry_run: bool,
+) -> Result<PruneReport, String> {
+    if !feed_file.exists() {
+        return Ok(PruneReport::default());
+    }
+    let _lock = lock_feed(feed_file)?;
+    let read_err = |e: std::io::Error| format!("Failed to read {}: {}", feed_file.display(), e);
+    let mut content = String::new();
+    match File::open(feed_file) {
+        Ok(mut file) => file.read_to_string(&mut content).map_err(read_err)?,
+        Err(_) => return Ok(PruneReport::default()),
+    };
+    let events: Vec<ActivityEvent> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    let (kept, report) = prune_events(events, config, now);
+    if dry_run || report.retired == 0 && report.summaries == 0 {
+        return Ok(report);
+    }
+
+    let mut out = String::new();
+    for event in &kept {
+        out.push_str(&serde_json::to_string(event).map_err(|e| e.to_string())?);
+        out.push('\n');
+    }
+    record_last_id(feed_file, last_id(feed_file)?)?;
+    let tmp = sidecar(feed_file, &format!("prune-{}", std::process::id()));
+    let write_err = |e: std::io::Error| format!("Failed to write {}: {}", tmp.display(), e);
+    let mut file = File::create(&tmp).map_err(write_err)?;
+    file.write_all(out.as_bytes()).map_err(write_err)?;
+    drop(file);
+
+    fs::rename(&tmp, feed_file).map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        format!("Failed to replace {}: {}", feed_file.display(), e)
+    })?;
+    Ok(report)
+}
+
+/// Prune the feed when it has grown past `auto_prune_bytes`
+pub fn auto_prune(
+    feed_file: &Path,
+    config: &ActivityConfig,
+    now: u64,
+) -> Result<Option<PruneReport>, String> {
+    let Some(limit) = config.auto_prune_bytes else {
+        return Ok(None);
+    };
+    let size = fs::metadata(feed_file).map(|m| m.len()).unwrap_or(0);
+    if size <= limit {
+        return Ok(None);
+    }
+    prune_feed(feed_file, config, now, false).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::activity::{emit_event, read_events};
+
+    const DAY: u64 = 86_400;
+
+    fn event(id: u64, timestamp: u64, issue: &str, kind: EventKind) -> ActivityEvent {
+        ActivityEvent {
+            id,
+            timestamp,
+            issue: issue.to_string(),
+            kind,
+            message: format!("event {}", id),
+            worker: Some(format!("w{}", id % 2)),
+            run: None,
+            data: None,
+        }
+    }
+
+    fn ids(events: &[ActivityEvent]) -> Vec<u64> {
+        events.iter().map(|e| e.id).collect()
+    }
+
+    #[test]
+    fn test_limits_keep_newest() {
+        let events = vec![
+            event(1, 10 * DAY, "bd-1", EventKind::Claimed),
+            event(2, 10 * DAY, "bd-2", EventKind::Claimed),
+            event(3, 10 * DAY + 5, "bd-1", EventKind::Progress),
+            event(4, 10 * DAY + 9, "bd-1", EventKind::Completed),
+        ];
+        let drop = |config: ActivityConfig| {
+            let config = ActivityConfig {
+                compact: false,
+                ..config
+            };
+            ids(&prune_events(events.clone(), &config, 10 * DAY + 10).0)
+        };
+
+        assert_eq!(
+            drop(ActivityConfig {
+                max_per_issue: Some(1),
+                ..Default::default()
+            }),
+            vec![2, 4]
+        );
+        assert_eq!(
+            drop(ActivityConfig {
+                max_events: Some(3),
+                ..Default::default()
+            }),
+            vec![2, 3, 4]
+        );
+        assert_eq!(
+            drop(ActivityConfig {
+                max_age_days: Some(1),
+                ..Default::default()
+            }),
+            vec![1, 2, 3, 4]
+        );
+    }
+
+    #[test]
+    fn test_compaction_rolls_days_into_summaries() {
+        let events = vec![
+            event(1, 2 * DAY + 100, "bd-1", EventKind::Claimed),
+            event(2, 2 * DAY + 200, "bd-1", EventKind::Failed),
+            event(3, 3 * DAY + 50, "bd-1", EventKind::Completed),
+            event(4, 3 * DAY + 60, "bd-2", EventKind::Claimed),
+            event(5, 9 * DAY, "bd-2", EventKind::Progress),
+        ];
+        let config = ActivityConfig {
+            max_age_days: Some(2),
+            ..Default::default()
+        };
+
+        let (kept, report) = prune_events(events, &config, 10 * DAY);
+
+        assert_eq!(report.retired, 4);
+        assert_eq!(report.summaries, 3);
+        assert_eq!(ids(&kept), vec![2, 3, 4, 5]);
+        let first = summary_of(&kept[0]).unwrap();
+        assert_eq!(first.day, "1970-01-03");
+        assert_eq!(first.events, 2);
+        assert_eq!(first.claimed_at, Some(2 * DAY + 100));
+        assert_eq!(first.workers, vec!["w1".to_string(), "w0".to_string()]);
+        assert_eq!(
+            kept[0].message,
+            "2 events on 1970-01-03 (claimed=1, failed=1)"
+        );
+        assert_eq!(
+            summary_of(&kept[1]).unwrap().completed_at,
+            Some(3 * DAY + 50)
+        );
+        assert_eq!(kept[3].kind, EventKind::Progress);
+
+        // A second pass merges into the existing summaries
+        let (again, report) = prune_events(kept.clone(), &config, 10 * DAY);
+        assert_eq!(report.retired, 0);
+        assert_eq!(report.summaries, 0);
+        assert_eq!(again, kept);
+    }
+
+    #[test]
+    fn test_prune_feed_keeps_next_id() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let feed = dir.path().join("activity.jsonl");
+        for _ in 0..5 {
+            emit_event(&feed, "bd-1", EventKind::Progress, "step", None, None).unwrap();
+        }
+        let config = ActivityConfig {
+            max_per_issue: Some(2),
+            ..Default::default()
+        };
+        let now = crate::activity::now_secs();
+
+        let dry = prune_feed(&feed, &config, now, true).unwrap();
+        assert_eq!(dry.retired, 3);
+        assert_eq!(read_events(&feed).unwrap().len(), 5);
+
+        let report = prune_feed(&feed, &config, now, false).unwrap();
+        assert_eq!((report.before, report.after), (5, 3));
+        let events = read_events(&feed).unwrap();
+        assert_eq!(ids(&events), vec![3, 4, 5]);
+        assert_eq!(summary_of(&events[0]).unwrap().events, 3);
+
+        let next = emit_event(&feed, "bd-1", EventKind::Completed, "done", None, None).unwrap();
+        assert_eq!(next.id, 6);
+
+        // Retiring everything, on a feed written before ids were recorded
+        let feed = dir.path().join("old.jsonl");
+        for _ in 0..3 {
+            emit_event(&feed, "bd-2", EventKind::Progress, "step", None, None).unwrap();
+        }
+        fs::remove_file(dir.path().join("old.jsonl.seq")).unwrap();
+        let drop_all = ActivityConfig {
+            max_age_days: Some(1),
+            compact: false,
+            ..Default::default()
+        };
+        prune_feed(&feed, &drop_all, now + 2 * 86_400, false).unwrap();
+        assert!(read_events(&feed).unwrap().is_empty());
+        let next = emit_event(&feed, "bd-2", EventKind::Claimed, "again", None, None).unwrap();
+        assert_eq!(next.id, 4);
+    }
+
+    #[test]
+    fn test_concurrent_prunes_keep_newest_events() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let feed = dir.path().join("activity.jsonl");
+        let config = ActivityConfig {
+            max_events: Some(5),
+            compact: false,
+            ..Default::default()
+        };
+        let now = crate::activity::now_secs();
+
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..10 {
+                        emit_event(&feed, "bd-1", EventKind::Progress, "step", None, None).unwrap();
+                        prune_feed(&feed, &config, now, false).unwrap();
+                    }
+                });
+            }
+        });
+
+        // Nothing retired comes back, and no prune leaves a temp file behind
+        assert_eq!(ids(&read_events(&feed).unwrap()), vec![36, 37, 38, 39, 40]);
+        let leftovers = fs::read_dir(dir.path())
+            .unwrap()
+            .filter(|e| {
+                e.as_ref()
+                    .unwrap()
+                    .file_name()
+                    .to_string_lossy()
+                    .contains("prune")
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+
+    #[test]
+    fn test_auto_prune_threshold_and_validation() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let feed = dir.path().join("activity.jsonl");
+        emit_event(&feed, "bd-1", EventKind::Progress, "step", None, None).unwrap();
+        let mut config = ActivityConfig {
+            max_events: Some(1),
+            auto_prune_bytes: Some(1 << 20),
+            ..Default::default()
+        };
+        assert_eq!(auto_prune(&feed, &config, 0).unwrap(), None);
+        config.auto_prune_bytes = Some(1);
+        assert!(auto_prune(&feed, &config, 0).unwrap().is_some());
+
+        config.max_events = None;
+        assert!(config
+            .validate()
+            .unwrap_err()
+            .contains("needs max_age_days"));
+        config.max_events = Some(0);
+        assert!(config.validate().unwrap_err().contains("at least 1"));
+    }
+}
@@ -0,0 +1,474 @@
+//! JSON Schemas for structured command output
+//!
+//! `schema <command>` prints the schema of what that command writes with
+//! `--format json`, so integrators can generate clients and validate
+//! output. Schemas mirror the serde types they describe; the tests
+//! serialize each type and check it against its schema, so a field added to
+//! a type without updating its schema fails the build.
+
+use serde_json::{json, Value};
+
+use crate::health::CheckStatus;
+use crate::lint::{Severity, RULES};
+
+/// Draft of JSON Schema the schemas are written against
+pub const SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// A command whose JSON output has a published schema
+#[derive(Debug, Clone, Copy)]
+pub struct SchemaInfo {
+    pub command: &'static str,
+    pub description: &'static str,
+    build: fn() -> Value,
+}
+
+/// Every published schema, by command name
+pub const SCHEMAS: &[SchemaInfo] = &[
+    SchemaInfo {
+        command: "lint",
+        description: "Report of `lint all|issue|epic --format json`",
+        build: lint_report,
+    },
+    SchemaInfo {
+        command: "gate",
+        description: "`data` of a lint gate event in the activity feed",
+        build: gate_result,
+    },
+    SchemaInfo {
+        command: "health",
+        description: "Report of `health --format json`",
+        build: health_report,
+    },
+    SchemaInfo {
+        command: "graph-analyze",
+        description: "Analysis of `graph analyze --format json`",
+        build: graph_analysis,
+    },
+    SchemaInfo {
+        command: "graph-validate",
+        description: "Validation result of `graph validate --format json`",
+        build: structure_report,
+    },
+    SchemaInfo {
+        command: "report-status",
+        description: "Status of `report status --format json`",
+        build: status_report,
+    },
+];
+
+/// Schema for `command`, with `$schema` and `$id` filled in
+pub fn schema_for(command: &str) -> Result<Value, String> {
+    let info = SCHEMAS
+        .iter()
+        .find(|s| s.command == command)
+        .ok_or_else(|| {
+            let known: Vec<&str> = SCHEMAS.iter().map(|s| s.command).collect();
+            format!("No schema for '{}' (known: {})", command, known.join(", "))
+        })?;
+    let mut schema = (info.build)();
+    schema["$schema"] = SCHEMA_DIALECT.into();
+    schema["$id"] = format!("ralph-beads-cli/{}", info.command).into();
+    schema["title"] = info.description.into();
+    Ok(schema)
+}
+
+/// Object with `properties`; every key in `required` must be present
+fn object(properties: Value, required: &[&str]) -> Value {
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    })
+}
+
+fn array(items: Value) -> Value {
+    json!({ "type": "array", "items": items })
+}
+
+fn nullable(schema: Value) -> Value {
+    json!({ "anyOf": [schema, { "type": "null" }] })
+}
+
+fn string() -> Value {
+    json!({ "type": "string" })
+}
+
+fn count() -> Value {
+    json!({ "type": "integer", "minimum": 0 })
+}
+
+fn string_enum<T: std::fmt::Display>(values: &[T]) -> Value {
+    let values: Vec<String> = values.iter().map(T::to_string).collect();
+    json!({ "type": "string", "enum": values })
+}
+
+fn severity() -> Value {
+    string_enum(&[Severity::Info, Severity::Warning, Severity::Error])
+}
+
+fn lint_finding() -> Value {
+    let rules: Vec<&str> = RULES.iter().map(|r| r.id).collect();
+    object(
+        json!({
+            "issue_id": string(),
+            "rule": string_enum(&rules),
+            "severity": severity(),
+            "message": string(),
+            "suggestion": string(),
+        }),
+        &["issue_id", "rule", "severity", "message"],
+    )
+}
+
+fn lint_report() -> Value {
+    object(
+        json!({
+            "config": string(),
+            "issues_checked": count(),
+            "errors": count(),
+            "warnings": count(),
+            "findings": array(lint_finding()),
+        }),
+        &["config", "issues_checked", "errors", "warnings", "findings"],
+    )
+}
+
+fn gate_result() -> Value {
+    object(
+        json!({
+            "gate": { "const": "lint" },
+            "issue_id": string(),
+            "passed": { "type": "boolean" },
+            "errors": count(),
+            "warnings": count(),
+            "findings": array(lint_finding()),
+        }),
+        &[
+            "gate", "issue_id", "passed", "errors", "warnings", "findings",
+        ],
+    )
+}
+
+fn health_report() -> Value {
+    let statuses = [
+        CheckStatus::Pass,
+        CheckStatus::Skipped,
+        CheckStatus::Warn,
+        CheckStatus::Fail,
+    ];
+    object(
+        json!({
+            "status": string_enum(&statuses),
+            "checks": array(object(
+                json!({
+                    "name": string(),
+                    "status": string_enum(&statuses),
+                    "detail": string(),
+                    "hint": string(),
+                }),
+                &["name", "status", "detail"],
+            )),
+        }),
+        &["status", "checks"],
+    )
+}
+
+fn graph_analysis() -> Value {
+    let ids = array(string());
+    object(
+        json!({
+            "epic": string(),
+            "nodes": count(),
+            "edges": count(),
+            "critical_path": ids,
+            "waves": array(ids.clone()),
+            "max_parallelism": count(),
+            "bottlenecks": array(object(
+                json!({ "id": string(), "title": string(), "dependents": count() }),
+                &["id", "title", "dependents"],
+            )),
+            "orphans": ids,
+            "external_blockers": array(object(
+                json!({ "id": string(), "blocked_by": string() }),
+                &["id", "blocked_by"],
+            )),
+            "cycles": array(ids.clone()),
+            "diagrams": object(
+                json!({ "dot": string(), "mermaid": string() }),
+                &["dot", "mermaid"],
+            ),
+        }),
+        &[
+            "epic",
+            "nodes",
+            "edges",
+            "critical_path",
+            "waves",
+            "max_parallelism",
+            "bottlenecks",
+            "orphans",
+            "external_blockers",
+            "cycles",
+            "diagrams",
+        ],
+    )
+}
+
+fn structure_report() -> Value {
+    object(
+        json!({
+            "epic": string(),
+            "nodes": count(),
+            "workers": nullable(count()),
+            "findings": array(object(
+                json!({
+                    "check": string_enum(&[
+                        "cycle",
+                        "diamond",
+                        "fan-in",
+                        "front-size",
+                        "acceptance-criteria",
+                    ]),
+                    "severity": severity(),
+                    "issues": array(string()),
+                    "message": string(),
+                    "suggestion": string(),
+                }),
+                &["check", "severity", "issues", "message", "suggestion"],
+            )),
+        }),
+        &["epic", "nodes", "workers", "findings"],
+    )
+}
+
+fn status_report() -> Value {
+    object(
+        json!({
+            "epic": string(),
+            "title": string(),
+            "generated_at": count(),
+            "progress": object(
+                json!({
+                    "total": count(),
+                    "done": count(),
+                    "in_progress": count(),
+                    "blocked": count(),
+                    "ready": count(),
+                    "percent": { "type": "integer", "minimum": 0, "maximum": 100 },
+                }),
+                &["total", "done", "in_progress", "blocked", "ready", "percent"],
+            ),
+            "tasks": array(object(
+                json!({
+                    "id": string(),
+                    "title": string(),
+                    "state": string_enum(&["in_progress", "blocked", "ready", "done"]),
+                    "worker": string(),
+                    "blocked_by": array(string()),
+                }),
+                &["id", "title", "state", "blocked_by"],
+            )),
+            "open_gates": array(object(
+                json!({
+                    "issue": string(),
+                    "gate": string(),
+                    "message": string(),
+                    "timestamp": count(),
+                }),
+                &["issue", "gate", "message", "timestamp"],
+            )),
+            "recent_failures": array(object(
+                json!({
+                    "issue": string(),
+                    "message": string(),
+                    "timestamp": count(),
+                    "worker": string(),
+                }),
+                &["issue", "message", "timestamp"],
+            )),
+            "eta": nullable(object(
+                json!({
+                    "remaining": count(),
+                    "estimated": count(),
+                    "low_secs": count(),
+                    "high_secs": count(),
+                }),
+                &["remaining", "estimated", "low_secs", "high_secs"],
+            )),
+        }),
+        &[
+            "epic",
+            "title",
+            "generated_at",
+            "progress",
+            "tasks",
+            "open_gates",
+            "recent_failures",
+            "eta",
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::activity::{ActivityEvent, EventKind};
+    use crate::beads::{test_issue, Issue};
+    use crate::graph::{self, DepGraph, ValidateOptions};
+    use crate::health::{self, HealthOptions};
+    use crate::lint::{self, LintConfig};
+    use crate::report;
+    use std::collections::BTreeMap;
+
+    /// Check `value` against the subset of JSON Schema used above
+    fn conforms(schema: &Value, value: &Value, path: &str) -> Result<(), String> {
+        if let Some(options) = schema["anyOf"].as_array() {
+            return options
+                .iter()
+                .find(|s| conforms(s, value, path).is_ok())
+                .map(|_| ())
+                .ok_or_else(|| format!("{}: matches no anyOf branch", path));
+        }
+        if let Some(constant) = schema.get("const") {
+            return (constant == value)
+                .then_some(())
+                .ok_or_else(|| format!("{}: expected {}", path, constant));
+        }
+        if let Some(allowed) = schema["enum"].as_array() {
+            if !allowed.contains(value) {
+                return Err(format!("{}: {} not in enum", path, value));
+            }
+        }
+        match (schema["type"].as_str(), value) {
+            (Some("object"), Value::Object(map)) => {
+                for key in schema["required"].as_array().unwrap() {
+                    if !map.contains_key(key.as_str().unwrap()) {
+                        return Err(format!("{}: missing {}", path, key));
+                    }
+                }
+                for (key, item) in map {
+                    let property = schema["properties"]
+                        .get(key)
+                        .ok_or_else(|| format!("{}: unexpected property {}", path, key))?;
+                    conforms(property, item, &format!("{}.{}", path, key))?;
+                }
+                Ok(())
+            }
+            (Some("array"), Value::Array(items)) => {
+                items.iter().enumerate().try_for_each(|(i, item)| {
+                    conforms(&schema["items"], item, &format!("{}[{}]", path, i))
+                })
+            }
+            (Some("string"), Value::String(_))
+            | (Some("boolean"), Value::Bool(_))
+            | (Some("null"), Value::Null) => Ok(()),
+            (Some("integer"), Value::Number(n)) if n.is_u64() => Ok(()),
+            (expected, _) => Err(format!("{}: expected {:?}, got {}", path, expected, value)),
+        }
+    }
+
+    fn assert_conforms(command: &str, value: &Value) {
+        let schema = schema_for(command).unwrap();
+        if let Err(e) = conforms(&schema, value, command) {
+            panic!("{} output does not match its schema: {}", command, e);
+        }
+    }
+
+    fn sample_issues() -> Vec<Issue> {
+        vec![
+            test_issue("a", "open", &[]),
+            test_issue("b", "open", &["a"]),
+            test_issue("c", "open", &["a"]),
+            test_issue("d", "open", &["b", "c", "x"]),
+            test_issue("e", "open", &["e"]),
+        ]
+    }
+
+    #[test]
+    fn test_lint_and_gate_outputs_match() {
+        let issues = sample_issues();
+        let report = lint::build_report(
+            &issues,
+            &lint::Related::default(),
+            &LintConfig::default(),
+            "defaults",
+            0,
+        );
+        assert!(!report.findings.is_empty());
+        assert_conforms("lint", &serde_json::to_value(&report).unwrap());
+
+        for result in lint::gate_results(&report, &issues) {
+            let mut data = serde_json::to_value(&result).unwrap();
+            data["gate"] = "lint".into();
+            assert_conforms("gate", &data);
+        }
+    }
+
+    #[test]
+    fn test_graph_outputs_match() {
+        let graph = DepGraph::new(sample_issues());
+        let analysis = graph::analyze("epic", &graph, 1);
+        let mut out = serde_json::to_value(&analysis).unwrap();
+        out["diagrams"] = json!({
+            "dot": graph::render_dot(&graph, &analysis),
+            "mermaid": graph::render_mermaid(&graph, &analysis),
+        });
+        assert_conforms("graph-analyze", &out);
+
+        let report = graph::validate_structure(
+            "epic",
+            &graph,
+            &LintConfig::default(),
+            &ValidateOptions {
+                workers: Some(1),
+                max_fan_in: 1,
+            },
+        );
+        assert!(report.findings.len() >= 5);
+        assert_conforms("graph-validate", &serde_json::to_value(&report).unwrap());
+    }
+
+    #[test]
+    fn test_status_report_matches() {
+        let epic = Issue {
+            id: "epic".to_string(),
+            ..Default::default()
+        };
+        let mut issues = sample_issues();
+        issues[0].status = "closed".to_string();
+        issues[1].status = "in_progress".to_string();
+        let events = vec![ActivityEvent {
+            id: 1,
+            timestamp: 100,
+            issue: "b".to_string(),
+            kind: EventKind::Failed,
+            message: "tests failed".to_string(),
+            worker: Some("w1".to_string()),
+            run: None,
+            data: None,
+        }];
+        let estimates = BTreeMap::from([("b".to_string(), (60, 120))]);
+        let status = report::build_status(&epic, &issues, &events, &estimates, 5, 200);
+        assert!(status.eta.is_some());
+        assert_conforms("report-status", &serde_json::to_value(&status).unwrap());
+
+        let status = report::build_status(&epic, &[], &[], &BTreeMap::new(), 5, 200);
+        assert_conforms("report-status", &serde_json::to_value(&status).unwrap());
+    }
+
+    #[test]
+    fn test_health_output_matches() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let checks = health::check_beads(dir.path(), HealthOptions::default());
+        let out = json!({ "status": health::overall_status(&checks), "checks": checks });
+        assert_conforms("health", &out);
+    }
+
+    #[test]
+    fn test_unknown_command() {
+        let err = schema_for("swarm").unwrap_err();
+        assert!(err.contains("known: lint, gate"));
+        assert_eq!(schema_for("lint").unwrap()["$id"], "ralph-beads-cli/lint");
+    }
+}
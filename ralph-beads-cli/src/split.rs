@@ -0,0 +1,277 @@
+//! Splitting an oversized task
+//!
+//! `swarm split ID --into N` (also `task split`) divides a task's acceptance criteria into N
+//! consecutive groups of near-equal size. Each group becomes a task under
+//! the same epic, titled after its first criterion and carrying the
+//! original description for context. Unless `--no-setup`, a setup task
+//! comes first and every part depends on it.
+//!
+//! The parts are drafted as an epic plan and linted before anything is
+//! created, then created by epic scaffolding. The original task's criteria
+//! are replaced by one per part, and it is blocked by the parts, so it can
+//! be closed once they are. If any step fails, the created parts are
+//! deleted again.
+
+use std::path::Path;
+
+use crate::beads::{self, delete_issue, Dependency, Issue};
+use crate::epic::{scaffold_into, slugify, Plan, PlanTask, ScaffoldResult};
+use crate::lint;
+
+/// Label of the setup task before it has an ID
+pub const SETUP_DRAFT_ID: &str = "(setup)";
+
+/// Label of part `n` (1-based) before it has an ID
+pub fn part_draft_id(n: usize) -> String {
+    format!("(part {})", n)
+}
+
+/// As a checkbox criterion, which is how split criteria are written
+fn checkbox(text: &str) -> String {
+    format!("- [ ] {}", text)
+}
+
+/// The plan for splitting `task` into `into` parts, plus a setup task first
+/// when `setup` is set
+pub fn split_plan(task: &Issue, into: usize, setup: bool) -> Result<Plan, String> {
+    let criteria: Vec<&str> = lint::criteria(&task.acceptance_criteria)
+        .into_iter()
+        .map(|(text, _)| text)
+        .collect();
+    if into < 2 {
+        return Err(format!("--into must be at least 2, got {}", into));
+    }
+    if criteria.len() < into {
+        return Err(format!(
+            "{} has {} acceptance criteria; it can be split into at most that many parts",
+            task.id,
+            criteria.len()
+        ));
+    }
+
+    let context = match task.description.trim() {
+        "" => format!("Split from {}: {}", task.id, task.title),
+        description => format!("Split from {}: {}\n\n{}", task.id, task.title, description),
+    };
+    let mut tasks = Vec::with_capacity(into + 1);
+    if setup {
+        tasks.push(PlanTask {
+            key: "setup".to_string(),
+            title: format!("Set up {}", task.title),
+            description: format!(
+                "Groundwork the parts of {} build on.\n\n{}",
+                task.id, context
+            ),
+            phase: None,
+            priority: task.priority,
+            acceptance_criteria: checkbox(&format!(
+                "The parts of {} can start without further setup",
+                task.id
+            )),
+            depends_on: Vec::new(),
+        });
+    }
+
+    let (base, extra) = (criteria.len() / into, criteria.len() % into);
+    let mut rest = criteria.as_slice();
+    for part in 0..into {
+        let (group, tail) = rest.split_at(base + usize::from(part < extra));
+        rest = tail;
+        let title = group[0].trim_end_matches('.').to_string();
+        tasks.push(PlanTask {
+            key: format!("part-{}-{}", part + 1, slugify(&title, None)),
+            title,
+            description: context.clone(),
+            phase: None,
+            priority: task.priority,
+            acceptance_criteria: group
+                .iter()
+                .map(|c| checkbox(c))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            depends_on: if setup { vec![0] } else { Vec::new() },
+        });
+    }
+
+    Ok(Plan {
+        title: task.title.clone(),
+        description: String::new(),
+        tasks,
+    })
+}
+
+/// Labels for the plan's tasks before they have IDs
+pub fn draft_ids(plan: &Plan) -> Vec<String> {
+    let setup = plan.tasks.first().is_some_and(|t| t.key == "setup");
+    (0..plan.tasks.len())
+        .map(|i| match (setup, i) {
+            (true, 0) => SETUP_DRAFT_ID.to_string(),
+            (true, i) => part_draft_id(i),
+            (false, i) => part_draft_id(i + 1),
+        })
+        .collect()
+}
+
+/// The plan's tasks as lint sees them, under `epic`
+pub fn drafts(plan: &Plan, epic: &str) -> Vec<Issue> {
+    let ids = draft_ids(plan);
+    plan.tasks
+        .iter()
+        .zip(&ids)
+        .map(|(task, id)| {
+            let mut dependencies = vec![Dependency {
+                issue_id: id.clone(),
+                depends_on_id: epic.to_string(),
+                dep_type: "parent-child".to_string(),
+            }];
+            dependencies.extend(task.depends_on.iter().map(|&d| Dependency {
+                issue_id: id.clone(),
+                depends_on_id: ids[d].clone(),
+                dep_type: "blocks".to_string(),
+            }));
+            Issue {
+                id: id.clone(),
+                title: task.title.clone(),
+                description: task.description.clone(),
+                acceptance_criteria: task.acceptance_criteria.clone(),
+                status: "open".to_string(),
+                priority: task.priority,
+                issue_type: "task".to_string(),
+                dependencies,
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+/// The original task's criteria once its work has moved to `parts`
+pub fn remaining_criteria(parts: &[String]) -> String {
+    parts
+        .iter()
+        .map(|id| checkbox(&format!("{} is closed", id)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Create the parts of `plan` under `epic`, block `task_id` on them, and
+/// replace its criteria, deleting the created parts if a later step fails
+pub fn apply_split(
+    dir: &Path,
+    task_id: &str,
+    plan: &Plan,
+    epic: &str,
+    setup: bool,
+) -> Result<ScaffoldResult, String> {
+    let result = scaffold_into(dir, plan, Some(epic))?;
+    let parts = &result.task_ids[usize::from(setup)..];
+    let linked = parts
+        .iter()
+        .try_for_each(|part| beads::add_dependency(dir, task_id, part, "blocks"))
+        .and_then(|_| beads::update_acceptance(dir, task_id, &remaining_criteria(parts)));
+    let Err(e) = linked else {
+        return Ok(result);
+    };
+    let leftover: Vec<String> = result
+        .task_ids
+        .iter()
+        .rev()
+        .filter(|id| delete_issue(dir, id).is_err())
+        .cloned()
+        .collect();
+    if leftover.is_empty() {
+        Err(format!("{} (rolled back)", e))
+    } else {
+        Err(format!(
+            "{} (could not roll back {})",
+            e,
+            leftover.join(", ")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(criteria: &str) -> Issue {
+        Issue {
+            id: "bd-7".to_string(),
+            title: "Build auth".to_string(),
+            description: "Login and logout.".to_string(),
+            acceptance_criteria: criteria.to_string(),
+            status: "open".to_string(),
+            priority: Some(1),
+            issue_type: "task".to_string(),
+            ..Default::default()
+        }
+    }
+
+    const CRITERIA: &str = "- [ ] Login form renders.\n\
+                            - [ ] Login posts credentials\n\
+                            - Logout clears the session\n\
+                            - [x] Sessions expire after an hour\n\
+                            - Failed logins are rate limited";
+
+    #[test]
+    fn test_criteria_are_grouped_in_order() {
+        let plan = split_plan(&task(CRITERIA), 2, true).unwrap();
+
+        let titles: Vec<&str> = plan.tasks.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            vec![
+                "Set up Build auth",
+                "Login form renders",
+                "Sessions expire after an hour"
+            ]
+        );
+        assert_eq!(
+            plan.tasks[1].acceptance_criteria,
+            "- [ ] Login form renders.\n- [ ] Login posts credentials\n- [ ] Logout clears the session"
+        );
+        assert_eq!(plan.tasks[2].acceptance_criteria.lines().count(), 2);
+        assert_eq!(plan.tasks[1].depends_on, vec![0]);
+        assert!(plan.tasks[2]
+            .description
+            .starts_with("Split from bd-7: Build auth\n\nLogin and logout."));
+        assert_eq!(plan.tasks[2].priority, Some(1));
+    }
+
+    #[test]
+    fn test_split_needs_enough_criteria() {
+        assert!(split_plan(&task(CRITERIA), 1, false)
+            .unwrap_err()
+            .contains("at least 2"));
+        assert!(split_plan(&task(CRITERIA), 6, false)
+            .unwrap_err()
+            .contains("has 5 acceptance criteria"));
+
+        let plan = split_plan(&task(CRITERIA), 5, false).unwrap();
+        assert_eq!(plan.tasks.len(), 5);
+        assert!(plan.tasks.iter().all(|t| t.depends_on.is_empty()));
+        assert_eq!(draft_ids(&plan)[0], "(part 1)");
+    }
+
+    #[test]
+    fn test_drafts_lint_clean_and_link_to_setup() {
+        let plan = split_plan(&task(CRITERIA), 2, true).unwrap();
+        let drafts = drafts(&plan, "bd-1");
+
+        let report = lint::build_report(
+            &drafts,
+            &lint::Related::default(),
+            &lint::LintConfig::default(),
+            "test",
+            0,
+        )
+        .for_issues(&draft_ids(&plan));
+
+        assert_eq!(drafts[1].parent(), Some("bd-1"));
+        assert_eq!(drafts[1].blockers().collect::<Vec<_>>(), vec!["(setup)"]);
+        assert!(!report.has_errors(), "{:?}", report.findings);
+        assert_eq!(
+            remaining_criteria(&["bd-8".to_string(), "bd-9".to_string()]),
+            "- [ ] bd-8 is closed\n- [ ] bd-9 is closed"
+        );
+    }
+}
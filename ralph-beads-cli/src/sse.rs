@@ -0,0 +1,515 @@
+//! Server-Sent Events bridge for the activity feed
+//!
+//! Exposes the local activity feed over HTTP so dashboards and IDE
+//! extensions can subscribe to real-time progress without polling the CLI.
+//!
+//! Endpoints:
+//! - `GET /events?prefix=<issue-prefix>&kind=<kind>&replay=1` - event stream
+//! - `GET /health` - liveness probe
+//!
+//! Clients that reconnect with a `Last-Event-ID` header resume after that
+//! event instead of missing whatever was published while they were away.
+//!
+//! Browsers only let pages read the stream from origins passed with
+//! `--allow-origin`; without one, no CORS header is sent, so a page the user
+//! happens to open can't read the feed off localhost.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::activity::{read_events, ActivityEvent, EventKind, FeedTail};
+
+/// Interval between keep-alive comments on idle streams
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Upper bound on request head size
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// Upper bound on the request line
+const MAX_REQUEST_LINE: usize = 2 * 1024;
+
+/// How long a client may take to send its request head
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Parsed request line and the headers the bridge cares about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: HashMap<String, String>,
+    pub last_event_id: Option<u64>,
+    /// `Origin` header, sent by browsers on cross-origin requests
+    pub origin: Option<String>,
+}
+
+/// Per-connection subscription filter
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Subscription {
+    /// Only events whose issue starts with this prefix
+    pub prefix: Option<String>,
+    pub kind: Option<EventKind>,
+    /// Replay events after this id before streaming new ones
+    pub resume_after: Option<u64>,
+}
+
+impl Subscription {
+    /// Build a subscription from request query parameters and headers
+    pub fn from_request(request: &Request) -> Result<Self, String> {
+        let kind = match request.query.get("kind") {
+            Some(k) => Some(k.parse::<EventKind>()?),
+            None => None,
+        };
+        let resume_after = match request.last_event_id {
+            Some(id) => Some(id),
+            None if request.query.get("replay").map(String::as_str) == Some("1") => Some(0),
+            None => None,
+        };
+
+        Ok(Subscription {
+            prefix: request.query.get("prefix").cloned(),
+            kind,
+            resume_after,
+        })
+    }
+
+    /// Whether an event should be delivered on this connection
+    pub fn matches(&self, event: &ActivityEvent) -> bool {
+        if let Some(ref prefix) = self.prefix {
+            if !event.issue.starts_with(prefix.as_str()) {
+                return false;
+            }
+        }
+        if let Some(kind) = self.kind {
+            if event.kind != kind {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Decode `%XX` escapes and `+` in a query component
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = |b: u8| (b as char).to_digit(16);
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => match (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                (Some(hi), Some(lo)) => {
+                    out.push((hi * 16 + lo) as u8);
+                    i += 2;
+                }
+                _ => out.push(b'%'),
+            },
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Parse a query string into key/value pairs
+pub fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let mut parts = pair.splitn(2, '=');
+            let key = percent_decode(parts.next().unwrap_or_default());
+            let value = percent_decode(parts.next().unwrap_or_default());
+            (key, value)
+        })
+        .collect()
+}
+
+/// Parse an HTTP request head
+pub fn parse_request(head: &str) -> Result<Request, String> {
+    let mut lines = head.lines();
+    let request_line = lines.next().ok_or("Empty request")?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().ok_or("Missing method")?.to_string();
+    let target = parts.next().ok_or("Missing request target")?;
+
+    let (path, query) = match target.split_once('?') {
+        Some((p, q)) => (p.to_string(), parse_query(q)),
+        None => (target.to_string(), HashMap::new()),
+    };
+
+    let mut last_event_id = None;
+    let mut origin = None;
+    for line in lines {
+        if let Some((name, value)) = line.split_once(':') {
+            let name = name.trim();
+            if name.eq_ignore_ascii_case("last-event-id") {
+                last_event_id = value.trim().parse().ok();
+            } else if name.eq_ignore_ascii_case("origin") {
+                origin = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    Ok(Request {
+        method,
+        path,
+        query,
+        last_event_id,
+        origin,
+    })
+}
+
+/// Encode an event as an SSE message
+pub fn format_sse_event(event: &ActivityEvent) -> String {
+    let data = serde_json::to_string(event).unwrap_or_default();
+    format!(
+        "id: {}\nevent: {}\ndata: {}\n\n",
+        event.id, event.kind, data
+    )
+}
+
+/// CORS header echoing the request's origin when it is allowed
+fn cors_header(origin: Option<&str>, allowed: &[String]) -> String {
+    match origin {
+        Some(origin) if allowed.iter().any(|a| a == origin) => {
+            format!(
+                "Access-Control-Allow-Origin: {}\r\nVary: Origin\r\n",
+                origin
+            )
+        }
+        _ => String::new(),
+    }
+}
+
+fn write_response(stream: &mut TcpStream, status: &str, cors: &str, body: &str) {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\n\
+         {}Connection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        cors,
+        body
+    );
+    let _ = stream.write_all(response.as_bytes());
+}
+
+/// Read the request head (up to the blank line) from a connection
+///
+/// Reads at most `MAX_REQUEST_BYTES`, so a client can't make the server
+/// buffer an endless line.
+fn read_request_head(stream: &TcpStream) -> Result<String, String> {
+    let mut reader = BufReader::new(stream.take(MAX_REQUEST_BYTES as u64 + 1));
+    let mut head = String::new();
+    loop {
+        let mut line = String::new();
+        let n = reader
+            .read_line(&mut line)
+            .map_err(|e| format!("Failed to read request: {}", e))?;
+        if head.is_empty() && line.len() > MAX_REQUEST_LINE {
+            return Err("Request line too long".to_string());
+        }
+        if head.len() + line.len() > MAX_REQUEST_BYTES {
+            return Err("Request head too large".to_string());
+        }
+        if n == 0 || line == "\r\n" || line == "\n" {
+            break;
+        }
+        head.push_str(&line);
+    }
+    Ok(head)
+}
+
+/// Stream matching events to a client until it disconnects
+fn stream_events(
+    stream: &mut TcpStream,
+    feed_file: &Path,
+    subscription: &Subscription,
+    poll_interval: Duration,
+    cors: &str,
+) -> std::io::Result<()> {
+    stream.write_all(
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\
+             Connection: keep-alive\r\n{}\r\n",
+            cors
+        )
+        .as_bytes(),
+    )?;
+
+    // Take the tail position before replaying so nothing published in
+    // between is lost; replayed ids are skipped when the tail catches up
+    let mut tail = FeedTail::at_end(feed_file);
+    let mut last_sent = 0;
+
+    if let Some(after) = subscription.resume_after {
+        for event in read_events(feed_file).unwrap_or_default() {
+            if event.id > after && subscription.matches(&event) {
+                stream.write_all(format_sse_event(&event).as_bytes())?;
+                last_sent = event.id;
+            }
+        }
+    }
+    stream.flush()?;
+
+    let mut last_write = Instant::now();
+    loop {
+        for event in tail.poll(feed_file).unwrap_or_default() {
+            if event.id > last_sent && subscription.matches(&event) {
+                stream.write_all(format_sse_event(&event).as_bytes())?;
+                stream.flush()?;
+                last_sent = event.id;
+                last_write = Instant::now();
+            }
+        }
+
+        // Writing is the only way to notice a client that went away
+        if last_write.elapsed() >= KEEPALIVE_INTERVAL {
+            stream.write_all(b": keepalive\n\n")?;
+            stream.flush()?;
+            last_write = Instant::now();
+        }
+
+        thread::sleep(poll_interval);
+    }
+}
+
+fn handle_connection(
+    mut stream: TcpStream,
+    feed_file: PathBuf,
+    poll_interval: Duration,
+    allow_origins: &[String],
+) {
+    let _ = stream.set_read_timeout(Some(REQUEST_TIMEOUT));
+    let request = match read_request_head(&stream).and_then(|head| parse_request(&head)) {
+        Ok(r) => r,
+        Err(e) => {
+            write_response(&mut stream, "400 Bad Request", "", &e);
+            return;
+        }
+    };
+    let cors = cors_header(request.origin.as_deref(), allow_origins);
+
+    if request.method != "GET" {
+        write_response(
+            &mut stream,
+            "405 Method Not Allowed",
+            &cors,
+            "Only GET is supported",
+        );
+        return;
+    }
+
+    match request.path.as_str() {
+        "/health" => write_response(&mut stream, "200 OK", &cors, "ok"),
+        "/events" => match Subscription::from_request(&request) {
+            Ok(subscription) => {
+                // Any error here means the client disconnected
+                let _ = stream_events(&mut stream, &feed_file, &subscription, poll_interval, &cors);
+            }
+            Err(e) => write_response(&mut stream, "400 Bad Request", &cors, &e),
+        },
+        _ => write_response(&mut stream, "404 Not Found", &cors, "Not found"),
+    }
+}
+
+/// Serve the feed on an already bound listener, one thread per client
+///
+/// Browser pages from `allow_origins` (e.g. `http://localhost:3000`) may
+/// read the stream; pages from anywhere else may not.
+pub fn serve_listener(
+    listener: TcpListener,
+    feed_file: &Path,
+    poll_interval: Duration,
+    allow_origins: &[String],
+) -> Result<(), String> {
+    let allow_origins = Arc::new(allow_origins.to_vec());
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let feed_file = feed_file.to_path_buf();
+                let allow_origins = Arc::clone(&allow_origins);
+                thread::spawn(move || {
+                    handle_connection(stream, feed_file, poll_interval, &allow_origins)
+                });
+            }
+            Err(e) => tracing::warn!("failed to accept connection: {}", e),
+        }
+    }
+    Ok(())
+}
+
+/// Bind `addr` and serve the activity feed over SSE
+pub fn serve(
+    feed_file: &Path,
+    addr: &str,
+    poll_interval: Duration,
+    allow_origins: &[String],
+) -> Result<(), String> {
+    let listener =
+        TcpListener::bind(addr).map_err(|e| format!("Failed to bind {}: {}", addr, e))?;
+    serve_listener(listener, feed_file, poll_interval, allow_origins)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::activity::emit_event;
+    use std::io::Read;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_request() {
+        let head = "GET /events?prefix=bd-a3f8&kind=failed HTTP/1.1\r\n\
+                    Host: localhost\r\nLast-Event-ID: 42\r\n";
+
+        let request = parse_request(head).unwrap();
+
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.path, "/events");
+        assert_eq!(request.query.get("prefix").unwrap(), "bd-a3f8");
+        assert_eq!(request.last_event_id, Some(42));
+    }
+
+    #[test]
+    fn test_parse_query_decodes() {
+        let query = parse_query("prefix=bd%2Da3f8&message=hello+world&empty=");
+
+        assert_eq!(query.get("prefix").unwrap(), "bd-a3f8");
+        assert_eq!(query.get("message").unwrap(), "hello world");
+        assert_eq!(query.get("empty").unwrap(), "");
+    }
+
+    #[test]
+    fn test_subscription_from_request() {
+        let request = parse_request("GET /events?kind=ci&replay=1 HTTP/1.1\r\n").unwrap();
+        let subscription = Subscription::from_request(&request).unwrap();
+        assert_eq!(subscription.kind, Some(EventKind::Ci));
+        assert_eq!(subscription.resume_after, Some(0));
+
+        let request = parse_request("GET /events?kind=bogus HTTP/1.1\r\n").unwrap();
+        assert!(Subscription::from_request(&request).is_err());
+    }
+
+    #[test]
+    fn test_subscription_prefix_filter() {
+        let event = ActivityEvent {
+            id: 1,
+            timestamp: 0,
+            issue: "bd-a3f8.2".to_string(),
+            kind: EventKind::Progress,
+            message: String::new(),
+            worker: None,
+            run: None,
+            data: None,
+        };
+
+        let subscription = |prefix: &str| Subscription {
+            prefix: Some(prefix.to_string()),
+            ..Default::default()
+        };
+        assert!(subscription("bd-a3f8").matches(&event));
+        assert!(!subscription("bd-b").matches(&event));
+    }
+
+    #[test]
+    fn test_format_sse_event() {
+        let event = ActivityEvent {
+            id: 5,
+            timestamp: 0,
+            issue: "bd-1".to_string(),
+            kind: EventKind::Completed,
+            message: "done".to_string(),
+            worker: None,
+            run: None,
+            data: None,
+        };
+
+        let message = format_sse_event(&event);
+
+        assert!(message.starts_with("id: 5\nevent: completed\ndata: {"));
+        assert!(message.ends_with("}\n\n"));
+    }
+
+    #[test]
+    fn test_serve_replays_matching_events() {
+        let dir = TempDir::new().unwrap();
+        let feed = dir.path().join("activity.jsonl");
+        emit_event(&feed, "bd-1.1", EventKind::Claimed, "one", None, None).unwrap();
+        emit_event(&feed, "bd-2.1", EventKind::Claimed, "two", None, None).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server_feed = feed.clone();
+        let origins = vec!["http://localhost:3000".to_string()];
+        thread::spawn(move || {
+            serve_listener(listener, &server_feed, Duration::from_millis(20), &origins)
+        });
+
+        let mut client = TcpStream::connect(addr).unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        client
+            .write_all(b"GET /events?prefix=bd-1&replay=1 HTTP/1.1\r\n\r\n")
+            .unwrap();
+
+        let mut received = String::new();
+        let mut buf = [0u8; 1024];
+        while !received.contains("\n\n") || !received.contains("data:") {
+            let n = client.read(&mut buf).unwrap();
+            assert!(n > 0, "server closed the stream");
+            received.push_str(&String::from_utf8_lossy(&buf[..n]));
+        }
+
+        assert!(received.starts_with("HTTP/1.1 200 OK"));
+        assert!(received.contains("text/event-stream"));
+        assert!(received.contains("\"message\":\"one\""));
+        assert!(!received.contains("\"message\":\"two\""));
+        assert!(!received.contains("Access-Control-Allow-Origin"));
+    }
+
+    #[test]
+    fn test_cors_only_for_allowed_origins() {
+        let allowed = vec!["http://localhost:3000".to_string()];
+
+        assert_eq!(
+            cors_header(Some("http://localhost:3000"), &allowed),
+            "Access-Control-Allow-Origin: http://localhost:3000\r\nVary: Origin\r\n"
+        );
+        assert_eq!(cors_header(Some("https://evil.example"), &allowed), "");
+        assert_eq!(cors_header(None, &allowed), "");
+        let request = parse_request("GET /events HTTP/1.1\r\nOrigin: http://a.test\r\n").unwrap();
+        assert_eq!(request.origin.as_deref(), Some("http://a.test"));
+    }
+
+    #[test]
+    fn test_oversized_request_line_is_refused() {
+        let dir = TempDir::new().unwrap();
+        let feed = dir.path().join("activity.jsonl");
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || serve_listener(listener, &feed, Duration::from_millis(20), &[]));
+
+        let mut client = TcpStream::connect(addr).unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        // No newline: the server must stop reading on its own
+        let _ = client.write_all(format!("GET /{}", "a".repeat(MAX_REQUEST_BYTES)).as_bytes());
+
+        let mut received = String::new();
+        let _ = client.read_to_string(&mut received);
+        assert!(
+            received.starts_with("HTTP/1.1 400 Bad Request"),
+            "{}",
+            received
+        );
+        assert!(received.ends_with("Request line too long"));
+    }
+}
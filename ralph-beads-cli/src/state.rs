@@ -1,10 +1,63 @@
 //! Workflow state types for Ralph-Beads
 //!
-//! Provides the WorkflowMode enum used by iteration calculation.
+//! Provides the WorkflowMode enum used by iteration calculation, the legal
+//! transitions between modes, and a small session file recording each
+//! transition with its reason and time.
+//!
+//! Each session gets a run ID when it starts. Exported as `RALPH_BEADS_RUN`,
+//! it tags everything the session writes (e.g. activity events) so output
+//! from concurrent sessions can be told apart.
+//!
+//! Session files carry a `schema_version`. Older files are upgraded in
+//! memory on load, one version step at a time, and rewritten in the current
+//! format on the next save (or by `state migrate`).
+//!
+//! Every update bumps the file's `revision` under an exclusive lock on a
+//! sidecar `.lock` file. Writers that read the session earlier pass the
+//! revision they saw; if another process saved in between, the update is
+//! refused with a conflict instead of overwriting its changes.
 
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::env;
 use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::stop::{self, Continuation, StopConfig, StopSignals};
+
+/// Default location of the session state file, relative to the project root
+/// (`--data-dir` moves it; see [`crate::data_dirs`])
+pub const DEFAULT_SESSION_FILE: &str = ".beads/session.json";
+
+/// Session file format written by this build
+///
+/// - 1: unversioned files (mode, transitions, optional run ID)
+/// - 2: adds `schema_version`; modes are stored by canonical name only
+/// - 3: adds `revision` and `iteration_count`
+pub const SCHEMA_VERSION: u32 = 3;
+
+/// Environment variable carrying the current run ID
+pub const RUN_ENV: &str = "RALPH_BEADS_RUN";
+
+/// A fresh run ID: start time plus a process-and-clock discriminator
+///
+/// Sortable by start time (e.g. `run-6963a1f0-3fa2c`) and distinct for
+/// sessions started in the same second.
+pub fn new_run_id() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let salt = (now.subsec_nanos() ^ std::process::id().rotate_left(16)) & 0xfffff;
+    format!("run-{:08x}-{:05x}", now.as_secs(), salt)
+}
+
+/// The run ID of the session this process belongs to, from `RALPH_BEADS_RUN`
+pub fn current_run() -> Option<String> {
+    env::var(RUN_ENV).ok().filter(|r| !r.trim().is_empty())
+}
 
 /// Workflow modes for Ralph-Beads execution
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -46,6 +99,461 @@ impl FromStr for WorkflowMode {
     }
 }
 
+impl WorkflowMode {
+    pub const ALL: [WorkflowMode; 4] = [
+        WorkflowMode::Planning,
+        WorkflowMode::Building,
+        WorkflowMode::Paused,
+        WorkflowMode::Complete,
+    ];
+
+    /// Modes a session may start in
+    pub const INITIAL: [WorkflowMode; 2] = [WorkflowMode::Planning, WorkflowMode::Building];
+
+    /// Modes reachable from this one
+    pub fn next_modes(self) -> &'static [WorkflowMode] {
+        use WorkflowMode::*;
+        match self {
+            Planning => &[Building, Paused],
+            Building => &[Paused, Complete, Planning],
+            Paused => &[Planning, Building],
+            Complete => &[Planning],
+        }
+    }
+
+    /// Check that a session may move from `from` (`None` = new session) to `to`
+    pub fn check_transition(from: Option<WorkflowMode>, to: WorkflowMode) -> Result<(), String> {
+        let allowed: &[WorkflowMode] = match from {
+            None => &Self::INITIAL,
+            Some(from) => from.next_modes(),
+        };
+        if allowed.contains(&to) {
+            return Ok(());
+        }
+        let names: Vec<String> = allowed.iter().map(|m| m.to_string()).collect();
+        Err(match from {
+            None => format!(
+                "A session cannot start in {} (allowed: {})",
+                to,
+                names.join(", ")
+            ),
+            Some(from) => format!(
+                "Illegal mode transition {} -> {} (allowed from {}: {})",
+                from,
+                to,
+                from,
+                names.join(", ")
+            ),
+        })
+    }
+}
+
+/// One recorded mode change
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModeTransition {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<WorkflowMode>,
+    pub to: WorkflowMode,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Unix timestamp (seconds)
+    pub timestamp: u64,
+}
+
+/// What a planning session produced, carried into the building session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanLink {
+    /// Bead holding the plan document (usually the epic)
+    pub plan_id: String,
+    /// Gate bead that must be closed (approved) before building starts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval_gate: Option<String>,
+}
+
+/// A human's request that the loop stop at its next check
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StopRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Unix timestamp (seconds)
+    pub timestamp: u64,
+}
+
+/// The working tree as `state should-continue` last saw it change
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProgressMark {
+    /// Fingerprint of HEAD and the uncommitted changes
+    pub fingerprint: String,
+    /// `iteration_count` when the fingerprint was taken
+    pub iteration: u32,
+}
+
+/// Persisted session state: the current mode and how it got there
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionState {
+    pub schema_version: u32,
+    /// Run ID assigned when the session started (see `RUN_ENV`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<WorkflowMode>,
+    pub transitions: Vec<ModeTransition>,
+    /// Number of saved updates, for compare-and-swap between processes
+    pub revision: u64,
+    /// Loop iterations run so far; only ever grows within a session
+    pub iteration_count: u32,
+    /// Plan and approval gate the session builds from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan: Option<PlanLink>,
+    /// Set by `state stop`; the loop ends at its next check
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_requested: Option<StopRequest>,
+    /// Last change to the working tree, for no-progress detection
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress: Option<ProgressMark>,
+}
+
+impl Default for SessionState {
+    fn default() -> Self {
+        SessionState {
+            schema_version: SCHEMA_VERSION,
+            run_id: None,
+            mode: None,
+            transitions: Vec::new(),
+            revision: 0,
+            iteration_count: 0,
+            plan: None,
+            stop_requested: None,
+            progress: None,
+        }
+    }
+}
+
+/// Why a session update was not saved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpdateError {
+    /// Another writer saved since the caller read revision `expected`
+    Conflict { expected: u64, found: u64 },
+    /// The session file is unreadable or the change is not allowed
+    Invalid(String),
+    /// The session file or its lock couldn't be written
+    Io(String),
+}
+
+impl fmt::Display for UpdateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UpdateError::Conflict { expected, found } => write!(
+                f,
+                "Session changed since revision {} (now {}); reload it or pass --retry",
+                expected, found
+            ),
+            UpdateError::Invalid(e) | UpdateError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<UpdateError> for String {
+    fn from(e: UpdateError) -> Self {
+        e.to_string()
+    }
+}
+
+/// Canonical name of a mode value written under any accepted alias
+fn normalize_mode(value: &mut Value) -> Result<(), String> {
+    if let Value::String(name) = value {
+        *name = name.parse::<WorkflowMode>()?.to_string();
+    }
+    Ok(())
+}
+
+/// v1 -> v2: canonical mode names, and `mode` filled in from the last
+/// transition when missing
+fn migrate_v1(state: &mut Map<String, Value>) -> Result<(), String> {
+    if let Some(mode) = state.get_mut("mode") {
+        normalize_mode(mode)?;
+    }
+    if let Some(Value::Array(transitions)) = state.get_mut("transitions") {
+        for transition in transitions.iter_mut().filter_map(Value::as_object_mut) {
+            for key in ["from", "to"] {
+                if let Some(mode) = transition.get_mut(key) {
+                    normalize_mode(mode)?;
+                }
+            }
+        }
+    }
+    if state.get("mode").is_none_or(Value::is_null) {
+        let last = state
+            .get("transitions")
+            .and_then(Value::as_array)
+            .and_then(|t| t.last())
+            .and_then(|t| t.get("to"))
+            .cloned();
+        if let Some(mode) = last {
+            state.insert("mode".to_string(), mode);
+        }
+    }
+    Ok(())
+}
+
+/// One schema upgrade step, applied to the session's JSON object
+type Migration = fn(&mut Map<String, Value>) -> Result<(), String>;
+
+/// v2 -> v3: sessions start at revision 0 with no iterations recorded
+fn migrate_v2(state: &mut Map<String, Value>) -> Result<(), String> {
+    state.entry("revision").or_insert(0.into());
+    state.entry("iteration_count").or_insert(0.into());
+    Ok(())
+}
+
+/// Upgrade steps: `MIGRATIONS[i]` takes version `i + 1` to `i + 2`
+const MIGRATIONS: [Migration; 2] = [migrate_v1, migrate_v2];
+
+/// Upgrade a serialized session to `SCHEMA_VERSION`, returning the version
+/// it was written in
+pub fn migrate_session(value: &mut Value) -> Result<u32, String> {
+    let state = value
+        .as_object_mut()
+        .ok_or("session state must be a JSON object")?;
+    let from = match state.get("schema_version") {
+        None => 1,
+        Some(v) => v
+            .as_u64()
+            .filter(|v| *v >= 1)
+            .ok_or_else(|| format!("invalid schema_version {}", v))? as u32,
+    };
+    if from > SCHEMA_VERSION {
+        return Err(format!(
+            "schema_version {} is newer than this ralph-beads-cli supports ({})",
+            from, SCHEMA_VERSION
+        ));
+    }
+    for (i, step) in MIGRATIONS.iter().enumerate().skip(from as usize - 1) {
+        step(state).map_err(|e| format!("migrating v{} -> v{}: {}", i + 1, i + 2, e))?;
+    }
+    state.insert("schema_version".to_string(), SCHEMA_VERSION.into());
+    Ok(from)
+}
+
+impl SessionState {
+    /// Start a new session in `mode` under `run_id`
+    pub fn start(
+        run_id: String,
+        mode: WorkflowMode,
+        reason: Option<String>,
+        now: u64,
+    ) -> Result<Self, String> {
+        let mut session = SessionState {
+            run_id: Some(run_id),
+            ..Default::default()
+        };
+        session.transition(mode, reason, now)?;
+        Ok(session)
+    }
+
+    /// Start a session that replaces `self`
+    ///
+    /// A building session keeps the plan link, so it still waits on the
+    /// planning session's approval; a new planning session starts unlinked.
+    pub fn succeed(
+        &self,
+        run_id: String,
+        mode: WorkflowMode,
+        reason: Option<String>,
+        now: u64,
+    ) -> Result<Self, String> {
+        let mut session = Self::start(run_id, mode, reason, now)?;
+        if mode != WorkflowMode::Planning {
+            session.plan = self.plan.clone();
+        }
+        Ok(session)
+    }
+
+    /// Link the plan document and approval gate the session builds from
+    pub fn link_plan(&mut self, plan_id: &str, approval_gate: Option<&str>) -> Result<(), String> {
+        if plan_id.trim().is_empty() {
+            return Err("Plan ID must not be empty".to_string());
+        }
+        self.plan = Some(PlanLink {
+            plan_id: plan_id.to_string(),
+            approval_gate: approval_gate.map(String::from),
+        });
+        Ok(())
+    }
+
+    /// The approval gate that must pass before entering `to`, if any
+    ///
+    /// Only building is gated: planning, pausing, and completing never wait
+    /// on approval.
+    pub fn approval_required(&self, to: WorkflowMode) -> Option<&str> {
+        if to != WorkflowMode::Building {
+            return None;
+        }
+        self.plan.as_ref()?.approval_gate.as_deref()
+    }
+
+    /// Whether the session has started and not yet completed
+    pub fn is_active(&self) -> bool {
+        self.mode.is_some_and(|m| m != WorkflowMode::Complete)
+    }
+
+    /// Load the session file (missing file = new session)
+    pub fn load(path: &Path) -> Result<Self, String> {
+        Self::load_versioned(path).map(|(session, _)| session)
+    }
+
+    /// Load the session file, upgrading older formats, together with the
+    /// schema version the file was written in
+    pub fn load_versioned(path: &Path) -> Result<(Self, u32), String> {
+        if !path.exists() {
+            return Ok((Self::default(), SCHEMA_VERSION));
+        }
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let invalid = |e: String| format!("Invalid session file {}: {}", path.display(), e);
+        let mut value: Value =
+            serde_json::from_str(&content).map_err(|e| invalid(e.to_string()))?;
+        let version = migrate_session(&mut value).map_err(invalid)?;
+        let session = serde_json::from_value(value).map_err(|e| invalid(e.to_string()))?;
+        Ok((session, version))
+    }
+
+    /// Write the session file, creating parent directories
+    ///
+    /// The file is replaced atomically, so readers never see a partial write.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        create_parent(path)?;
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        let tmp = sidecar(path, "tmp");
+        fs::write(&tmp, json + "\n")
+            .map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+        fs::rename(&tmp, path).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// Load, change, and save the session file as one step
+    ///
+    /// Holds the file's lock throughout and bumps `revision`. With
+    /// `expected`, the file must still be at that revision; otherwise the
+    /// update fails with `UpdateError::Conflict`, unless `merge` is set, in
+    /// which case `change` is applied to the newer session instead (it must
+    /// then only make changes that merge, like `record_iterations`).
+    pub fn update<F>(
+        path: &Path,
+        expected: Option<u64>,
+        merge: bool,
+        change: F,
+    ) -> Result<SessionState, UpdateError>
+    where
+        F: FnOnce(&mut SessionState) -> Result<(), String>,
+    {
+        create_parent(path).map_err(UpdateError::Io)?;
+        let lock_path = sidecar(path, "lock");
+        let lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .map_err(|e| {
+                UpdateError::Io(format!("Failed to open {}: {}", lock_path.display(), e))
+            })?;
+        lock.lock().map_err(|e| {
+            UpdateError::Io(format!("Failed to lock {}: {}", lock_path.display(), e))
+        })?;
+
+        let mut session = Self::load(path).map_err(UpdateError::Invalid)?;
+        let found = session.revision;
+        if let Some(expected) = expected.filter(|&e| e != found && !merge) {
+            return Err(UpdateError::Conflict { expected, found });
+        }
+        change(&mut session).map_err(UpdateError::Invalid)?;
+        session.schema_version = SCHEMA_VERSION;
+        session.revision = found + 1;
+        session.save(path).map_err(UpdateError::Io)?;
+        Ok(session)
+    }
+
+    /// Raise `iteration_count` to `count`
+    ///
+    /// The count never goes down: a lower `count` is an error, or with
+    /// `merge` (another writer got further first) leaves the count alone.
+    pub fn record_iterations(&mut self, count: u32, merge: bool) -> Result<(), String> {
+        if count < self.iteration_count && !merge {
+            return Err(format!(
+                "iteration_count only grows ({} < {})",
+                count, self.iteration_count
+            ));
+        }
+        self.iteration_count = self.iteration_count.max(count);
+        Ok(())
+    }
+
+    /// Whether the loop should run another iteration, and why not
+    pub fn should_continue(&self, config: &StopConfig, signals: &StopSignals) -> Continuation {
+        stop::evaluate(self, config, signals)
+    }
+
+    /// Ask the loop to stop at its next check, or withdraw the request
+    pub fn request_stop(&mut self, stop: bool, reason: Option<String>, now: u64) {
+        self.stop_requested = stop.then_some(StopRequest {
+            reason,
+            timestamp: now,
+        });
+    }
+
+    /// Iterations since the working tree last changed, remembering
+    /// `fingerprint` as the latest change when it differs
+    pub fn mark_progress(&mut self, fingerprint: &str) -> u32 {
+        match self.progress {
+            Some(ref mark) if mark.fingerprint == fingerprint => {
+                self.iteration_count.saturating_sub(mark.iteration)
+            }
+            _ => {
+                self.progress = Some(ProgressMark {
+                    fingerprint: fingerprint.to_string(),
+                    iteration: self.iteration_count,
+                });
+                0
+            }
+        }
+    }
+
+    /// Move to `to` if the transition table allows it, recording the change
+    pub fn transition(
+        &mut self,
+        to: WorkflowMode,
+        reason: Option<String>,
+        now: u64,
+    ) -> Result<&ModeTransition, String> {
+        WorkflowMode::check_transition(self.mode, to)?;
+        self.transitions.push(ModeTransition {
+            from: self.mode,
+            to,
+            reason,
+            timestamp: now,
+        });
+        self.mode = Some(to);
+        Ok(self.transitions.last().unwrap())
+    }
+}
+
+/// `<file>.<extension>` next to the session file
+/// `path` with `.extension` appended, for files kept beside it
+pub(crate) fn sidecar(path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(extension);
+    PathBuf::from(name)
+}
+
+fn create_parent(path: &Path) -> Result<(), String> {
+    match path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        Some(parent) => fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e)),
+        None => Ok(()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -108,4 +616,210 @@ mod tests {
         let deserialized: WorkflowMode = serde_json::from_str(&json).unwrap();
         assert_eq!(deserialized, WorkflowMode::Planning);
     }
+
+    #[test]
+    fn test_check_transition() {
+        use WorkflowMode::*;
+        assert!(WorkflowMode::check_transition(None, Planning).is_ok());
+        assert!(WorkflowMode::check_transition(Some(Planning), Building).is_ok());
+        assert!(WorkflowMode::check_transition(Some(Building), Complete).is_ok());
+        assert!(WorkflowMode::check_transition(Some(Complete), Planning).is_ok());
+
+        let err = WorkflowMode::check_transition(Some(Planning), Complete).unwrap_err();
+        assert_eq!(
+            err,
+            "Illegal mode transition planning -> complete (allowed from planning: building, paused)"
+        );
+        assert!(WorkflowMode::check_transition(None, Paused).is_err());
+        assert!(WorkflowMode::check_transition(Some(Building), Building).is_err());
+    }
+
+    #[test]
+    fn test_session_transitions_persist() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("nested/session.json");
+
+        let mut session = SessionState::load(&path).unwrap();
+        session
+            .transition(WorkflowMode::Planning, None, 100)
+            .unwrap();
+        session
+            .transition(
+                WorkflowMode::Building,
+                Some("plan approved".to_string()),
+                200,
+            )
+            .unwrap();
+        assert!(session
+            .transition(WorkflowMode::Planning, None, 300)
+            .is_ok());
+        assert!(session
+            .transition(WorkflowMode::Complete, None, 400)
+            .is_err());
+        session.save(&path).unwrap();
+
+        let loaded = SessionState::load(&path).unwrap();
+        assert_eq!(loaded, session);
+        assert_eq!(loaded.mode, Some(WorkflowMode::Planning));
+        assert_eq!(loaded.transitions.len(), 3);
+        assert_eq!(loaded.transitions[1].from, Some(WorkflowMode::Planning));
+        assert_eq!(
+            loaded.transitions[1].reason.as_deref(),
+            Some("plan approved")
+        );
+    }
+
+    #[test]
+    fn test_start_session_with_run_id() {
+        let id = new_run_id();
+        assert!(id.starts_with("run-"));
+
+        let session =
+            SessionState::start("run-1".to_string(), WorkflowMode::Planning, None, 100).unwrap();
+        assert_eq!(session.run_id.as_deref(), Some("run-1"));
+        assert!(session.is_active());
+        assert!(SessionState::start("run-2".to_string(), WorkflowMode::Paused, None, 100).is_err());
+    }
+
+    #[test]
+    fn test_plan_link_gates_building() {
+        let mut planning =
+            SessionState::start("run-1".to_string(), WorkflowMode::Planning, None, 100).unwrap();
+        assert_eq!(planning.approval_required(WorkflowMode::Building), None);
+        assert!(planning.link_plan(" ", None).is_err());
+
+        planning.link_plan("bd-7", Some("bd-7.gate")).unwrap();
+        assert_eq!(
+            planning.approval_required(WorkflowMode::Building),
+            Some("bd-7.gate")
+        );
+        assert_eq!(planning.approval_required(WorkflowMode::Paused), None);
+
+        // A replacement session keeps waiting on the same approval
+        let building = planning
+            .succeed("run-2".to_string(), WorkflowMode::Building, None, 200)
+            .unwrap();
+        assert_eq!(building.run_id.as_deref(), Some("run-2"));
+        assert_eq!(building.plan, planning.plan);
+        let replanning = building
+            .succeed("run-3".to_string(), WorkflowMode::Planning, None, 300)
+            .unwrap();
+        assert_eq!(replanning.plan, None);
+    }
+
+    #[test]
+    fn test_migrate_unversioned_session() {
+        let mut value = serde_json::json!({
+            "run_id": "run-1",
+            "transitions": [
+                { "to": "plan", "timestamp": 100 },
+                { "from": "Planning", "to": "build", "timestamp": 200 }
+            ]
+        });
+        assert_eq!(migrate_session(&mut value).unwrap(), 1);
+
+        let session: SessionState = serde_json::from_value(value).unwrap();
+        assert_eq!(session.schema_version, SCHEMA_VERSION);
+        assert_eq!(session.mode, Some(WorkflowMode::Building));
+        assert_eq!(session.transitions[1].from, Some(WorkflowMode::Planning));
+    }
+
+    #[test]
+    fn test_migrate_rejects_bad_versions() {
+        let mut newer = serde_json::json!({ "schema_version": SCHEMA_VERSION + 1 });
+        assert!(migrate_session(&mut newer).unwrap_err().contains("newer"));
+
+        let mut bad_mode = serde_json::json!({ "mode": "sleeping" });
+        assert!(migrate_session(&mut bad_mode)
+            .unwrap_err()
+            .contains("v1 -> v2"));
+
+        let mut current = serde_json::to_value(SessionState::default()).unwrap();
+        assert_eq!(migrate_session(&mut current).unwrap(), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_migrate_v2_adds_revision() {
+        let mut value = serde_json::json!({ "schema_version": 2, "mode": "building" });
+        assert_eq!(migrate_session(&mut value).unwrap(), 2);
+        assert_eq!(value["revision"], 0);
+        assert_eq!(value["iteration_count"], 0);
+    }
+
+    #[test]
+    fn test_update_compare_and_swap() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("session.json");
+
+        let first =
+            SessionState::update(&path, Some(0), false, |s| s.record_iterations(3, false)).unwrap();
+        assert_eq!((first.revision, first.iteration_count), (1, 3));
+
+        // A writer still holding revision 0 lost the race
+        let stale = SessionState::update(&path, Some(0), false, |s| s.record_iterations(2, false));
+        assert_eq!(
+            stale.unwrap_err(),
+            UpdateError::Conflict {
+                expected: 0,
+                found: 1
+            }
+        );
+
+        // Merging keeps the monotonic count at its highest value
+        let merged =
+            SessionState::update(&path, Some(0), true, |s| s.record_iterations(2, true)).unwrap();
+        assert_eq!((merged.revision, merged.iteration_count), (2, 3));
+
+        let err = SessionState::update(&path, None, false, |s| s.record_iterations(1, false))
+            .unwrap_err();
+        assert!(matches!(err, UpdateError::Invalid(ref e) if e.contains("only grows")));
+        assert_eq!(SessionState::load(&path).unwrap().revision, 2);
+    }
+
+    #[test]
+    fn test_mark_progress_counts_idle_iterations() {
+        let mut session = SessionState {
+            iteration_count: 2,
+            ..Default::default()
+        };
+
+        assert_eq!(session.mark_progress("a"), 0);
+        session.iteration_count = 5;
+        assert_eq!(session.mark_progress("a"), 3);
+        assert_eq!(session.mark_progress("b"), 0);
+        assert_eq!(session.progress.as_ref().unwrap().iteration, 5);
+
+        session.request_stop(true, None, 10);
+        assert_eq!(session.stop_requested.as_ref().unwrap().timestamp, 10);
+        session.request_stop(false, None, 11);
+        assert!(session.stop_requested.is_none());
+    }
+
+    #[test]
+    fn test_concurrent_updates_keep_every_increment() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("session.json");
+
+        let writers: Vec<_> = (0..8)
+            .map(|_| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..5 {
+                        SessionState::update(&path, None, false, |s| {
+                            let next = s.iteration_count + 1;
+                            s.record_iterations(next, false)
+                        })
+                        .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let session = SessionState::load(&path).unwrap();
+        assert_eq!(session.iteration_count, 40);
+        assert_eq!(session.revision, 40);
+    }
 }
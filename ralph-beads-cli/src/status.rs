@@ -0,0 +1,370 @@
+//! Situational overview for the start of an iteration
+//!
+//! `status` gathers what an agent would otherwise piece together from
+//! `worktree`, `state show`, `health`, `report status`, and `lint`: the
+//! worktree and the issue it serves, the session, problems with the beads
+//! setup, the parent epic's progress, open gates and recent failures from
+//! the activity feed, and lint errors on the issue.
+//!
+//! Sections are gathered independently. One that can't be (bd missing, not
+//! a git repository) is listed under `unavailable` instead of failing the
+//! whole command.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::activity::{format_timestamp, ActivityEvent, EventKind};
+use crate::beads::Issue;
+use crate::bundle::EpicSummary;
+use crate::health::{self, CheckStatus, HealthCheck};
+use crate::lint::{LintFinding, LintReport, Severity};
+use crate::report::{self, Failure, OpenGate};
+use crate::state::SessionState;
+use crate::worktree::CurrentWorktree;
+
+/// The issue being worked on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IssueStatus {
+    pub id: String,
+    pub title: String,
+    pub status: String,
+    /// Blockers that are not closed yet
+    pub open_blockers: Vec<String>,
+}
+
+impl IssueStatus {
+    /// `blockers` are the issue's blockers as fetched from bd
+    pub fn new(issue: &Issue, blockers: &[Issue]) -> Self {
+        IssueStatus {
+            id: issue.id.clone(),
+            title: issue.title.clone(),
+            status: issue.status.clone(),
+            open_blockers: blockers
+                .iter()
+                .filter(|b| !b.is_closed())
+                .map(|b| b.id.clone())
+                .collect(),
+        }
+    }
+}
+
+/// The session's approval gate while it is still open
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PendingApproval {
+    pub plan: String,
+    pub gate: String,
+    pub status: String,
+}
+
+/// Overall health and the checks that did not pass
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HealthSummary {
+    pub status: CheckStatus,
+    pub problems: Vec<HealthCheck>,
+}
+
+impl HealthSummary {
+    pub fn new(checks: Vec<HealthCheck>) -> Self {
+        HealthSummary {
+            status: health::overall_status(&checks),
+            problems: checks
+                .into_iter()
+                .filter(|c| c.status > CheckStatus::Skipped)
+                .collect(),
+        }
+    }
+}
+
+/// Lint errors on the issue
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LintSummary {
+    pub errors: usize,
+    pub warnings: usize,
+    /// Error-severity findings only; warnings are counted
+    pub findings: Vec<LintFinding>,
+}
+
+impl LintSummary {
+    pub fn new(report: LintReport) -> Self {
+        LintSummary {
+            errors: report.errors,
+            warnings: report.warnings,
+            findings: report
+                .findings
+                .into_iter()
+                .filter(|f| f.severity == Severity::Error)
+                .collect(),
+        }
+    }
+}
+
+/// Everything `status` reports
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Status {
+    pub generated_at: String,
+    pub worktree: Option<CurrentWorktree>,
+    pub issue: Option<IssueStatus>,
+    pub session: Option<SessionState>,
+    pub approval: Option<PendingApproval>,
+    pub health: Option<HealthSummary>,
+    pub epic: Option<EpicSummary>,
+    pub open_gates: Vec<OpenGate>,
+    pub recent_failures: Vec<Failure>,
+    pub lint: Option<LintSummary>,
+    /// Sections that could not be gathered, with the reason
+    pub unavailable: BTreeMap<&'static str, String>,
+}
+
+impl Status {
+    /// An empty overview; callers fill in the sections they can gather
+    pub fn new(now: u64) -> Self {
+        Status {
+            generated_at: format_timestamp(now),
+            worktree: None,
+            issue: None,
+            session: None,
+            approval: None,
+            health: None,
+            epic: None,
+            open_gates: Vec::new(),
+            recent_failures: Vec::new(),
+            lint: None,
+            unavailable: BTreeMap::new(),
+        }
+    }
+
+    /// The value of `result`, or `None` with the reason recorded under `name`
+    pub fn gather<T, E: Into<String>>(
+        &mut self,
+        name: &'static str,
+        result: Result<T, E>,
+    ) -> Option<T> {
+        result
+            .map_err(|e| self.unavailable.insert(name, e.into()))
+            .ok()
+    }
+
+    /// Fill in open gates and the newest `max_failures` failures from the
+    /// feed, for `issue` when known and across the feed otherwise
+    pub fn add_feed(
+        &mut self,
+        events: &[ActivityEvent],
+        issue: Option<&Issue>,
+        max_failures: usize,
+    ) {
+        let events: Vec<&ActivityEvent> = events
+            .iter()
+            .filter(|e| issue.is_none_or(|i| e.issue == i.id))
+            .collect();
+        if let Some(issue) = issue {
+            let tasks = BTreeMap::from([(issue.id.as_str(), issue)]);
+            self.open_gates = report::open_gates(&events, &tasks);
+        }
+        self.recent_failures = events
+            .iter()
+            .rev()
+            .filter(|e| e.kind == EventKind::Failed)
+            .take(max_failures)
+            .map(|e| Failure {
+                issue: e.issue.clone(),
+                message: e.message.clone(),
+                timestamp: e.timestamp,
+                worker: e.worker.clone(),
+            })
+            .collect();
+    }
+}
+
+/// Render the overview as plain text, one section per block
+pub fn render_text(status: &Status) -> String {
+    let mut out = String::new();
+    match status.worktree {
+        Some(ref worktree) => out.push_str(&format!(
+            "Worktree: {} ({})\n",
+            worktree.root.display(),
+            worktree.branch.as_deref().unwrap_or("detached")
+        )),
+        None => out.push_str("Worktree: none\n"),
+    }
+    match status.issue {
+        Some(ref issue) => {
+            out.push_str(&format!(
+                "Issue:    {} {} ({})\n",
+                issue.id, issue.title, issue.status
+            ));
+            if !issue.open_blockers.is_empty() {
+                out.push_str(&format!(
+                    "          blocked by {}\n",
+                    issue.open_blockers.join(", ")
+                ));
+            }
+        }
+        None => out.push_str("Issue:    none\n"),
+    }
+    if let Some(ref session) = status.session {
+        let mode = session
+            .mode
+            .map(|m| m.to_string())
+            .unwrap_or_else(|| "none".to_string());
+        out.push_str(&format!(
+            "Session:  {} (iteration {}, revision {})\n",
+            mode, session.iteration_count, session.revision
+        ));
+    }
+    if let Some(ref approval) = status.approval {
+        out.push_str(&format!(
+            "Approval: {} for {} is {}\n",
+            approval.gate, approval.plan, approval.status
+        ));
+    }
+    if let Some(ref epic) = status.epic {
+        out.push_str(&format!(
+            "Epic:     {} {} ({}/{} done)\n",
+            epic.id, epic.title, epic.done, epic.total
+        ));
+    }
+    if let Some(ref health) = status.health {
+        out.push_str(&format!("Health:   {}\n", health.status));
+        for check in &health.problems {
+            out.push_str(&format!(
+                "          {} {}: {}\n",
+                check.status, check.name, check.detail
+            ));
+        }
+    }
+    if let Some(ref lint) = status.lint {
+        out.push_str(&format!(
+            "Lint:     {} errors, {} warnings\n",
+            lint.errors, lint.warnings
+        ));
+        for finding in &lint.findings {
+            out.push_str(&format!(
+                "          [{}] {}\n",
+                finding.rule, finding.message
+            ));
+        }
+    }
+    if !status.open_gates.is_empty() {
+        out.push_str("\nOpen gates:\n");
+        for gate in &status.open_gates {
+            out.push_str(&format!(
+                "  {} on {}: {}\n",
+                gate.gate, gate.issue, gate.message
+            ));
+        }
+    }
+    if !status.recent_failures.is_empty() {
+        out.push_str("\nRecent failures:\n");
+        for failure in &status.recent_failures {
+            out.push_str(&format!(
+                "  {} {}: {}\n",
+                format_timestamp(failure.timestamp),
+                failure.issue,
+                failure.message
+            ));
+        }
+    }
+    if !status.unavailable.is_empty() {
+        out.push_str("\nUnavailable:\n");
+        for (section, reason) in &status.unavailable {
+            out.push_str(&format!("  {}: {}\n", section, reason));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beads::test_issue;
+    use serde_json::json;
+
+    fn event(
+        id: u64,
+        issue: &str,
+        kind: EventKind,
+        data: Option<serde_json::Value>,
+    ) -> ActivityEvent {
+        ActivityEvent {
+            id,
+            timestamp: id,
+            issue: issue.to_string(),
+            kind,
+            message: format!("event {}", id),
+            worker: None,
+            run: None,
+            data,
+        }
+    }
+
+    #[test]
+    fn test_feed_sections_follow_the_issue() {
+        let current = test_issue("bd-2", "in_progress", &[]);
+        let events = vec![
+            event(1, "bd-1", EventKind::Failed, None),
+            event(2, "bd-2", EventKind::Failed, None),
+            event(
+                3,
+                "bd-2",
+                EventKind::Gate,
+                Some(json!({"gate": "lint", "passed": false})),
+            ),
+            event(
+                4,
+                "bd-2",
+                EventKind::Gate,
+                Some(json!({"gate": "ci", "passed": true})),
+            ),
+            event(5, "bd-2", EventKind::Failed, None),
+        ];
+
+        let mut status = Status::new(0);
+        status.add_feed(&events, Some(&current), 1);
+        let mut all = Status::new(0);
+        all.add_feed(&events, None, 5);
+
+        let gates: Vec<&str> = status.open_gates.iter().map(|g| g.gate.as_str()).collect();
+        assert_eq!(gates, vec!["lint"]);
+        assert_eq!(status.recent_failures.len(), 1);
+        assert_eq!(status.recent_failures[0].message, "event 5");
+        assert!(all.open_gates.is_empty());
+        assert_eq!(all.recent_failures.len(), 3);
+    }
+
+    #[test]
+    fn test_gather_records_missing_sections() {
+        let mut status = Status::new(0);
+
+        let found = status.gather("session", Ok::<_, String>(1));
+        let missing: Option<u32> = status.gather("issue", Err("bd is unavailable".to_string()));
+
+        assert_eq!(found, Some(1));
+        assert_eq!(missing, None);
+        assert_eq!(status.unavailable["issue"], "bd is unavailable");
+        assert!(render_text(&status).contains("Unavailable:\n  issue: bd is unavailable\n"));
+    }
+
+    #[test]
+    fn test_render_text() {
+        let mut status = Status::new(0);
+        status.issue = Some(IssueStatus::new(
+            &test_issue("bd-2", "in_progress", &[]),
+            &[
+                test_issue("bd-1", "open", &[]),
+                test_issue("bd-0", "closed", &[]),
+            ],
+        ));
+        status.health = Some(HealthSummary {
+            status: CheckStatus::Pass,
+            problems: Vec::new(),
+        });
+
+        let text = render_text(&status);
+
+        assert!(text.starts_with("Worktree: none\n"));
+        assert!(text
+            .contains("Issue:    bd-2 Title of bd-2 (in_progress)\n          blocked by bd-1\n"));
+        assert!(text.contains("Health:   pass\n"));
+        assert!(!text.contains("Open gates"));
+    }
+}
@@ -0,0 +1,386 @@
+//! Iteration stop conditions
+//!
+//! `state should-continue` is asked once per loop iteration whether another
+//! should run. Beyond the session being active and under its iteration
+//! limit, the `[stop]` section of `.ralph-beads.toml` ends loops that are
+//! no longer getting anywhere:
+//!
+//! - a run of `failed` events with no preflight or completion in between
+//! - iterations that leave the working tree (HEAD and uncommitted changes)
+//!   untouched
+//! - spending that has reached `cost.max_cost_usd`
+//! - a gate on the issue whose latest event has not passed
+//!
+//! A human can also stop the loop with `state stop`. Each stop is reported
+//! under its own `continuation_reason`.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+use crate::activity::{ActivityEvent, EventKind};
+use crate::data_dirs::PROJECT_DIR;
+use crate::state::{SessionState, WorkflowMode};
+use crate::worktree::git;
+
+/// Why the loop should (or should not) run another iteration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContinuationReason {
+    /// Nothing stops the loop
+    Continue,
+    /// No session, or it is paused or complete
+    NotActive,
+    /// `iteration_count` reached the limit
+    IterationLimit,
+    /// Someone ran `state stop`
+    StopRequested,
+    /// Too many failures in a row
+    ConsecutiveFailures,
+    /// The working tree stopped changing
+    NoProgress,
+    /// Spending reached the budget
+    BudgetExhausted,
+    /// A gate on the issue is still open
+    GatePending,
+}
+
+impl fmt::Display for ContinuationReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ContinuationReason::Continue => "continue",
+            ContinuationReason::NotActive => "not_active",
+            ContinuationReason::IterationLimit => "iteration_limit",
+            ContinuationReason::StopRequested => "stop_requested",
+            ContinuationReason::ConsecutiveFailures => "consecutive_failures",
+            ContinuationReason::NoProgress => "no_progress",
+            ContinuationReason::BudgetExhausted => "budget_exhausted",
+            ContinuationReason::GatePending => "gate_pending",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl ContinuationReason {
+    /// Whether the loop stops because it is failing, rather than because it
+    /// is done, paused, or waiting
+    pub fn is_failure(&self) -> bool {
+        matches!(
+            self,
+            ContinuationReason::IterationLimit
+                | ContinuationReason::ConsecutiveFailures
+                | ContinuationReason::NoProgress
+                | ContinuationReason::BudgetExhausted
+        )
+    }
+}
+
+/// The `[stop]` configuration section
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StopConfig {
+    /// Failures in a row that stop the loop (0 disables)
+    pub max_consecutive_failures: u32,
+    /// Iterations without a change to the working tree that stop the loop
+    /// (0 disables)
+    pub no_progress_iterations: u32,
+    /// Stop once spending reaches `cost.max_cost_usd`
+    pub budget: bool,
+    /// Stop while a gate on the issue is open
+    pub open_gates: bool,
+}
+
+impl Default for StopConfig {
+    fn default() -> Self {
+        StopConfig {
+            max_consecutive_failures: 3,
+            no_progress_iterations: 3,
+            budget: true,
+            open_gates: true,
+        }
+    }
+}
+
+/// What the conditions are judged on, gathered by the caller
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StopSignals {
+    pub max_iterations: Option<u32>,
+    pub consecutive_failures: u32,
+    /// Iterations since the working tree last changed; `None` outside git
+    pub idle_iterations: Option<u32>,
+    pub spent_usd: f64,
+    pub budget_usd: Option<f64>,
+    /// Gates still open on the issue
+    pub open_gates: Vec<String>,
+}
+
+/// The answer to "run another iteration?"
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Continuation {
+    pub should_continue: bool,
+    pub continuation_reason: ContinuationReason,
+    pub detail: String,
+}
+
+impl Continuation {
+    fn stop(reason: ContinuationReason, detail: String) -> Self {
+        Continuation {
+            should_continue: false,
+            continuation_reason: reason,
+            detail,
+        }
+    }
+}
+
+/// Judge `session` against the enabled conditions, first match wins
+pub fn evaluate(
+    session: &SessionState,
+    config: &StopConfig,
+    signals: &StopSignals,
+) -> Continuation {
+    if let Some(ref request) = session.stop_requested {
+        let detail = request
+            .reason
+            .clone()
+            .unwrap_or_else(|| "stop requested".to_string());
+        return Continuation::stop(ContinuationReason::StopRequested, detail);
+    }
+    match session.mode {
+        Some(WorkflowMode::Planning) | Some(WorkflowMode::Building) => {}
+        Some(mode) => {
+            return Continuation::stop(
+                ContinuationReason::NotActive,
+                format!("session is {}", mode),
+            )
+        }
+        None => return Continuation::stop(ContinuationReason::NotActive, "no session".to_string()),
+    }
+    if let Some(max) = signals
+        .max_iterations
+        .filter(|&m| session.iteration_count >= m)
+    {
+        return Continuation::stop(
+            ContinuationReason::IterationLimit,
+            format!("{} of {} iterations used", session.iteration_count, max),
+        );
+    }
+    if let Some(budget) = signals
+        .budget_usd
+        .filter(|&b| config.budget && signals.spent_usd >= b)
+    {
+        return Continuation::stop(
+            ContinuationReason::BudgetExhausted,
+            format!("spent ${:.4} of ${:.4}", signals.spent_usd, budget),
+        );
+    }
+    if config.open_gates && !signals.open_gates.is_empty() {
+        return Continuation::stop(
+            ContinuationReason::GatePending,
+            format!("waiting on {}", signals.open_gates.join(", ")),
+        );
+    }
+    let max_failures = config.max_consecutive_failures;
+    if max_failures > 0 && signals.consecutive_failures >= max_failures {
+        return Continuation::stop(
+            ContinuationReason::ConsecutiveFailures,
+            format!("{} failures in a row", signals.consecutive_failures),
+        );
+    }
+    let max_idle = config.no_progress_iterations;
+    if let Some(idle) = signals
+        .idle_iterations
+        .filter(|&i| max_idle > 0 && i >= max_idle)
+    {
+        return Continuation::stop(
+            ContinuationReason::NoProgress,
+            format!("no files changed in {} iterations", idle),
+        );
+    }
+    Continuation {
+        should_continue: true,
+        continuation_reason: ContinuationReason::Continue,
+        detail: format!("iteration {}", session.iteration_count + 1),
+    }
+}
+
+/// Failures since the last preflight or completion, newest last in `events`
+pub fn trailing_failures(events: &[&ActivityEvent]) -> u32 {
+    let mut run = 0;
+    for event in events.iter().rev() {
+        match event.kind {
+            EventKind::Failed => run += 1,
+            EventKind::Preflight | EventKind::Completed => break,
+            _ => {}
+        }
+    }
+    run
+}
+
+/// FNV-1a, stable across builds so saved fingerprints stay comparable
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Fingerprint of HEAD, uncommitted changes, and the names of untracked files
+///
+/// The beads directory is left out: the session and feed change on every
+/// iteration whether or not work got done.
+pub fn tree_fingerprint(dir: &Path) -> Result<String, String> {
+    let exclude = format!(":(exclude){}", PROJECT_DIR);
+    let head = git(dir, &["rev-parse", "HEAD"]).unwrap_or_default();
+    let status = git(
+        dir,
+        &[
+            "status",
+            "--porcelain",
+            "--untracked-files=all",
+            "--",
+            ".",
+            &exclude,
+        ],
+    )?;
+    let diff = git(dir, &["diff", "HEAD", "--no-color", "--", ".", &exclude]).unwrap_or_default();
+    let text = format!("{}\0{}\0{}", head, status, diff);
+    Ok(format!("{:016x}", fnv1a(text.as_bytes())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::StopRequest;
+
+    fn session(mode: WorkflowMode, iterations: u32) -> SessionState {
+        SessionState {
+            mode: Some(mode),
+            iteration_count: iterations,
+            ..Default::default()
+        }
+    }
+
+    fn event(kind: EventKind) -> ActivityEvent {
+        ActivityEvent {
+            id: 0,
+            timestamp: 0,
+            issue: "bd-1".to_string(),
+            kind,
+            message: String::new(),
+            worker: None,
+            run: None,
+            data: None,
+        }
+    }
+
+    fn reason(session: &SessionState, signals: &StopSignals) -> ContinuationReason {
+        evaluate(session, &StopConfig::default(), signals).continuation_reason
+    }
+
+    #[test]
+    fn test_each_condition_has_its_own_reason() {
+        let building = session(WorkflowMode::Building, 4);
+        let signals = StopSignals::default();
+        let mut stopped = building.clone();
+        stopped.stop_requested = Some(StopRequest {
+            reason: Some("reviewing".to_string()),
+            timestamp: 0,
+        });
+
+        let cases = [
+            (
+                StopSignals {
+                    max_iterations: Some(4),
+                    ..signals.clone()
+                },
+                ContinuationReason::IterationLimit,
+            ),
+            (
+                StopSignals {
+                    spent_usd: 2.0,
+                    budget_usd: Some(2.0),
+                    ..signals.clone()
+                },
+                ContinuationReason::BudgetExhausted,
+            ),
+            (
+                StopSignals {
+                    open_gates: vec!["ci".to_string()],
+                    ..signals.clone()
+                },
+                ContinuationReason::GatePending,
+            ),
+            (
+                StopSignals {
+                    consecutive_failures: 3,
+                    ..signals.clone()
+                },
+                ContinuationReason::ConsecutiveFailures,
+            ),
+            (
+                StopSignals {
+                    idle_iterations: Some(3),
+                    ..signals.clone()
+                },
+                ContinuationReason::NoProgress,
+            ),
+        ];
+        for (signals, expected) in cases {
+            assert_eq!(reason(&building, &signals), expected);
+        }
+        assert!(!ContinuationReason::GatePending.is_failure());
+        assert!(!ContinuationReason::StopRequested.is_failure());
+        assert!(ContinuationReason::NoProgress.is_failure());
+
+        let go = evaluate(&building, &StopConfig::default(), &signals);
+        assert!(go.should_continue);
+        assert_eq!(go.detail, "iteration 5");
+        let stop = evaluate(&stopped, &StopConfig::default(), &signals);
+        assert_eq!(stop.continuation_reason, ContinuationReason::StopRequested);
+        assert_eq!(stop.detail, "reviewing");
+        assert_eq!(
+            reason(&session(WorkflowMode::Paused, 0), &signals),
+            ContinuationReason::NotActive
+        );
+        assert_eq!(
+            reason(&SessionState::default(), &signals),
+            ContinuationReason::NotActive
+        );
+    }
+
+    #[test]
+    fn test_disabled_conditions_never_stop() {
+        let config = StopConfig {
+            max_consecutive_failures: 0,
+            no_progress_iterations: 0,
+            budget: false,
+            open_gates: false,
+        };
+        let signals = StopSignals {
+            consecutive_failures: 10,
+            idle_iterations: Some(10),
+            spent_usd: 5.0,
+            budget_usd: Some(1.0),
+            open_gates: vec!["ci".to_string()],
+            ..Default::default()
+        };
+
+        let go = evaluate(&session(WorkflowMode::Building, 1), &config, &signals);
+
+        assert!(go.should_continue);
+    }
+
+    #[test]
+    fn test_trailing_failures_reset_on_preflight() {
+        let events = [
+            event(EventKind::Failed),
+            event(EventKind::Preflight),
+            event(EventKind::Failed),
+            event(EventKind::Progress),
+            event(EventKind::Failed),
+        ];
+        let refs: Vec<&ActivityEvent> = events.iter().collect();
+
+        assert_eq!(trailing_failures(&refs), 2);
+        assert_eq!(trailing_failures(&refs[..2]), 0);
+    }
+}
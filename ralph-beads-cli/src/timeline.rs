@@ -0,0 +1,453 @@
+//! Activity timeline aggregation for Ralph-Beads
+//!
+//! Folds the raw activity feed into per-task timelines (claimed →
+//! progress → preflight → completed) with durations, idle gaps, and
+//! failure loops, for retrospectives and status reporting.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::activity::{format_duration, format_timestamp, ActivityEvent, EventKind};
+use crate::retention::summary_of;
+
+/// Consecutive failures at which a task is considered stuck in a loop
+pub const FAILURE_LOOP_THRESHOLD: u32 = 3;
+
+/// Where a task stands at the end of the reporting window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    Completed,
+    InProgress,
+    Failing,
+}
+
+/// A stretch of time with no events for a task
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdleGap {
+    pub from: u64,
+    pub to: u64,
+    pub seconds: u64,
+}
+
+/// Aggregated lifecycle of a single task
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskTimeline {
+    pub issue: String,
+    pub status: TaskStatus,
+    pub first_event: u64,
+    pub last_event: u64,
+    pub claimed_at: Option<u64>,
+    pub completed_at: Option<u64>,
+    /// Seconds from claim (or first event) to completion (or last event)
+    pub duration_seconds: u64,
+    /// Duration minus idle gaps
+    pub active_seconds: u64,
+    pub event_count: usize,
+    pub counts: BTreeMap<String, u32>,
+    pub workers: Vec<String>,
+    pub idle_gaps: Vec<IdleGap>,
+    /// Longest run of failures not interrupted by preflight or completion
+    pub max_consecutive_failures: u32,
+    pub failure_loop: bool,
+}
+
+/// Report over all tasks in a window of the feed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActivityReport {
+    pub epic: Option<String>,
+    pub since: Option<u64>,
+    pub generated_at: u64,
+    pub total_events: usize,
+    pub tasks: Vec<TaskTimeline>,
+}
+
+/// Whether `issue` belongs to `epic` under beads' hierarchical IDs
+///
+/// Matches the epic itself and any descendant (`bd-a3f8`, `bd-a3f8.1`,
+/// `bd-a3f8.1.2`).
+pub fn in_epic(issue: &str, epic: &str) -> bool {
+    issue == epic
+        || issue
+            .strip_prefix(epic)
+            .is_some_and(|rest| rest.starts_with('.'))
+}
+
+/// Build the timeline for one task from its events (in feed order)
+///
+/// Compaction summaries count as the events they replaced.
+pub fn build_timeline(issue: &str, events: &[&ActivityEvent], idle_threshold: u64) -> TaskTimeline {
+    let first_event = events
+        .first()
+        .map(|e| summary_of(e).map_or(e.timestamp, |s| s.first))
+        .unwrap_or(0);
+    let last_event = events.last().map(|e| e.timestamp).unwrap_or(0);
+
+    let mut counts: BTreeMap<String, u32> = BTreeMap::new();
+    let mut workers: Vec<String> = Vec::new();
+    let mut claimed_at = None;
+    let mut completed_at = None;
+    let mut idle_gaps = Vec::new();
+    let mut run = 0;
+    let mut max_run = 0;
+    let mut previous: Option<u64> = None;
+    let mut event_count = 0;
+
+    for event in events {
+        if let Some(summary) = summary_of(event) {
+            event_count += summary.events as usize;
+            for (kind, n) in summary.counts {
+                *counts.entry(kind).or_insert(0) += n;
+            }
+            for worker in summary.workers {
+                if !workers.contains(&worker) {
+                    workers.push(worker);
+                }
+            }
+            claimed_at = claimed_at.or(summary.claimed_at);
+            if summary.completed_at.is_some() {
+                completed_at = summary.completed_at;
+                run = 0;
+            }
+            previous = Some(summary.last);
+            continue;
+        }
+        event_count += 1;
+        *counts.entry(event.kind.to_string()).or_insert(0) += 1;
+
+        if let Some(ref worker) = event.worker {
+            if !workers.contains(worker) {
+                workers.push(worker.clone());
+            }
+        }
+
+        match event.kind {
+            EventKind::Claimed if claimed_at.is_none() => claimed_at = Some(event.timestamp),
+            EventKind::Completed => completed_at = Some(event.timestamp),
+            _ => {}
+        }
+
+        match event.kind {
+            EventKind::Failed => {
+                run += 1;
+                max_run = max_run.max(run);
+            }
+            EventKind::Preflight | EventKind::Completed => run = 0,
+            _ => {}
+        }
+
+        if let Some(prev) = previous {
+            let gap = event.timestamp.saturating_sub(prev);
+            if gap >= idle_threshold {
+                idle_gaps.push(IdleGap {
+                    from: prev,
+                    to: event.timestamp,
+                    seconds: gap,
+                });
+            }
+        }
+        previous = Some(event.timestamp);
+    }
+
+    let start = claimed_at.unwrap_or(first_event);
+    let end = completed_at.unwrap_or(last_event);
+    let duration_seconds = end.saturating_sub(start);
+    let idle: u64 = idle_gaps
+        .iter()
+        .filter(|g| g.from >= start && g.to <= end)
+        .map(|g| g.seconds)
+        .sum();
+
+    let failure_loop = max_run >= FAILURE_LOOP_THRESHOLD;
+    let status = if completed_at.is_some() {
+        TaskStatus::Completed
+    } else if run > 0 {
+        TaskStatus::Failing
+    } else {
+        TaskStatus::InProgress
+    };
+
+    TaskTimeline {
+        issue: issue.to_string(),
+        status,
+        first_event,
+        last_event,
+        claimed_at,
+        completed_at,
+        duration_seconds,
+        active_seconds: duration_seconds.saturating_sub(idle),
+        event_count,
+        counts,
+        workers,
+        idle_gaps,
+        max_consecutive_failures: max_run,
+        failure_loop,
+    }
+}
+
+/// Aggregate feed events into a per-task report
+///
+/// Only events for `epic` (and its descendants) at or after `since` are
+/// considered. Tasks are ordered by their first event.
+pub fn build_report(
+    events: &[ActivityEvent],
+    epic: Option<&str>,
+    since: Option<u64>,
+    idle_threshold: u64,
+    now: u64,
+) -> ActivityReport {
+    let selected: Vec<&ActivityEvent> = events
+        .iter()
+        .filter(|e| epic.is_none_or(|epic| in_epic(&e.issue, epic)))
+        .filter(|e| since.is_none_or(|since| e.timestamp >= since))
+        .collect();
+
+    let mut by_issue: BTreeMap<&str, Vec<&ActivityEvent>> = BTreeMap::new();
+    for event in &selected {
+        by_issue
+            .entry(event.issue.as_str())
+            .or_default()
+            .push(event);
+    }
+
+    let mut tasks: Vec<TaskTimeline> = by_issue
+        .iter()
+        .map(|(issue, events)| build_timeline(issue, events, idle_threshold))
+        .collect();
+    tasks.sort_by_key(|t| t.first_event);
+
+    ActivityReport {
+        epic: epic.map(|e| e.to_string()),
+        since,
+        generated_at: now,
+        total_events: selected.len(),
+        tasks,
+    }
+}
+
+/// Render a report as plain text
+pub fn render_text(report: &ActivityReport) -> String {
+    let mut out = String::new();
+    let scope = report.epic.as_deref().unwrap_or("all issues");
+    out.push_str(&format!(
+        "Activity report for {} ({} events, {} tasks)\n",
+        scope,
+        report.total_events,
+        report.tasks.len()
+    ));
+
+    for task in &report.tasks {
+        let status = match task.status {
+            TaskStatus::Completed => "completed",
+            TaskStatus::InProgress => "in progress",
+            TaskStatus::Failing => "failing",
+        };
+        out.push_str(&format!(
+            "\n{} [{}]\n  span: {} -> {}\n  duration: {} (active {})\n",
+            task.issue,
+            status,
+            format_timestamp(task.claimed_at.unwrap_or(task.first_event)),
+            format_timestamp(task.completed_at.unwrap_or(task.last_event)),
+            format_duration(task.duration_seconds),
+            format_duration(task.active_seconds),
+        ));
+
+        let counts: Vec<String> = task
+            .counts
+            .iter()
+            .map(|(kind, n)| format!("{}={}", kind, n))
+            .collect();
+        out.push_str(&format!("  events: {}\n", counts.join(", ")));
+
+        if !task.workers.is_empty() {
+            out.push_str(&format!("  workers: {}\n", task.workers.join(", ")));
+        }
+        for gap in &task.idle_gaps {
+            out.push_str(&format!(
+                "  idle: {} from {}\n",
+                format_duration(gap.seconds),
+                format_timestamp(gap.from)
+            ));
+        }
+        if task.failure_loop {
+            out.push_str(&format!(
+                "  WARNING: failure loop ({} consecutive failures)\n",
+                task.max_consecutive_failures
+            ));
+        }
+    }
+
+    out
+}
+
+/// Render a report as a Mermaid gantt chart
+pub fn render_mermaid(report: &ActivityReport) -> String {
+    let mut out = String::from("gantt\n");
+    let title = report.epic.as_deref().unwrap_or("all issues");
+    out.push_str(&format!("    title Activity timeline: {}\n", title));
+    out.push_str("    dateFormat X\n");
+    out.push_str("    axisFormat %m-%d %H:%M\n");
+
+    for (i, task) in report.tasks.iter().enumerate() {
+        let start = task.claimed_at.unwrap_or(task.first_event);
+        // Mermaid drops zero-length bars; give instant tasks one second
+        let end = task.completed_at.unwrap_or(task.last_event).max(start + 1);
+        let tag = match (task.status, task.failure_loop) {
+            (_, true) => "crit, ",
+            (TaskStatus::Completed, _) => "done, ",
+            (TaskStatus::InProgress, _) => "active, ",
+            (TaskStatus::Failing, _) => "crit, ",
+        };
+        out.push_str(&format!("    section {}\n", task.issue));
+        out.push_str(&format!(
+            "    {} :{}t{}, {}, {}\n",
+            task.issue.replace(':', "-"),
+            tag,
+            i,
+            start,
+            end
+        ));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(id: u64, timestamp: u64, issue: &str, kind: EventKind) -> ActivityEvent {
+        ActivityEvent {
+            id,
+            timestamp,
+            issue: issue.to_string(),
+            kind,
+            message: String::new(),
+            worker: Some("worker-a".to_string()),
+            run: None,
+            data: None,
+        }
+    }
+
+    #[test]
+    fn test_in_epic() {
+        assert!(in_epic("bd-a3f8", "bd-a3f8"));
+        assert!(in_epic("bd-a3f8.1", "bd-a3f8"));
+        assert!(in_epic("bd-a3f8.1.2", "bd-a3f8"));
+        assert!(!in_epic("bd-a3f89", "bd-a3f8"));
+        assert!(!in_epic("bd-b", "bd-a3f8"));
+    }
+
+    #[test]
+    fn test_completed_timeline_durations() {
+        let events = [
+            event(1, 1_000, "bd-1.1", EventKind::Claimed),
+            event(2, 1_100, "bd-1.1", EventKind::Progress),
+            event(3, 5_000, "bd-1.1", EventKind::Preflight),
+            event(4, 5_200, "bd-1.1", EventKind::Completed),
+        ];
+        let refs: Vec<&ActivityEvent> = events.iter().collect();
+
+        let timeline = build_timeline("bd-1.1", &refs, 3_600);
+
+        assert_eq!(timeline.status, TaskStatus::Completed);
+        assert_eq!(timeline.duration_seconds, 4_200);
+        assert_eq!(timeline.idle_gaps.len(), 1);
+        assert_eq!(timeline.idle_gaps[0].seconds, 3_900);
+        assert_eq!(timeline.active_seconds, 300);
+        assert_eq!(timeline.counts.get("progress"), Some(&1));
+        assert_eq!(timeline.workers, vec!["worker-a".to_string()]);
+        assert!(!timeline.failure_loop);
+    }
+
+    #[test]
+    fn test_failure_loop_detection() {
+        let events = [
+            event(1, 0, "bd-1.2", EventKind::Claimed),
+            event(2, 10, "bd-1.2", EventKind::Failed),
+            event(3, 20, "bd-1.2", EventKind::Progress),
+            event(4, 30, "bd-1.2", EventKind::Failed),
+            event(5, 40, "bd-1.2", EventKind::Failed),
+        ];
+        let refs: Vec<&ActivityEvent> = events.iter().collect();
+
+        let timeline = build_timeline("bd-1.2", &refs, 3_600);
+
+        assert_eq!(timeline.status, TaskStatus::Failing);
+        assert_eq!(timeline.max_consecutive_failures, 3);
+        assert!(timeline.failure_loop);
+    }
+
+    #[test]
+    fn test_preflight_resets_failure_run() {
+        let events = [
+            event(1, 0, "bd-1.3", EventKind::Failed),
+            event(2, 10, "bd-1.3", EventKind::Failed),
+            event(3, 20, "bd-1.3", EventKind::Preflight),
+            event(4, 30, "bd-1.3", EventKind::Failed),
+        ];
+        let refs: Vec<&ActivityEvent> = events.iter().collect();
+
+        let timeline = build_timeline("bd-1.3", &refs, 3_600);
+
+        assert_eq!(timeline.max_consecutive_failures, 2);
+        assert!(!timeline.failure_loop);
+    }
+
+    #[test]
+    fn test_build_report_filters_epic_and_since() {
+        let events = vec![
+            event(1, 100, "bd-1.1", EventKind::Claimed),
+            event(2, 200, "bd-2.1", EventKind::Claimed),
+            event(3, 300, "bd-1.2", EventKind::Claimed),
+            event(4, 400, "bd-1.1", EventKind::Completed),
+        ];
+
+        let report = build_report(&events, Some("bd-1"), Some(150), 3_600, 500);
+
+        assert_eq!(report.total_events, 2);
+        let issues: Vec<&str> = report.tasks.iter().map(|t| t.issue.as_str()).collect();
+        assert_eq!(issues, vec!["bd-1.2", "bd-1.1"]);
+    }
+
+    #[test]
+    fn test_timeline_counts_compacted_events() {
+        let events = vec![
+            event(1, 1_000, "bd-1.1", EventKind::Claimed),
+            event(2, 1_500, "bd-1.1", EventKind::Progress),
+            event(3, 2_000, "bd-1.1", EventKind::Progress),
+            event(4, 9_000, "bd-1.1", EventKind::Completed),
+        ];
+        let config = crate::retention::ActivityConfig {
+            max_per_issue: Some(1),
+            ..Default::default()
+        };
+        let (compacted, _) = crate::retention::prune_events(events, &config, 10_000);
+        let refs: Vec<&ActivityEvent> = compacted.iter().collect();
+
+        let timeline = build_timeline("bd-1.1", &refs, 3_600);
+
+        assert_eq!(timeline.event_count, 4);
+        assert_eq!(timeline.first_event, 1_000);
+        assert_eq!(timeline.claimed_at, Some(1_000));
+        assert_eq!(timeline.duration_seconds, 8_000);
+        assert_eq!(timeline.counts["progress"], 2);
+        assert_eq!(timeline.workers, vec!["worker-a".to_string()]);
+    }
+
+    #[test]
+    fn test_render_mermaid() {
+        let events = vec![
+            event(1, 100, "bd-1.1", EventKind::Claimed),
+            event(2, 400, "bd-1.1", EventKind::Completed),
+        ];
+        let report = build_report(&events, Some("bd-1"), None, 3_600, 500);
+
+        let chart = render_mermaid(&report);
+
+        assert!(chart.starts_with("gantt\n"));
+        assert!(chart.contains("dateFormat X"));
+        assert!(chart.contains("section bd-1.1"));
+        assert!(chart.contains("bd-1.1 :done, t0, 100, 400"));
+    }
+}
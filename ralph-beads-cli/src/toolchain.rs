@@ -0,0 +1,295 @@
+//! Toolchain version pins
+//!
+//! A worker on the wrong toolchain fails in ways that look like bugs in the
+//! task: a lint that only exists in a newer rustc, a syntax error from an
+//! older node. `health` compares the installed rustc, node, python, and bd
+//! against the versions the project pins, from the `[toolchain]` section of
+//! `.ralph-beads.toml` or else the usual pin files:
+//!
+//! - rustc: `rust-toolchain.toml` (`channel`) or `rust-toolchain`
+//! - node: `.nvmrc`
+//! - python: `.python-version`
+//!
+//! A pin matches any release it is a prefix of (`1.78` matches 1.78.2).
+//! Channel names such as `stable` or `lts/*` aren't versions and are not
+//! checked. A mismatch or a missing tool is a warning whose hint is the
+//! command that installs the pinned version.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use crate::bd_version;
+use crate::health::{CheckStatus, HealthCheck};
+
+/// The `[toolchain]` configuration section: pins that override pin files
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ToolchainConfig {
+    pub rustc: Option<String>,
+    pub node: Option<String>,
+    pub python: Option<String>,
+    pub bd: Option<String>,
+}
+
+/// A tool whose version can be pinned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tool {
+    Rustc,
+    Node,
+    Python,
+    Bd,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 4] = [Tool::Rustc, Tool::Node, Tool::Python, Tool::Bd];
+
+    /// Name of the tool's health check
+    pub fn check_name(self) -> &'static str {
+        match self {
+            Tool::Rustc => "rustc",
+            Tool::Node => "node",
+            Tool::Python => "python",
+            Tool::Bd => "bd-version",
+        }
+    }
+
+    fn configured(self, config: &ToolchainConfig) -> Option<&String> {
+        match self {
+            Tool::Rustc => config.rustc.as_ref(),
+            Tool::Node => config.node.as_ref(),
+            Tool::Python => config.python.as_ref(),
+            Tool::Bd => config.bd.as_ref(),
+        }
+    }
+
+    /// Pin files in the project root, in order of precedence
+    fn pin_files(self) -> &'static [&'static str] {
+        match self {
+            Tool::Rustc => &["rust-toolchain.toml", "rust-toolchain"],
+            Tool::Node => &[".nvmrc"],
+            Tool::Python => &[".python-version"],
+            Tool::Bd => &[],
+        }
+    }
+
+    /// Command that installs `version` of the tool
+    pub fn install_command(self, version: &str) -> String {
+        match self {
+            Tool::Rustc => format!("rustup toolchain install {}", version),
+            Tool::Node => format!("nvm install {}", version),
+            Tool::Python => format!("pyenv install {}", version),
+            Tool::Bd => format!(
+                "go install github.com/steveyegge/beads/cmd/bd@v{}",
+                version.trim_start_matches('v')
+            ),
+        }
+    }
+
+    /// Installed version as reported by the tool, `None` when it is missing
+    fn installed(self, dir: &Path) -> Option<String> {
+        let (program, env) = match self {
+            Tool::Bd => return bd_version::detected_version(dir).map(|v| v.to_string()),
+            // Without this rustup installs the pinned toolchain on first use
+            Tool::Rustc => ("rustc", Some(("RUSTUP_AUTO_INSTALL", "0"))),
+            Tool::Node => ("node", None),
+            Tool::Python => ("python3", None),
+        };
+        let mut command = Command::new(program);
+        command.arg("--version").current_dir(dir);
+        if let Some((key, value)) = env {
+            command.env(key, value);
+        }
+        let output = command.output().ok().filter(|o| o.status.success())?;
+        // Python 2 printed its version on stderr
+        let text = [output.stdout, output.stderr].concat();
+        Some(String::from_utf8_lossy(&text).trim().to_string())
+    }
+}
+
+/// A pinned version and where it was declared
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pin {
+    pub version: String,
+    pub source: String,
+}
+
+/// The first meaningful line of a pin file's content
+fn pin_from_file(name: &str, content: &str) -> Option<String> {
+    if name.ends_with(".toml") {
+        let value: toml::Value = toml::from_str(content).ok()?;
+        return value
+            .get("toolchain")?
+            .get("channel")?
+            .as_str()
+            .map(str::to_string);
+    }
+    content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .find(|line| !line.is_empty())
+        .and_then(|line| line.split_whitespace().next())
+        .map(str::to_string)
+}
+
+/// The pin for `tool`: the configured one, else the first pin file found
+pub fn read_pin(dir: &Path, tool: Tool, config: &ToolchainConfig) -> Option<Pin> {
+    if let Some(version) = tool.configured(config) {
+        return Some(Pin {
+            version: version.clone(),
+            source: "[toolchain]".to_string(),
+        });
+    }
+    tool.pin_files().iter().find_map(|name| {
+        let content = fs::read_to_string(dir.join(name)).ok()?;
+        Some(Pin {
+            version: pin_from_file(name, &content)?,
+            source: name.to_string(),
+        })
+    })
+}
+
+/// Numeric components of the first version in `text` (`v20.11.0` -> 20, 11, 0)
+fn version_parts(text: &str) -> Option<Vec<u64>> {
+    let start = text.find(|c: char| c.is_ascii_digit())?;
+    let version = text[start..]
+        .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .next()?;
+    version
+        .split('.')
+        .filter(|p| !p.is_empty())
+        .map(|p| p.parse().ok())
+        .collect()
+}
+
+/// Whether `installed` is a release of `pin`; `None` when the pin isn't a
+/// version (`stable`, `lts/*`, `nightly-2024-05-01`)
+pub fn pin_matches(pin: &str, installed: &str) -> Option<bool> {
+    let pin = pin.trim().trim_start_matches('v');
+    if !pin.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let wanted = version_parts(pin)?;
+    let found = version_parts(installed)?;
+    Some(found.len() >= wanted.len() && found[..wanted.len()] == wanted[..])
+}
+
+fn check_tool(dir: &Path, tool: Tool, pin: &Pin) -> HealthCheck {
+    let name = tool.check_name();
+    let install = tool.install_command(&pin.version);
+    let Some(installed) = tool.installed(dir) else {
+        return HealthCheck::new(
+            name,
+            CheckStatus::Warn,
+            format!("not installed; {} pins {}", pin.source, pin.version),
+        )
+        .with_hint(install);
+    };
+    match pin_matches(&pin.version, &installed) {
+        Some(true) => HealthCheck::new(
+            name,
+            CheckStatus::Pass,
+            format!("{} matches {} ({})", installed, pin.version, pin.source),
+        ),
+        Some(false) => HealthCheck::new(
+            name,
+            CheckStatus::Warn,
+            format!(
+                "{} does not match {} ({})",
+                installed, pin.version, pin.source
+            ),
+        )
+        .with_hint(install),
+        None => HealthCheck::new(
+            name,
+            CheckStatus::Skipped,
+            format!("{} pins '{}', not a version", pin.source, pin.version),
+        ),
+    }
+}
+
+/// Compare installed tools against the project's pins; unpinned tools are
+/// not checked
+pub fn check_toolchain(dir: &Path, config: &ToolchainConfig) -> Vec<HealthCheck> {
+    Tool::ALL
+        .iter()
+        .filter_map(|&tool| {
+            let pin = read_pin(dir, tool, config)?;
+            Some(check_tool(dir, tool, &pin))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_pin_matches_by_prefix() {
+        assert_eq!(
+            pin_matches("1.78", "rustc 1.78.0 (9b00956e5 2024-04-29)"),
+            Some(true)
+        );
+        assert_eq!(pin_matches("1.78.1", "rustc 1.78.0"), Some(false));
+        assert_eq!(pin_matches("1.7", "rustc 1.78.0"), Some(false));
+        assert_eq!(pin_matches("v20", "v20.11.0"), Some(true));
+        assert_eq!(pin_matches("3.12.1", "Python 3.12.1"), Some(true));
+        assert_eq!(pin_matches("stable", "rustc 1.78.0"), None);
+        assert_eq!(pin_matches("lts/*", "v20.11.0"), None);
+    }
+
+    #[test]
+    fn test_pins_come_from_config_then_files() {
+        let tmp = TempDir::new().unwrap();
+        fs::write(
+            tmp.path().join("rust-toolchain.toml"),
+            "[toolchain]\nchannel = \"1.78\"\ncomponents = [\"clippy\"]\n",
+        )
+        .unwrap();
+        fs::write(tmp.path().join("rust-toolchain"), "1.70\n").unwrap();
+        fs::write(tmp.path().join(".nvmrc"), "v20.11.0\n").unwrap();
+        fs::write(
+            tmp.path().join(".python-version"),
+            "# project\n3.12.1 3.11\n",
+        )
+        .unwrap();
+        let config = ToolchainConfig {
+            node: Some("18".to_string()),
+            ..Default::default()
+        };
+
+        let pin = |tool| read_pin(tmp.path(), tool, &config).map(|p| (p.version, p.source));
+
+        assert_eq!(
+            pin(Tool::Rustc),
+            Some(("1.78".to_string(), "rust-toolchain.toml".to_string()))
+        );
+        assert_eq!(
+            pin(Tool::Node),
+            Some(("18".to_string(), "[toolchain]".to_string()))
+        );
+        assert_eq!(pin(Tool::Python).unwrap().0, "3.12.1");
+        assert_eq!(pin(Tool::Bd), None);
+    }
+
+    #[test]
+    fn test_mismatch_hints_the_install_command() {
+        let tmp = TempDir::new().unwrap();
+        let pin = Pin {
+            version: "0.1".to_string(),
+            source: "[toolchain]".to_string(),
+        };
+
+        // Whatever python3 is installed (if any), it is not 0.1
+        let check = check_tool(tmp.path(), Tool::Python, &pin);
+
+        assert_eq!(check.status, CheckStatus::Warn);
+        assert_eq!(check.hint.as_deref(), Some("pyenv install 0.1"));
+        assert_eq!(
+            Tool::Bd.install_command("v0.30"),
+            "go install github.com/steveyegge/beads/cmd/bd@v0.30"
+        );
+    }
+}
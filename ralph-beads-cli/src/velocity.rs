@@ -0,0 +1,336 @@
+//! Epic burn-down and velocity
+//!
+//! Turns the activity feed's completions for an epic's tasks into a
+//! throughput picture: tasks finished per day over a recent window, the
+//! average claim-to-completion time, when the open tasks will be done at
+//! the current pace, and how much each worker has finished. Rendered as
+//! JSON or as short text with a sparkline for embedding in status reports.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::activity::{format_duration, format_timestamp, ActivityEvent, EventKind};
+use crate::beads::Issue;
+use crate::estimate;
+use crate::retention::summary_of;
+
+/// Sparkline levels, lowest first
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+const DAY_SECS: u64 = 86_400;
+
+/// Tasks completed on one UTC day
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DayCount {
+    /// `YYYY-MM-DD`
+    pub day: String,
+    pub completed: usize,
+}
+
+/// Tasks one worker completed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WorkerThroughput {
+    pub worker: String,
+    pub completed: usize,
+    /// Mean claim-to-completion time of those tasks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub average_duration_secs: Option<u64>,
+}
+
+/// Velocity of an epic at `generated_at`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Velocity {
+    pub epic: String,
+    pub title: String,
+    pub generated_at: u64,
+    pub total: usize,
+    pub done: usize,
+    pub remaining: usize,
+    /// Completions per day, oldest first, from the window's start (or the
+    /// epic's first event, when later) through today
+    pub days: Vec<DayCount>,
+    /// Mean completions per day over `days`
+    pub per_day: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub average_duration_secs: Option<u64>,
+    /// When the remaining tasks finish at `per_day`; absent when nothing
+    /// remains or nothing was completed in the window
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub projected_completion: Option<u64>,
+    pub workers: Vec<WorkerThroughput>,
+}
+
+/// Build the velocity of `epic` from its `tasks` and the activity feed
+///
+/// A task counts as completed on the day of its latest `completed` event
+/// and is credited to that event's worker, or to the worker of its latest
+/// claim. Compaction summaries count as the events they replaced.
+pub fn build_velocity(
+    epic: &Issue,
+    tasks: &[Issue],
+    events: &[ActivityEvent],
+    window_days: u64,
+    now: u64,
+) -> Velocity {
+    let ids: BTreeSet<&str> = tasks.iter().map(|t| t.id.as_str()).collect();
+    let mut durations = estimate::task_durations(events);
+    durations.retain(|issue, _| ids.contains(issue.as_str()));
+    let events: Vec<&ActivityEvent> = events
+        .iter()
+        .filter(|e| ids.contains(e.issue.as_str()))
+        .collect();
+
+    let mut claimers: BTreeMap<&str, String> = BTreeMap::new();
+    let mut completions: BTreeMap<&str, (u64, Option<String>)> = BTreeMap::new();
+    for event in &events {
+        if let Some(summary) = summary_of(event) {
+            if let Some(worker) = summary.workers.last() {
+                claimers.insert(&event.issue, worker.clone());
+            }
+            if let Some(at) = summary.completed_at {
+                completions.insert(&event.issue, (at, None));
+            }
+            continue;
+        }
+        match event.kind {
+            EventKind::Claimed => {
+                if let Some(ref worker) = event.worker {
+                    claimers.insert(&event.issue, worker.clone());
+                }
+            }
+            EventKind::Completed => {
+                completions.insert(&event.issue, (event.timestamp, event.worker.clone()));
+            }
+            _ => {}
+        }
+    }
+
+    let average =
+        |secs: Vec<u64>| (!secs.is_empty()).then(|| secs.iter().sum::<u64>() / secs.len() as u64);
+
+    let today = now / DAY_SECS;
+    let first_day = events
+        .iter()
+        .map(|e| summary_of(e).map_or(e.timestamp, |s| s.first) / DAY_SECS)
+        .min()
+        .unwrap_or(today);
+    let start = today
+        .saturating_sub(window_days.max(1) - 1)
+        .max(first_day.min(today));
+    let days: Vec<DayCount> = (start..=today)
+        .map(|day| DayCount {
+            day: format_timestamp(day * DAY_SECS)[..10].to_string(),
+            completed: completions
+                .values()
+                .filter(|(at, _)| at / DAY_SECS == day)
+                .count(),
+        })
+        .collect();
+    let in_window: usize = days.iter().map(|d| d.completed).sum();
+    let per_day = in_window as f64 / days.len() as f64;
+
+    let mut by_worker: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+    for (issue, (_, worker)) in &completions {
+        let worker = worker
+            .clone()
+            .or_else(|| claimers.get(issue).cloned())
+            .unwrap_or_else(|| "unknown".to_string());
+        by_worker.entry(worker).or_default().push(issue);
+    }
+    let mut workers: Vec<WorkerThroughput> = by_worker
+        .into_iter()
+        .map(|(worker, issues)| WorkerThroughput {
+            worker,
+            completed: issues.len(),
+            average_duration_secs: average(
+                issues
+                    .iter()
+                    .filter_map(|i| durations.get(*i).copied())
+                    .collect(),
+            ),
+        })
+        .collect();
+    workers.sort_by_key(|w| std::cmp::Reverse(w.completed));
+
+    let done = tasks.iter().filter(|t| t.is_closed()).count();
+    let remaining = tasks.len() - done;
+    let projected_completion = (remaining > 0 && in_window > 0)
+        .then(|| now + remaining as u64 * DAY_SECS * days.len() as u64 / in_window as u64);
+
+    Velocity {
+        epic: epic.id.clone(),
+        title: epic.title.clone(),
+        generated_at: now,
+        total: tasks.len(),
+        done,
+        remaining,
+        days,
+        per_day,
+        average_duration_secs: average(durations.into_values().collect()),
+        projected_completion,
+        workers,
+    }
+}
+
+/// One character per count, scaled to the largest
+pub fn sparkline(counts: &[usize]) -> String {
+    let max = counts.iter().copied().max().unwrap_or(0);
+    counts
+        .iter()
+        .map(|&c| match max {
+            0 => SPARKS[0],
+            _ => SPARKS[c * (SPARKS.len() - 1) / max],
+        })
+        .collect()
+}
+
+/// Render the velocity as plain text
+pub fn render_text(velocity: &Velocity) -> String {
+    let mut out = format!("Velocity for {}: {}\n", velocity.epic, velocity.title);
+    out.push_str(&format!(
+        "Completed:  {}/{} ({} remaining)\n",
+        velocity.done, velocity.total, velocity.remaining
+    ));
+    let counts: Vec<usize> = velocity.days.iter().map(|d| d.completed).collect();
+    out.push_str(&format!(
+        "Last {} days: {} {:.1}/day\n",
+        velocity.days.len(),
+        sparkline(&counts),
+        velocity.per_day
+    ));
+    if let Some(secs) = velocity.average_duration_secs {
+        out.push_str(&format!("Average task: {}\n", format_duration(secs)));
+    }
+    let projection = match velocity.projected_completion {
+        Some(at) => format_timestamp(at)[..10].to_string(),
+        None if velocity.remaining == 0 => "done".to_string(),
+        None => "unknown (nothing completed in the window)".to_string(),
+    };
+    out.push_str(&format!("Projected:  {}\n", projection));
+
+    if !velocity.workers.is_empty() {
+        out.push_str("\nWorkers:\n");
+        for worker in &velocity.workers {
+            out.push_str(&format!("  {} {} done", worker.worker, worker.completed));
+            if let Some(secs) = worker.average_duration_secs {
+                out.push_str(&format!(", average {}", format_duration(secs)));
+            }
+            out.push('\n');
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 20_000 * DAY_SECS;
+
+    fn task(id: &str, status: &str) -> Issue {
+        Issue {
+            id: id.to_string(),
+            title: format!("Task {}", id),
+            status: status.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn event(timestamp: u64, issue: &str, kind: EventKind, worker: Option<&str>) -> ActivityEvent {
+        ActivityEvent {
+            id: timestamp,
+            timestamp,
+            issue: issue.to_string(),
+            kind,
+            message: String::new(),
+            worker: worker.map(str::to_string),
+            run: None,
+            data: None,
+        }
+    }
+
+    fn epic() -> Issue {
+        Issue {
+            id: "bd-1".to_string(),
+            title: "Auth".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn tasks() -> Vec<Issue> {
+        vec![
+            task("bd-1.1", "closed"),
+            task("bd-1.2", "closed"),
+            task("bd-1.3", "closed"),
+            task("bd-1.4", "open"),
+            task("bd-1.5", "open"),
+        ]
+    }
+
+    fn events() -> Vec<ActivityEvent> {
+        vec![
+            event(DAY, "bd-1.1", EventKind::Claimed, Some("a")),
+            event(DAY + 3_600, "bd-1.1", EventKind::Completed, None),
+            event(DAY + 3_600, "bd-1.2", EventKind::Claimed, Some("b")),
+            event(DAY + DAY_SECS, "bd-1.2", EventKind::Completed, Some("b")),
+            event(DAY + DAY_SECS, "bd-1.3", EventKind::Claimed, Some("a")),
+            event(
+                DAY + DAY_SECS + 7_200,
+                "bd-1.3",
+                EventKind::Completed,
+                Some("a"),
+            ),
+            event(DAY + DAY_SECS, "bd-2.1", EventKind::Completed, Some("c")),
+        ]
+    }
+
+    #[test]
+    fn test_daily_counts_and_projection() {
+        let now = DAY + 3 * DAY_SECS + 60;
+        let velocity = build_velocity(&epic(), &tasks(), &events(), 14, now);
+
+        let counts: Vec<usize> = velocity.days.iter().map(|d| d.completed).collect();
+        assert_eq!(counts, vec![1, 2, 0, 0]);
+        assert_eq!(velocity.days[0].day, format_timestamp(DAY)[..10]);
+        assert_eq!(velocity.per_day, 0.75);
+        assert_eq!(velocity.remaining, 2);
+        // Two tasks at three quarters of a task per day
+        assert_eq!(velocity.projected_completion, Some(now + 8 * DAY_SECS / 3));
+        assert_eq!(
+            velocity.average_duration_secs,
+            Some((3_600 + 82_800 + 7_200) / 3)
+        );
+    }
+
+    #[test]
+    fn test_window_and_workers() {
+        let now = DAY + 3 * DAY_SECS + 60;
+        let velocity = build_velocity(&epic(), &tasks(), &events(), 2, now);
+
+        assert_eq!(velocity.days.len(), 2);
+        assert_eq!(velocity.per_day, 0.0);
+        assert_eq!(velocity.projected_completion, None);
+        let workers: Vec<(&str, usize, Option<u64>)> = velocity
+            .workers
+            .iter()
+            .map(|w| (w.worker.as_str(), w.completed, w.average_duration_secs))
+            .collect();
+        assert_eq!(
+            workers,
+            vec![("a", 2, Some((3_600 + 7_200) / 2)), ("b", 1, Some(82_800))]
+        );
+        assert!(render_text(&velocity).contains("unknown (nothing completed in the window)"));
+    }
+
+    #[test]
+    fn test_sparkline_and_render() {
+        assert_eq!(sparkline(&[0, 0]), "▁▁");
+        assert_eq!(sparkline(&[0, 1, 2, 4]), "▁▂▄█");
+
+        let velocity = build_velocity(&epic(), &tasks(), &events(), 14, DAY + DAY_SECS + 60);
+        let text = render_text(&velocity);
+        assert!(text.starts_with("Velocity for bd-1: Auth\nCompleted:  3/5 (2 remaining)\n"));
+        assert!(text.contains("Last 2 days: ▄█ 1.5/day\n"));
+        assert!(text.contains("  a 2 done, average 1h30m\n"));
+    }
+}
@@ -0,0 +1,233 @@
+//! Multi-repo workspaces
+//!
+//! A workspace is a `[workspace]` section listing sibling repositories,
+//! each with its own beads database (or `.beads/redirect`) and its own
+//! `.ralph-beads.toml`. Commands run with `--workspace` visit every repo
+//! and aggregate the results, so one report covers a whole service fleet.
+//! Repo paths are relative to the file that declares the workspace.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use crate::beads::{list_issues, ListFilter};
+use crate::config::{load_config, LoadedConfig};
+use crate::lint::{build_report, gather_related, LintReport};
+
+/// The `[workspace]` configuration section
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkspaceConfig {
+    /// Repository directories, relative to the config file
+    pub repos: Vec<String>,
+}
+
+impl WorkspaceConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        let mut seen = BTreeSet::new();
+        for repo in &self.repos {
+            if repo.trim().is_empty() {
+                return Err("workspace.repos entries must not be empty".to_string());
+            }
+            if !seen.insert(repo.trim_end_matches('/')) {
+                return Err(format!("workspace.repos lists '{}' twice", repo));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One repository of the workspace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WorkspaceRepo {
+    /// Directory name, used to label results
+    pub name: String,
+    pub path: PathBuf,
+    pub exists: bool,
+    /// Whether the repo has a `.beads` directory
+    pub beads: bool,
+}
+
+/// Repositories of the workspace declared in `loaded`
+pub fn workspace_repos(loaded: &LoadedConfig) -> Result<Vec<WorkspaceRepo>, String> {
+    let Some(ref source) = loaded.source else {
+        return Err("No workspace: no .ralph-beads.toml found".to_string());
+    };
+    if loaded.config.workspace.repos.is_empty() {
+        return Err(format!(
+            "No workspace: {} has no [workspace] repos",
+            source.display()
+        ));
+    }
+    let root = source.parent().unwrap_or(Path::new("."));
+    Ok(loaded
+        .config
+        .workspace
+        .repos
+        .iter()
+        .map(|repo| {
+            let path = root.join(repo);
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| repo.clone());
+            WorkspaceRepo {
+                name,
+                exists: path.is_dir(),
+                beads: path.join(".beads").is_dir(),
+                path,
+            }
+        })
+        .collect())
+}
+
+/// Lint outcome for one repository
+#[derive(Debug, Clone, Serialize)]
+pub struct RepoLint {
+    pub repo: String,
+    pub path: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report: Option<LintReport>,
+    /// Why the repo couldn't be linted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Lint reports across the workspace, with totals
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkspaceLint {
+    pub repos: Vec<RepoLint>,
+    pub issues_checked: usize,
+    pub errors: usize,
+    pub warnings: usize,
+    /// Repos that could not be linted
+    pub failed: usize,
+}
+
+/// Combine per-repo results into a workspace report
+pub fn summarize_lint(repos: Vec<RepoLint>) -> WorkspaceLint {
+    let reports = repos.iter().filter_map(|r| r.report.as_ref());
+    WorkspaceLint {
+        issues_checked: reports.clone().map(|r| r.issues_checked).sum(),
+        errors: reports.clone().map(|r| r.errors).sum(),
+        warnings: reports.map(|r| r.warnings).sum(),
+        failed: repos.iter().filter(|r| r.error.is_some()).count(),
+        repos,
+    }
+}
+
+fn lint_repo(repo: &WorkspaceRepo, now: u64) -> Result<LintReport, String> {
+    if !repo.exists {
+        return Err(format!("{} does not exist", repo.path.display()));
+    }
+    // Each repo lints under its own configuration
+    let loaded = load_config(&repo.path)?;
+    let issues = list_issues(&repo.path, &ListFilter::default())?;
+    let related = gather_related(&repo.path, &issues)?;
+    Ok(build_report(
+        &issues,
+        &related,
+        &loaded.config.lint,
+        &loaded.source_description(),
+        now,
+    ))
+}
+
+/// Lint the open issues of every repo; a failing repo doesn't stop the rest
+pub fn lint_workspace(repos: &[WorkspaceRepo], now: u64) -> WorkspaceLint {
+    summarize_lint(
+        repos
+            .iter()
+            .map(|repo| {
+                let result = lint_repo(repo, now);
+                RepoLint {
+                    repo: repo.name.clone(),
+                    path: repo.path.clone(),
+                    error: result.as_ref().err().cloned(),
+                    report: result.ok(),
+                }
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CONFIG_FILE;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_validate() {
+        let config = WorkspaceConfig {
+            repos: vec!["../api".to_string(), "../api/".to_string()],
+        };
+        assert!(config.validate().unwrap_err().contains("twice"));
+        assert!(WorkspaceConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_repos_resolve_against_config_file() {
+        let root = TempDir::new().unwrap();
+        let hub = root.path().join("hub");
+        fs::create_dir_all(root.path().join("api/.beads")).unwrap();
+        fs::create_dir_all(hub.join("nested")).unwrap();
+        fs::write(
+            hub.join(CONFIG_FILE),
+            "[workspace]\nrepos = [\"../api\", \"../web\"]\n",
+        )
+        .unwrap();
+
+        let loaded = load_config(&hub.join("nested")).unwrap();
+        let repos = workspace_repos(&loaded).unwrap();
+
+        assert_eq!(repos.len(), 2);
+        assert_eq!(repos[0].name, "api");
+        assert!(repos[0].exists && repos[0].beads);
+        assert!(!repos[1].exists);
+
+        let lint = lint_workspace(&repos[1..], 0);
+        assert_eq!(lint.failed, 1);
+        assert!(lint.repos[0]
+            .error
+            .as_ref()
+            .unwrap()
+            .contains("does not exist"));
+    }
+
+    #[test]
+    fn test_no_workspace() {
+        let err = workspace_repos(&LoadedConfig::default()).unwrap_err();
+        assert!(err.contains("No workspace"));
+    }
+
+    #[test]
+    fn test_summarize_lint() {
+        let report = LintReport {
+            config: "built-in defaults".to_string(),
+            issues_checked: 3,
+            errors: 1,
+            warnings: 2,
+            findings: Vec::new(),
+        };
+        let lint = summarize_lint(vec![
+            RepoLint {
+                repo: "api".to_string(),
+                path: PathBuf::from("../api"),
+                report: Some(report.clone()),
+                error: None,
+            },
+            RepoLint {
+                repo: "web".to_string(),
+                path: PathBuf::from("../web"),
+                report: Some(report),
+                error: None,
+            },
+        ]);
+        assert_eq!(
+            (lint.issues_checked, lint.errors, lint.warnings, lint.failed),
+            (6, 2, 4, 0)
+        );
+    }
+}
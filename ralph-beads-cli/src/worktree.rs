@@ -0,0 +1,1200 @@
+//! Git worktree inspection for Ralph-Beads
+//!
+//! Provides worktree discovery and conflict forecasting so the swarm
+//! orchestrator can serialize tasks whose branches touch the same code,
+//! and a per-worktree health report for deciding what to clean up.
+//!
+//! Worktrees created for an issue get a branch and directory named after
+//! it, a context file naming the issue, and an entry in a registry kept in
+//! the repository's common git directory so listings can show which issue
+//! each worktree serves. `worktree guard` uses the same record to stop an
+//! agent from working on one issue in another issue's worktree.
+//! `[worktree.cache]` can point the build tools of every worktree at shared
+//! caches.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::activity::{now_secs, ActivityEvent};
+use crate::bootstrap::BootstrapConfig;
+use crate::build_cache::CacheConfig;
+use crate::epic::slugify;
+use crate::limits::{self, LimitError};
+use crate::state::sidecar;
+
+/// The `[worktree]` configuration section
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorktreeConfig {
+    /// Recipe run on each worktree `create-for-issue` makes
+    pub bootstrap: BootstrapConfig,
+    /// Build caches shared by all worktrees
+    pub cache: CacheConfig,
+}
+
+impl WorktreeConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        self.bootstrap.validate()?;
+        self.cache.validate()
+    }
+}
+
+/// A single entry from `git worktree list`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorktreeInfo {
+    pub path: String,
+    pub head: String,
+    /// Short branch name, `None` for detached worktrees
+    pub branch: Option<String>,
+    /// Whether this is the main (first listed) worktree
+    pub is_main: bool,
+}
+
+impl WorktreeInfo {
+    /// Task ID guessed from the worktree's branch: its last path segment
+    ///
+    /// Exact for `molecule/<id>` branches. Issue worktrees are named
+    /// `task/<id>-<slug>`, so for those prefer the registry entry, which
+    /// [`match_issues`] and the worktree reports consult first.
+    pub fn task_id(&self) -> Option<String> {
+        self.branch
+            .as_deref()
+            .and_then(|b| b.rsplit('/').next())
+            .map(|s| s.to_string())
+    }
+}
+
+/// Registry of issue worktrees, relative to the common git directory
+pub const REGISTRY_FILE: &str = "ralph-beads/worktrees.json";
+
+/// Context file written into each issue worktree, relative to its root
+pub const CONTEXT_FILE: &str = ".ralph-beads/worktree.json";
+
+/// What an issue worktree records about itself in its context file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorktreeContext {
+    pub issue: String,
+    pub title: String,
+    pub branch: String,
+    pub base: String,
+    /// Unix timestamp of creation
+    pub created_at: u64,
+}
+
+/// The worktree a command runs in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CurrentWorktree {
+    pub root: PathBuf,
+    /// Checked-out branch, `None` when detached
+    pub branch: Option<String>,
+    /// Context file, for worktrees made by `create-for-issue`
+    pub context: Option<WorktreeContext>,
+}
+
+/// A worktree with the issue it serves
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IssueWorktree {
+    #[serde(flatten)]
+    pub worktree: WorktreeInfo,
+    /// Issue from the registry, or derived from the branch name
+    pub issue: Option<String>,
+}
+
+/// Lines touched in a file, as inclusive ranges in the base revision
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileChange {
+    pub path: String,
+    pub ranges: Vec<(u32, u32)>,
+}
+
+/// Overlap between two change sets on a single file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileOverlap {
+    pub path: String,
+    /// Base-revision line ranges modified by both sides
+    pub overlapping_ranges: Vec<(u32, u32)>,
+}
+
+/// Likelihood that merging two branches will conflict
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictRisk {
+    /// Same files touched, but in disjoint line ranges
+    Low,
+    /// Overlapping line ranges in at least one file
+    High,
+}
+
+/// A pair of participants (branches or the base branch) likely to conflict
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConflictPair {
+    pub a: String,
+    pub b: String,
+    pub task_a: Option<String>,
+    pub task_b: Option<String>,
+    pub risk: ConflictRisk,
+    pub files: Vec<FileOverlap>,
+}
+
+/// Change set contributed by one participant in the analysis
+#[derive(Debug, Clone)]
+pub struct ChangeSet {
+    pub label: String,
+    pub task_id: Option<String>,
+    pub changes: Vec<FileChange>,
+}
+
+/// Run a git command in `dir`, returning stdout on success
+pub(crate) fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let command = format!("git {}", args.join(" "));
+    let _permit = limits::acquire(&command).map_err(|e| match e {
+        LimitError::Queued(message) => message,
+        LimitError::Io(message) => format!("{} failed: {}", command, message),
+    })?;
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Parse `git worktree list --porcelain` output
+pub fn parse_worktree_list(porcelain: &str) -> Vec<WorktreeInfo> {
+    let mut worktrees = Vec::new();
+
+    for block in porcelain.split("\n\n") {
+        let mut path = None;
+        let mut head = String::new();
+        let mut branch = None;
+
+        for line in block.lines() {
+            if let Some(p) = line.strip_prefix("worktree ") {
+                path = Some(p.to_string());
+            } else if let Some(h) = line.strip_prefix("HEAD ") {
+                head = h.to_string();
+            } else if let Some(b) = line.strip_prefix("branch ") {
+                branch = Some(b.trim_start_matches("refs/heads/").to_string());
+            }
+        }
+
+        if let Some(path) = path {
+            let is_main = worktrees.is_empty();
+            worktrees.push(WorktreeInfo {
+                path,
+                head,
+                branch,
+                is_main,
+            });
+        }
+    }
+
+    worktrees
+}
+
+/// List all worktrees of the repository containing `dir`
+pub fn list_worktrees(dir: &Path) -> Result<Vec<WorktreeInfo>, String> {
+    git(dir, &["worktree", "list", "--porcelain"]).map(|out| parse_worktree_list(&out))
+}
+
+/// `<id>-<slug>`, or just the id when the title has no usable characters
+fn issue_slug(id: &str, title: &str) -> String {
+    match slugify(title, Some(40)) {
+        slug if slug.is_empty() => id.to_string(),
+        slug => format!("{}-{}", id, slug),
+    }
+}
+
+/// Branch for an issue worktree, e.g. `task/bd-42-add-login-page`
+pub fn issue_branch(id: &str, title: &str) -> String {
+    format!("task/{}", issue_slug(id, title))
+}
+
+/// Directory for an issue worktree
+///
+/// Defaults to `<repo>-worktrees/<id>-<slug>` next to the repository root.
+pub fn issue_worktree_path(
+    repo_root: &Path,
+    root: Option<&Path>,
+    id: &str,
+    title: &str,
+) -> PathBuf {
+    let root = root.map(Path::to_path_buf).unwrap_or_else(|| {
+        let name = repo_root
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "repo".to_string());
+        repo_root
+            .parent()
+            .unwrap_or(repo_root)
+            .join(format!("{}-worktrees", name))
+    });
+    root.join(issue_slug(id, title))
+}
+
+/// Absolute path of a `git rev-parse` path query run in `dir`
+fn git_path(dir: &Path, query: &str) -> Result<PathBuf, String> {
+    let out = git(dir, &["rev-parse", query])?;
+    let path = PathBuf::from(out.trim());
+    Ok(if path.is_absolute() {
+        path
+    } else {
+        dir.join(path)
+    })
+}
+
+/// Root of the worktree containing `dir`
+pub fn repo_root(dir: &Path) -> Result<PathBuf, String> {
+    git_path(dir, "--show-toplevel")
+}
+
+/// The repository's common git directory, shared by all its worktrees
+pub fn common_dir(dir: &Path) -> Result<PathBuf, String> {
+    let path = git_path(dir, "--git-common-dir")?;
+    Ok(fs::canonicalize(&path).unwrap_or(path))
+}
+
+/// The worktree containing `dir`, with its context file if it has one
+pub fn current_worktree(dir: &Path) -> Result<CurrentWorktree, String> {
+    let root = repo_root(dir)?;
+    let branch = git(dir, &["branch", "--show-current"])?.trim().to_string();
+    let path = root.join(CONTEXT_FILE);
+    let context = match fs::read_to_string(&path) {
+        Ok(content) => Some(
+            serde_json::from_str(&content)
+                .map_err(|e| format!("Invalid context file {}: {}", path.display(), e))?,
+        ),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    Ok(CurrentWorktree {
+        root,
+        branch: (!branch.is_empty()).then_some(branch),
+        context,
+    })
+}
+
+/// Read a worktree registry file (worktree path to issue ID)
+pub fn read_registry(path: &Path) -> Result<BTreeMap<String, String>, String> {
+    match fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid worktree registry {}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+/// Change the registry at `path` as one step: the read and the write
+/// happen under the registry's lock, and the write replaces the file
+/// through a temp file
+fn update_registry<F>(path: &Path, change: F) -> Result<(), String>
+where
+    F: FnOnce(&mut BTreeMap<String, String>) -> bool,
+{
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let lock_path = sidecar(path, "lock");
+    let lock = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .map_err(|e| format!("Failed to open {}: {}", lock_path.display(), e))?;
+    lock.lock()
+        .map_err(|e| format!("Failed to lock {}: {}", lock_path.display(), e))?;
+
+    let mut registry = read_registry(path)?;
+    if !change(&mut registry) {
+        return Ok(());
+    }
+    let json = serde_json::to_string_pretty(&registry).map_err(|e| e.to_string())?;
+    let tmp = sidecar(path, "tmp");
+    fs::write(&tmp, json + "\n")
+        .map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+    fs::rename(&tmp, path).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Record that the worktree at `worktree` serves `issue`
+pub fn register_worktree(path: &Path, worktree: &str, issue: &str) -> Result<(), String> {
+    update_registry(path, |registry| {
+        registry.insert(worktree.to_string(), issue.to_string());
+        true
+    })
+}
+
+/// Forget the worktree at `worktree`; a missing entry is not an error
+pub fn unregister_worktree(path: &Path, worktree: &str) -> Result<(), String> {
+    update_registry(path, |registry| registry.remove(worktree).is_some())
+}
+
+/// Registry of the repository containing `dir`
+pub fn registry_path(dir: &Path) -> Result<PathBuf, String> {
+    Ok(common_dir(dir)?.join(REGISTRY_FILE))
+}
+
+/// Add `pattern` to the repository's `info/exclude` unless already listed
+fn exclude_pattern(common_dir: &Path, pattern: &str) -> Result<(), String> {
+    let path = common_dir.join("info").join("exclude");
+    let existing = fs::read_to_string(&path).unwrap_or_default();
+    if existing.lines().any(|line| line.trim() == pattern) {
+        return Ok(());
+    }
+    fs::create_dir_all(common_dir.join("info")).map_err(|e| e.to_string())?;
+    let separator = if existing.is_empty() || existing.ends_with('\n') {
+        ""
+    } else {
+        "\n"
+    };
+    fs::write(&path, format!("{}{}{}\n", existing, separator, pattern))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Create a worktree on a new branch off `base` for an issue
+///
+/// Writes the context file into the new worktree (kept out of `git status`
+/// through `info/exclude`) and registers the worktree. Updating the issue's
+/// status is left to the caller.
+pub fn create_issue_worktree(
+    dir: &Path,
+    id: &str,
+    title: &str,
+    base: &str,
+    root: Option<&Path>,
+) -> Result<(PathBuf, WorktreeContext), String> {
+    let repo_root = repo_root(dir)?;
+    let path = issue_worktree_path(&repo_root, root, id, title);
+    if path.exists() {
+        return Err(format!("{} already exists", path.display()));
+    }
+    let context = WorktreeContext {
+        issue: id.to_string(),
+        title: title.to_string(),
+        branch: issue_branch(id, title),
+        base: base.to_string(),
+        created_at: now_secs(),
+    };
+    let path_arg = path.to_string_lossy().into_owned();
+    git(
+        dir,
+        &["worktree", "add", "-b", &context.branch, &path_arg, base],
+    )?;
+
+    if let Err(e) = write_context(dir, &path, &context) {
+        // Don't leave a worktree behind that `worktree list` can't pair
+        // with its issue
+        let _ = git(dir, &["worktree", "remove", "--force", &path_arg]);
+        let _ = git(dir, &["branch", "-D", &context.branch]);
+        return Err(e);
+    }
+    Ok((path, context))
+}
+
+/// Write the context file of a new issue worktree and register it
+fn write_context(dir: &Path, path: &Path, context: &WorktreeContext) -> Result<(), String> {
+    let common_dir = self::common_dir(dir)?;
+    exclude_pattern(&common_dir, CONTEXT_FILE)?;
+    let context_path = path.join(CONTEXT_FILE);
+    if let Some(parent) = context_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(context).map_err(|e| e.to_string())?;
+    fs::write(&context_path, json + "\n")
+        .map_err(|e| format!("Failed to write {}: {}", context_path.display(), e))?;
+
+    // git lists worktrees by canonical path
+    let listed = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    register_worktree(
+        &common_dir.join(REGISTRY_FILE),
+        &listed.to_string_lossy(),
+        &context.issue,
+    )
+}
+
+/// Undo [`create_issue_worktree`]: remove the worktree, its branch, and its
+/// registry entry
+pub fn remove_issue_worktree(dir: &Path, path: &Path, branch: &str) -> Result<(), String> {
+    let listed = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let path_arg = path.to_string_lossy().into_owned();
+    git(dir, &["worktree", "remove", "--force", &path_arg])?;
+    git(dir, &["branch", "-D", branch])?;
+    unregister_worktree(&registry_path(dir)?, &listed.to_string_lossy())
+}
+
+/// Pair worktrees with their issue, preferring the registry over the branch
+pub fn match_issues(
+    worktrees: Vec<WorktreeInfo>,
+    registry: &BTreeMap<String, String>,
+) -> Vec<IssueWorktree> {
+    worktrees
+        .into_iter()
+        .map(|worktree| IssueWorktree {
+            issue: registry
+                .get(&worktree.path)
+                .cloned()
+                .or_else(|| (!worktree.is_main).then(|| worktree.task_id()).flatten()),
+            worktree,
+        })
+        .collect()
+}
+
+/// List worktrees of the repository containing `dir` with their issues
+pub fn list_issue_worktrees(dir: &Path) -> Result<Vec<IssueWorktree>, String> {
+    let registry = read_registry(&registry_path(dir)?)?;
+    Ok(match_issues(list_worktrees(dir)?, &registry))
+}
+
+/// Whether the worktree a command runs in is the one made for an issue
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IssueGuard {
+    /// Issue the agent means to work on
+    pub issue: String,
+    pub worktree: PathBuf,
+    /// Issue the worktree was created for, from its context file or the
+    /// registry
+    pub registered: Option<String>,
+    /// Worktree registered for `issue`, when that is another one
+    pub expected: Option<String>,
+    pub allowed: bool,
+    pub detail: String,
+}
+
+/// Check that `current` was created for `issue`
+///
+/// Branch names are not consulted: only worktrees made by
+/// `create-for-issue` are known to serve an issue. Any other worktree, such
+/// as the main checkout, is refused unless `allow_unregistered`.
+pub fn guard_issue(
+    current: &CurrentWorktree,
+    registry: &BTreeMap<String, String>,
+    issue: &str,
+    allow_unregistered: bool,
+) -> IssueGuard {
+    let root = fs::canonicalize(&current.root).unwrap_or_else(|_| current.root.clone());
+    let registered = current
+        .context
+        .as_ref()
+        .map(|c| c.issue.clone())
+        .or_else(|| registry.get(root.to_string_lossy().as_ref()).cloned());
+    let expected = registry
+        .iter()
+        .find(|(path, id)| id.as_str() == issue && Path::new(path) != root)
+        .map(|(path, _)| path.clone());
+
+    let (allowed, mut detail) = match registered {
+        Some(ref serves) if serves == issue => {
+            (true, format!("{} serves {}", root.display(), issue))
+        }
+        Some(ref serves) => (
+            false,
+            format!("{} serves {}, not {}", root.display(), serves, issue),
+        ),
+        None if allow_unregistered => {
+            (true, format!("{} is not an issue worktree", root.display()))
+        }
+        None => (
+            false,
+            format!(
+                "{} is not an issue worktree (create one with `worktree create-for-issue {}`)",
+                root.display(),
+                issue
+            ),
+        ),
+    };
+    if let (false, Some(ref path)) = (allowed, &expected) {
+        detail.push_str(&format!("; work on {} in {}", issue, path));
+    }
+    IssueGuard {
+        issue: issue.to_string(),
+        worktree: root,
+        registered,
+        expected,
+        allowed,
+        detail,
+    }
+}
+
+/// Parse the old-side range of a unified diff hunk header
+///
+/// `@@ -12,3 +12,4 @@` yields `(12, 14)`. Pure insertions (`-12,0`) are
+/// reported as touching the line they follow.
+fn parse_hunk_header(line: &str) -> Option<(u32, u32)> {
+    let old = line.strip_prefix("@@ -")?.split_whitespace().next()?;
+    let mut parts = old.splitn(2, ',');
+    let start: u32 = parts.next()?.parse().ok()?;
+    let count: u32 = match parts.next() {
+        Some(c) => c.parse().ok()?,
+        None => 1,
+    };
+    let end = start + count.saturating_sub(1);
+    Some((start, end.max(start)))
+}
+
+/// Parse `git diff --unified=0` output into per-file base-revision ranges
+pub fn parse_diff_ranges(diff: &str) -> Vec<FileChange> {
+    let mut changes: Vec<FileChange> = Vec::new();
+    let mut old_path: Option<String> = None;
+    // File headers only appear between `diff --git` and the first hunk;
+    // a removed line starting with "-- " must not be mistaken for one
+    let mut in_header = false;
+
+    for line in diff.lines() {
+        if line.starts_with("diff --git ") {
+            in_header = true;
+        } else if !in_header {
+            if line.starts_with("@@ ") {
+                if let (Some(range), Some(current)) = (parse_hunk_header(line), changes.last_mut())
+                {
+                    current.ranges.push(range);
+                }
+            }
+        } else if let Some(p) = line.strip_prefix("--- ") {
+            old_path = p.strip_prefix("a/").map(|s| s.to_string());
+        } else if let Some(p) = line.strip_prefix("+++ ") {
+            // Prefer the base path so renames line up with the other side;
+            // fall back to the new path for added files
+            let path = old_path
+                .take()
+                .or_else(|| p.strip_prefix("b/").map(|s| s.to_string()));
+            if let Some(path) = path {
+                changes.push(FileChange {
+                    path,
+                    ranges: Vec::new(),
+                });
+            }
+            in_header = false;
+        }
+    }
+
+    changes
+}
+
+/// Changes made on `to` since it diverged from `from`
+pub fn diff_since_merge_base(dir: &Path, from: &str, to: &str) -> Result<Vec<FileChange>, String> {
+    let spec = format!("{}...{}", from, to);
+    git(dir, &["diff", "--unified=0", "--no-color", &spec]).map(|out| parse_diff_ranges(&out))
+}
+
+/// Intersect two sorted-or-unsorted range lists
+fn overlapping_ranges(a: &[(u32, u32)], b: &[(u32, u32)]) -> Vec<(u32, u32)> {
+    let mut result = Vec::new();
+    for &(a_start, a_end) in a {
+        for &(b_start, b_end) in b {
+            let start = a_start.max(b_start);
+            let end = a_end.min(b_end);
+            if start <= end {
+                result.push((start, end));
+            }
+        }
+    }
+    result.sort_unstable();
+    result.dedup();
+    result
+}
+
+/// Compare two change sets, returning a conflict pair if they share files
+pub fn compare_change_sets(a: &ChangeSet, b: &ChangeSet) -> Option<ConflictPair> {
+    let b_files: BTreeMap<&str, &FileChange> =
+        b.changes.iter().map(|c| (c.path.as_str(), c)).collect();
+
+    let files: Vec<FileOverlap> = a
+        .changes
+        .iter()
+        .filter_map(|change| {
+            b_files.get(change.path.as_str()).map(|other| FileOverlap {
+                path: change.path.clone(),
+                overlapping_ranges: overlapping_ranges(&change.ranges, &other.ranges),
+            })
+        })
+        .collect();
+
+    if files.is_empty() {
+        return None;
+    }
+
+    let risk = if files.iter().any(|f| !f.overlapping_ranges.is_empty()) {
+        ConflictRisk::High
+    } else {
+        ConflictRisk::Low
+    };
+
+    Some(ConflictPair {
+        a: a.label.clone(),
+        b: b.label.clone(),
+        task_a: a.task_id.clone(),
+        task_b: b.task_id.clone(),
+        risk,
+        files,
+    })
+}
+
+/// Pairwise conflict analysis over a set of change sets
+///
+/// Results are ordered with high-risk pairs first.
+pub fn forecast_conflicts(change_sets: &[ChangeSet]) -> Vec<ConflictPair> {
+    let mut pairs = Vec::new();
+    for (i, a) in change_sets.iter().enumerate() {
+        for b in &change_sets[i + 1..] {
+            if let Some(pair) = compare_change_sets(a, b) {
+                pairs.push(pair);
+            }
+        }
+    }
+    pairs.sort_by_key(|p| std::cmp::Reverse(p.risk));
+    pairs
+}
+
+/// Forecast conflicts between all active worktree branches and `base`
+///
+/// Each branch is diffed against its merge base with `base`. The base
+/// branch contributes a separate change set per worktree (what landed on
+/// `base` since that branch forked), so branches that have fallen behind
+/// are flagged as well.
+pub fn forecast_worktree_conflicts(dir: &Path, base: &str) -> Result<Vec<ConflictPair>, String> {
+    let worktrees = list_worktrees(dir)?;
+    let registry = read_registry(&registry_path(dir)?)?;
+    let mut branch_sets = Vec::new();
+    let mut pairs = Vec::new();
+
+    for wt in &worktrees {
+        let branch = match &wt.branch {
+            Some(b) if b != base => b,
+            _ => continue,
+        };
+
+        let changes = diff_since_merge_base(dir, base, branch)?;
+        let set = ChangeSet {
+            label: branch.clone(),
+            task_id: registry.get(&wt.path).cloned().or_else(|| wt.task_id()),
+            changes,
+        };
+
+        let base_set = ChangeSet {
+            label: base.to_string(),
+            task_id: None,
+            changes: diff_since_merge_base(dir, branch, base)?,
+        };
+        if let Some(pair) = compare_change_sets(&set, &base_set) {
+            pairs.push(pair);
+        }
+
+        branch_sets.push(set);
+    }
+
+    pairs.extend(forecast_conflicts(&branch_sets));
+    pairs.sort_by_key(|p| std::cmp::Reverse(p.risk));
+    Ok(pairs)
+}
+
+/// Health of one worktree, for cleanup decisions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorktreeReport {
+    pub path: String,
+    pub branch: Option<String>,
+    pub task_id: Option<String>,
+    pub is_main: bool,
+    /// Whether the worktree directory still exists (otherwise prunable)
+    pub exists: bool,
+    /// Bytes on disk, excluding worktrees nested inside this one
+    pub disk_bytes: u64,
+    /// Unix timestamp of the HEAD commit
+    pub last_commit: Option<u64>,
+    /// Commits on HEAD not on the base branch
+    pub ahead: Option<u32>,
+    /// Commits on the base branch not on HEAD
+    pub behind: Option<u32>,
+    /// Uncommitted changes, including untracked files
+    pub dirty: bool,
+    /// Worker that last reported on the worktree's task in the activity feed
+    pub worker: Option<String>,
+}
+
+/// Sort order for worktree reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportSort {
+    Path,
+    /// Largest first
+    Disk,
+    /// Oldest last commit first
+    Age,
+    /// Furthest behind the base branch first
+    Behind,
+}
+
+impl ReportSort {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "path" => Ok(ReportSort::Path),
+            "disk" => Ok(ReportSort::Disk),
+            "age" => Ok(ReportSort::Age),
+            "behind" => Ok(ReportSort::Behind),
+            _ => Err(format!(
+                "Invalid sort: {} (expected path, disk, age, or behind)",
+                s
+            )),
+        }
+    }
+}
+
+/// Which worktrees to keep in a report
+#[derive(Debug, Clone, Default)]
+pub struct ReportFilter {
+    /// Only worktrees with uncommitted changes
+    pub dirty: bool,
+    /// Only worktrees whose last commit is at least this many seconds old
+    pub older_than: Option<u64>,
+    /// Leave out the main worktree
+    pub skip_main: bool,
+}
+
+impl ReportFilter {
+    pub fn matches(&self, report: &WorktreeReport, now: u64) -> bool {
+        if self.dirty && !report.dirty {
+            return false;
+        }
+        if self.skip_main && report.is_main {
+            return false;
+        }
+        if let Some(min_age) = self.older_than {
+            // A worktree without a readable commit is as stale as it gets
+            if report
+                .last_commit
+                .is_some_and(|t| now.saturating_sub(t) < min_age)
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Sort `reports` in place
+pub fn sort_reports(reports: &mut [WorktreeReport], sort: ReportSort) {
+    match sort {
+        ReportSort::Path => reports.sort_by(|a, b| a.path.cmp(&b.path)),
+        ReportSort::Disk => reports.sort_by_key(|r| std::cmp::Reverse(r.disk_bytes)),
+        ReportSort::Age => reports.sort_by_key(|r| r.last_commit.unwrap_or(0)),
+        ReportSort::Behind => reports.sort_by_key(|r| std::cmp::Reverse(r.behind.unwrap_or(0))),
+    }
+}
+
+/// Format a byte count with a binary unit (e.g. `1.5 GiB`)
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Parse `git rev-list --left-right --count base...HEAD` into (ahead, behind)
+fn parse_ahead_behind(output: &str) -> Option<(u32, u32)> {
+    let mut counts = output.split_whitespace().map(|n| n.parse::<u32>().ok());
+    let behind = counts.next()??;
+    let ahead = counts.next()??;
+    Some((ahead, behind))
+}
+
+/// Total size of files under `dir`, not following symlinks or descending into `skip`
+pub fn dir_size(dir: &Path, skip: &[PathBuf]) -> u64 {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| {
+            let path = entry.path();
+            match entry.file_type() {
+                Ok(t) if t.is_dir() && !skip.contains(&path) => dir_size(&path, skip),
+                Ok(t) if t.is_file() => entry.metadata().map_or(0, |m| m.len()),
+                _ => 0,
+            }
+        })
+        .sum()
+}
+
+/// Worker of the most recent event for `task` that names one
+pub fn worker_for_task(events: &[ActivityEvent], task: &str) -> Option<String> {
+    events
+        .iter()
+        .rev()
+        .filter(|e| e.issue == task)
+        .find_map(|e| e.worker.clone())
+}
+
+/// Report disk usage, commit age, divergence from `base`, dirtiness, and
+/// worker for every worktree of the repository containing `dir`
+pub fn worktree_reports(
+    dir: &Path,
+    base: &str,
+    events: &[ActivityEvent],
+) -> Result<Vec<WorktreeReport>, String> {
+    let worktrees = list_worktrees(dir)?;
+    let paths: Vec<PathBuf> = worktrees.iter().map(|wt| PathBuf::from(&wt.path)).collect();
+    let registry = read_registry(&registry_path(dir)?)?;
+
+    Ok(worktrees
+        .iter()
+        .map(|wt| {
+            let path = Path::new(&wt.path);
+            let exists = path.is_dir();
+            let task_id = registry.get(&wt.path).cloned().or_else(|| wt.task_id());
+            let (ahead, behind) = if exists {
+                git(
+                    path,
+                    &[
+                        "rev-list",
+                        "--left-right",
+                        "--count",
+                        &format!("{}...HEAD", base),
+                    ],
+                )
+                .ok()
+                .and_then(|out| parse_ahead_behind(&out))
+                .map_or((None, None), |(a, b)| (Some(a), Some(b)))
+            } else {
+                (None, None)
+            };
+            WorktreeReport {
+                path: wt.path.clone(),
+                branch: wt.branch.clone(),
+                is_main: wt.is_main,
+                exists,
+                disk_bytes: if exists { dir_size(path, &paths) } else { 0 },
+                last_commit: exists
+                    .then(|| git(path, &["log", "-1", "--format=%ct"]).ok())
+                    .flatten()
+                    .and_then(|out| out.trim().parse().ok()),
+                ahead,
+                behind,
+                dirty: exists
+                    && git(path, &["status", "--porcelain"])
+                        .is_ok_and(|out| !out.trim().is_empty()),
+                worker: task_id.as_deref().and_then(|t| worker_for_task(events, t)),
+                task_id,
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change_set(label: &str, files: &[(&str, &[(u32, u32)])]) -> ChangeSet {
+        ChangeSet {
+            label: label.to_string(),
+            task_id: Some(label.to_string()),
+            changes: files
+                .iter()
+                .map(|(path, ranges)| FileChange {
+                    path: path.to_string(),
+                    ranges: ranges.to_vec(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_parse_worktree_list() {
+        let porcelain = "worktree /repo\nHEAD abc123\nbranch refs/heads/main\n\n\
+                         worktree /repo-wt/task-1\nHEAD def456\nbranch refs/heads/molecule/bd-42\n\n\
+                         worktree /repo-wt/detached\nHEAD 789abc\ndetached\n";
+
+        let worktrees = parse_worktree_list(porcelain);
+
+        assert_eq!(worktrees.len(), 3);
+        assert!(worktrees[0].is_main);
+        assert_eq!(worktrees[0].branch.as_deref(), Some("main"));
+        assert_eq!(worktrees[1].branch.as_deref(), Some("molecule/bd-42"));
+        assert_eq!(worktrees[1].task_id().as_deref(), Some("bd-42"));
+        assert!(!worktrees[1].is_main);
+        assert_eq!(worktrees[2].branch, None);
+        assert_eq!(worktrees[2].task_id(), None);
+    }
+
+    #[test]
+    fn test_issue_names() {
+        assert_eq!(
+            issue_branch("bd-42", "Add login page"),
+            "task/bd-42-add-login-page"
+        );
+        assert_eq!(issue_branch("bd-42", "???"), "task/bd-42");
+        assert_eq!(
+            issue_worktree_path(Path::new("/src/app"), None, "bd-42", "Add login"),
+            PathBuf::from("/src/app-worktrees/bd-42-add-login")
+        );
+        assert_eq!(
+            issue_worktree_path(Path::new("/src/app"), Some(Path::new("/wt")), "bd-1", ""),
+            PathBuf::from("/wt/bd-1")
+        );
+    }
+
+    #[test]
+    fn test_registry_and_issue_matching() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let registry_file = dir.path().join(REGISTRY_FILE);
+        assert!(read_registry(&registry_file).unwrap().is_empty());
+
+        register_worktree(&registry_file, "/wt/bd-42-add-login", "bd-42").unwrap();
+        let registry = read_registry(&registry_file).unwrap();
+
+        let porcelain = "worktree /repo\nHEAD a\nbranch refs/heads/main\n\n\
+                         worktree /wt/bd-42-add-login\nHEAD b\nbranch refs/heads/task/bd-42-add-login\n\n\
+                         worktree /wt/other\nHEAD c\nbranch refs/heads/molecule/bd-7\n";
+        let matched = match_issues(parse_worktree_list(porcelain), &registry);
+
+        let issues: Vec<Option<&str>> = matched.iter().map(|w| w.issue.as_deref()).collect();
+        assert_eq!(issues, vec![None, Some("bd-42"), Some("bd-7")]);
+    }
+
+    #[test]
+    fn test_concurrent_registrations_are_kept() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let registry_file = dir.path().join(REGISTRY_FILE);
+
+        std::thread::scope(|scope| {
+            for i in 0..8 {
+                let registry_file = &registry_file;
+                scope.spawn(move || {
+                    let worktree = format!("/wt/bd-{}", i);
+                    register_worktree(registry_file, &worktree, &format!("bd-{}", i)).unwrap();
+                });
+            }
+        });
+        unregister_worktree(&registry_file, "/wt/bd-0").unwrap();
+
+        assert_eq!(read_registry(&registry_file).unwrap().len(), 7);
+    }
+
+    #[test]
+    fn test_guard_issue() {
+        let context = |issue: &str| WorktreeContext {
+            issue: issue.to_string(),
+            title: String::new(),
+            branch: format!("task/{}", issue),
+            base: "main".to_string(),
+            created_at: 0,
+        };
+        let worktree = |root: &str, issue: Option<&str>| CurrentWorktree {
+            root: PathBuf::from(root),
+            branch: Some("task/x".to_string()),
+            context: issue.map(context),
+        };
+        let registry: BTreeMap<String, String> = [
+            ("/wt/bd-1".to_string(), "bd-1".to_string()),
+            ("/wt/bd-2".to_string(), "bd-2".to_string()),
+        ]
+        .into_iter()
+        .collect();
+
+        let own = guard_issue(
+            &worktree("/wt/bd-1", Some("bd-1")),
+            &registry,
+            "bd-1",
+            false,
+        );
+        let wrong = guard_issue(
+            &worktree("/wt/bd-1", Some("bd-1")),
+            &registry,
+            "bd-2",
+            false,
+        );
+        let registered = guard_issue(&worktree("/wt/bd-2", None), &registry, "bd-2", false);
+        let main = guard_issue(&worktree("/repo", None), &registry, "bd-1", false);
+        let main_allowed = guard_issue(&worktree("/repo", None), &registry, "bd-1", true);
+
+        assert!(own.allowed);
+        assert!(!wrong.allowed);
+        assert_eq!(wrong.registered.as_deref(), Some("bd-1"));
+        assert_eq!(
+            wrong.detail,
+            "/wt/bd-1 serves bd-1, not bd-2; work on bd-2 in /wt/bd-2"
+        );
+        assert!(registered.allowed);
+        assert!(!main.allowed);
+        assert!(main.detail.contains("create-for-issue bd-1"));
+        assert!(main_allowed.allowed);
+    }
+
+    #[test]
+    fn test_parse_hunk_header() {
+        assert_eq!(
+            parse_hunk_header("@@ -12,3 +12,4 @@ fn main()"),
+            Some((12, 14))
+        );
+        assert_eq!(parse_hunk_header("@@ -7 +7 @@"), Some((7, 7)));
+        assert_eq!(parse_hunk_header("@@ -20,0 +21,2 @@"), Some((20, 20)));
+        assert_eq!(parse_hunk_header("not a hunk"), None);
+    }
+
+    #[test]
+    fn test_parse_diff_ranges() {
+        let diff = "diff --git a/src/lib.rs b/src/lib.rs\n\
+                    --- a/src/lib.rs\n\
+                    +++ b/src/lib.rs\n\
+                    @@ -1,2 +1,3 @@\n\
+                    +use std::fmt;\n\
+                    @@ -40 +41 @@\n\
+                    -old\n\
+                    +new\n\
+                    diff --git a/new.txt b/new.txt\n\
+                    --- /dev/null\n\
+                    +++ b/new.txt\n\
+                    @@ -0,0 +1 @@\n\
+                    +hello\n";
+
+        let changes = parse_diff_ranges(diff);
+
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].path, "src/lib.rs");
+        assert_eq!(changes[0].ranges, vec![(1, 2), (40, 40)]);
+        assert_eq!(changes[1].path, "new.txt");
+        assert_eq!(changes[1].ranges, vec![(0, 0)]);
+    }
+
+    #[test]
+    fn test_no_conflict_for_disjoint_files() {
+        let a = change_set("a", &[("src/a.rs", &[(1, 10)])]);
+        let b = change_set("b", &[("src/b.rs", &[(1, 10)])]);
+
+        assert!(forecast_conflicts(&[a, b]).is_empty());
+    }
+
+    #[test]
+    fn test_low_risk_for_same_file_disjoint_lines() {
+        let a = change_set("a", &[("src/lib.rs", &[(1, 10)])]);
+        let b = change_set("b", &[("src/lib.rs", &[(50, 60)])]);
+
+        let pairs = forecast_conflicts(&[a, b]);
+
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].risk, ConflictRisk::Low);
+        assert!(pairs[0].files[0].overlapping_ranges.is_empty());
+    }
+
+    #[test]
+    fn test_high_risk_for_overlapping_lines() {
+        let a = change_set("a", &[("src/lib.rs", &[(1, 10)])]);
+        let b = change_set("b", &[("src/lib.rs", &[(50, 60)])]);
+        let c = change_set("c", &[("src/lib.rs", &[(8, 12)])]);
+
+        let pairs = forecast_conflicts(&[a, b, c]);
+
+        assert_eq!(pairs.len(), 3);
+        assert_eq!(pairs[0].risk, ConflictRisk::High);
+        assert_eq!((pairs[0].a.as_str(), pairs[0].b.as_str()), ("a", "c"));
+        assert_eq!(pairs[0].files[0].overlapping_ranges, vec![(8, 10)]);
+        assert_eq!(pairs[0].task_a.as_deref(), Some("a"));
+    }
+
+    fn report(path: &str, disk_bytes: u64, last_commit: u64, dirty: bool) -> WorktreeReport {
+        WorktreeReport {
+            path: path.to_string(),
+            branch: None,
+            task_id: None,
+            is_main: false,
+            exists: true,
+            disk_bytes,
+            last_commit: Some(last_commit),
+            ahead: None,
+            behind: None,
+            dirty,
+            worker: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_ahead_behind() {
+        assert_eq!(parse_ahead_behind("3\t5\n"), Some((5, 3)));
+        assert_eq!(parse_ahead_behind("garbage"), None);
+    }
+
+    #[test]
+    fn test_dir_size_skips_nested_worktrees() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(dir.path().join("a.txt"), "12345").unwrap();
+        let nested = dir.path().join("wt");
+        fs::create_dir(&nested).unwrap();
+        fs::write(nested.join("b.txt"), "123").unwrap();
+
+        assert_eq!(dir_size(dir.path(), &[]), 8);
+        assert_eq!(dir_size(dir.path(), &[nested]), 5);
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536 * 1024), "1.5 MiB");
+    }
+
+    #[test]
+    fn test_report_filter_and_sort() {
+        let now = 1_768_176_000;
+        let mut reports = vec![
+            report("/wt/a", 10, now - 60, true),
+            report("/wt/b", 30, now - 86_400 * 10, false),
+            report("/wt/c", 20, now - 86_400 * 30, true),
+        ];
+
+        let stale = ReportFilter {
+            older_than: Some(86_400 * 7),
+            ..Default::default()
+        };
+        let kept: Vec<&str> = reports
+            .iter()
+            .filter(|r| stale.matches(r, now))
+            .map(|r| r.path.as_str())
+            .collect();
+        assert_eq!(kept, vec!["/wt/b", "/wt/c"]);
+
+        let dirty = ReportFilter {
+            dirty: true,
+            ..stale
+        };
+        assert_eq!(reports.iter().filter(|r| dirty.matches(r, now)).count(), 1);
+
+        sort_reports(&mut reports, ReportSort::Disk);
+        assert_eq!(reports[0].path, "/wt/b");
+        sort_reports(&mut reports, ReportSort::Age);
+        assert_eq!(reports[0].path, "/wt/c");
+    }
+
+    #[test]
+    fn test_worker_for_task() {
+        let event = |issue: &str, worker: Option<&str>| ActivityEvent {
+            id: 0,
+            timestamp: 0,
+            issue: issue.to_string(),
+            kind: crate::activity::EventKind::Progress,
+            message: String::new(),
+            worker: worker.map(String::from),
+            run: None,
+            data: None,
+        };
+        let events = vec![
+            event("bd-1", Some("w1")),
+            event("bd-1", Some("w2")),
+            event("bd-1", None),
+            event("bd-2", Some("w3")),
+        ];
+        assert_eq!(worker_for_task(&events, "bd-1").as_deref(), Some("w2"));
+        assert_eq!(worker_for_task(&events, "bd-9"), None);
+    }
+}